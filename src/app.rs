@@ -1,7 +1,33 @@
 //! Application state machine for the TUI.
 
+use crate::bookmarks;
+use crate::commands::{ObjectName, SessionOption, WorkspaceCommand, quote_ident};
+use crate::config::{self, Keymap};
 use crate::db;
-use crate::tui::autocomplete::Autocomplete;
+use crate::db::query::{HealthSnapshot, InsertColumns, SessionInfo};
+use crate::display::{DateStyle, NumberFormat};
+use crate::dml;
+use crate::history;
+use crate::scripts;
+use crate::state::State;
+use crate::timezone::Zone;
+use crate::tui::autocomplete::{self, Autocomplete};
+use crate::tui::chart::ChartView;
+use crate::tui::compare::CompareView;
+use crate::tui::history_search::HistorySearch;
+use crate::tui::inspector::Inspector;
+use crate::tui::picker::Picker;
+use crate::tui::plan::PlanView;
+use crate::tui::prompt::Prompt;
+use crate::tui::signature::{self, SignatureHint};
+use crate::tui::theme::Theme;
+use crate::tui::vim::Vim;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 /// Which pane currently has focus.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,17 +40,99 @@ pub enum FocusPane {
     Sidebar,
 }
 
+/// What an object browser node represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Database,
+    Schema,
+    Table,
+    View,
+    Procedure,
+    Function,
+    Trigger,
+    /// A grouping such as "Views" or a table's "Columns".
+    Folder,
+    /// A column, index, or key under a table, or a placeholder.
+    Detail,
+}
+
+impl NodeKind {
+    /// Whether children are fetched on first expansion.
+    pub fn is_lazy(self) -> bool {
+        matches!(self, NodeKind::Database | NodeKind::Table | NodeKind::View)
+    }
+
+    /// Whether the node is a database object that `sp_help` can describe.
+    pub fn is_object(self) -> bool {
+        matches!(
+            self,
+            NodeKind::Table
+                | NodeKind::View
+                | NodeKind::Procedure
+                | NodeKind::Function
+                | NodeKind::Trigger
+        )
+    }
+}
+
 /// A node in the object browser tree.
 #[derive(Debug, Clone)]
 pub struct ObjectNode {
-    /// Display label.
+    /// Display label. Objects in a database-level folder (views,
+    /// procedures, ...) are labelled `schema.name`.
     pub name: String,
-    /// Depth in the tree (0 = database, 1 = schema, 2 = table).
+    /// What the node represents.
+    pub kind: NodeKind,
+    /// Extra text shown dimmed after the name, e.g. a column's type.
+    pub info: Option<String>,
+    /// Depth in the tree, starting at 0 for databases.
     pub depth: u8,
     /// Whether this node is expanded.
     pub expanded: bool,
     /// Children (lazy-loaded).
     pub children: Vec<ObjectNode>,
+    /// Whether children have been fetched; databases, tables, and views
+    /// start unloaded and load on first expansion.
+    pub loaded: bool,
+}
+
+impl ObjectNode {
+    /// A collapsed node with no children yet.
+    pub fn new(name: impl Into<String>, kind: NodeKind, depth: u8) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            info: None,
+            depth,
+            expanded: false,
+            children: Vec::new(),
+            loaded: !kind.is_lazy(),
+        }
+    }
+}
+
+/// A stored procedure parameter, for parameter hints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcParam {
+    /// Parameter name including the `@`.
+    pub name: String,
+    /// Declared type, e.g. `nvarchar(50)`.
+    pub type_name: String,
+    /// Declared `OUTPUT`.
+    pub output: bool,
+    /// Default value, when the server records one.
+    pub default: Option<String>,
+}
+
+/// A foreign key, for join suggestions. Columns pair up by position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKey {
+    /// Referencing table.
+    pub table: String,
+    pub columns: Vec<String>,
+    /// Referenced table.
+    pub ref_table: String,
+    pub ref_columns: Vec<String>,
 }
 
 /// A single result set from a query.
@@ -32,8 +140,13 @@ pub struct ObjectNode {
 pub struct ResultSet {
     /// Column headers.
     pub columns: Vec<String>,
+    /// SQL type name of each column (e.g. `decimal`), empty when unknown.
+    pub column_types: Vec<String>,
     /// Row data as strings.
     pub rows: Vec<Vec<String>>,
+    /// Cells that are SQL NULL, as (row, column). Their text in `rows` is
+    /// `NULL`, the same as a string that says so.
+    pub nulls: HashSet<(usize, usize)>,
 }
 
 /// Query result data ready for display.
@@ -45,6 +158,67 @@ pub struct QueryResult {
     pub elapsed_ms: u128,
     /// Optional error message.
     pub error: Option<String>,
+    /// The SQL that produced the result, when it was typed rather than
+    /// generated by a slash command.
+    pub sql: Option<String>,
+}
+
+/// Details of the last failed query, for `\errverbose`.
+#[derive(Debug, Clone, Default)]
+pub struct QueryError {
+    /// The error message.
+    pub message: String,
+    /// SQL Server error number; `None` for client-side errors such as a
+    /// dropped connection.
+    pub number: Option<u32>,
+    /// Severity (class) of a server error.
+    pub severity: Option<u8>,
+    /// State of a server error.
+    pub state: Option<u8>,
+    /// Line within the batch or procedure where the error was raised.
+    pub line: Option<u32>,
+    /// Procedure the error was raised in, if not the batch itself.
+    pub procedure: Option<String>,
+    /// Name of the server that raised the error.
+    pub server: Option<String>,
+    /// The batch that failed.
+    pub sql: String,
+}
+
+impl QueryError {
+    /// The batch line the error points at, when it was raised in the batch
+    /// itself rather than in a procedure it called.
+    pub fn batch_line(&self) -> Option<(usize, &str)> {
+        if self.procedure.is_some() {
+            return None;
+        }
+        let line = self.line? as usize;
+        let text = self.sql.lines().nth(line.checked_sub(1)?)?;
+        Some((line, text))
+    }
+
+    /// Property/value rows describing the error.
+    pub fn details(&self) -> Vec<Vec<String>> {
+        let mut rows = vec![vec!["Message".to_string(), self.message.clone()]];
+        let fields = [
+            ("Number", self.number.map(|n| n.to_string())),
+            ("Severity", self.severity.map(|s| s.to_string())),
+            ("State", self.state.map(|s| s.to_string())),
+            ("Line", self.line.map(|l| l.to_string())),
+            ("Procedure", self.procedure.clone()),
+            ("Server", self.server.clone()),
+            (
+                "Statement",
+                self.batch_line().map(|(_, text)| text.trim().to_string()),
+            ),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                rows.push(vec![name.to_string(), value]);
+            }
+        }
+        rows
+    }
 }
 
 impl QueryResult {
@@ -64,6 +238,26 @@ impl QueryResult {
             .unwrap_or(&[])
     }
 
+    /// A cell of a specific result set, `None` for NULL (or no such cell).
+    pub fn value(&self, index: usize, row: usize, col: usize) -> Option<&str> {
+        self.result_sets.get(index)?.value(row, col)
+    }
+
+    /// Whether a cell of a specific result set is NULL.
+    pub fn is_null(&self, index: usize, row: usize, col: usize) -> bool {
+        self.result_sets
+            .get(index)
+            .is_some_and(|rs| rs.nulls.contains(&(row, col)))
+    }
+
+    /// Rows of a specific result set with `None` for NULL cells.
+    pub fn cells_for(&self, index: usize) -> Vec<Vec<Option<&str>>> {
+        self.result_sets
+            .get(index)
+            .map(|rs| (0..rs.rows.len()).map(|r| rs.row_values(r)).collect())
+            .unwrap_or_default()
+    }
+
     /// Get columns of a specific result set.
     pub fn columns_for(&self, index: usize) -> &[String] {
         self.result_sets
@@ -72,6 +266,22 @@ impl QueryResult {
             .unwrap_or(&[])
     }
 
+    /// Get column types of a specific result set.
+    pub fn column_types_for(&self, index: usize) -> &[String] {
+        self.result_sets
+            .get(index)
+            .map(|rs| rs.column_types.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Header labels (name plus type) of a specific result set.
+    pub fn header_labels_for(&self, index: usize) -> Vec<String> {
+        self.result_sets
+            .get(index)
+            .map(|rs| (0..rs.columns.len()).map(|i| rs.header_label(i)).collect())
+            .unwrap_or_default()
+    }
+
     /// Total row count across all result sets.
     pub fn total_rows(&self) -> usize {
         self.result_sets.iter().map(|rs| rs.rows.len()).sum()
@@ -80,21 +290,362 @@ impl QueryResult {
     /// Helper to create a single-resultset QueryResult.
     pub fn single(columns: Vec<String>, rows: Vec<Vec<String>>, elapsed_ms: u128) -> Self {
         Self {
-            result_sets: vec![ResultSet { columns, rows }],
+            result_sets: vec![ResultSet {
+                columns,
+                rows,
+                ..Default::default()
+            }],
             elapsed_ms,
             error: None,
+            sql: None,
+        }
+    }
+}
+
+impl ResultSet {
+    /// A cell, `None` for NULL (or no such cell).
+    pub fn value(&self, row: usize, col: usize) -> Option<&str> {
+        if self.nulls.contains(&(row, col)) {
+            return None;
+        }
+        self.rows.get(row)?.get(col).map(String::as_str)
+    }
+
+    /// A row's cells, `None` for NULL.
+    pub fn row_values(&self, row: usize) -> Vec<Option<&str>> {
+        (0..self.rows.get(row).map_or(0, Vec::len))
+            .map(|col| self.value(row, col))
+            .collect()
+    }
+
+    /// Header label for a column, including its SQL type when known.
+    pub fn header_label(&self, index: usize) -> String {
+        let name = self.columns.get(index).map(|s| s.as_str()).unwrap_or("");
+        match self.column_types.get(index) {
+            Some(ty) if !ty.is_empty() => format!("{} ({})", name, ty),
+            _ => name.to_string(),
+        }
+    }
+}
+
+/// Aggregates over one result column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats {
+    /// Number of non-NULL values.
+    pub count: usize,
+    /// Sum, when every non-NULL value is numeric.
+    pub sum: Option<f64>,
+    /// Average, when every non-NULL value is numeric.
+    pub avg: Option<f64>,
+    /// Smallest value (numeric or lexicographic).
+    pub min: Option<String>,
+    /// Largest value (numeric or lexicographic).
+    pub max: Option<String>,
+}
+
+impl ColumnStats {
+    /// Compute aggregates over a column's `values`, ignoring NULLs (`None`).
+    pub fn compute<'a>(values: impl Iterator<Item = Option<&'a str>>) -> Self {
+        let values: Vec<&str> = values.flatten().collect();
+        let numbers: Option<Vec<f64>> = values.iter().map(|v| v.trim().parse().ok()).collect();
+
+        match numbers {
+            Some(nums) if !nums.is_empty() => {
+                let sum: f64 = nums.iter().sum();
+                let min = nums.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = nums.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                Self {
+                    count: nums.len(),
+                    sum: Some(sum),
+                    avg: Some(sum / nums.len() as f64),
+                    min: Some(format_number(min)),
+                    max: Some(format_number(max)),
+                }
+            }
+            _ => Self {
+                count: values.len(),
+                sum: None,
+                avg: None,
+                min: values.iter().min().map(|s| s.to_string()),
+                max: values.iter().max().map(|s| s.to_string()),
+            },
+        }
+    }
+}
+
+/// Format a number without float noise: integers as-is, others to 4 places.
+pub fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        let s = format!("{:.4}", n);
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+/// A script file open in the editor.
+#[derive(Debug, Clone)]
+pub struct OpenFile {
+    pub path: PathBuf,
+    /// Buffer contents as last loaded or saved, to detect changes.
+    pub saved: String,
+}
+
+/// An action that would lose unsaved changes, held until the user decides.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiscardAction {
+    Quit,
+    Open(PathBuf),
+}
+
+/// Something to run outside the TUI, with the terminal handed over.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Suspend {
+    /// Edit this text in `$EDITOR` and load the result into the editor.
+    Edit(String),
+    /// Run this shell command (`\!`); empty starts an interactive shell.
+    Shell(String),
+}
+
+/// A query re-run on an interval by `\watch`.
+#[derive(Debug, Clone)]
+pub struct Watch {
+    /// The query to run.
+    pub query: String,
+    /// Time between runs.
+    pub interval: Duration,
+    /// Number of completed runs.
+    pub iterations: u64,
+    /// When the last run finished; `None` before the first.
+    pub last_run: Option<Instant>,
+}
+
+impl Watch {
+    /// Whether the next run is due.
+    pub fn is_due(&self) -> bool {
+        self.last_run
+            .is_none_or(|last| last.elapsed() >= self.interval)
+    }
+
+    /// Count a finished run and start waiting for the next.
+    pub fn finished(&mut self) {
+        self.iterations += 1;
+        self.last_run = Some(Instant::now());
+    }
+}
+
+/// Periodic server health probe for the status bar (`health_interval` in
+/// the config file).
+#[derive(Debug, Clone)]
+pub struct HealthProbe {
+    /// Time between probes.
+    pub interval: Duration,
+    /// When the last probe ran; `None` before the first.
+    pub last_run: Option<Instant>,
+    /// The latest figures.
+    pub snapshot: Option<HealthSnapshot>,
+    /// The probe failed (usually for lack of `VIEW SERVER STATE`) and has
+    /// stopped.
+    pub failed: bool,
+}
+
+impl HealthProbe {
+    /// A probe running every `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_run: None,
+            snapshot: None,
+            failed: false,
         }
     }
+
+    /// Whether the next probe is due.
+    pub fn is_due(&self) -> bool {
+        !self.failed
+            && self
+                .last_run
+                .is_none_or(|last| last.elapsed() >= self.interval)
+    }
 }
 
+/// Periodic `SELECT 1` on the active connection once it has been idle for
+/// a while, so a connection dropped by the network or the server is found
+/// and replaced before the next query needs it.
+pub struct Keepalive {
+    /// Idle time before a check.
+    pub interval: Duration,
+    /// When the connection was last used or checked.
+    pub last_used: Instant,
+}
+
+impl Keepalive {
+    /// Checks after `interval` of idleness.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_used: Instant::now(),
+        }
+    }
+
+    /// Whether the connection has been idle long enough to check.
+    pub fn is_due(&self) -> bool {
+        self.last_used.elapsed() >= self.interval
+    }
+
+    /// Note that the connection was just used.
+    pub fn touch(&mut self) {
+        self.last_used = Instant::now();
+    }
+}
+
+/// A results cell being edited: the table the result came from, the cell,
+/// and the row's primary key as column names and SQL literals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellEdit {
+    pub table: String,
+    pub result_set: usize,
+    pub row: usize,
+    pub col: usize,
+    pub key: Vec<(String, String)>,
+}
+
+/// A `/` search in the results grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultSearch {
+    /// Lowercased text to look for.
+    pub pattern: String,
+    /// The match last jumped to, as (row, display position of the column).
+    pub current: Option<(usize, usize)>,
+}
+
+/// A query workspace: an editor with its own results, scroll state, and
+/// database, and optionally its own connection. The active workspace's state
+/// lives in the `App` fields; the others are parked here.
+pub struct Workspace {
+    /// Tab label.
+    pub name: String,
+    /// Whether the workspace has its own connection rather than sharing the
+    /// main one.
+    pub own_connection: bool,
+    /// The own connection while the workspace is inactive (the event loop
+    /// holds it while active).
+    pub connection: Option<db::ConnectionHandle>,
+    /// Details of the own connection while the workspace is inactive.
+    pub session: Option<SessionInfo>,
+    editor: tui_textarea::TextArea<'static>,
+    file: Option<OpenFile>,
+    result: QueryResult,
+    previous_results: VecDeque<QueryResult>,
+    result_scroll: usize,
+    result_col_scroll: usize,
+    pinned_columns: usize,
+    current_result_set: usize,
+    watch: Option<Watch>,
+    last_error: Option<QueryError>,
+    current_database: String,
+    history_index: Option<usize>,
+}
+
+impl Workspace {
+    /// An empty workspace in `database`.
+    pub fn new(name: &str, database: &str, theme: &Theme) -> Self {
+        let mut editor = tui_textarea::TextArea::default();
+        style_editor(&mut editor, theme);
+        Self {
+            name: name.to_string(),
+            own_connection: false,
+            connection: None,
+            session: None,
+            editor,
+            file: None,
+            result: QueryResult::default(),
+            previous_results: VecDeque::new(),
+            result_scroll: 0,
+            result_col_scroll: 0,
+            pinned_columns: 0,
+            current_result_set: 0,
+            watch: None,
+            last_error: None,
+            current_database: database.to_string(),
+            history_index: None,
+        }
+    }
+
+    /// The query of the workspace's `\watch`, if it has one.
+    pub fn watch_query(&self) -> Option<&str> {
+        self.watch.as_ref().map(|w| w.query.as_str())
+    }
+
+    /// The database the workspace is in.
+    pub fn database(&self) -> &str {
+        &self.current_database
+    }
+
+    /// Keep the result of a `\watch` run while the workspace is inactive,
+    /// as [`App::set_result`] would, along with its error, if any.
+    pub fn watch_finished(&mut self, result: QueryResult, error: Option<QueryError>) {
+        self.result = result;
+        self.result_scroll = 0;
+        self.result_col_scroll = 0;
+        self.current_result_set = 0;
+        if error.is_some() {
+            self.last_error = error;
+        }
+        if let Some(ref mut watch) = self.watch {
+            watch.finished();
+        }
+    }
+}
+
+/// Default sidebar width in columns.
+pub const SIDEBAR_WIDTH: u16 = 22;
+/// Narrowest sidebar `resize_sidebar` allows.
+pub const SIDEBAR_MIN_WIDTH: u16 = 12;
+/// Widest sidebar `resize_sidebar` allows.
+pub const SIDEBAR_MAX_WIDTH: u16 = 80;
+/// Default idle time, in seconds, before a [`Keepalive`] check.
+pub const KEEPALIVE_INTERVAL: u64 = 300;
+/// How many past query results `\compare` can go back to.
+pub const KEPT_RESULTS: usize = 5;
+
 /// The main application state.
 pub struct App {
     /// Which pane has focus.
     pub focus: FocusPane,
     /// Whether the sidebar is visible.
     pub sidebar_visible: bool,
+    /// Sidebar width in columns, including its border.
+    pub sidebar_width: u16,
+    /// Draw sidebar glyphs in plain ASCII.
+    pub ascii_icons: bool,
+    /// UI colors.
+    pub theme: Theme,
     /// The SQL editor text area.
     pub editor: tui_textarea::TextArea<'static>,
+    /// Editor key bindings.
+    pub keymap: Keymap,
+    /// Vim emulation state when the Vim keymap is selected.
+    pub vim: Option<Vim>,
+    /// Script file the editor is editing, if any.
+    pub file: Option<OpenFile>,
+    /// Open single-line prompt (save as, ...), if any.
+    pub prompt: Option<Prompt>,
+    /// Action waiting on the unsaved-changes dialog, if any.
+    pub confirm_discard: Option<DiscardAction>,
+    /// Work that needs the terminal to itself, run once the current key has
+    /// been handled.
+    pub suspend: Option<Suspend>,
+    /// File that query results are also appended to (`\o`).
+    pub tee: Option<PathBuf>,
+    /// Query being re-run on an interval (`\watch`), if any.
+    pub watch: Option<Watch>,
+    /// The most recent query error, for `\errverbose`.
+    pub last_error: Option<QueryError>,
+    /// Client-side variables, substituted for `$(name)` in queries.
+    pub variables: HashMap<String, String>,
+    /// Format for results written to files: table, csv or json.
+    pub output_format: String,
     /// Current query results.
     pub result: QueryResult,
     /// Object browser tree.
@@ -103,64 +654,246 @@ pub struct App {
     pub result_scroll: usize,
     /// Horizontal scroll offset in the results table (columns).
     pub result_col_scroll: usize,
+    /// Number of leading result columns kept visible while scrolling right.
+    pub pinned_columns: usize,
+    /// Rows of the current result set marked with Space, for generating
+    /// INSERT statements.
+    pub marked_rows: BTreeSet<usize>,
+    /// Show count/sum/avg/min/max of the focused column in the results footer.
+    pub show_column_stats: bool,
+    /// Inner (width, height) of the results pane from the last draw.
+    pub results_viewport: Cell<(u16, u16)>,
+    /// Display order of the result columns (`<`/`>`), as column indexes;
+    /// empty for the order the query returned them in.
+    pub column_order: Vec<usize>,
+    /// Scrolled (unpinned) columns that fit in the grid at the last draw.
+    pub results_visible_columns: Cell<usize>,
     /// Sidebar scroll offset.
     pub sidebar_scroll: usize,
     /// Connection info string for the status bar.
     pub connection_info: String,
+    /// Connected with `ApplicationIntent=ReadOnly` (`--application-intent`).
+    pub read_only_intent: bool,
     /// Current database name.
     pub current_database: String,
     /// Whether the app should quit.
     pub should_quit: bool,
     /// Whether a query is currently running.
     pub query_running: bool,
+    /// Server health probe, if enabled.
+    pub health: Option<HealthProbe>,
+    /// Idle connection checks (`keepalive_interval` setting), if on.
+    pub keepalive: Option<Keepalive>,
+    /// Session options set with `\set option`, in the order set; applied
+    /// again on every new connection.
+    pub session_options: Vec<SessionOption>,
+    /// One-line message in the status bar, cleared by the next key press.
+    pub notice: Option<String>,
+    /// Whether the results show the `\blocking` tree, where `s` loads
+    /// `\session` for the selected row.
+    pub blocking_view: bool,
+    /// Head blocker of the `\blocking` tree on screen, for `k` to load a
+    /// `\kill` for it.
+    pub blocking_head: Option<String>,
+    /// Rows fetched so far by the running query, once it has been fetching
+    /// for a moment.
+    pub fetched_rows: Option<usize>,
     /// Query history.
     pub history: Vec<String>,
+    /// Starred queries, shown first in the quick-access menu (F2).
+    pub bookmarks: Vec<String>,
     /// Current position in history (-1 = current editor content).
     pub history_index: Option<usize>,
+    /// Open reverse history search (Ctrl+R), if any.
+    pub history_search: Option<HistorySearch>,
     /// Show help overlay.
     pub show_help: bool,
+    /// Open value inspector overlay, if any.
+    pub inspector: Option<Inspector>,
+    /// Open bar chart overlay, if any.
+    pub chart: Option<ChartView>,
+    /// Open result comparison overlay (`\compare`), if any.
+    pub compare: Option<CompareView>,
+    /// Open estimated plan overlay (`\explain`), if any.
+    pub plan: Option<PlanView>,
+    /// Last `type = value` answer for each query parameter, by lowercased
+    /// name, offered again the next time it is asked for.
+    pub param_answers: HashMap<String, String>,
+    /// Results the last typed queries replaced, newest first, for
+    /// `\compare`.
+    pub previous_results: VecDeque<QueryResult>,
+    /// Open list picker overlay (snippets, ...), if any.
+    pub picker: Option<Picker>,
+    /// Row and time of the last Enter in the sidebar, to detect double-Enter.
+    pub last_sidebar_enter: Option<(usize, Instant)>,
+    /// Show row counts and sizes next to sidebar tables.
+    pub sidebar_stats: bool,
+    /// Databases whose table stats have been fetched.
+    pub stats_loaded: HashSet<String>,
+    /// Sidebar nodes whose children are waiting to be fetched, oldest
+    /// first, as paths of child indexes from the root.
+    pub pending_loads: VecDeque<Vec<usize>>,
     /// Autocomplete state.
     pub autocomplete: Autocomplete,
+    /// Parameter hint for the `EXEC` call at the cursor, if any.
+    pub signature: Option<SignatureHint>,
+    /// Procedure parameters already fetched, keyed by name as typed.
+    pub proc_params: HashMap<String, Vec<ProcParam>>,
     /// Which result set is currently displayed (for multi-resultset queries).
     pub current_result_set: usize,
     /// Expanded display mode (vertical record layout).
     pub expanded_mode: bool,
     /// Show query timing in results.
     pub show_timing: bool,
+    /// Text shown in the grid for NULL values (`\pset null`).
+    pub null_display: String,
+    /// How dates and times are written (`\pset datestyle`).
+    pub date_style: DateStyle,
+    /// How numbers are shown in the grid (`\pset thousands`, `\pset decimals`).
+    pub number_format: NumberFormat,
+    /// Symbol shown before money values in the grid (`\pset currency`).
+    pub currency: Option<String>,
+    /// Show the row-number gutter left of the grid (`\pset rownum`).
+    pub show_row_numbers: bool,
+    /// Active `/` search in the results grid, highlighted until cleared.
+    pub result_search: Option<ResultSearch>,
+    /// Zone `datetimeoffset` values are shown in (`\set timezone`).
+    pub timezone: Option<Zone>,
+    /// Whether `datetime2` values are taken as UTC and shown in
+    /// [`App::timezone`] too (`\set timezone_datetime2 on`).
+    pub timezone_datetime2: bool,
     /// Username used for the connection.
     pub user: String,
+    /// Server version, SPID, encryption, and transaction count of the
+    /// active connection, if they could be fetched.
+    pub session: Option<SessionInfo>,
+    /// Query workspaces; the entry at `active_workspace` is a placeholder
+    /// whose state is in the fields above.
+    pub workspaces: Vec<Workspace>,
+    /// Index of the workspace being shown.
+    pub active_workspace: usize,
+    /// Workspace change to make once the current key has been handled, as
+    /// it may need to open or swap connections.
+    pub workspace_request: Option<WorkspaceCommand>,
+    /// Generated script to load into the editor of the workspace being
+    /// opened for it.
+    pub generated_script: Option<String>,
+}
+
+/// Borrow `(column, literal)` pairs for the [`dml`] builders.
+fn key_pairs(key: &[(String, String)]) -> Vec<(&str, String)> {
+    key.iter().map(|(c, l)| (c.as_str(), l.clone())).collect()
+}
+
+/// A cell's value as offered at the cell edit prompt: `NULL` for NULL and
+/// `'NULL'` for the text `NULL`.
+pub fn cell_text(value: Option<&str>) -> String {
+    match value {
+        None => "NULL".to_string(),
+        Some("NULL") => "'NULL'".to_string(),
+        Some(value) => value.to_string(),
+    }
+}
+
+/// The value typed at the cell edit prompt, the reverse of [`cell_text`]:
+/// `None` for `NULL`.
+pub fn parse_cell_text(input: &str) -> Option<&str> {
+    match input {
+        "NULL" => None,
+        "'NULL'" => Some("NULL"),
+        input => Some(input),
+    }
+}
+
+/// Editor styles that don't come from the syntax highlighter.
+fn style_editor(editor: &mut tui_textarea::TextArea<'static>, theme: &Theme) {
+    editor.set_cursor_line_style(ratatui::style::Style::default());
+    editor.set_line_number_style(ratatui::style::Style::default().fg(theme.muted));
 }
 
 impl App {
     /// Create a new App with default state.
     pub fn new(host: &str, port: u16, database: &str, user: &str) -> Self {
         let mut editor = tui_textarea::TextArea::default();
-        editor.set_cursor_line_style(ratatui::style::Style::default());
-        editor.set_line_number_style(
-            ratatui::style::Style::default().fg(ratatui::style::Color::DarkGray),
-        );
+        style_editor(&mut editor, &Theme::default());
 
         Self {
             focus: FocusPane::Editor,
             sidebar_visible: true,
+            sidebar_width: SIDEBAR_WIDTH,
+            ascii_icons: false,
+            theme: Theme::default(),
             editor,
+            keymap: Keymap::Default,
+            vim: None,
+            file: None,
+            prompt: None,
+            confirm_discard: None,
+            suspend: None,
+            tee: None,
+            watch: None,
+            last_error: None,
+            variables: HashMap::new(),
+            output_format: "table".to_string(),
             result: QueryResult::default(),
             objects: Vec::new(),
             result_scroll: 0,
             result_col_scroll: 0,
+            pinned_columns: 0,
+            marked_rows: BTreeSet::new(),
+            show_column_stats: false,
+            results_viewport: Cell::new((0, 0)),
+            results_visible_columns: Cell::new(1),
+            column_order: Vec::new(),
             sidebar_scroll: 0,
             connection_info: format!("{}:{}", host, port),
+            read_only_intent: false,
             current_database: database.to_string(),
             should_quit: false,
             query_running: false,
+            fetched_rows: None,
+            blocking_view: false,
+            blocking_head: None,
+            health: None,
+            keepalive: None,
+            session_options: Vec::new(),
+            notice: None,
             history: Vec::new(),
             history_index: None,
+            bookmarks: Vec::new(),
+            history_search: None,
             show_help: false,
+            inspector: None,
+            chart: None,
+            compare: None,
+            plan: None,
+            previous_results: VecDeque::new(),
+            param_answers: HashMap::new(),
+            picker: None,
+            last_sidebar_enter: None,
+            sidebar_stats: false,
+            stats_loaded: HashSet::new(),
+            pending_loads: VecDeque::new(),
             autocomplete: Autocomplete::default(),
+            signature: None,
+            proc_params: HashMap::new(),
             current_result_set: 0,
             expanded_mode: false,
             show_timing: false,
+            null_display: "NULL".to_string(),
+            date_style: DateStyle::Raw,
+            number_format: NumberFormat::default(),
+            currency: None,
+            show_row_numbers: false,
+            result_search: None,
+            timezone: None,
+            timezone_datetime2: false,
             user: user.to_string(),
+            session: None,
+            workspaces: vec![Workspace::new(database, database, &Theme::default())],
+            active_workspace: 0,
+            workspace_request: None,
+            generated_script: None,
         }
     }
 
@@ -187,30 +920,341 @@ impl App {
         }
     }
 
+    /// Apply display state saved by an earlier session.
+    pub fn restore_state(&mut self, state: &State) {
+        self.expanded_mode = state.expanded.unwrap_or(self.expanded_mode);
+        self.show_timing = state.timing.unwrap_or(self.show_timing);
+        self.sidebar_visible = state.sidebar_visible.unwrap_or(self.sidebar_visible);
+        if let Some(ref text) = state.null_display {
+            self.null_display = text.clone();
+        }
+        if !self.sidebar_visible && self.focus == FocusPane::Sidebar {
+            self.focus = FocusPane::Editor;
+        }
+    }
+
+    /// The display state to save for the next session.
+    pub fn saved_state(&self) -> State {
+        State {
+            expanded: Some(self.expanded_mode),
+            timing: Some(self.show_timing),
+            sidebar_visible: Some(self.sidebar_visible),
+            null_display: Some(self.null_display.clone()),
+            database: Some(self.current_database.clone()),
+        }
+    }
+
     /// Get the current editor content as a string.
     pub fn get_editor_text(&self) -> String {
         self.editor.lines().join("\n")
     }
 
+    /// Switch the UI colors.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        style_editor(&mut self.editor, &theme);
+        for workspace in &mut self.workspaces {
+            style_editor(&mut workspace.editor, &theme);
+        }
+    }
+
+    /// Add an empty workspace in `database` and return its index. It isn't
+    /// shown until switched to.
+    pub fn add_workspace(
+        &mut self,
+        database: &str,
+        connection: Option<(db::ConnectionHandle, Option<SessionInfo>)>,
+    ) -> usize {
+        let mut workspace = Workspace::new(database, database, &self.theme);
+        if let Some((connection, session)) = connection {
+            workspace.own_connection = true;
+            workspace.connection = Some(connection);
+            workspace.session = session;
+        }
+        self.workspaces.push(workspace);
+        self.workspaces.len() - 1
+    }
+
+    /// Show workspace `index`, parking the current one. Connections are left
+    /// to the caller.
+    pub fn switch_workspace(&mut self, index: usize) {
+        if index == self.active_workspace || index >= self.workspaces.len() {
+            return;
+        }
+        self.exchange_workspace(self.active_workspace);
+        self.exchange_workspace(index);
+        self.active_workspace = index;
+        self.dismiss_overlays();
+    }
+
+    /// Close the current workspace and show the one before it. Does nothing
+    /// if it is the last one.
+    pub fn close_workspace(&mut self) {
+        if self.workspaces.len() < 2 {
+            return;
+        }
+        self.workspaces.remove(self.active_workspace);
+        self.active_workspace = self.active_workspace.saturating_sub(1);
+        // The closed workspace's state goes into the placeholder slot
+        self.exchange_workspace(self.active_workspace);
+        self.dismiss_overlays();
+    }
+
+    /// Swap the workspace state in the `App` fields with the state parked at
+    /// `index`.
+    fn exchange_workspace(&mut self, index: usize) {
+        let ws = &mut self.workspaces[index];
+        std::mem::swap(&mut self.editor, &mut ws.editor);
+        std::mem::swap(&mut self.file, &mut ws.file);
+        std::mem::swap(&mut self.result, &mut ws.result);
+        std::mem::swap(&mut self.previous_results, &mut ws.previous_results);
+        std::mem::swap(&mut self.result_scroll, &mut ws.result_scroll);
+        std::mem::swap(&mut self.result_col_scroll, &mut ws.result_col_scroll);
+        std::mem::swap(&mut self.pinned_columns, &mut ws.pinned_columns);
+        std::mem::swap(&mut self.current_result_set, &mut ws.current_result_set);
+        std::mem::swap(&mut self.watch, &mut ws.watch);
+        std::mem::swap(&mut self.last_error, &mut ws.last_error);
+        std::mem::swap(&mut self.current_database, &mut ws.current_database);
+        std::mem::swap(&mut self.history_index, &mut ws.history_index);
+        self.marked_rows.clear();
+        self.column_order.clear();
+    }
+
+    /// Close popups that belong to the workspace being left.
+    fn dismiss_overlays(&mut self) {
+        self.autocomplete.dismiss();
+        self.signature = None;
+        self.inspector = None;
+        self.chart = None;
+        self.compare = None;
+        self.plan = None;
+        self.blocking_view = false;
+        self.blocking_head = None;
+    }
+
+    /// An inactive workspace whose `\watch` query is due to run.
+    pub fn background_watch_due(&self) -> Option<usize> {
+        self.workspaces.iter().enumerate().position(|(i, ws)| {
+            i != self.active_workspace && ws.watch.as_ref().is_some_and(|w| w.is_due())
+        })
+    }
+
+    /// A workspace with unsaved changes, preferring the current one.
+    pub fn modified_workspace(&self) -> Option<usize> {
+        if self.is_modified() {
+            return Some(self.active_workspace);
+        }
+        self.workspaces.iter().enumerate().position(|(i, ws)| {
+            i != self.active_workspace
+                && ws
+                    .file
+                    .as_ref()
+                    .is_some_and(|f| f.saved != ws.editor.lines().join("\n"))
+        })
+    }
+
+    /// `[property, value]` rows for `\conninfo` beyond the server, database,
+    /// and user: the application intent, the replica that served the
+    /// connection with whether its database is read-only, the server
+    /// version, SPID, TDS version and transport, encryption, and packet
+    /// size.
+    pub fn connection_details(&self) -> Vec<Vec<String>> {
+        let intent = if self.read_only_intent {
+            "ReadOnly"
+        } else {
+            "ReadWrite"
+        };
+        let mut rows = vec![vec!["Application intent".to_string(), intent.to_string()]];
+        if let Some(ref session) = self.session {
+            let access = if session.read_only {
+                "read-only"
+            } else {
+                "read-write"
+            };
+            rows.push(vec![
+                "Replica".to_string(),
+                format!("{} ({})", session.replica, access),
+            ]);
+            let encrypted = match session.encrypted {
+                Some(true) => "yes",
+                Some(false) => "no",
+                None => "unknown (needs VIEW SERVER STATE)",
+            };
+            let packet_size = match session.packet_size {
+                Some(bytes) => format!("{} bytes", bytes),
+                None => "unknown (needs VIEW SERVER STATE)".to_string(),
+            };
+            rows.extend([
+                vec!["Server version".to_string(), session.server_version.clone()],
+                vec!["SPID".to_string(), session.spid.clone()],
+                vec!["Protocol".to_string(), session.protocol.clone()],
+                vec!["Encrypted".to_string(), encrypted.to_string()],
+                vec!["Packet size".to_string(), packet_size],
+            ]);
+        }
+        rows
+    }
+
+    /// Workspaces as `[#, name, database, connection, watch]` rows for
+    /// display.
+    pub fn workspace_rows(&self) -> Vec<Vec<String>> {
+        self.workspaces
+            .iter()
+            .enumerate()
+            .map(|(i, ws)| {
+                let active = i == self.active_workspace;
+                let (database, watch) = if active {
+                    (&self.current_database, &self.watch)
+                } else {
+                    (&ws.current_database, &ws.watch)
+                };
+                vec![
+                    format!("{}{}", i + 1, if active { " *" } else { "" }),
+                    ws.name.clone(),
+                    database.clone(),
+                    if ws.own_connection { "own" } else { "shared" }.to_string(),
+                    watch
+                        .as_ref()
+                        .map(|w| format!("\\watch {}s", w.interval.as_secs()))
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect()
+    }
+
     /// Clear the editor.
     pub fn clear_editor(&mut self) {
         self.editor = tui_textarea::TextArea::default();
-        self.editor
-            .set_cursor_line_style(ratatui::style::Style::default());
-        self.editor.set_line_number_style(
-            ratatui::style::Style::default().fg(ratatui::style::Color::DarkGray),
-        );
+        style_editor(&mut self.editor, &self.theme);
     }
 
-    /// Push current query to history and reset index.
+    /// Push current query to history (and the history file) and reset index.
+    /// Consecutive duplicates are recorded once.
     pub fn push_history(&mut self) {
         let text = self.get_editor_text();
-        if !text.trim().is_empty() {
+        if !text.trim().is_empty() && self.history.last() != Some(&text) {
+            history::append(&text).ok();
             self.history.push(text);
+            if self.history.len() > history::MAX_ENTRIES {
+                self.history.remove(0);
+            }
+            self.autocomplete.set_history(&self.history);
         }
         self.history_index = None;
     }
 
+    /// Star or unstar a query and persist the change. Returns whether the
+    /// query is starred afterwards.
+    pub fn toggle_bookmark(&mut self, query: &str) -> bool {
+        let starred = if let Some(pos) = self.bookmarks.iter().position(|b| b == query) {
+            self.bookmarks.remove(pos);
+            false
+        } else {
+            self.bookmarks.push(query.to_string());
+            true
+        };
+        bookmarks::save(&self.bookmarks).ok();
+        starred
+    }
+
+    /// The most recent history entry that is SQL rather than a slash command.
+    pub fn last_sql_query(&self) -> Option<&str> {
+        self.history
+            .iter()
+            .rev()
+            .map(|h| h.as_str())
+            .find(|h| !h.trim_start().starts_with('\\'))
+    }
+
+    /// Store the columns of the current result set's single row as
+    /// variables named `prefix` + column name (`\gset`). NULL values unset
+    /// the variable.
+    pub fn gset(&mut self, prefix: &str) {
+        let set = self.current_result_set;
+        let rows = self.result.rows_for(set);
+        if rows.len() != 1 {
+            self.set_status(format!(
+                "\\gset needs a result with exactly one row (this one has {})",
+                rows.len()
+            ));
+            return;
+        }
+        let values: Vec<Option<String>> = self.result.cells_for(set)[0]
+            .iter()
+            .map(|v| v.map(str::to_string))
+            .collect();
+        let columns = self.result.columns_for(set).to_vec();
+        let mut names = Vec::new();
+        for (column, value) in columns.iter().zip(values) {
+            if column.is_empty() {
+                continue;
+            }
+            let name = format!("{}{}", prefix, column);
+            match value {
+                Some(value) => self.variables.insert(name.clone(), value),
+                None => self.variables.remove(&name),
+            };
+            names.push(name);
+        }
+        if names.is_empty() {
+            self.set_status("\\gset: the result has no named columns");
+        } else {
+            self.set_status(format!("Set {}", names.join(", ")));
+        }
+    }
+
+    /// Replace the results pane with a one-line status message.
+    pub fn set_status(&mut self, msg: impl Into<String>) {
+        self.set_result(QueryResult::single(
+            vec!["Status".to_string()],
+            vec![vec![msg.into()]],
+            0,
+        ));
+    }
+
+    /// Replace the results pane, scrolled to the top of the first result
+    /// set.
+    pub fn set_result(&mut self, result: QueryResult) {
+        // The same columns again keep their display order
+        if !self.has_shown_columns(&result) {
+            self.column_order.clear();
+        }
+        self.result = result;
+        self.result_scroll = 0;
+        self.result_col_scroll = 0;
+        self.current_result_set = 0;
+        self.marked_rows.clear();
+        self.result_search = None;
+    }
+
+    /// Keep the shown result for `\compare` if a typed query produced it,
+    /// dropping the oldest beyond `KEPT_RESULTS`.
+    pub fn keep_result(&mut self) {
+        if self.result.sql.is_some() {
+            self.previous_results
+                .push_front(std::mem::take(&mut self.result));
+            self.previous_results.truncate(KEPT_RESULTS);
+        }
+    }
+
+    /// Load a history entry into the editor (from Ctrl+R search).
+    pub fn load_history_entry(&mut self, text: &str) {
+        self.set_editor_text(text);
+        self.history_index = None;
+        self.focus = FocusPane::Editor;
+    }
+
+    /// Load a generated script into the editor if it is empty, else into a
+    /// new workspace so the text being edited is kept.
+    pub fn open_generated(&mut self, script: String) {
+        if self.get_editor_text().trim().is_empty() {
+            self.load_history_entry(&script);
+        } else {
+            self.generated_script = Some(script);
+            self.workspace_request = Some(WorkspaceCommand::New { database: None });
+        }
+    }
+
     /// Navigate history backward.
     pub fn history_prev(&mut self) {
         if self.history.is_empty() {
@@ -238,8 +1282,54 @@ impl App {
         }
     }
 
+    /// Whether the open script file has unsaved changes. A scratch buffer
+    /// (no file) is never considered modified.
+    pub fn is_modified(&self) -> bool {
+        self.file
+            .as_ref()
+            .is_some_and(|f| f.saved != self.get_editor_text())
+    }
+
+    /// Load a script file into the editor.
+    pub fn open_file(&mut self, path: &Path) -> std::io::Result<()> {
+        let text = scripts::read(path)?;
+        self.set_editor_text(&text);
+        self.file = Some(OpenFile {
+            path: path.to_path_buf(),
+            saved: self.get_editor_text(),
+        });
+        self.focus = FocusPane::Editor;
+        Ok(())
+    }
+
+    /// Save the editor to `path`, or to the open file if `None`. The saved
+    /// path becomes the open file.
+    pub fn save_file(&mut self, path: Option<&Path>) -> std::io::Result<PathBuf> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => self
+                .file
+                .as_ref()
+                .map(|f| f.path.clone())
+                .ok_or_else(|| std::io::Error::other("no file to save to"))?,
+        };
+        let text = self.get_editor_text();
+        scripts::write(&path, &text)?;
+        self.file = Some(OpenFile {
+            path: path.clone(),
+            saved: text,
+        });
+        Ok(path)
+    }
+
+    /// Switch editor key bindings.
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+        self.vim = (keymap == Keymap::Vim).then(Vim::default);
+    }
+
     /// Set editor text content.
-    fn set_editor_text(&mut self, text: &str) {
+    pub fn set_editor_text(&mut self, text: &str) {
         let lines: Vec<String> = text.lines().map(|l| l.to_string()).collect();
         let lines = if lines.is_empty() {
             vec!["".to_string()]
@@ -247,11 +1337,7 @@ impl App {
             lines
         };
         self.editor = tui_textarea::TextArea::new(lines);
-        self.editor
-            .set_cursor_line_style(ratatui::style::Style::default());
-        self.editor.set_line_number_style(
-            ratatui::style::Style::default().fg(ratatui::style::Color::DarkGray),
-        );
+        style_editor(&mut self.editor, &self.theme);
     }
 
     /// Scroll results down.
@@ -262,6 +1348,30 @@ impl App {
         }
     }
 
+    /// Scroll results to `row` (from 0), clamped to the last row.
+    pub fn go_to_row(&mut self, row: usize) {
+        let row_count = self.result.rows_for(self.current_result_set).len();
+        self.result_scroll = row.min(row_count.saturating_sub(1));
+    }
+
+    /// Rows the results grid shows at once, from the last draw (less the
+    /// header row).
+    pub fn results_page_rows(&self) -> usize {
+        (self.results_viewport.get().1 as usize)
+            .saturating_sub(1)
+            .max(1)
+    }
+
+    /// Scroll results down one page.
+    pub fn page_results_down(&mut self) {
+        self.go_to_row(self.result_scroll + self.results_page_rows());
+    }
+
+    /// Scroll results up one page.
+    pub fn page_results_up(&mut self) {
+        self.result_scroll = self.result_scroll.saturating_sub(self.results_page_rows());
+    }
+
     /// Scroll results up.
     pub fn scroll_results_up(&mut self) {
         self.result_scroll = self.result_scroll.saturating_sub(1);
@@ -270,14 +1380,393 @@ impl App {
     /// Scroll results right (horizontal).
     pub fn scroll_results_right(&mut self) {
         let col_count = self.result.columns_for(self.current_result_set).len();
-        if col_count > 0 && self.result_col_scroll + 1 < col_count {
-            self.result_col_scroll += 1;
+        let start = self.result_col_scroll.max(self.pinned_columns);
+        if col_count > 0 && start + 1 < col_count {
+            self.result_col_scroll = start + 1;
         }
     }
 
-    /// Scroll results left (horizontal).
+    /// Scroll results left (horizontal), stopping at the pinned columns.
     pub fn scroll_results_left(&mut self) {
-        self.result_col_scroll = self.result_col_scroll.saturating_sub(1);
+        self.result_col_scroll = self
+            .result_col_scroll
+            .saturating_sub(1)
+            .max(self.pinned_columns);
+    }
+
+    /// Scroll results right by a screenful of columns, up to the last one.
+    pub fn page_results_right(&mut self) {
+        let col_count = self.result.columns_for(self.current_result_set).len();
+        let start = self.result_col_scroll.max(self.pinned_columns);
+        let page = self.results_visible_columns.get().max(1);
+        self.result_col_scroll = (start + page)
+            .min(col_count.saturating_sub(1))
+            .max(self.pinned_columns);
+    }
+
+    /// Scroll results left by a screenful of columns.
+    pub fn page_results_left(&mut self) {
+        let page = self.results_visible_columns.get().max(1);
+        self.result_col_scroll = self
+            .result_col_scroll
+            .saturating_sub(page)
+            .max(self.pinned_columns);
+    }
+
+    /// Scroll results to the first (unpinned) or last column.
+    pub fn results_column_edge(&mut self, last: bool) {
+        let col_count = self.result.columns_for(self.current_result_set).len();
+        self.result_col_scroll = if last {
+            col_count.saturating_sub(1).max(self.pinned_columns)
+        } else {
+            self.pinned_columns
+        };
+    }
+
+    /// The result column shown at display position `position`.
+    pub fn column_at(&self, position: usize) -> usize {
+        self.column_order.get(position).copied().unwrap_or(position)
+    }
+
+    /// Move the focused column one place left or right in the display
+    /// order, keeping the focus on it. It doesn't move into the pinned
+    /// columns, where it couldn't keep the focus.
+    pub fn move_focused_column(&mut self, right: bool) {
+        let col_count = self.result.columns_for(self.current_result_set).len();
+        let position = self.result_col_scroll.max(self.pinned_columns);
+        let target = if right {
+            position + 1
+        } else {
+            match position.checked_sub(1) {
+                Some(target) => target,
+                None => return,
+            }
+        };
+        if target >= col_count || target < self.pinned_columns {
+            return;
+        }
+        if self.column_order.len() != col_count {
+            self.column_order = (0..col_count).collect();
+        }
+        self.column_order.swap(position, target);
+        self.result_col_scroll = target;
+    }
+
+    /// Whether `result` has the columns shown in the grid, so the display
+    /// order applies to it too, as when `\watch` runs the query again.
+    fn has_shown_columns(&self, result: &QueryResult) -> bool {
+        self.current_result_set == 0 && result.columns_for(0) == self.result.columns_for(0)
+    }
+
+    /// `result` with its first result set's columns in the grid's display
+    /// order when they are the columns shown, for copying it with `\o`.
+    pub fn in_display_order<'a>(&self, result: &'a QueryResult) -> Cow<'a, QueryResult> {
+        if self.column_order.is_empty() || !self.has_shown_columns(result) {
+            return Cow::Borrowed(result);
+        }
+        let order = &self.column_order;
+        let mut result = result.clone();
+        if let Some(rs) = result.result_sets.first_mut() {
+            let pick = |values: &[String]| -> Vec<String> {
+                order
+                    .iter()
+                    .filter_map(|&i| values.get(i).cloned())
+                    .collect()
+            };
+            rs.columns = pick(&rs.columns);
+            rs.column_types = pick(&rs.column_types);
+            rs.rows = rs.rows.iter().map(|row| pick(row)).collect();
+            rs.nulls = rs
+                .nulls
+                .iter()
+                .filter_map(|&(r, c)| Some((r, order.iter().position(|&i| i == c)?)))
+                .collect();
+        }
+        Cow::Owned(result)
+    }
+
+    /// Pin the first `count` result columns (0 unpins).
+    pub fn set_pinned_columns(&mut self, count: usize) {
+        self.pinned_columns = count;
+        self.result_col_scroll = self.result_col_scroll.max(count);
+    }
+
+    /// Scroll expanded-mode results down one line, given the total line count.
+    pub fn scroll_expanded_down(&mut self, total_lines: usize) {
+        if self.result_scroll + 1 < total_lines {
+            self.result_scroll += 1;
+        }
+    }
+
+    /// Jump to the start of the next expanded-mode record.
+    ///
+    /// `record_starts` holds the first line of each record as laid out by
+    /// the results pane.
+    pub fn next_record(&mut self, record_starts: &[usize]) {
+        let current = record_at_line(record_starts, self.result_scroll);
+        if let Some(&start) = record_starts.get(current + 1) {
+            self.result_scroll = start;
+        }
+    }
+
+    /// Jump to the start of the current record, or the previous one if
+    /// already at a record boundary.
+    pub fn prev_record(&mut self, record_starts: &[usize]) {
+        let current = record_at_line(record_starts, self.result_scroll);
+        let target = match record_starts.get(current) {
+            Some(&start) if start < self.result_scroll => current,
+            _ => current.saturating_sub(1),
+        };
+        if let Some(&start) = record_starts.get(target) {
+            self.result_scroll = start;
+        }
+    }
+
+    /// The focused results cell as (row, column): the top visible row and
+    /// the first scrolled (unpinned) column.
+    pub fn focused_cell(&self) -> Option<(usize, usize)> {
+        let columns = self.result.columns_for(self.current_result_set);
+        let rows = self.result.rows_for(self.current_result_set);
+        let position = self.result_col_scroll.max(self.pinned_columns);
+        (self.result_scroll < rows.len() && position < columns.len())
+            .then(|| (self.result_scroll, self.column_at(position)))
+    }
+
+    /// Open the value inspector on the focused results cell.
+    pub fn inspect_focused_cell(&mut self) {
+        let Some((row, col)) = self.focused_cell() else {
+            return;
+        };
+        let rs_idx = self.current_result_set;
+        let column = &self.result.columns_for(rs_idx)[col];
+        let column_type = self
+            .result
+            .column_types_for(rs_idx)
+            .get(col)
+            .map(|s| s.as_str())
+            .unwrap_or("");
+        let value = self.result.rows_for(rs_idx)[row]
+            .get(col)
+            .map(|s| s.as_str())
+            .unwrap_or("");
+        self.inspector = Some(Inspector::for_value(column, column_type, value));
+    }
+
+    /// Insert the focused cell's value at the editor cursor and focus the
+    /// editor, e.g. a generated statement from `\frag`.
+    pub fn focused_cell_to_editor(&mut self) {
+        let Some((row, col)) = self.focused_cell() else {
+            return;
+        };
+        let Some(value) = self.result.value(self.current_result_set, row, col) else {
+            return;
+        };
+        if value.is_empty() {
+            return;
+        }
+        // One per line, so statements picked from several rows stack up
+        self.editor.insert_str(format!("{}\n", value));
+        self.focus = FocusPane::Editor;
+    }
+
+    /// Whether a cell of the current result set matches the `/` search.
+    pub fn cell_matches(&self, row: usize, col: usize) -> bool {
+        self.result_search.as_ref().is_some_and(|search| {
+            self.result
+                .value(self.current_result_set, row, col)
+                .is_some_and(|value| contains_lowercase(value, &search.pattern))
+        })
+    }
+
+    /// Search the results for `pattern` (case-insensitive) from the
+    /// focused cell on. An empty pattern clears the search.
+    pub fn search_results(&mut self, pattern: &str) {
+        if pattern.is_empty() {
+            self.result_search = None;
+            return;
+        }
+        self.result_search = Some(ResultSearch {
+            pattern: pattern.to_lowercase(),
+            current: None,
+        });
+        self.next_result_match(true);
+    }
+
+    /// Jump to the next (or previous) match of the `/` search, wrapping
+    /// around, and scroll it into view. Shows which match it is.
+    pub fn next_result_match(&mut self, forward: bool) {
+        let Some(search) = &self.result_search else {
+            return;
+        };
+        let rs_idx = self.current_result_set;
+        let rows = self.result.rows_for(rs_idx).len();
+        let cols = self.result.columns_for(rs_idx).len();
+        let cells = rows * cols;
+        // Cells in display order: row by row, columns as shown
+        let matches: Vec<usize> = (0..cells)
+            .filter(|&i| self.cell_matches(i / cols.max(1), self.column_at(i % cols.max(1))))
+            .collect();
+        if matches.is_empty() {
+            self.notice = Some(format!("No match for {}", search.pattern));
+            return;
+        }
+        // Start after the last match, or just before the focused cell so a
+        // match there is found first
+        let focused = self
+            .focused_cell()
+            .map(|(r, _)| (r, self.result_col_scroll.max(self.pinned_columns)));
+        let from = match (search.current, focused) {
+            (Some((r, c)), _) => Some(r * cols + c),
+            (None, Some((r, c))) => (r * cols + c).checked_sub(1),
+            (None, None) => None,
+        };
+        let index = match (forward, from) {
+            (true, Some(from)) => matches.iter().position(|&m| m > from).unwrap_or(0),
+            (true, None) => 0,
+            (false, Some(from)) => matches
+                .iter()
+                .rposition(|&m| m < from)
+                .unwrap_or(matches.len() - 1),
+            (false, None) => matches.len() - 1,
+        };
+        let (row, col) = (matches[index] / cols, matches[index] % cols);
+        self.result_scroll = row;
+        if col >= self.pinned_columns {
+            self.result_col_scroll = col;
+        }
+        if let Some(search) = &mut self.result_search {
+            search.current = Some((row, col));
+        }
+        self.notice = Some(format!("Match {} of {}", index + 1, matches.len()));
+    }
+
+    /// Mark or unmark the focused results row and move to the next one.
+    pub fn toggle_marked_row(&mut self) {
+        let Some((row, _)) = self.focused_cell() else {
+            return;
+        };
+        if !self.marked_rows.remove(&row) {
+            self.marked_rows.insert(row);
+        }
+        self.scroll_results_down();
+    }
+
+    /// INSERT statements into `table` for the marked rows, or the focused
+    /// row if none are marked, one per line. `table`'s computed and
+    /// `rowversion` columns are left out, and an identity column is written
+    /// between `SET IDENTITY_INSERT` on and off.
+    pub fn insert_statements(&self, table: &str, table_columns: &InsertColumns) -> Option<String> {
+        let rs_idx = self.current_result_set;
+        let rows = self.result.cells_for(rs_idx);
+        let columns = self.result.columns_for(rs_idx);
+        let types = self.result.column_types_for(rs_idx);
+        let picked: Vec<usize> = if self.marked_rows.is_empty() {
+            vec![self.focused_cell()?.0]
+        } else {
+            self.marked_rows
+                .iter()
+                .copied()
+                .filter(|&r| r < rows.len())
+                .collect()
+        };
+        // Columns in display order, as reordered in the grid
+        let included: Vec<usize> = (0..columns.len())
+            .map(|p| self.column_at(p))
+            .filter(|&i| {
+                !table_columns
+                    .generated
+                    .iter()
+                    .any(|g| g.eq_ignore_ascii_case(&columns[i]))
+            })
+            .collect();
+        let mut statements: Vec<String> = picked
+            .iter()
+            .map(|&r| {
+                let values: Vec<(&str, String)> = included
+                    .iter()
+                    .map(|&i| {
+                        let ty = types.get(i).map(String::as_str).unwrap_or("");
+                        let value = rows[r].get(i).copied().flatten();
+                        (columns[i].as_str(), dml::literal(value, ty))
+                    })
+                    .collect();
+                dml::insert(table, &values)
+            })
+            .collect();
+        if let Some(ref identity) = table_columns.identity
+            && included
+                .iter()
+                .any(|&i| columns[i].eq_ignore_ascii_case(identity))
+        {
+            statements.insert(0, format!("SET IDENTITY_INSERT {} ON;", table));
+            statements.push(format!("SET IDENTITY_INSERT {} OFF;", table));
+        }
+        Some(statements.join("\n"))
+    }
+
+    /// Start editing the focused cell of a result read from `table`, whose
+    /// primary key is `key_columns`. `source_columns` is the table column
+    /// each result column reads (`None` for expressions), as from
+    /// [`crate::db::query::fetch_source_columns`]. Fails unless the cell is a
+    /// table column under its own name and every key column is in the
+    /// result.
+    pub fn cell_edit(
+        &self,
+        table: &str,
+        key_columns: &[String],
+        source_columns: &[Option<String>],
+    ) -> Result<CellEdit, String> {
+        let (row, col) = self.focused_cell().ok_or("No cell to edit")?;
+        let rs_idx = self.current_result_set;
+        let columns = self.result.columns_for(rs_idx);
+        let types = self.result.column_types_for(rs_idx);
+        let source = |i: usize| source_columns.get(i).and_then(Option::as_deref);
+        if !source(col).is_some_and(|s| s.eq_ignore_ascii_case(&columns[col])) {
+            return Err(format!(
+                "{} is not a column of {}; aliases and expressions can't be edited",
+                columns[col], table
+            ));
+        }
+        let key = key_columns
+            .iter()
+            .map(|name| {
+                let i = (0..columns.len())
+                    .find(|&i| source(i).is_some_and(|s| s.eq_ignore_ascii_case(name)))
+                    .ok_or_else(|| format!("Primary key column {} is not in the result", name))?;
+                let ty = types.get(i).map(String::as_str).unwrap_or("");
+                let value = self.result.value(rs_idx, row, i);
+                Ok((name.clone(), dml::literal(value, ty)))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(CellEdit {
+            table: table.to_string(),
+            result_set: rs_idx,
+            row,
+            col,
+            key,
+        })
+    }
+
+    /// The UPDATE that sets the edited cell to `value` (`None` for NULL).
+    pub fn cell_update(&self, edit: &CellEdit, value: Option<&str>) -> String {
+        let column = &self.result.columns_for(edit.result_set)[edit.col];
+        let ty = self
+            .result
+            .column_types_for(edit.result_set)
+            .get(edit.col)
+            .map(String::as_str)
+            .unwrap_or("");
+        dml::update(
+            &edit.table,
+            &[(column.as_str(), dml::literal(value, ty))],
+            &key_pairs(&edit.key),
+        )
+    }
+
+    /// The SELECT that re-reads the edited cell.
+    pub fn cell_refresh(&self, edit: &CellEdit) -> String {
+        let column = &self.result.columns_for(edit.result_set)[edit.col];
+        dml::select(&edit.table, &[column.as_str()], &key_pairs(&edit.key))
     }
 
     /// Scroll sidebar down.
@@ -296,6 +1785,8 @@ impl App {
             self.current_result_set += 1;
             self.result_scroll = 0;
             self.result_col_scroll = 0;
+            self.marked_rows.clear();
+            self.column_order.clear();
         }
     }
 
@@ -305,20 +1796,323 @@ impl App {
             self.current_result_set -= 1;
             self.result_scroll = 0;
             self.result_col_scroll = 0;
+            self.marked_rows.clear();
+            self.column_order.clear();
         }
     }
 
-    /// Toggle expand/collapse on the selected sidebar node.
+    /// Handle Enter in the sidebar: toggle the node, and on a second Enter
+    /// within half a second also insert its name into the editor.
+    pub fn sidebar_enter(&mut self) {
+        let now = Instant::now();
+        let double = self.last_sidebar_enter.is_some_and(|(row, at)| {
+            row == self.sidebar_scroll && now.duration_since(at) < Duration::from_millis(500)
+        });
+        if double {
+            // The second toggle undoes the first
+            self.last_sidebar_enter = None;
+            self.toggle_sidebar_node();
+            self.insert_sidebar_name();
+        } else {
+            self.last_sidebar_enter = Some((self.sidebar_scroll, now));
+            self.toggle_sidebar_node();
+        }
+    }
+
+    /// Toggle expand/collapse on the selected sidebar node. Expanding a node
+    /// whose children haven't been fetched shows a placeholder and queues the
+    /// load for [`App::load_pending_node`].
     pub fn toggle_sidebar_node(&mut self) {
-        if let Some(node) = get_flat_node_mut(&mut self.objects, self.sidebar_scroll) {
-            node.expanded = !node.expanded;
+        let Some(path) = flat_path(&self.objects, self.sidebar_scroll) else {
+            return;
+        };
+        let Some(node) = node_at_mut(&mut self.objects, &path) else {
+            return;
+        };
+        node.expanded = !node.expanded;
+        if node.expanded && !node.loaded && !self.pending_loads.contains(&path) {
+            node.children = vec![ObjectNode::new(
+                "loading…",
+                NodeKind::Detail,
+                node.depth + 1,
+            )];
+            self.pending_loads.push_back(path);
+        }
+    }
+
+    /// Fetch the children of the oldest node queued by
+    /// [`App::toggle_sidebar_node`]: schemas and object folders for a
+    /// database; columns, indexes, and keys for a table or view. A node that
+    /// fails to load shows the error as its child and stays unloaded, so
+    /// expanding it again retries.
+    pub async fn load_pending_node(&mut self, client: &mut db::ConnectionHandle) {
+        let Some(path) = self.pending_loads.pop_front() else {
+            return;
+        };
+        let names: Vec<String> = (1..=path.len())
+            .filter_map(|n| node_at_mut(&mut self.objects, &path[..n]).map(|n| n.name.clone()))
+            .collect();
+        // The tree may have been reloaded since the node was queued
+        let Some(mut node) = node_at_mut(&mut self.objects, &path)
+            .filter(|n| !n.loaded)
+            .cloned()
+        else {
+            return;
+        };
+        let loaded = match (node.kind, names.as_slice()) {
+            (NodeKind::Database, _) => db::query::load_database(client, &mut node).await,
+            (NodeKind::Table, [database, schema, table]) => {
+                db::query::load_table_details(client, database, schema, table, &mut node).await
+            }
+            (NodeKind::View, [database, ..]) => match node.name.clone().split_once('.') {
+                Some((schema, view)) => {
+                    db::query::load_table_details(client, database, schema, view, &mut node).await
+                }
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        };
+        match loaded {
+            Ok(()) => node.loaded = true,
+            Err(e) => {
+                node.children = vec![ObjectNode::new(
+                    format!("failed: {}", e),
+                    NodeKind::Detail,
+                    node.depth + 1,
+                )];
+                self.set_status(format!("Failed to load {}: {}", node.name, e));
+            }
+        }
+        if let Some(target) = node_at_mut(&mut self.objects, &path) {
+            *target = node;
+        }
+    }
+
+    /// Widen (positive `delta`) or narrow the sidebar and save the new width
+    /// to the config file.
+    pub fn resize_sidebar(&mut self, delta: i16) {
+        let width = self
+            .sidebar_width
+            .saturating_add_signed(delta)
+            .clamp(SIDEBAR_MIN_WIDTH, SIDEBAR_MAX_WIDTH);
+        if width == self.sidebar_width {
+            return;
+        }
+        self.sidebar_width = width;
+        if let Err(e) = config::save_setting("sidebar_width", &width.to_string()) {
+            self.set_status(format!("Could not save sidebar width: {}", e));
+        }
+    }
+
+    /// Show or hide row counts and sizes next to sidebar tables.
+    pub fn toggle_sidebar_stats(&mut self) {
+        self.sidebar_stats = !self.sidebar_stats;
+        if self.sidebar_stats {
+            self.set_status("Loading table row counts and sizes");
+        } else {
+            self.stats_loaded.clear();
+            for node in self.objects.iter_mut() {
+                clear_table_info(node);
+            }
+            self.set_status("Table stats hidden");
+        }
+    }
+
+    /// The next expanded database whose table stats are wanted but not yet
+    /// fetched.
+    pub fn database_needing_stats(&self) -> Option<String> {
+        if !self.sidebar_stats {
+            return None;
+        }
+        self.objects
+            .iter()
+            .find(|db| db.loaded && !self.stats_loaded.contains(&db.name))
+            .map(|db| db.name.clone())
+    }
+
+    /// Fetch row counts and sizes for `database`'s tables and show them as
+    /// the table nodes' info. A failure is reported once and not retried.
+    pub async fn load_table_stats(&mut self, client: &mut db::ConnectionHandle, database: &str) {
+        self.stats_loaded.insert(database.to_string());
+        let stats = match db::query::fetch_table_stats(client, database).await {
+            Ok(stats) => stats,
+            Err(e) => {
+                self.set_status(format!(
+                    "Could not load table stats for {}: {}",
+                    database, e
+                ));
+                return;
+            }
+        };
+        let Some(db_node) = self.objects.iter_mut().find(|n| n.name == database) else {
+            return;
+        };
+        for schema in db_node
+            .children
+            .iter_mut()
+            .filter(|n| n.kind == NodeKind::Schema)
+        {
+            for table in schema.children.iter_mut() {
+                if let Some(&(rows, kb)) = stats.get(&(schema.name.clone(), table.name.clone())) {
+                    table.info = Some(format_table_stats(rows, kb));
+                }
+            }
+        }
+    }
+
+    /// SQL describing the selected sidebar node with `sp_help`, if it is a
+    /// table, view, procedure, function, or trigger.
+    pub fn describe_sidebar_node(&self) -> Option<String> {
+        let (database, object) = self.sidebar_object()?;
+        Some(format!(
+            "EXEC {}.sys.sp_help N'{}'",
+            quote_ident(&database),
+            object.local().replace('\'', "''")
+        ))
+    }
+
+    /// The database and name of the selected sidebar node, if it is a
+    /// table, view, procedure, function, or trigger.
+    pub fn sidebar_object(&self) -> Option<(String, ObjectName)> {
+        let chain = self.sidebar_chain()?;
+        let node = *chain.last()?;
+        if !node.kind.is_object() {
+            return None;
+        }
+        let object = ObjectName {
+            database: None,
+            schema: chain
+                .iter()
+                .find(|n| n.kind == NodeKind::Schema)
+                .map(|schema| schema.name.clone()),
+            name: node.name.clone(),
+        };
+        Some((chain[0].name.clone(), object))
+    }
+
+    /// Bracket-quoted name of the selected sidebar node for use in SQL:
+    /// `[schema].[name]` for tables, views, and other objects, and just
+    /// `[name]` for columns, databases, and schemas.
+    pub fn sidebar_sql_name(&self) -> Option<String> {
+        let chain = self.sidebar_chain()?;
+        let node = *chain.last()?;
+        match node.kind {
+            NodeKind::Database | NodeKind::Schema => Some(quote_ident(&node.name)),
+            NodeKind::Table => {
+                let schema = chain.iter().find(|n| n.kind == NodeKind::Schema)?;
+                Some(format!(
+                    "{}.{}",
+                    quote_ident(&schema.name),
+                    quote_ident(&node.name)
+                ))
+            }
+            // Labelled `schema.name` in their folders
+            NodeKind::View | NodeKind::Procedure | NodeKind::Function | NodeKind::Trigger => {
+                let (schema, name) = node.name.split_once('.')?;
+                Some(format!("{}.{}", quote_ident(schema), quote_ident(name)))
+            }
+            NodeKind::Detail => {
+                let folder = chain.get(chain.len().checked_sub(2)?)?;
+                folder
+                    .name
+                    .starts_with("Columns")
+                    .then(|| quote_ident(&node.name))
+            }
+            NodeKind::Folder => None,
+        }
+    }
+
+    /// Insert the selected sidebar node's SQL name at the editor cursor.
+    pub fn insert_sidebar_name(&mut self) {
+        match self.sidebar_sql_name() {
+            Some(name) => {
+                self.editor.insert_str(&name);
+                self.set_status(format!("Inserted {}", name));
+            }
+            None => self.set_status("Nothing to insert for this node"),
+        }
+    }
+
+    /// The selected sidebar node and its ancestors, from the database down.
+    fn sidebar_chain(&self) -> Option<Vec<&ObjectNode>> {
+        let path = flat_path(&self.objects, self.sidebar_scroll)?;
+        let mut nodes = &self.objects;
+        let mut chain = Vec::with_capacity(path.len());
+        for &i in &path {
+            let node = nodes.get(i)?;
+            chain.push(node);
+            nodes = &node.children;
+        }
+        Some(chain)
+    }
+
+    /// Update autocomplete for the editor cursor, first fetching the columns
+    /// of any in-scope tables that haven't been seen yet.
+    pub async fn update_completions(&mut self, client: &mut db::ConnectionHandle) {
+        let (row, col) = self.editor.cursor();
+        let lines: Vec<String> = self.editor.lines().to_vec();
+        let tables = match autocomplete::context(&lines, row, col) {
+            autocomplete::CompletionContext::Column { tables, .. } => tables,
+            autocomplete::CompletionContext::JoinOn { tables, joined, .. } => {
+                let key = autocomplete::table_key(&joined.0);
+                if let Entry::Vacant(entry) = self.autocomplete.foreign_keys.entry(key) {
+                    let keys = db::query::fetch_foreign_keys(client, &joined.0)
+                        .await
+                        .unwrap_or_default();
+                    entry.insert(keys);
+                }
+                tables
+            }
+            _ => Vec::new(),
+        };
+        for table in tables {
+            let key = autocomplete::table_key(&table);
+            if let Entry::Vacant(entry) = self.autocomplete.columns.entry(key) {
+                // Cached even when empty, so a bad name is only tried once
+                let columns = db::query::fetch_columns(client, &table)
+                    .await
+                    .unwrap_or_default();
+                entry.insert(columns);
+            }
+        }
+        self.autocomplete.update(&lines, row, col);
+    }
+
+    /// Update the parameter hint for the editor cursor, fetching the
+    /// procedure's parameters the first time it is seen.
+    pub async fn update_signature(&mut self, client: &mut db::ConnectionHandle) {
+        let (row, col) = self.editor.cursor();
+        let lines = self.editor.lines();
+        let mut before: String = lines[..row].iter().map(|l| format!("{}\n", l)).collect();
+        before.extend(lines[row].chars().take(col));
+
+        let Some((proc, arg)) = signature::exec_context(&before) else {
+            self.signature = None;
+            return;
+        };
+        if !self.proc_params.contains_key(&proc) {
+            // Unknown procedures and errors are cached as empty too, so a
+            // bad name doesn't query on every keystroke
+            let params = db::query::fetch_proc_params(client, &proc)
+                .await
+                .unwrap_or_default();
+            self.proc_params.insert(proc.clone(), params);
         }
+        let params = self.proc_params[&proc].clone();
+        self.signature = (!params.is_empty()).then_some(SignatureHint { proc, params, arg });
     }
 
     /// Build the object tree from a database connection.
     pub async fn load_objects(&mut self, client: &mut db::ConnectionHandle) {
         match db::query::fetch_object_tree(client).await {
-            Ok(objects) => self.objects = objects,
+            Ok(objects) => {
+                let procedures = db::query::fetch_procedures(client)
+                    .await
+                    .unwrap_or_default();
+                self.autocomplete
+                    .set_objects(table_names(&objects), procedures);
+                self.objects = objects;
+            }
             Err(e) => {
                 self.result.error = Some(format!("Failed to load objects: {}", e));
             }
@@ -326,48 +2120,541 @@ impl App {
     }
 }
 
-/// Get a mutable reference to the node at the given flat index in the tree.
-fn get_flat_node_mut(nodes: &mut [ObjectNode], target: usize) -> Option<&mut ObjectNode> {
+/// Index of the expanded-mode record containing the given line.
+pub fn record_at_line(record_starts: &[usize], line: usize) -> usize {
+    record_starts
+        .partition_point(|&start| start <= line)
+        .saturating_sub(1)
+}
+
+/// Compact "rows, size" text for a table, e.g. `12.5K rows, 3.2 MB`.
+fn format_table_stats(rows: u64, kb: u64) -> String {
+    let scaled = |n: f64, units: &[&str]| {
+        let mut n = n;
+        let mut unit = 0;
+        while n >= 1000.0 && unit + 1 < units.len() {
+            n /= 1000.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            format!("{}{}", n, units[0])
+        } else {
+            format!("{:.1}{}", n, units[unit])
+        }
+    };
+    format!(
+        "{} rows, {}",
+        scaled(rows as f64, &["", "K", "M", "B"]),
+        scaled(kb as f64, &[" KB", " MB", " GB", " TB"])
+    )
+}
+
+/// Remove stats annotations from tables under `node`.
+fn clear_table_info(node: &mut ObjectNode) {
+    if node.kind == NodeKind::Table {
+        node.info = None;
+    }
+    for child in node.children.iter_mut() {
+        clear_table_info(child);
+    }
+}
+
+/// Whether `text` contains `needle` (already lowercased), ignoring case,
+/// without lowercasing a copy of `text`.
+fn contains_lowercase(text: &str, needle: &str) -> bool {
+    needle.is_empty()
+        || text.char_indices().any(|(i, _)| {
+            let mut rest = needle.chars();
+            text[i..]
+                .chars()
+                .flat_map(char::to_lowercase)
+                .zip(rest.by_ref())
+                .all(|(a, b)| a == b)
+                && rest.next().is_none()
+        })
+}
+
+/// Path (child indexes from the root) of the node at the given flat index
+/// in the visible tree.
+fn flat_path(nodes: &[ObjectNode], target: usize) -> Option<Vec<usize>> {
     let mut idx = 0;
-    get_flat_node_mut_inner(nodes, target, &mut idx)
+    let mut path = Vec::new();
+    flat_path_inner(nodes, target, &mut idx, &mut path).then_some(path)
 }
 
-fn get_flat_node_mut_inner<'a>(
-    nodes: &'a mut [ObjectNode],
+fn flat_path_inner(
+    nodes: &[ObjectNode],
     target: usize,
     idx: &mut usize,
-) -> Option<&'a mut ObjectNode> {
-    for node in nodes.iter_mut() {
+    path: &mut Vec<usize>,
+) -> bool {
+    for (i, node) in nodes.iter().enumerate() {
+        path.push(i);
         if *idx == target {
-            return Some(node);
+            return true;
         }
         *idx += 1;
-        if node.expanded
-            && let Some(found) = get_flat_node_mut_inner(&mut node.children, target, idx)
-        {
-            return Some(found);
+        if node.expanded && flat_path_inner(&node.children, target, idx, path) {
+            return true;
         }
+        path.pop();
+    }
+    false
+}
+
+/// The node at `path`, if it still exists.
+fn node_at_mut<'a>(nodes: &'a mut [ObjectNode], path: &[usize]) -> Option<&'a mut ObjectNode> {
+    let (first, rest) = path.split_first()?;
+    let node = nodes.get_mut(*first)?;
+    if rest.is_empty() {
+        Some(node)
+    } else {
+        node_at_mut(&mut node.children, rest)
     }
-    None
 }
 
-/// Flatten the object tree for display, returning (depth, name, expanded, has_children).
-pub fn flatten_tree(nodes: &[ObjectNode]) -> Vec<(u8, String, bool, bool)> {
+/// Names of all loaded tables, for autocomplete.
+fn table_names(nodes: &[ObjectNode]) -> Vec<String> {
+    let mut out = Vec::new();
+    for node in nodes {
+        if node.kind == NodeKind::Table {
+            out.push(node.name.clone());
+        }
+        out.extend(table_names(&node.children));
+    }
+    out
+}
+
+/// Flatten the object tree for display: every node whose ancestors are all
+/// expanded, in display order.
+pub fn flatten_tree(nodes: &[ObjectNode]) -> Vec<&ObjectNode> {
     let mut out = Vec::new();
     flatten_tree_inner(nodes, &mut out);
     out
 }
 
-fn flatten_tree_inner(nodes: &[ObjectNode], out: &mut Vec<(u8, String, bool, bool)>) {
+fn flatten_tree_inner<'a>(nodes: &'a [ObjectNode], out: &mut Vec<&'a ObjectNode>) {
     for node in nodes {
-        out.push((
-            node.depth,
-            node.name.clone(),
-            node.expanded,
-            !node.children.is_empty(),
-        ));
+        out.push(node);
         if node.expanded {
             flatten_tree_inner(&node.children, out);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_with_databases(names: &[&str]) -> App {
+        let mut app = App::new("localhost", 1433, "master", "sa");
+        app.objects = names
+            .iter()
+            .map(|name| ObjectNode::new(*name, NodeKind::Database, 0))
+            .collect();
+        app
+    }
+
+    #[test]
+    fn test_toggle_sidebar_node_queues_loads() {
+        let mut app = app_with_databases(&["a", "b"]);
+        app.sidebar_scroll = 0;
+        app.toggle_sidebar_node();
+        // `a` now shows its placeholder, so `b` is the third visible row
+        app.sidebar_scroll = 2;
+        app.toggle_sidebar_node();
+        assert_eq!(app.pending_loads, VecDeque::from([vec![0], vec![1]]));
+        assert_eq!(app.objects[1].children[0].name, "loading…");
+
+        // Collapsing and expanding again doesn't queue it twice
+        app.toggle_sidebar_node();
+        app.toggle_sidebar_node();
+        assert_eq!(app.pending_loads.len(), 2);
+    }
+
+    #[test]
+    fn test_insert_statements() {
+        let mut app = app_with_databases(&[]);
+        let mut result = QueryResult::single(
+            ["id", "name", "total", "version"]
+                .iter()
+                .map(|c| c.to_string())
+                .collect(),
+            vec![
+                vec!["1", "O'Brien", "10", "0x01"],
+                vec!["2", "NULL", "20", "0x02"],
+            ]
+            .into_iter()
+            .map(|row| row.into_iter().map(str::to_string).collect())
+            .collect(),
+            0,
+        );
+        result.result_sets[0].column_types = ["int", "nvarchar", "int", "binary"]
+            .map(str::to_string)
+            .to_vec();
+        result.result_sets[0].nulls.insert((1, 1));
+        app.set_result(result);
+        app.marked_rows.extend([0, 1]);
+        app.column_order = vec![1, 0, 2, 3];
+
+        let columns = InsertColumns {
+            generated: vec!["Total".to_string(), "version".to_string()],
+            identity: Some("id".to_string()),
+        };
+        assert_eq!(
+            app.insert_statements("dbo.t", &columns).unwrap(),
+            "SET IDENTITY_INSERT dbo.t ON;\n\
+             INSERT INTO dbo.t ([name], [id]) VALUES (N'O''Brien', 1);\n\
+             INSERT INTO dbo.t ([name], [id]) VALUES (NULL, 2);\n\
+             SET IDENTITY_INSERT dbo.t OFF;"
+        );
+        assert_eq!(
+            app.insert_statements("t", &InsertColumns::default())
+                .unwrap()
+                .lines()
+                .next(),
+            Some(
+                "INSERT INTO t ([name], [id], [total], [version]) VALUES (N'O''Brien', 1, 10, 0x01);"
+            )
+        );
+    }
+
+    #[test]
+    fn test_move_focused_column() {
+        let mut app = app_with_databases(&[]);
+        let result = QueryResult::single(
+            ["a", "b", "c", "d"].map(str::to_string).to_vec(),
+            vec![["1", "NULL", "3", "4"].map(str::to_string).to_vec()],
+            0,
+        );
+        app.set_result(result.clone());
+        app.result.result_sets[0].nulls.insert((0, 1));
+        app.set_pinned_columns(1);
+        app.result_col_scroll = 2;
+
+        app.move_focused_column(false);
+        assert_eq!(app.column_order, vec![0, 2, 1, 3]);
+        assert_eq!(app.focused_cell(), Some((0, 2)));
+        // Not into the pinned column
+        app.move_focused_column(false);
+        assert_eq!(app.column_order, vec![0, 2, 1, 3]);
+        assert_eq!(app.focused_cell(), Some((0, 2)));
+        app.result_col_scroll = 3;
+        app.move_focused_column(true);
+        assert_eq!(app.column_order, vec![0, 2, 1, 3]);
+
+        let mut again = result.clone();
+        again.result_sets[0].nulls.insert((0, 1));
+        let shown = app.in_display_order(&again);
+        assert_eq!(shown.result_sets[0].columns, ["a", "c", "b", "d"]);
+        assert_eq!(shown.result_sets[0].rows[0], ["1", "3", "NULL", "4"]);
+        assert!(shown.result_sets[0].nulls.contains(&(0, 2)));
+        // The same columns again keep their order; others don't
+        app.set_result(again);
+        assert_eq!(app.column_order, vec![0, 2, 1, 3]);
+        let other = QueryResult::single(vec!["x".to_string()], Vec::new(), 0);
+        assert_eq!(app.in_display_order(&other).result_sets[0].columns, ["x"]);
+        app.set_result(other);
+        assert!(app.column_order.is_empty());
+    }
+
+    #[test]
+    fn test_switch_and_close_workspace() {
+        let mut app = app_with_databases(&[]);
+        app.set_editor_text("SELECT 1");
+        app.marked_rows.insert(0);
+        let index = app.add_workspace("tempdb", None);
+        app.switch_workspace(index);
+        assert_eq!(app.active_workspace, 1);
+        assert_eq!(app.get_editor_text(), "");
+        assert_eq!(app.current_database, "tempdb");
+        assert!(app.marked_rows.is_empty());
+
+        app.set_editor_text("SELECT 2");
+        app.switch_workspace(0);
+        assert_eq!(app.get_editor_text(), "SELECT 1");
+        assert_eq!(app.current_database, "master");
+
+        app.close_workspace();
+        assert_eq!(app.workspaces.len(), 1);
+        assert_eq!(app.active_workspace, 0);
+        assert_eq!(app.get_editor_text(), "SELECT 2");
+        assert_eq!(app.current_database, "tempdb");
+        app.close_workspace();
+        assert_eq!(app.workspaces.len(), 1);
+    }
+
+    #[test]
+    fn test_modified_workspace() {
+        let mut app = app_with_databases(&[]);
+        let index = app.add_workspace("master", None);
+        assert_eq!(app.modified_workspace(), None);
+
+        app.workspaces[index].file = Some(OpenFile {
+            path: PathBuf::from("a.sql"),
+            saved: "SELECT 1".to_string(),
+        });
+        assert_eq!(app.modified_workspace(), Some(index));
+
+        // The current workspace comes first
+        app.file = Some(OpenFile {
+            path: PathBuf::from("b.sql"),
+            saved: "SELECT 2".to_string(),
+        });
+        assert_eq!(app.modified_workspace(), Some(0));
+    }
+
+    #[test]
+    fn test_watch_finished_in_background() {
+        let mut app = app_with_databases(&[]);
+        let index = app.add_workspace("master", None);
+        app.workspaces[index].watch = Some(Watch {
+            query: "SELECT 1".to_string(),
+            interval: Duration::from_secs(60),
+            iterations: 0,
+            last_run: None,
+        });
+        app.inspector = Some(Inspector::for_definition("v", "SELECT 1"));
+        assert_eq!(app.background_watch_due(), Some(index));
+
+        let result = QueryResult::single(vec!["n".to_string()], vec![vec!["1".to_string()]], 0);
+        app.workspaces[index].watch_finished(result, None);
+        assert_eq!(app.background_watch_due(), None);
+        assert!(app.inspector.is_some());
+
+        app.switch_workspace(index);
+        assert_eq!(app.result.rows_for(0), [vec!["1".to_string()]]);
+        assert_eq!(app.watch.as_ref().map(|w| w.iterations), Some(1));
+    }
+
+    #[test]
+    fn test_result_search() {
+        let mut app = app_with_databases(&[]);
+        app.set_result(QueryResult::single(
+            vec!["a".to_string(), "b".to_string()],
+            vec![
+                vec!["Ünicode".to_string(), "x".to_string()],
+                vec!["y".to_string(), "ÜNI".to_string()],
+            ],
+            0,
+        ));
+        app.search_results("üni");
+        assert_eq!(app.result_search.as_ref().unwrap().current, Some((0, 0)));
+        assert!(app.cell_matches(1, 1) && !app.cell_matches(0, 1));
+        app.next_result_match(true);
+        assert_eq!(app.result_search.as_ref().unwrap().current, Some((1, 1)));
+        app.next_result_match(true);
+        assert_eq!(app.result_search.as_ref().unwrap().current, Some((0, 0)));
+        app.next_result_match(false);
+        assert_eq!(app.result_search.as_ref().unwrap().current, Some((1, 1)));
+
+        app.set_result(QueryResult::default());
+        assert!(app.result_search.is_none());
+    }
+
+    #[test]
+    fn test_contains_lowercase() {
+        assert!(contains_lowercase("Hello World", "o w"));
+        assert!(contains_lowercase("ÉCOLE", "éc"));
+        assert!(contains_lowercase("abc", ""));
+        assert!(!contains_lowercase("ab", "abc"));
+        assert!(!contains_lowercase("", "a"));
+    }
+
+    #[test]
+    fn test_cell_edit() {
+        let mut app = app_with_databases(&[]);
+        let mut result = QueryResult::single(
+            ["ID", "Name", "qty", "total"].map(str::to_string).to_vec(),
+            vec![
+                ["1", "a", "3", "6"].map(str::to_string).to_vec(),
+                ["2", "NULL", "4", "8"].map(str::to_string).to_vec(),
+            ],
+            0,
+        );
+        result.result_sets[0].column_types = ["int", "nvarchar", "int", "int"]
+            .map(str::to_string)
+            .to_vec();
+        app.set_result(result);
+        app.result_scroll = 1;
+        app.result_col_scroll = 1;
+        // SELECT ID, Name, price AS qty, qty * 2 AS total FROM dbo.t
+        let sources =
+            [Some("id"), Some("Name"), Some("price"), None].map(|s| s.map(str::to_string));
+        let key = ["id".to_string()];
+
+        assert_eq!(
+            app.cell_edit("dbo.t", &["id".to_string(), "region".to_string()], &sources),
+            Err("Primary key column region is not in the result".to_string())
+        );
+        let edit = app.cell_edit("dbo.t", &key, &sources).unwrap();
+        assert_eq!((edit.row, edit.col), (1, 1));
+        assert_eq!(edit.key, vec![("id".to_string(), "2".to_string())]);
+        assert_eq!(
+            app.cell_update(&edit, Some("O'Brien")),
+            "UPDATE dbo.t SET [Name] = N'O''Brien' WHERE [id] = 2;"
+        );
+        assert_eq!(
+            app.cell_update(&edit, None),
+            "UPDATE dbo.t SET [Name] = NULL WHERE [id] = 2;"
+        );
+        assert_eq!(
+            app.cell_refresh(&edit),
+            "SELECT [Name] FROM dbo.t WHERE [id] = 2;"
+        );
+
+        // Aliased and computed columns aren't the table column they're named
+        app.result_col_scroll = 2;
+        assert_eq!(
+            app.cell_edit("dbo.t", &key, &sources),
+            Err(
+                "qty is not a column of dbo.t; aliases and expressions can't be edited".to_string()
+            )
+        );
+        app.result_col_scroll = 3;
+        assert!(app.cell_edit("dbo.t", &key, &sources).is_err());
+        // Nor is anything the result couldn't be described for
+        app.result_col_scroll = 1;
+        assert!(app.cell_edit("dbo.t", &key, &[]).is_err());
+
+        app.set_result(QueryResult::default());
+        assert_eq!(
+            app.cell_edit("dbo.t", &key, &sources),
+            Err("No cell to edit".to_string())
+        );
+    }
+
+    #[test]
+    fn test_cell_text() {
+        for value in [None, Some("NULL"), Some(""), Some("x")] {
+            assert_eq!(parse_cell_text(&cell_text(value)), value);
+        }
+        assert_eq!(cell_text(Some("NULL")), "'NULL'");
+        assert_eq!(parse_cell_text("null"), Some("null"));
+    }
+
+    #[test]
+    fn test_header_label() {
+        let rs = ResultSet {
+            columns: ["id", "total", "note", "x"].map(str::to_string).to_vec(),
+            column_types: ["int", "decimal", "nvarchar", ""]
+                .map(str::to_string)
+                .to_vec(),
+            ..Default::default()
+        };
+        assert_eq!(rs.header_label(0), "id (int)");
+        assert_eq!(rs.header_label(1), "total (decimal)");
+        assert_eq!(rs.header_label(2), "note (nvarchar)");
+        // Unknown type
+        assert_eq!(rs.header_label(3), "x");
+        assert_eq!(rs.header_label(4), "");
+    }
+
+    #[test]
+    fn test_record_navigation() {
+        let starts = [0, 4, 9];
+        assert_eq!(record_at_line(&starts, 0), 0);
+        assert_eq!(record_at_line(&starts, 3), 0);
+        assert_eq!(record_at_line(&starts, 4), 1);
+        assert_eq!(record_at_line(&starts, 20), 2);
+        assert_eq!(record_at_line(&[], 5), 0);
+
+        let mut app = app_with_databases(&[]);
+        app.result_scroll = 2;
+        app.next_record(&starts);
+        assert_eq!(app.result_scroll, 4);
+        app.next_record(&starts);
+        app.next_record(&starts);
+        assert_eq!(app.result_scroll, 9);
+        // Mid-record goes to the record's start, then the one before
+        app.result_scroll = 6;
+        app.prev_record(&starts);
+        assert_eq!(app.result_scroll, 4);
+        app.prev_record(&starts);
+        assert_eq!(app.result_scroll, 0);
+        app.prev_record(&starts);
+        assert_eq!(app.result_scroll, 0);
+    }
+
+    #[test]
+    fn test_keep_result() {
+        let mut app = app_with_databases(&[]);
+        // Results of slash commands aren't kept
+        app.set_result(QueryResult::default());
+        app.keep_result();
+        assert!(app.previous_results.is_empty());
+
+        for n in 0..KEPT_RESULTS + 2 {
+            app.keep_result();
+            app.set_result(QueryResult {
+                sql: Some(format!("SELECT {}", n)),
+                ..Default::default()
+            });
+        }
+        let kept: Vec<_> = app
+            .previous_results
+            .iter()
+            .filter_map(|r| r.sql.as_deref())
+            .collect();
+        assert_eq!(
+            kept,
+            ["SELECT 5", "SELECT 4", "SELECT 3", "SELECT 2", "SELECT 1"]
+        );
+    }
+
+    #[test]
+    fn test_format_table_stats() {
+        assert_eq!(format_table_stats(512, 800), "512 rows, 800 KB");
+        assert_eq!(format_table_stats(1234, 2048), "1.2K rows, 2.0 MB");
+        assert_eq!(
+            format_table_stats(3_500_000_000, 5_000_000_000),
+            "3.5B rows, 5.0 TB"
+        );
+    }
+
+    #[test]
+    fn test_sidebar_sql_name() {
+        let expanded = |mut node: ObjectNode, children: Vec<ObjectNode>| {
+            node.expanded = true;
+            node.loaded = true;
+            node.children = children;
+            node
+        };
+        let table = expanded(
+            ObjectNode::new("Order Lines", NodeKind::Table, 2),
+            vec![expanded(
+                ObjectNode::new("Columns (1)", NodeKind::Folder, 3),
+                vec![ObjectNode::new("id", NodeKind::Detail, 4)],
+            )],
+        );
+        let mut app = app_with_databases(&[]);
+        app.objects = vec![expanded(
+            ObjectNode::new("shop", NodeKind::Database, 0),
+            vec![
+                expanded(ObjectNode::new("dbo", NodeKind::Schema, 1), vec![table]),
+                expanded(
+                    ObjectNode::new("Views (1)", NodeKind::Folder, 1),
+                    vec![ObjectNode::new("dbo.open]orders", NodeKind::View, 2)],
+                ),
+            ],
+        )];
+        let names: Vec<Option<String>> = (0..7)
+            .map(|row| {
+                app.sidebar_scroll = row;
+                app.sidebar_sql_name()
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                Some("[shop]".to_string()),
+                Some("[dbo]".to_string()),
+                Some("[dbo].[Order Lines]".to_string()),
+                None,
+                Some("[id]".to_string()),
+                None,
+                Some("[dbo].[open]]orders]".to_string()),
+            ]
+        );
+    }
+}
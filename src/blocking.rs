@@ -0,0 +1,107 @@
+//! Blocking chains (`\blocking`): which sessions are waiting on which,
+//! from `sys.dm_exec_requests`, arranged as a blocker → blocked tree.
+
+use std::collections::{HashMap, HashSet};
+
+/// Sessions that are blocked or blocking others. The first two columns are
+/// the session and the session blocking it (`0` for none); idle blockers
+/// holding an open transaction have no request, so their details come from
+/// the session and its last statement.
+pub const QUERY: &str = "SELECT s.session_id, ISNULL(r.blocking_session_id, 0) AS blocked_by, \
+     COALESCE(r.status, s.status) AS status, r.wait_type, r.wait_resource, r.wait_time AS wait_ms, \
+     DATEDIFF(SECOND, COALESCE(r.start_time, s.last_request_start_time), SYSDATETIME()) AS duration_s, \
+     s.login_name, s.host_name, DB_NAME(COALESCE(r.database_id, s.database_id)) AS database_name, \
+     LEFT(t.text, 200) AS statement \
+     FROM sys.dm_exec_sessions s \
+     LEFT JOIN sys.dm_exec_requests r ON r.session_id = s.session_id \
+     LEFT JOIN sys.dm_exec_connections c ON c.session_id = s.session_id \
+     OUTER APPLY sys.dm_exec_sql_text(COALESCE(r.sql_handle, c.most_recent_sql_handle)) t \
+     WHERE r.blocking_session_id <> 0 \
+     OR s.session_id IN (SELECT blocking_session_id FROM sys.dm_exec_requests WHERE blocking_session_id <> 0) \
+     ORDER BY s.session_id";
+
+/// Arrange [`QUERY`] rows as a tree: each head blocker followed by the
+/// sessions it blocks, indented under it, with the session column prefixed
+/// `└─ `. Returns the rows and the head blocker with the most sessions
+/// waiting behind it.
+pub fn tree(rows: &[Vec<String>]) -> (Vec<Vec<String>>, Option<String>) {
+    let cell = |row: &Vec<String>, i: usize| row.get(i).cloned().unwrap_or_default();
+    let sessions: HashSet<String> = rows.iter().map(|row| cell(row, 0)).collect();
+    let mut children: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut heads = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        let blocker = cell(row, 1);
+        if sessions.contains(&blocker) && blocker != cell(row, 0) {
+            children.entry(blocker).or_default().push(i);
+        } else {
+            heads.push(i);
+        }
+    }
+
+    let mut out = Vec::with_capacity(rows.len());
+    let mut visited = HashSet::new();
+    let mut worst: Option<(usize, String)> = None;
+    // Sessions blocking each other in a cycle have no head; start from the
+    // first one not yet shown
+    let starts = heads.clone().into_iter().chain(0..rows.len());
+    for start in starts {
+        if visited.contains(&start) {
+            continue;
+        }
+        let before = out.len();
+        let mut stack = vec![(start, 0)];
+        while let Some((i, depth)) = stack.pop() {
+            if !visited.insert(i) {
+                continue;
+            }
+            let mut row = rows[i].clone();
+            if depth > 0 {
+                row[0] = format!("{}└─ {}", "   ".repeat(depth - 1), row[0]);
+            }
+            out.push(row);
+            if let Some(kids) = children.get(&cell(&rows[i], 0)) {
+                stack.extend(kids.iter().rev().map(|&k| (k, depth + 1)));
+            }
+        }
+        let blocked = out.len() - before - 1;
+        if blocked > 0 && worst.as_ref().is_none_or(|(n, _)| blocked > *n) {
+            worst = Some((blocked, cell(&rows[start], 0)));
+        }
+    }
+    (out, worst.map(|(_, session)| session))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(session: &str, blocked_by: &str) -> Vec<String> {
+        vec![session.to_string(), blocked_by.to_string(), "x".to_string()]
+    }
+
+    #[test]
+    fn test_tree() {
+        let rows = vec![
+            row("52", "0"),
+            row("53", "52"),
+            row("54", "53"),
+            row("55", "52"),
+            row("60", "0"),
+            row("61", "60"),
+        ];
+        let (tree, head) = tree(&rows);
+        let labels: Vec<&str> = tree.iter().map(|r| r[0].as_str()).collect();
+        assert_eq!(
+            labels,
+            vec!["52", "└─ 53", "   └─ 54", "└─ 55", "60", "└─ 61"]
+        );
+        assert_eq!(head.as_deref(), Some("52"));
+    }
+
+    #[test]
+    fn test_tree_cycle() {
+        let (tree, head) = tree(&[row("70", "71"), row("71", "70")]);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(head.as_deref(), Some("70"));
+    }
+}
@@ -0,0 +1,32 @@
+//! Starred queries, stored one per line in the config directory using the
+//! same escaping as the history file.
+
+use crate::config;
+use crate::history::{decode, encode};
+
+const BOOKMARKS_FILE: &str = "bookmarks";
+
+/// Load starred queries in the order they were starred.
+pub fn load() -> Vec<String> {
+    let Some(path) = config::config_file(BOOKMARKS_FILE) else {
+        return Vec::new();
+    };
+    std::fs::read_to_string(path)
+        .map(|contents| {
+            contents
+                .lines()
+                .filter(|l| !l.is_empty())
+                .map(decode)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Overwrite the bookmarks file with `entries`.
+pub fn save(entries: &[String]) -> std::io::Result<()> {
+    let Some(path) = config::config_file(BOOKMARKS_FILE) else {
+        return Ok(());
+    };
+    let body: String = entries.iter().map(|e| encode(e) + "\n").collect();
+    std::fs::write(path, body)
+}
@@ -0,0 +1,311 @@
+//! `meow diff-data --table <t> --source <a> --target <b>`: compare a
+//! table's rows in two databases by primary key, and optionally print the
+//! statements that bring the target in line with the source.
+
+use super::{connect, open_output};
+use crate::Args;
+use crate::commands::ObjectName;
+use crate::{db, dml};
+use std::collections::HashMap;
+use std::io::Write;
+
+/// A table column as far as the comparison is concerned.
+#[derive(Debug, Clone)]
+struct TableColumn {
+    name: String,
+    /// SQL type name, as in [`crate::app::ResultSet::column_types`].
+    type_name: String,
+    identity: bool,
+    /// Position in the primary key, from 1; 0 for other columns.
+    key_ordinal: usize,
+}
+
+/// A result row, `None` for NULL.
+type Cells<'a> = [Option<&'a str>];
+
+/// How a source row differs from the target.
+#[derive(Debug, PartialEq, Eq)]
+enum Change<'a> {
+    /// Only in the source.
+    Insert(&'a Cells<'a>),
+    /// In both; the source row and the indexes of the columns that differ.
+    Update(&'a Cells<'a>, Vec<usize>),
+    /// Only in the target.
+    Delete(&'a Cells<'a>),
+}
+
+/// Compare `table` between the `source` and `target` databases, each a
+/// database on the `-S` server or `host[,port]/database`. Prints a report,
+/// or with `sql` the DELETE, UPDATE, and INSERT statements that make the
+/// target match. Date and time values are read as ISO 8601 text so that
+/// fractional seconds are compared and written back. Returns 1 if the rows
+/// differ, like `diff`.
+pub async fn run(
+    args: Args,
+    table: &str,
+    source: &str,
+    target: &str,
+    sql: bool,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let table = ObjectName::parse(table).ok_or_else(|| format!("Not a table name: {}", table))?;
+    let table = table.qualified();
+    let mut source_client = connect(&endpoint(&args, source))
+        .await
+        .map_err(|e| format!("{}: {}", source, e))?;
+    let mut target_client = connect(&endpoint(&args, target))
+        .await
+        .map_err(|e| format!("{}: {}", target, e))?;
+
+    let columns = fetch_columns(&mut source_client, &table).await?;
+    let mut key: Vec<(usize, usize)> = columns
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.key_ordinal > 0)
+        .map(|(i, c)| (c.key_ordinal, i))
+        .collect();
+    if key.is_empty() {
+        return Err(format!("{} has no primary key to match rows by", table).into());
+    }
+    key.sort();
+    let key: Vec<usize> = key.into_iter().map(|(_, i)| i).collect();
+
+    // The same column list on both sides keeps the rows aligned
+    let select = format!(
+        "SELECT {} FROM {}",
+        columns
+            .iter()
+            .map(|c| dml::lossless_column(&c.name, &c.type_name))
+            .collect::<Vec<_>>()
+            .join(", "),
+        table
+    );
+    let source_result = db::query::execute_query(&mut source_client, &select)
+        .await
+        .map_err(|e| format!("{}: {}", source, e))?;
+    let target_result = db::query::execute_query(&mut target_client, &select)
+        .await
+        .map_err(|e| format!("{}: {}", target, e))?;
+    let types = source_result.column_types_for(0);
+    let source_rows = source_result.cells_for(0);
+    let target_rows = target_result.cells_for(0);
+    let changes = diff(&key, &source_rows, &target_rows);
+
+    let mut output = open_output(&args)?;
+    let pairs = |row: &Cells, indexes: &mut dyn Iterator<Item = usize>| -> Vec<(&str, String)> {
+        indexes
+            .map(|i| {
+                let ty = types.get(i).map(String::as_str).unwrap_or("");
+                (columns[i].name.as_str(), dml::literal(row[i], ty))
+            })
+            .collect()
+    };
+    let key_text = |row: &Cells| {
+        key.iter()
+            .map(|&i| format!("{}={}", columns[i].name, row[i].unwrap_or("NULL")))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    if sql {
+        // Deletes first so re-inserted keys and unique values don't collide
+        for change in &changes {
+            if let Change::Delete(row) = change {
+                writeln!(
+                    output,
+                    "{}",
+                    dml::delete(&table, &pairs(row, &mut key.iter().copied()))
+                )?;
+            }
+        }
+        for change in &changes {
+            if let Change::Update(row, differing) = change {
+                writeln!(
+                    output,
+                    "{}",
+                    dml::update(
+                        &table,
+                        &pairs(row, &mut differing.iter().copied()),
+                        &pairs(row, &mut key.iter().copied())
+                    )
+                )?;
+            }
+        }
+        let inserts: Vec<&Cells> = changes
+            .iter()
+            .filter_map(|c| match c {
+                Change::Insert(row) => Some(*row),
+                _ => None,
+            })
+            .collect();
+        let identity = !inserts.is_empty() && columns.iter().any(|c| c.identity);
+        if identity {
+            writeln!(output, "SET IDENTITY_INSERT {} ON;", table)?;
+        }
+        for row in inserts {
+            writeln!(
+                output,
+                "{}",
+                dml::insert(&table, &pairs(row, &mut (0..columns.len())))
+            )?;
+        }
+        if identity {
+            writeln!(output, "SET IDENTITY_INSERT {} OFF;", table)?;
+        }
+    } else {
+        for change in &changes {
+            match change {
+                Change::Insert(row) => writeln!(output, "+ {}", key_text(row))?,
+                Change::Delete(row) => writeln!(output, "- {}", key_text(row))?,
+                Change::Update(row, differing) => {
+                    writeln!(output, "~ {}", key_text(row))?;
+                    let target_row = target_row(&key, &target_rows, row);
+                    for &i in differing {
+                        let old = target_row.and_then(|t| t[i]).unwrap_or("NULL");
+                        let new = row[i].unwrap_or("NULL");
+                        writeln!(output, "    {}: {} → {}", columns[i].name, old, new)?;
+                    }
+                }
+            }
+        }
+    }
+    output.flush()?;
+
+    if !args.quiet {
+        let count = |f: fn(&Change) -> bool| changes.iter().filter(|c| f(c)).count();
+        eprintln!(
+            "{} to insert, {} to update, {} to delete",
+            count(|c| matches!(c, Change::Insert(_))),
+            count(|c| matches!(c, Change::Update(..))),
+            count(|c| matches!(c, Change::Delete(_)))
+        );
+    }
+    Ok(if changes.is_empty() { 0 } else { 1 })
+}
+
+/// `args` pointed at `spec`: a database on the `-S` server, or
+/// `host[,port]/database`.
+fn endpoint(args: &Args, spec: &str) -> Args {
+    let mut args = args.clone();
+    match spec.rsplit_once('/') {
+        Some((server, database)) => {
+            args.server = server.to_string();
+            args.database = Some(database.to_string());
+        }
+        None => args.database = Some(spec.to_string()),
+    }
+    args
+}
+
+/// The columns of `table` (quoted, as from [`ObjectName::qualified`]) that
+/// can be compared and written: everything but computed and `rowversion`
+/// columns.
+async fn fetch_columns(
+    client: &mut db::ConnectionHandle,
+    table: &str,
+) -> Result<Vec<TableColumn>, Box<dyn std::error::Error>> {
+    let table_literal = table.replace('\'', "''");
+    let sql = format!(
+        "IF OBJECT_ID(N'{t}') IS NULL RAISERROR(N'Table %s not found', 16, 1, N'{t}') ELSE \
+         SELECT c.name, TYPE_NAME(c.system_type_id), CAST(c.is_identity AS char(1)), CAST(ISNULL(ic.key_ordinal, 0) AS varchar(10)) \
+         FROM sys.columns c \
+         LEFT JOIN sys.indexes i ON i.object_id = c.object_id AND i.is_primary_key = 1 \
+         LEFT JOIN sys.index_columns ic ON ic.object_id = i.object_id AND ic.index_id = i.index_id AND ic.column_id = c.column_id \
+         WHERE c.object_id = OBJECT_ID(N'{t}') AND c.is_computed = 0 AND TYPE_NAME(c.system_type_id) <> 'timestamp' \
+         ORDER BY c.column_id",
+        t = table_literal
+    );
+    let result = db::query::execute_query(client, &sql).await?;
+    Ok(result
+        .rows_for(0)
+        .iter()
+        .map(|row| TableColumn {
+            name: row[0].clone(),
+            type_name: row[1].clone(),
+            identity: row[2] == "1",
+            key_ordinal: row[3].parse().unwrap_or(0),
+        })
+        .collect())
+}
+
+/// Match `source` and `target` rows on the `key` columns: source rows in
+/// their order (inserts and updates), then target rows with no match.
+fn diff<'a>(
+    key: &[usize],
+    source: &'a [Vec<Option<&'a str>>],
+    target: &'a [Vec<Option<&'a str>>],
+) -> Vec<Change<'a>> {
+    let key_of =
+        |row: &'a Cells<'a>| -> Vec<Option<&'a str>> { key.iter().map(|&i| row[i]).collect() };
+    let mut unmatched: HashMap<Vec<Option<&str>>, &Cells> = target
+        .iter()
+        .map(|row| (key_of(row), row.as_slice()))
+        .collect();
+
+    let mut changes = Vec::new();
+    for row in source {
+        match unmatched.remove(&key_of(row)) {
+            None => changes.push(Change::Insert(row)),
+            Some(other) => {
+                let differing: Vec<usize> =
+                    (0..row.len()).filter(|&i| row[i] != other[i]).collect();
+                if !differing.is_empty() {
+                    changes.push(Change::Update(row, differing));
+                }
+            }
+        }
+    }
+    for row in target {
+        if unmatched.contains_key(&key_of(row)) {
+            changes.push(Change::Delete(row));
+        }
+    }
+    changes
+}
+
+/// The target row with the same key as `row`.
+fn target_row<'a>(
+    key: &[usize],
+    target: &'a [Vec<Option<&'a str>>],
+    row: &Cells,
+) -> Option<&'a Vec<Option<&'a str>>> {
+    target.iter().find(|t| key.iter().all(|&i| t[i] == row[i]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn row<'a>(cells: &[&'a str]) -> Vec<Option<&'a str>> {
+        cells.iter().map(|c| Some(*c)).collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let source = vec![row(&["1", "a"]), row(&["2", "b"]), row(&["3", "c"])];
+        let target = vec![row(&["4", "d"]), row(&["2", "B"]), row(&["1", "a"])];
+        assert_eq!(
+            diff(&[0], &source, &target),
+            vec![
+                Change::Update(&source[1], vec![1]),
+                Change::Insert(&source[2]),
+                Change::Delete(&target[0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_endpoint() {
+        let args = Args::parse_from(["meow", "-S", "prod,1433"]);
+        let local = endpoint(&args, "Config");
+        assert_eq!(
+            (local.server.as_str(), local.database()),
+            ("prod,1433", "Config")
+        );
+        let remote = endpoint(&args, "qa:1434/Config");
+        assert_eq!(
+            (remote.server.as_str(), remote.database()),
+            ("qa:1434", "Config")
+        );
+    }
+}
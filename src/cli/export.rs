@@ -0,0 +1,198 @@
+//! `meow export --table <t> --out <file>`: stream a whole table to a CSV or
+//! NDJSON file, row by row, in constant memory.
+
+use super::{connect, csv_line, date_style, format_for_extension, ndjson_line};
+use crate::Args;
+use crate::commands::ObjectName;
+use crate::db;
+use crate::display::{self, DateStyle};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// How often the progress line is updated, in rows.
+const PROGRESS_ROWS: usize = 10_000;
+
+/// Export `table` to `out` as `format` (`csv` or `ndjson`; by default from
+/// the file extension, else `csv`). The rows are written to a file next to
+/// `out` that replaces it only once the export has succeeded.
+pub async fn run(
+    args: Args,
+    table: &str,
+    format: Option<&str>,
+    out: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let format = match format {
+        Some(format) => format,
+        None => out
+            .extension()
+            .and_then(|ext| format_for_extension(&ext.to_string_lossy().to_lowercase()))
+            .unwrap_or("csv"),
+    };
+    if !matches!(format, "csv" | "ndjson") {
+        return Err(format!("export writes csv or ndjson, not {}", format).into());
+    }
+    let table = ObjectName::parse(table).ok_or_else(|| format!("Not a table name: {}", table))?;
+
+    let mut client = connect(&args).await?;
+    let partial = partial_path(out);
+    let file =
+        std::fs::File::create(&partial).map_err(|e| format!("{}: {}", partial.display(), e))?;
+    let mut rows = RowWriter {
+        writer: io::BufWriter::new(file),
+        csv: format == "csv",
+        headers: !args.no_headers,
+        style: date_style(),
+        count: 0,
+    };
+    let progress = !args.quiet && io::stderr().is_terminal();
+    let start = Instant::now();
+    let exported = db::query::stream_query(
+        &mut client,
+        &format!("SELECT * FROM {}", table.qualified()),
+        |columns, types, row| {
+            rows.write(columns, types, row)?;
+            if progress && rows.count > 0 && rows.count % PROGRESS_ROWS == 0 {
+                eprint!("\rExported {} rows...", rows.count);
+            }
+            Ok(())
+        },
+    )
+    .await
+    .and_then(|_| Ok(rows.writer.flush()?))
+    .and_then(|()| Ok(std::fs::rename(&partial, out)?));
+    if let Err(e) = exported {
+        std::fs::remove_file(&partial).ok();
+        return Err(e);
+    }
+
+    if !args.quiet {
+        if progress {
+            eprint!("\r");
+        }
+        eprintln!(
+            "Exported {} rows to {} in {:.1}s",
+            rows.count,
+            out.display(),
+            start.elapsed().as_secs_f64()
+        );
+    }
+    Ok(())
+}
+
+/// `.<name>.part` next to `out`, where the export is written until done.
+fn partial_path(out: &Path) -> PathBuf {
+    let name = out
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    out.with_file_name(format!(".{}.part", name))
+}
+
+/// Writes streamed rows as CSV, with a header row unless `headers` is off,
+/// or as NDJSON.
+struct RowWriter<W: Write> {
+    writer: W,
+    csv: bool,
+    headers: bool,
+    style: DateStyle,
+    /// Rows written so far.
+    count: usize,
+}
+
+impl<W: Write> RowWriter<W> {
+    /// Write a row, or for `None` (the result's columns arriving) the CSV
+    /// header, so an empty table still gets one.
+    fn write(
+        &mut self,
+        columns: &[String],
+        types: &[String],
+        row: Option<Vec<Option<String>>>,
+    ) -> io::Result<()> {
+        let Some(row) = row else {
+            if self.csv && self.headers {
+                let header: Vec<Option<&str>> = columns.iter().map(|c| Some(c.as_str())).collect();
+                writeln!(self.writer, "{}", csv_line(&header))?;
+                // Only the first result set's columns
+                self.headers = false;
+            }
+            return Ok(());
+        };
+        let row = display::format_row(&row, types, self.style);
+        let row: Vec<Option<&str>> = row.iter().map(|v| v.as_deref()).collect();
+        if self.csv {
+            writeln!(self.writer, "{}", csv_line(&row))?;
+        } else {
+            writeln!(self.writer, "{}", ndjson_line(columns, &row))?;
+        }
+        self.count += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn writer(csv: bool, headers: bool) -> RowWriter<Vec<u8>> {
+        RowWriter {
+            writer: Vec::new(),
+            csv,
+            headers,
+            style: DateStyle::Raw,
+            count: 0,
+        }
+    }
+
+    #[test]
+    fn test_row_writer_csv() {
+        let columns = ["id".to_string(), "note".to_string()];
+        let types = ["int".to_string(), "nvarchar".to_string()];
+        let mut rows = writer(true, true);
+        rows.write(&columns, &types, None).unwrap();
+        assert_eq!(String::from_utf8_lossy(&rows.writer), "id,note\n");
+
+        rows.write(
+            &columns,
+            &types,
+            Some(vec![Some("1".into()), Some("a,b".into())]),
+        )
+        .unwrap();
+        rows.write(&columns, &types, Some(vec![Some("2".into()), None]))
+            .unwrap();
+        rows.write(
+            &columns,
+            &types,
+            Some(vec![Some("3".into()), Some("".into())]),
+        )
+        .unwrap();
+        // NULL and the empty string read back apart
+        assert_eq!(
+            String::from_utf8_lossy(&rows.writer),
+            "id,note\n1,\"a,b\"\n2,\n3,\"\"\n"
+        );
+        assert_eq!(rows.count, 3);
+
+        let mut rows = writer(true, false);
+        rows.write(&columns, &types, None).unwrap();
+        assert!(rows.writer.is_empty());
+    }
+
+    #[test]
+    fn test_row_writer_ndjson() {
+        let columns = ["id".to_string()];
+        let mut rows = writer(false, true);
+        rows.write(&columns, &["int".to_string()], None).unwrap();
+        rows.write(&columns, &["int".to_string()], Some(vec![None]))
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&rows.writer), "{\"id\":null}\n");
+    }
+
+    #[test]
+    fn test_partial_path() {
+        assert_eq!(
+            partial_path(Path::new("out/orders.csv")),
+            Path::new("out/.orders.csv.part")
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! `meow migrate <dir>`: run the `.sql` scripts in a directory that haven't
+//! been applied yet, recording each one in a tracking table.
+
+use super::{connect, open_output, run_script};
+use crate::Args;
+use crate::db;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Table recording applied scripts, created on first use.
+const TRACKING_TABLE: &str = "dbo.meow_migrations";
+
+/// Comment that keeps a script out of a transaction.
+const NO_TRANSACTION: &str = "meow:no-transaction";
+
+/// Statements SQL Server refuses to run inside a user transaction.
+const NON_TRANSACTIONAL: &[&str] = &[
+    "CREATE DATABASE",
+    "ALTER DATABASE",
+    "DROP DATABASE",
+    "BACKUP",
+    "RESTORE",
+    "RECONFIGURE",
+    "CREATE FULLTEXT",
+    "ALTER FULLTEXT",
+    "DROP FULLTEXT",
+];
+
+/// Apply the pending scripts in `dir`, in lexical order. Each script runs in
+/// a transaction together with its tracking row, so a failed script leaves
+/// nothing behind; scripts that can't run in a transaction (see
+/// [`needs_no_transaction`]) run without one. Stops at the first failure.
+/// Returns the process exit code.
+pub async fn run(args: Args, dir: &Path) -> Result<i32, Box<dyn std::error::Error>> {
+    let files = pending_candidates(dir)?;
+    let mut client = connect(&args).await?;
+    let mut output = open_output(&args)?;
+
+    let create = format!(
+        "IF OBJECT_ID(N'{table}', N'U') IS NULL \
+         CREATE TABLE {table} (\
+         name nvarchar(260) NOT NULL PRIMARY KEY, \
+         applied_at datetime2 NOT NULL DEFAULT SYSUTCDATETIME())",
+        table = TRACKING_TABLE
+    );
+    db::query::execute_query(&mut client, &create).await?;
+    let applied =
+        db::query::execute_query(&mut client, &format!("SELECT name FROM {}", TRACKING_TABLE))
+            .await?;
+    let applied: Vec<&str> = applied
+        .rows_for(0)
+        .iter()
+        .filter_map(|row| row.first().map(String::as_str))
+        .collect();
+
+    let (mut done, mut skipped) = (0, 0);
+    for path in &files {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if applied.contains(&name.as_str()) {
+            skipped += 1;
+            continue;
+        }
+
+        let text = crate::scripts::read(path)?;
+        let transaction = !needs_no_transaction(&text);
+        if !args.quiet {
+            eprintln!(
+                "Applying {}{}",
+                name,
+                if transaction {
+                    ""
+                } else {
+                    " (without a transaction)"
+                }
+            );
+        }
+
+        if transaction {
+            db::query::execute_query(&mut client, "BEGIN TRANSACTION").await?;
+        }
+        let mut code = run_script(
+            &mut client,
+            &text,
+            &args,
+            &mut output,
+            &HashMap::new(),
+            true,
+        )
+        .await?;
+        if code == 0 {
+            let record = format!(
+                "INSERT INTO {} (name) VALUES (N'{}')",
+                TRACKING_TABLE,
+                name.replace('\'', "''")
+            );
+            if let Err(e) = db::query::execute_query(&mut client, &record).await {
+                eprintln!("{}: could not record migration: {}", name, e);
+                code = 1;
+            }
+        }
+        if transaction {
+            let end = if code == 0 {
+                "COMMIT TRANSACTION"
+            } else {
+                "IF @@TRANCOUNT > 0 ROLLBACK TRANSACTION"
+            };
+            db::query::execute_query(&mut client, end).await?;
+        }
+        if code != 0 {
+            eprintln!(
+                "{} failed{}; {} applied before it",
+                name,
+                if transaction {
+                    " and was rolled back"
+                } else {
+                    ""
+                },
+                done
+            );
+            return Ok(code);
+        }
+        done += 1;
+    }
+
+    if !args.quiet {
+        eprintln!("{} applied, {} already applied", done, skipped);
+    }
+    Ok(0)
+}
+
+/// The `.sql` files directly in `dir`, sorted by name.
+fn pending_candidates(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| format!("{}: {}", dir.display(), e))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.is_file()
+                && p.extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("sql"))
+        })
+        .collect();
+    files.sort_by_key(|p| p.file_name().map(|n| n.to_os_string()));
+    Ok(files)
+}
+
+/// Whether a script must run outside a transaction: it contains a statement
+/// SQL Server doesn't allow in one, or opts out with a
+/// `-- meow:no-transaction` comment.
+pub fn needs_no_transaction(text: &str) -> bool {
+    if text.contains(NO_TRANSACTION) {
+        return true;
+    }
+    // Normalise whitespace so `CREATE\n  DATABASE` matches too
+    let words = text
+        .lines()
+        .map(|line| line.split("--").next().unwrap_or(""))
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_ascii_uppercase();
+    NON_TRANSACTIONAL.iter().any(|stmt| {
+        words.match_indices(stmt).any(|(i, _)| {
+            let end = i + stmt.len();
+            (i == 0 || words.as_bytes()[i - 1] == b' ')
+                && words
+                    .as_bytes()
+                    .get(end)
+                    .is_none_or(|&b| !(b.is_ascii_alphanumeric() || b == b'_'))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_needs_no_transaction() {
+        assert!(needs_no_transaction(
+            "ALTER  DATABASE CURRENT\nSET RECOVERY SIMPLE"
+        ));
+        assert!(needs_no_transaction("create\n  database shop"));
+        assert!(needs_no_transaction(
+            "-- meow:no-transaction\nEXEC sp_big_job"
+        ));
+        assert!(!needs_no_transaction("CREATE TABLE backups (id int)"));
+        assert!(!needs_no_transaction("-- BACKUP DATABASE x\nSELECT 1"));
+        assert!(!needs_no_transaction("ALTER TABLE t ADD restore_point int"));
+    }
+}
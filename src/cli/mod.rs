@@ -1,88 +1,633 @@
 //! Non-interactive CLI mode for scripting and piped input.
 
+pub mod diff_data;
+pub mod export;
+pub mod migrate;
+pub mod script_schema;
+
 use crate::Args;
+use crate::app::QueryError;
+use crate::commands::{self, SlashCommand};
 use crate::db;
+use crate::display::{self, DateStyle};
+use crate::scripts;
+use crate::variables;
+use std::collections::HashMap;
 use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Which parts of the output to print besides the rows themselves.
+#[derive(Debug, Clone, Copy)]
+pub struct OutputOptions {
+    /// Column headers (and result set banners) in `table` and `csv` output.
+    pub headers: bool,
+    /// Row count and timing trailers in `table` output.
+    pub footer: bool,
+    /// Widest a `table` column may be, in characters; `None` for no limit.
+    pub max_col_width: Option<usize>,
+    /// Wrap values wider than `max_col_width` onto extra lines instead of
+    /// truncating them.
+    pub wrap: bool,
+    /// How dates and times are written, in every format.
+    pub date_style: DateStyle,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            headers: true,
+            footer: true,
+            max_col_width: None,
+            wrap: false,
+            date_style: DateStyle::Raw,
+        }
+    }
+}
+
+impl OutputOptions {
+    /// Options from `--no-headers`, `--no-footer`, `--quiet`,
+    /// `--max-col-width`, `--truncate`, and `--wrap`, and the config file's
+    /// `datestyle`. Columns are limited to [`MAX_COL_WIDTH`] when writing to
+    /// a terminal (or when `--truncate` or `--wrap` asks for it); output to
+    /// files is left whole.
+    pub fn from_args(args: &Args) -> Self {
+        let terminal = args.output.is_none() && io::stdout().is_terminal();
+        let limit = terminal || args.truncate || args.wrap;
+        Self {
+            headers: !args.no_headers,
+            footer: !(args.no_footer || args.quiet),
+            max_col_width: args
+                .max_col_width
+                .or(limit.then_some(MAX_COL_WIDTH))
+                .map(|w| w.max(1)),
+            wrap: args.wrap,
+            date_style: date_style(),
+        }
+    }
+}
+
+/// The config file's `datestyle`, or [`DateStyle::Raw`].
+fn date_style() -> DateStyle {
+    crate::config::Settings::load()
+        .date_style
+        .as_deref()
+        .and_then(DateStyle::parse)
+        .unwrap_or_default()
+}
+
+/// Default column width limit for `table` output to a terminal.
+pub const MAX_COL_WIDTH: usize = 50;
 
-/// Run meow in CLI mode.
-pub async fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
-    let (host, port) = args.parse_server();
-    let user = args.user.as_deref().unwrap_or("sa");
-    let password = args.password.as_deref().unwrap_or("");
+/// Run meow in CLI mode. Returns the process exit code: 0 when every batch
+/// succeeded, otherwise the highest error severity seen (see [`exit_code`]).
+pub async fn run(args: Args) -> Result<i32, Box<dyn std::error::Error>> {
+    let mut client = connect(&args).await?;
+    let mut output = open_output(&args)?;
+    let vars = if args.no_meowrc {
+        HashMap::new()
+    } else {
+        run_rc(&mut client).await
+    };
 
-    let mut client =
-        db::connect(&host, port, user, password, &args.database, args.trust_cert).await?;
+    if !args.input.is_empty() {
+        return run_files(&mut client, &args, &mut output, &vars).await;
+    }
 
     // Determine SQL source
-    let sql = if let Some(ref input_file) = args.input {
-        std::fs::read_to_string(input_file)?
-    } else if !std::io::stdin().is_terminal() {
+    let sql = if !std::io::stdin().is_terminal() {
         // Read from stdin pipe
         let mut buf = String::new();
         io::stdin().lock().read_to_string(&mut buf)?;
         buf
     } else {
         // Interactive CLI mode — read line by line
-        return run_interactive(&mut client, &args).await;
+        return run_interactive(&mut client, &args, &mut output, vars).await;
     };
 
-    // Execute and output
-    execute_and_print(&mut client, &sql, &args).await?;
-    Ok(())
+    run_script(
+        &mut client,
+        &sql,
+        &args,
+        &mut output,
+        &vars,
+        args.abort_on_error,
+    )
+    .await
+}
+
+/// Run the startup file, if there is one, and return the variables it sets.
+/// Its SQL runs without printing results; of its slash commands `\set`
+/// (including `\set option`), `\unset`, and `\c` apply here, the rest are for the TUI and skipped.
+/// Failures are reported on stderr.
+async fn run_rc(client: &mut db::ConnectionHandle) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+    let Some(path) = crate::config::rc_file() else {
+        return vars;
+    };
+    let text = match scripts::read(&path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            return vars;
+        }
+    };
+    for command in scripts::rc_commands(&text) {
+        let command = match commands::parse(&command) {
+            Some(SlashCommand::UseDatabase(database)) => {
+                format!("USE {}", commands::quote_ident(&database))
+            }
+            _ => command,
+        };
+        match commands::parse(&command) {
+            Some(SlashCommand::SetVariable(Some((name, value)))) => {
+                vars.insert(name, value);
+            }
+            Some(SlashCommand::UnsetVariable(name)) => {
+                vars.remove(&name);
+            }
+            Some(SlashCommand::SetOption(Some(spec))) => {
+                let result = match commands::SessionOption::parse(&spec) {
+                    Ok(option) => db::query::execute_query(client, &option.sql())
+                        .await
+                        .map(|_| ()),
+                    Err(e) => Err(e.into()),
+                };
+                if let Err(e) = result {
+                    eprintln!("{}: {}", path.display(), e);
+                }
+            }
+            _ if command.starts_with('\\') => {}
+            _ => {
+                let sql = variables::interpolate(&command, &vars);
+                if let Err(e) = db::query::execute_query(client, &sql).await {
+                    eprintln!("{}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+    vars
+}
+
+/// Connect with the server and login from `args`.
+async fn connect(args: &Args) -> Result<db::ConnectionHandle, Box<dyn std::error::Error>> {
+    db::connect(&args.login(), args.database()).await
+}
+
+/// Run the `-i` files in order on one connection, with a progress line per
+/// file and a summary at the end when there are several. Stops after the
+/// first failed file with `--abort-on-error`. Returns the highest exit code.
+async fn run_files(
+    client: &mut db::ConnectionHandle,
+    args: &Args,
+    output: &mut dyn Write,
+    vars: &HashMap<String, String>,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let files = match scripts::expand_paths(&args.input) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(1);
+        }
+    };
+    let progress = files.len() > 1 && !args.quiet;
+
+    let mut code = 0;
+    let (mut ok, mut failed) = (0, 0);
+    for (i, path) in files.iter().enumerate() {
+        if progress {
+            eprintln!("[{}/{}] {}", i + 1, files.len(), path.display());
+        }
+        let file_code = match scripts::read(path) {
+            Ok(text) => run_script(client, &text, args, output, vars, args.abort_on_error).await?,
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                1
+            }
+        };
+        if file_code == 0 {
+            ok += 1;
+        } else {
+            failed += 1;
+            code = code.max(file_code);
+            if args.abort_on_error {
+                break;
+            }
+        }
+    }
+
+    if progress {
+        let mut summary = format!("{} file(s): {} ok, {} failed", files.len(), ok, failed);
+        let skipped = files.len() - ok - failed;
+        if skipped > 0 {
+            summary.push_str(&format!(", {} not run", skipped));
+        }
+        eprintln!("{}", summary);
+    }
+    Ok(code)
+}
+
+/// Run a script batch by batch (split on `GO`), reporting errors to stderr.
+/// `$(name)` references are filled in from `vars`. Stops at the first error
+/// if `abort` is set, otherwise carries on. Returns the exit code.
+async fn run_script(
+    client: &mut db::ConnectionHandle,
+    text: &str,
+    args: &Args,
+    output: &mut dyn Write,
+    vars: &HashMap<String, String>,
+    abort: bool,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let batches = match scripts::prepare(text, vars, !args.no_variables) {
+        Ok(batches) => batches,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(1);
+        }
+    };
+
+    let mut conditions = scripts::Conditions::default();
+    let mut code = 0;
+    for batch in &batches {
+        if let Some(ref branch) = batch.branch {
+            let value = match db::query::eval_branch(client, &conditions, branch).await {
+                Ok(value) => value,
+                Err(e) => {
+                    let error = db::query::query_error(&*e, "");
+                    report_error(&error, batch.line);
+                    code = code.max(exit_code(&error));
+                    if abort {
+                        break;
+                    }
+                    false
+                }
+            };
+            // Nesting was checked when the script was prepared
+            conditions.apply(branch, value).ok();
+            continue;
+        }
+        if !conditions.active() {
+            continue;
+        }
+        for _ in 0..batch.repeat {
+            if let Err(e) = execute_and_print(client, &batch.sql, args, output, None).await {
+                let error = db::query::query_error(&*e, &batch.sql);
+                report_error(&error, batch.line);
+                code = code.max(exit_code(&error));
+                break;
+            }
+        }
+        if code != 0 && abort {
+            break;
+        }
+    }
+    Ok(code)
+}
+
+/// Exit code for a failed batch: the error's severity (11-25) for server
+/// errors, so callers can tell user errors (16) from fatal ones (20+), and 1
+/// for client-side errors such as a dropped connection.
+fn exit_code(error: &QueryError) -> i32 {
+    error.severity.map_or(1, |s| i32::from(s).max(1))
+}
+
+/// Print an error to stderr, sqlcmd style. `first_line` is the line the
+/// batch starts on, so server line numbers point into the script.
+fn report_error(error: &QueryError, first_line: usize) {
+    if let (Some(number), Some(severity), Some(state)) = (error.number, error.severity, error.state)
+    {
+        let mut header = format!("Msg {}, Level {}, State {}", number, severity, state);
+        if let Some(ref procedure) = error.procedure {
+            header.push_str(&format!(", Procedure {}", procedure));
+        }
+        if let Some(line) = error.line {
+            let line = if error.procedure.is_some() {
+                line as usize
+            } else {
+                first_line + line as usize - 1
+            };
+            header.push_str(&format!(", Line {}", line));
+        }
+        eprintln!("{}", header);
+    }
+    eprintln!("{}", error.message);
+}
+
+/// Where results go: the `-o` file, or stdout.
+fn open_output(args: &Args) -> Result<io::BufWriter<Box<dyn Write>>, Box<dyn std::error::Error>> {
+    let output: Box<dyn Write> = if let Some(ref path) = args.output {
+        Box::new(std::fs::File::create(path)?)
+    } else {
+        Box::new(io::stdout())
+    };
+    Ok(io::BufWriter::new(output))
 }
 
 /// Run interactive CLI (line-by-line REPL).
 async fn run_interactive(
     client: &mut db::ConnectionHandle,
     args: &Args,
-) -> Result<(), Box<dyn std::error::Error>> {
+    output: &mut dyn Write,
+    mut vars: HashMap<String, String>,
+) -> Result<i32, Box<dyn std::error::Error>> {
     let stdin = io::stdin();
     let mut stdout = io::stdout();
+    // `\o <file>` target: results are also appended here
+    let mut tee: Option<PathBuf> = None;
+    let mut conditions = scripts::Conditions::default();
+    // Lines of a statement not yet terminated by `;` or `GO`
+    let mut buffer = String::new();
 
     loop {
-        print!("meow> ");
-        stdout.flush()?;
+        if !args.quiet {
+            print!(
+                "{}",
+                if buffer.is_empty() {
+                    "meow> "
+                } else {
+                    "meow-> "
+                }
+            );
+            stdout.flush()?;
+        }
 
         let mut line = String::new();
-        if stdin.lock().read_line(&mut line)? == 0 {
-            break; // EOF
+        let eof = stdin.lock().read_line(&mut line)? == 0;
+        if eof && (buffer.trim().is_empty() || !conditions.active()) {
+            break;
         }
 
         let trimmed = line.trim();
-        if trimmed.is_empty() {
+        if trimmed.is_empty() && !eof {
+            if !buffer.is_empty() {
+                buffer.push('\n');
+            }
+            continue;
+        }
+        match scripts::branch(&variables::interpolate(trimmed, &vars)) {
+            Ok(Some(branch)) => {
+                // A condition that fails to evaluate counts as false
+                let value = db::query::eval_branch(client, &conditions, &branch)
+                    .await
+                    .unwrap_or_else(|e| {
+                        eprintln!("{}", e);
+                        false
+                    });
+                if let Err(e) = conditions.apply(&branch, value) {
+                    eprintln!("{}", e);
+                }
+                continue;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        }
+        if !conditions.active() {
             continue;
         }
-        if trimmed.eq_ignore_ascii_case("quit") || trimmed.eq_ignore_ascii_case("exit") {
+        if buffer.is_empty()
+            && (trimmed.eq_ignore_ascii_case("quit") || trimmed.eq_ignore_ascii_case("exit"))
+        {
             break;
         }
+        match commands::parse(trimmed) {
+            Some(SlashCommand::Tee(target)) => {
+                match target {
+                    Some(path) => {
+                        if !args.quiet {
+                            println!("Copying results to {}", path);
+                        }
+                        tee = Some(PathBuf::from(path));
+                    }
+                    None => {
+                        if !args.quiet {
+                            println!("Stopped copying results");
+                        }
+                        tee = None;
+                    }
+                }
+                continue;
+            }
+            Some(SlashCommand::SetVariable(Some((name, value)))) => {
+                vars.insert(name, value);
+                continue;
+            }
+            Some(SlashCommand::SetVariable(None)) => {
+                for row in variables::rows(&vars) {
+                    println!("{} = {}", row[0], row[1]);
+                }
+                continue;
+            }
+            Some(SlashCommand::UnsetVariable(name)) => {
+                vars.remove(&name);
+                continue;
+            }
+            Some(SlashCommand::SetOption(Some(spec))) => {
+                let result = match commands::SessionOption::parse(&spec) {
+                    Ok(option) => db::query::execute_query(client, &option.sql())
+                        .await
+                        .map(|_| ()),
+                    Err(e) => Err(e.into()),
+                };
+                if let Err(e) = result {
+                    eprintln!("{}", e);
+                }
+                continue;
+            }
+            Some(SlashCommand::Prompt { text, name }) => {
+                print!("{}", text.unwrap_or_else(|| format!("{}: ", name)));
+                stdout.flush()?;
+                let mut value = String::new();
+                stdin.lock().read_line(&mut value)?;
+                vars.insert(name, value.trim_end_matches(['\r', '\n']).to_string());
+                continue;
+            }
+            _ => {}
+        }
 
-        execute_and_print(client, trimmed, args).await.ok();
+        // Run the buffer at `GO`, a terminating `;`, or end of input
+        let repeat = match scripts::go_separator(trimmed) {
+            Some(repeat) => repeat,
+            None => {
+                buffer.push_str(line.trim_end_matches(['\r', '\n']));
+                buffer.push('\n');
+                if !eof && !scripts::ends_statement(&buffer) {
+                    continue;
+                }
+                1
+            }
+        };
+        let batch = std::mem::take(&mut buffer);
+        if !batch.trim().is_empty() {
+            let sql = variables::interpolate(&batch, &vars);
+            for _ in 0..repeat {
+                if let Err(e) = execute_and_print(client, &sql, args, output, tee.as_deref()).await
+                {
+                    let error = db::query::query_error(&*e, &sql);
+                    report_error(&error, 1);
+                    if args.abort_on_error {
+                        return Ok(exit_code(&error));
+                    }
+                    break;
+                }
+            }
+        }
+        if eof {
+            break;
+        }
     }
 
-    Ok(())
+    Ok(0)
 }
 
-/// Execute a SQL statement and print results.
+/// Execute a SQL statement and print results to `writer`, also appending
+/// them to `tee` if set.
 async fn execute_and_print(
     client: &mut db::ConnectionHandle,
     sql: &str,
     args: &Args,
+    writer: &mut dyn Write,
+    tee: Option<&Path>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    if args.format() == "ndjson" {
+        return stream_ndjson(client, sql, writer, tee, date_style()).await;
+    }
+
     let result = db::query::execute_query(client, sql).await?;
+    let opts = OutputOptions::from_args(args);
+    write_result(writer, &result, args.format(), &opts)?;
+    writer.flush()?;
 
-    let output: Box<dyn Write> = if let Some(ref path) = args.output {
-        Box::new(std::fs::File::create(path)?)
-    } else {
-        Box::new(io::stdout())
+    if let Some(path) = tee {
+        append_result(path, &result, args.format(), &opts)?;
+    }
+
+    Ok(())
+}
+
+/// Run `sql` and write each row as an NDJSON line as soon as it arrives, so
+/// large results stream in constant memory. Lines are also appended to `tee`
+/// if set.
+async fn stream_ndjson(
+    client: &mut db::ConnectionHandle,
+    sql: &str,
+    writer: &mut dyn Write,
+    tee: Option<&Path>,
+    date_style: DateStyle,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut tee = match tee {
+        Some(path) => Some(io::BufWriter::new(
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?,
+        )),
+        None => None,
     };
-    let mut writer = io::BufWriter::new(output);
+    db::query::stream_query(client, sql, |columns, types, row| {
+        let Some(row) = row else {
+            return Ok(());
+        };
+        let row = display::format_row(&row, types, date_style);
+        let row: Vec<Option<&str>> = row.iter().map(|v| v.as_deref()).collect();
+        let line = ndjson_line(columns, &row);
+        writeln!(writer, "{}", line)?;
+        writer.flush()?;
+        if let Some(ref mut tee) = tee {
+            writeln!(tee, "{}", line)?;
+        }
+        Ok(())
+    })
+    .await?;
+    if let Some(ref mut tee) = tee {
+        tee.flush()?;
+    }
+    Ok(())
+}
 
-    match args.format.as_str() {
-        "csv" => print_csv(&mut writer, &result)?,
-        "json" => print_json(&mut writer, &result)?,
-        _ => print_table(&mut writer, &result)?,
+/// One row as a single-line JSON object, with `null` for NULL values.
+fn ndjson_line(columns: &[String], row: &[Option<&str>]) -> String {
+    let fields: Vec<String> = columns
+        .iter()
+        .zip(row)
+        .map(|(col, val)| {
+            let val = val.map_or_else(|| "null".to_string(), json_string);
+            format!("{}:{}", json_string(col), val)
+        })
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// Quote a string as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
     }
+    out.push('"');
+    out
+}
+
+/// The output format written to files with extension `ext` (lowercase).
+pub fn format_for_extension(ext: &str) -> Option<&'static str> {
+    match ext {
+        "csv" => Some("csv"),
+        "json" => Some("json"),
+        "ndjson" | "jsonl" => Some("ndjson"),
+        "md" | "markdown" => Some("markdown"),
+        "txt" => Some("table"),
+        _ => None,
+    }
+}
+
+/// Write results in the given output format (`table`, `csv`, `json`,
+/// `ndjson` or `markdown`).
+pub fn write_result(
+    writer: &mut dyn Write,
+    result: &crate::app::QueryResult,
+    format: &str,
+    opts: &OutputOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let styled;
+    let result = if opts.date_style == DateStyle::Raw {
+        result
+    } else {
+        styled = display::restyle_dates(result, opts.date_style);
+        &styled
+    };
+    match format {
+        "csv" => print_csv(writer, result, opts),
+        "json" => print_json(writer, result),
+        "ndjson" => print_ndjson(writer, result),
+        "markdown" => print_markdown(writer, result, opts),
+        _ => print_table(writer, result, opts),
+    }
+}
 
+/// Append results to a file in the given output format (for `\o`).
+pub fn append_result(
+    path: &Path,
+    result: &crate::app::QueryResult,
+    format: &str,
+    opts: &OutputOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let mut writer = io::BufWriter::new(file);
+    write_result(&mut writer, result, format, opts)?;
+    writer.flush()?;
     Ok(())
 }
 
@@ -90,13 +635,14 @@ async fn execute_and_print(
 fn print_table(
     writer: &mut dyn Write,
     result: &crate::app::QueryResult,
+    opts: &OutputOptions,
 ) -> Result<(), Box<dyn std::error::Error>> {
     for (set_idx, rs) in result.result_sets.iter().enumerate() {
         if rs.columns.is_empty() {
             continue;
         }
 
-        if result.result_sets.len() > 1 {
+        if opts.headers && result.result_sets.len() > 1 {
             writeln!(writer, "-- Result Set {} --", set_idx + 1)?;
         }
 
@@ -109,68 +655,113 @@ fn print_table(
                 let max_data = rs
                     .rows
                     .iter()
-                    .map(|r| r.get(i).map(|s| s.len()).unwrap_or(0))
+                    .map(|r| r.get(i).map(|s| s.chars().count()).unwrap_or(0))
                     .max()
                     .unwrap_or(0);
-                col.len().max(max_data)
+                let width = col.chars().count().max(max_data);
+                opts.max_col_width.map_or(width, |max| width.min(max))
             })
             .collect();
 
-        // Header
-        let header: Vec<String> = rs
-            .columns
-            .iter()
-            .zip(&widths)
-            .map(|(c, w)| format!("{:<width$}", c, width = w))
-            .collect();
-        writeln!(writer, "{}", header.join(" | "))?;
+        if opts.headers {
+            // Header
+            let header: Vec<String> = rs
+                .columns
+                .iter()
+                .zip(&widths)
+                .map(|(c, w)| format!("{:<width$}", fit_cell(c, *w, false)[0], width = w))
+                .collect();
+            writeln!(writer, "{}", header.join(" | "))?;
 
-        // Separator
-        let sep: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
-        writeln!(writer, "{}", sep.join("-+-"))?;
+            // Separator
+            let sep: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+            writeln!(writer, "{}", sep.join("-+-"))?;
+        }
 
-        // Data rows
+        // Data rows; a wrapped row takes as many lines as its tallest cell
         for row in &rs.rows {
-            let cells: Vec<String> = row
+            let cells: Vec<Vec<String>> = row
                 .iter()
                 .zip(&widths)
-                .map(|(val, w)| format!("{:<width$}", val, width = w))
+                .map(|(val, w)| fit_cell(val, *w, opts.wrap))
                 .collect();
-            writeln!(writer, "{}", cells.join(" | "))?;
+            let height = cells.iter().map(Vec::len).max().unwrap_or(1);
+            for line in 0..height {
+                let parts: Vec<String> = cells
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, w)| {
+                        let part = cell.get(line).map(String::as_str).unwrap_or("");
+                        format!("{:<width$}", part, width = w)
+                    })
+                    .collect();
+                writeln!(writer, "{}", parts.join(" | "))?;
+            }
         }
 
-        writeln!(writer, "\n({} rows)", rs.rows.len())?;
+        if opts.footer {
+            writeln!(writer, "\n({} rows)", rs.rows.len())?;
+        }
     }
 
-    writeln!(writer, "({}ms)", result.elapsed_ms)?;
+    if opts.footer {
+        writeln!(writer, "({}ms)", result.elapsed_ms)?;
+    }
 
     Ok(())
 }
 
+/// Fit a value into `width` characters: truncated with `…`, or split into
+/// lines when `wrap` is set.
+fn fit_cell(value: &str, width: usize, wrap: bool) -> Vec<String> {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= width {
+        return vec![value.to_string()];
+    }
+    if wrap {
+        return chars.chunks(width).map(|c| c.iter().collect()).collect();
+    }
+    let mut cut: String = chars[..width - 1].iter().collect();
+    cut.push('…');
+    vec![cut]
+}
+
 /// Print results as CSV.
 fn print_csv(
     writer: &mut dyn Write,
     result: &crate::app::QueryResult,
+    opts: &OutputOptions,
 ) -> Result<(), Box<dyn std::error::Error>> {
     for rs in &result.result_sets {
-        writeln!(writer, "{}", rs.columns.join(","))?;
-        for row in &rs.rows {
-            let escaped: Vec<String> = row
-                .iter()
-                .map(|v| {
-                    if v.contains(',') || v.contains('"') || v.contains('\n') {
-                        format!("\"{}\"", v.replace('"', "\"\""))
-                    } else {
-                        v.clone()
-                    }
-                })
-                .collect();
-            writeln!(writer, "{}", escaped.join(","))?;
+        if opts.headers {
+            writeln!(writer, "{}", rs.columns.join(","))?;
+        }
+        for r in 0..rs.rows.len() {
+            writeln!(writer, "{}", csv_line(&rs.row_values(r)))?;
         }
     }
     Ok(())
 }
 
+/// One row as a CSV line, quoting fields that need it. NULL values are
+/// empty fields and empty strings are `""`, so the two stay apart.
+fn csv_line(row: &[Option<&str>]) -> String {
+    let escaped: Vec<String> = row
+        .iter()
+        .map(|v| {
+            let Some(v) = v else {
+                return String::new();
+            };
+            if v.is_empty() || v.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", v.replace('"', "\"\""))
+            } else {
+                v.to_string()
+            }
+        })
+        .collect();
+    escaped.join(",")
+}
+
 /// Print results as JSON.
 fn print_json(
     writer: &mut dyn Write,
@@ -179,15 +770,10 @@ fn print_json(
     if result.result_sets.len() == 1 {
         let rs = &result.result_sets[0];
         writeln!(writer, "[")?;
-        for (i, row) in rs.rows.iter().enumerate() {
+        for i in 0..rs.rows.len() {
             write!(writer, "  {{")?;
-            for (j, (col, val)) in rs.columns.iter().zip(row).enumerate() {
-                write!(
-                    writer,
-                    "\"{}\": \"{}\"",
-                    col,
-                    val.replace('\\', "\\\\").replace('"', "\\\"")
-                )?;
+            for (j, (col, val)) in rs.columns.iter().zip(rs.row_values(i)).enumerate() {
+                write!(writer, "\"{}\": {}", col, json_value(val))?;
                 if j + 1 < rs.columns.len() {
                     write!(writer, ", ")?;
                 }
@@ -204,15 +790,10 @@ fn print_json(
         writeln!(writer, "[")?;
         for (set_idx, rs) in result.result_sets.iter().enumerate() {
             writeln!(writer, "  [")?;
-            for (i, row) in rs.rows.iter().enumerate() {
+            for i in 0..rs.rows.len() {
                 write!(writer, "    {{")?;
-                for (j, (col, val)) in rs.columns.iter().zip(row).enumerate() {
-                    write!(
-                        writer,
-                        "\"{}\": \"{}\"",
-                        col,
-                        val.replace('\\', "\\\\").replace('"', "\\\"")
-                    )?;
+                for (j, (col, val)) in rs.columns.iter().zip(rs.row_values(i)).enumerate() {
+                    write!(writer, "\"{}\": {}", col, json_value(val))?;
                     if j + 1 < rs.columns.len() {
                         write!(writer, ", ")?;
                     }
@@ -236,6 +817,165 @@ fn print_json(
     Ok(())
 }
 
+/// A value in [`print_json`] output: a quoted string, or `null`.
+fn json_value(val: Option<&str>) -> String {
+    match val {
+        Some(val) => format!("\"{}\"", val.replace('\\', "\\\\").replace('"', "\\\"")),
+        None => "null".to_string(),
+    }
+}
+
+/// Print results as Markdown tables, one per result set.
+fn print_markdown(
+    writer: &mut dyn Write,
+    result: &crate::app::QueryResult,
+    opts: &OutputOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let cell = |s: &str| s.replace('|', "\\|").replace('\n', " ");
+    for (set_idx, rs) in result.result_sets.iter().enumerate() {
+        if rs.columns.is_empty() {
+            continue;
+        }
+        if set_idx > 0 {
+            writeln!(writer)?;
+        }
+        let header: Vec<String> = rs.columns.iter().map(|c| cell(c)).collect();
+        writeln!(writer, "| {} |", header.join(" | "))?;
+        writeln!(writer, "|{}", " --- |".repeat(rs.columns.len()))?;
+        for row in &rs.rows {
+            let cells: Vec<String> = row.iter().map(|v| cell(v)).collect();
+            writeln!(writer, "| {} |", cells.join(" | "))?;
+        }
+        if opts.footer {
+            writeln!(writer, "\n({} rows)", rs.rows.len())?;
+        }
+    }
+    Ok(())
+}
+
+/// Print results as NDJSON: one JSON object per row, across all result sets.
+fn print_ndjson(
+    writer: &mut dyn Write,
+    result: &crate::app::QueryResult,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for rs in &result.result_sets {
+        for r in 0..rs.rows.len() {
+            writeln!(writer, "{}", ndjson_line(&rs.columns, &rs.row_values(r)))?;
+        }
+    }
+    Ok(())
+}
+
 /// Helper trait — re-export for stdin detection.
 use std::io::IsTerminal;
 use std::io::Read;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ndjson_line() {
+        let columns = vec!["id".to_string(), "note".to_string()];
+        let row = [Some("1"), Some("say \"hi\"\n\tC:\\")];
+        assert_eq!(
+            ndjson_line(&columns, &row),
+            r#"{"id":"1","note":"say \"hi\"\n\tC:\\"}"#
+        );
+        assert_eq!(
+            ndjson_line(&columns, &[Some("NULL"), None]),
+            r#"{"id":"NULL","note":null}"#
+        );
+    }
+
+    #[test]
+    fn test_csv_line() {
+        assert_eq!(
+            csv_line(&[Some("1"), Some("a,b"), Some("say \"hi\"")]),
+            "1,\"a,b\",\"say \"\"hi\"\"\""
+        );
+        // NULL is an empty field, the empty string a quoted one
+        assert_eq!(csv_line(&[None, Some(""), Some("x")]), ",\"\",x");
+        assert_eq!(csv_line(&[Some("NULL"), None]), "NULL,");
+    }
+
+    #[test]
+    fn test_markdown() {
+        let result = crate::app::QueryResult::single(
+            vec!["id".to_string(), "expr".to_string()],
+            vec![vec!["1".to_string(), "a|b".to_string()]],
+            0,
+        );
+        let opts = OutputOptions {
+            footer: false,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        write_result(&mut out, &result, "markdown", &opts).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "| id | expr |\n| --- | --- |\n| 1 | a\\|b |\n"
+        );
+        assert_eq!(format_for_extension("jsonl"), Some("ndjson"));
+        assert_eq!(format_for_extension("sql"), None);
+    }
+
+    #[test]
+    fn test_table_max_col_width() {
+        let result = crate::app::QueryResult::single(
+            vec!["id".to_string(), "note".to_string()],
+            vec![vec!["1".to_string(), "abcdefghij".to_string()]],
+            0,
+        );
+        let mut opts = OutputOptions {
+            footer: false,
+            max_col_width: Some(4),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        write_result(&mut out, &result, "table", &opts).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "id | note\n---+-----\n1  | abc…\n"
+        );
+
+        opts.wrap = true;
+        let mut out = Vec::new();
+        write_result(&mut out, &result, "table", &opts).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "id | note\n---+-----\n1  | abcd\n   | efgh\n   | ij  \n"
+        );
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let user = QueryError {
+            severity: Some(16),
+            ..Default::default()
+        };
+        assert_eq!(exit_code(&user), 16);
+        assert_eq!(exit_code(&QueryError::default()), 1);
+    }
+
+    #[test]
+    fn test_table_without_headers_or_footer() {
+        let result = crate::app::QueryResult::single(
+            vec!["id".to_string(), "name".to_string()],
+            vec![vec!["1".to_string(), "cat".to_string()]],
+            5,
+        );
+        let opts = OutputOptions {
+            headers: false,
+            footer: false,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        write_result(&mut out, &result, "table", &opts).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "1  | cat \n");
+
+        let mut out = Vec::new();
+        write_result(&mut out, &result, "csv", &opts).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "1,cat\n");
+    }
+}
@@ -0,0 +1,33 @@
+//! `meow script-schema --out <dir>`: write the schema of the `-d` database
+//! to one `.sql` file per object.
+
+use super::connect;
+use crate::Args;
+use crate::db;
+use std::path::Path;
+
+/// Script the database's objects into `out`, replacing files of the same
+/// name.
+pub async fn run(args: Args, out: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(&args).await?;
+    let objects = db::script::script_schema(&mut client, args.database()).await?;
+    let count = write_files(&objects, out)?;
+    if !args.quiet {
+        eprintln!("Scripted {} objects to {}", count, out.display());
+    }
+    Ok(())
+}
+
+/// Write each object to `dir/<schema>.<name>.<Kind>.sql`. Returns the
+/// number of files written.
+pub fn write_files(
+    objects: &[db::script::ScriptedObject],
+    dir: &Path,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    for object in objects {
+        let path = dir.join(object.file_name());
+        std::fs::write(&path, &object.ddl).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(objects.len())
+}
@@ -3,50 +3,413 @@
 /// Parsed slash command.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SlashCommand {
-    /// `\d` — list all tables and views.
-    ListAll,
-    /// `\d <table>` — describe a table's columns.
-    Describe(String),
+    /// `\d` — list all tables and views. The `+` variants of the listing
+    /// commands (`\d+`, `\dt+`, ...) set `verbose` to add sizes, row counts,
+    /// dates, and descriptions.
+    ListAll { verbose: bool },
+    /// `\d <table>` — describe a table: columns, indexes, keys, check
+    /// constraints, and triggers as separate result sets. `\d+ <table>`
+    /// adds identity, collation, and description details to the columns.
+    Describe { table: ObjectName, verbose: bool },
     /// `\dt` — list tables only.
-    ListTables,
+    ListTables { verbose: bool },
     /// `\dv` — list views only.
-    ListViews,
-    /// `\di` — list indexes.
-    ListIndexes,
+    ListViews { verbose: bool },
+    /// `\di` — list indexes; `\di <table>` lists one table's indexes with
+    /// their key and included columns and filters.
+    ListIndexes {
+        table: Option<ObjectName>,
+        verbose: bool,
+    },
     /// `\df` — list procedures and functions.
-    ListFunctions,
+    ListFunctions { verbose: bool },
     /// `\ds` — list schemas.
-    ListSchemas,
+    ListSchemas { verbose: bool },
     /// `\dn` — list databases.
-    ListDatabases,
+    ListDatabases { verbose: bool },
+    /// `\dT` — list user-defined types; `\dT <name>` lists a table type's
+    /// columns.
+    ListTypes(Option<String>),
+    /// `\dS` — list synonyms and their targets.
+    ListSynonyms,
+    /// `\du` — list server logins.
+    ListLogins,
+    /// `\du <db>` — list a database's users and their roles.
+    ListUsers(String),
+    /// `\dp [object]` — list GRANT/DENY permissions, optionally for one object.
+    ListPermissions(Option<String>),
+    /// `\dg` — list database roles and their members; `\dg <role>` lists
+    /// one role's members.
+    ListRoles(Option<String>),
+    /// `\sf <routine>` — show a routine's definition.
+    ShowDefinition(String),
+    /// `\ef <routine>` — load a routine's definition into the editor.
+    EditDefinition(String),
+    /// `\script db [dir]` — script the current database's schema into the
+    /// editor, or one file per object in `dir`.
+    ScriptDatabase(Option<String>),
+    /// `\script <table>` — load a table's CREATE TABLE script, with its
+    /// constraints and indexes, into the editor.
+    ScriptTable(ObjectName),
+    /// `\frag [table]` — index fragmentation with a REBUILD or REORGANIZE
+    /// statement per index; without a table, indexes of 1000+ pages in the
+    /// current database.
+    Fragmentation(Option<String>),
+    /// `\size [db]` — tables by reserved space, with data, index, and
+    /// unused space and row counts.
+    TableSizes(Option<String>),
+    /// `\jobs` — SQL Agent jobs with their last outcome and next run.
+    ListJobs,
+    /// `\jobs history <name>` — a job's run history, newest first.
+    JobHistory(String),
+    /// `\blocking` — show blocking chains as a blocker → blocked tree.
+    Blocking,
+    /// `\kill <spid>` — end a session.
+    Kill(u32),
+    /// `\session [spid]` — show a session's statement, transactions, memory
+    /// grant, tempdb use, and waits; without a spid, the current session.
+    Session(Option<u32>),
     /// `\c <db>` — switch database.
     UseDatabase(String),
     /// `\conninfo` — show connection info.
     ConnInfo,
+    /// `\show settings [key ...]` — show the session's SET options and
+    /// context, with the `SESSION_CONTEXT` values of the given keys.
+    ShowSettings(Vec<String>),
     /// `\x` — toggle expanded display.
     ToggleExpanded,
     /// `\timing` — toggle query timing display.
     ToggleTiming,
+    /// `\pset null [text]` — set the text shown for NULL values; no text
+    /// shows the current setting.
+    NullDisplay(Option<String>),
+    /// `\pset datestyle [raw|iso|locale|epoch]` — set how dates and times
+    /// are written; no style shows the current one.
+    DateStyle(Option<String>),
+    /// `\pset thousands [on|off]` — group digits in the grid with commas;
+    /// no value toggles.
+    Thousands(Option<bool>),
+    /// `\pset decimals [n|off]` — show non-integer numbers in the grid with
+    /// `n` decimal places; `off` or no value shows them as returned.
+    Decimals(Option<usize>),
+    /// `\pset currency [symbol|off]` — show money values in the grid with
+    /// `symbol` in front; `off` or no value shows them without.
+    Currency(Option<String>),
+    /// `\pset rownum [on|off]` — show row numbers left of the grid; no
+    /// value toggles.
+    RowNumbers(Option<bool>),
+    /// `\pin [n]` — keep the first `n` result columns visible (no arg unpins).
+    PinColumns(usize),
+    /// `\chart <label_col> <value_col>` — bar chart of the current result.
+    Chart { label: String, value: String },
+    /// `\compare [a b]` — compare two result sets side by side.
+    Compare(Option<(CompareSource, CompareSource)>),
+    /// `\explain [query]` — show the estimated plan of a query (default:
+    /// the last one).
+    Explain(Option<String>),
+    /// `\snip ...` — save, load, list, or delete named snippets.
+    Snippet(SnippetCommand),
+    /// `\ws ...` — list, open, switch, or close query workspaces.
+    Workspace(WorkspaceCommand),
+    /// `\e` — edit the last query in `$EDITOR`.
+    EditExternal,
+    /// `\i <path>` — run a script file, split into batches on `GO`.
+    RunScript(String),
+    /// `\o [file]` — also write query results to a file (no arg stops).
+    Tee(Option<String>),
+    /// `\! [cmd]` — run a shell command, or start a shell.
+    Shell(String),
+    /// `\watch [seconds]` — re-run the last query every `seconds` (default 2).
+    Watch(u64),
+    /// `\errverbose` — show the last error in full.
+    ErrVerbose,
+    /// `\gset [prefix]` — store the current single-row result's columns as
+    /// variables.
+    GSet(String),
+    /// `\set name value` — set a variable; `\set` alone lists them.
+    SetVariable(Option<(String, String)>),
+    /// `\set option NAME value` — apply a session option; without one, list
+    /// those set.
+    SetOption(Option<String>),
+    /// `\unset name` — remove a variable.
+    UnsetVariable(String),
+    /// `\prompt [text] name` — ask the user for a variable's value.
+    Prompt { text: Option<String>, name: String },
     /// `\?` — show help.
     Help,
     /// `\q` — quit.
     Quit,
 }
 
+/// `\snip` subcommands for named snippets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnippetCommand {
+    /// `\snip` — browse saved snippets.
+    List,
+    /// `\snip save <name>` — save the lines following the command, or the
+    /// last query run when there are none.
+    Save { name: String, body: Option<String> },
+    /// `\snip <name>` — load a snippet into the editor.
+    Load(String),
+    /// `\snip rm <name>` — delete a snippet.
+    Delete(String),
+}
+
+/// A result `\compare` can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareSource {
+    /// `n` — result set `n` (from 1) of the current result.
+    ResultSet(usize),
+    /// `-n` — the result of the query `n` runs back (from 1).
+    Previous(usize),
+    /// The result set being shown.
+    Current,
+}
+
+/// `\ws` subcommands for query workspaces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkspaceCommand {
+    /// `\ws` — list workspaces.
+    List,
+    /// `\ws new [db]` — open a workspace sharing the main connection, or
+    /// with its own connection to `db`.
+    New { database: Option<String> },
+    /// `\ws <n>` — switch to workspace `n` (counting from 1).
+    Switch(usize),
+    /// `\ws close` — close the current workspace.
+    Close,
+}
+
+/// An object name as typed, `name`, `schema.name`, or
+/// `database.schema.name`, with each part unquoted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectName {
+    pub database: Option<String>,
+    /// `None` for the default schema, as with `database..name`.
+    pub schema: Option<String>,
+    pub name: String,
+}
+
+impl ObjectName {
+    /// Parse a name of one to three dot-separated parts, each optionally
+    /// `[bracketed]` or `"quoted"` so it may contain dots itself.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = Vec::new();
+        let mut part = String::new();
+        let mut chars = text.trim().chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '[' | '"' => {
+                    let close = if c == '[' { ']' } else { '"' };
+                    part.push(c);
+                    loop {
+                        let c = chars.next()?;
+                        part.push(c);
+                        if c == close {
+                            if chars.peek() != Some(&close) {
+                                break;
+                            }
+                            part.push(chars.next()?);
+                        }
+                    }
+                }
+                '.' => parts.push(std::mem::take(&mut part)),
+                _ => part.push(c),
+            }
+        }
+        parts.push(part);
+        let mut parts: Vec<String> = parts.iter().map(|p| unquote_ident(p.trim())).collect();
+        let name = parts.pop().filter(|n| !n.is_empty())?;
+        let schema = parts.pop();
+        let database = parts.pop();
+        if !parts.is_empty()
+            || database.as_deref() == Some("")
+            || (database.is_none() && schema.as_deref() == Some(""))
+        {
+            return None;
+        }
+        Some(Self {
+            database,
+            schema: schema.filter(|s| !s.is_empty()),
+            name,
+        })
+    }
+
+    /// The name within its database, quoted for `OBJECT_ID`: `[schema].[name]`
+    /// or `[name]`.
+    pub fn local(&self) -> String {
+        match self.schema {
+            Some(ref schema) => format!("{}.{}", quote_ident(schema), quote_ident(&self.name)),
+            None => quote_ident(&self.name),
+        }
+    }
+
+    /// The name as written, quoted: `[db].[schema].[name]`, `[db]..[name]`,
+    /// `[schema].[name]`, or `[name]`.
+    pub fn qualified(&self) -> String {
+        match (&self.database, &self.schema) {
+            (Some(database), Some(_)) => format!("{}.{}", quote_ident(database), self.local()),
+            (Some(database), None) => format!("{}..{}", quote_ident(database), self.local()),
+            (None, _) => self.local(),
+        }
+    }
+}
+
+/// Isolation levels `\set option ISOLATION` accepts.
+const ISOLATION_LEVELS: &[&str] = &[
+    "READ UNCOMMITTED",
+    "READ COMMITTED",
+    "REPEATABLE READ",
+    "SNAPSHOT",
+    "SERIALIZABLE",
+];
+
+/// A session setting applied with `SET` (`\set option`), kept so it can be
+/// applied again when the connection is re-established.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionOption {
+    /// As written after `SET`: `ANSI_NULLS`, `ARITHABORT`, `NOCOUNT`,
+    /// `LOCK_TIMEOUT`, or `TRANSACTION ISOLATION LEVEL`.
+    pub name: String,
+    /// `ON`/`OFF`, milliseconds for `LOCK_TIMEOUT`, or an isolation level.
+    pub value: String,
+}
+
+impl SessionOption {
+    /// Parse `NAME value`, case-insensitively. The isolation level may be
+    /// given as `ISOLATION`, `ISOLATION LEVEL`, or in full, and its value
+    /// with underscores (`read_committed`).
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let upper = spec.to_uppercase();
+        let words: Vec<&str> = upper.split_whitespace().collect();
+        let (name, value) = match words.as_slice() {
+            ["TRANSACTION", "ISOLATION", "LEVEL", value @ ..]
+            | ["ISOLATION", "LEVEL", value @ ..]
+            | ["ISOLATION" | "ISOLATION_LEVEL", value @ ..] => (
+                "TRANSACTION ISOLATION LEVEL",
+                value.join(" ").replace('_', " "),
+            ),
+            [
+                name @ ("ANSI_NULLS" | "ARITHABORT" | "NOCOUNT" | "LOCK_TIMEOUT"),
+                value @ ..,
+            ] => (*name, value.join(" ")),
+            _ => {
+                return Err(format!(
+                    "Unknown option {} (ANSI_NULLS, ARITHABORT, NOCOUNT, LOCK_TIMEOUT, or ISOLATION)",
+                    spec.trim()
+                ));
+            }
+        };
+        let valid = match name {
+            "LOCK_TIMEOUT" => value.parse::<i64>().is_ok_and(|ms| ms >= -1),
+            "TRANSACTION ISOLATION LEVEL" => ISOLATION_LEVELS.contains(&value.as_str()),
+            _ => value == "ON" || value == "OFF",
+        };
+        if !valid {
+            let expected = match name {
+                "LOCK_TIMEOUT" => "milliseconds, or -1 to wait forever".to_string(),
+                "TRANSACTION ISOLATION LEVEL" => ISOLATION_LEVELS.join(", "),
+                _ => "ON or OFF".to_string(),
+            };
+            return Err(format!("Invalid value for {} ({})", name, expected));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            value,
+        })
+    }
+
+    /// The `SET` statement applying the option.
+    pub fn sql(&self) -> String {
+        format!("SET {} {}", self.name, self.value)
+    }
+}
+
 /// Result of handling a slash command.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CommandAction {
     /// Execute this SQL and display results.
     ExecuteSql(String),
+    /// Execute SQL whose `@P1`, `@P2`, ... are bound to `params` (names the
+    /// user typed), so they never become part of the SQL text.
+    ExecuteQuery { sql: String, params: Vec<String> },
     /// Display a message in the results pane (columns + rows).
     DisplayMessage {
         columns: Vec<String>,
         rows: Vec<Vec<String>>,
     },
+    /// Show blocking chains (the rows are arranged into a tree, so handled
+    /// by the caller).
+    Blocking,
     /// Toggle expanded mode.
     ToggleExpanded,
     /// Toggle timing mode.
     ToggleTiming,
+    /// Set (`Some`) or show (`None`) the text displayed for NULL values.
+    NullDisplay(Option<String>),
+    /// Set (`Some`) or show (`None`) the date/time display style.
+    DateStyle(Option<String>),
+    /// Turn digit grouping on or off (`None` toggles).
+    Thousands(Option<bool>),
+    /// Set the decimal places for numbers in the grid (`None` for as returned).
+    Decimals(Option<usize>),
+    /// Set the symbol shown before money values in the grid (`None` for none).
+    Currency(Option<String>),
+    /// Show or hide the row-number gutter (`None` toggles).
+    RowNumbers(Option<bool>),
+    /// Pin the first `n` result columns.
+    PinColumns(usize),
+    /// Chart the current result set by label and value columns.
+    ShowChart { label: String, value: String },
+    /// Compare two results, or by default the current result's first two
+    /// result sets, else the previous query's result with the current one.
+    Compare(Option<(CompareSource, CompareSource)>),
+    /// Show the estimated plan tree of this query, or the last one.
+    Explain(Option<String>),
+    /// Manage named snippets (needs file access, so handled by the caller).
+    Snippet(SnippetCommand),
+    /// Manage workspaces (needs app state and connections, so handled by
+    /// the caller).
+    Workspace(WorkspaceCommand),
+    /// Open the last query in an external editor.
+    EditExternal,
+    /// Fetch a routine's definition and show it (`edit: false`) or load it
+    /// into the editor as `CREATE OR ALTER` (`edit: true`).
+    Definition { name: String, edit: bool },
+    /// Script the current database's schema into the editor (`None`) or a
+    /// directory (needs the connection and file access, so handled by the
+    /// caller).
+    ScriptDatabase(Option<String>),
+    /// Script a table into the editor (needs the connection, so handled by
+    /// the caller).
+    ScriptTable(ObjectName),
+    /// Run the script at this path batch by batch.
+    RunScript(String),
+    /// Start (`Some`) or stop (`None`) copying results to a file.
+    Tee(Option<String>),
+    /// Run a shell command (empty for an interactive shell).
+    Shell(String),
+    /// Re-run the last query every this many seconds until a key is pressed.
+    Watch(u64),
+    /// Show the last error's number, severity, state, line, and procedure.
+    ErrVerbose,
+    /// Store the current single-row result as variables with this prefix.
+    GSet(String),
+    /// Set a variable to a value.
+    SetVariable { name: String, value: String },
+    /// Apply a session option, or report why it can't be (needs the
+    /// connection and app state, so handled by the caller).
+    SetOption(Result<SessionOption, String>),
+    /// List the session options set (needs app state, so handled by the
+    /// caller).
+    ListOptions,
+    /// Remove a variable.
+    UnsetVariable(String),
+    /// Ask for a variable's value, showing `text` (interactive, so handled by
+    /// the caller).
+    Prompt { text: Option<String>, name: String },
+    /// List all variables (needs app state, so handled by the caller).
+    ListVariables,
     /// Quit the application.
     Quit,
 }
@@ -62,56 +425,675 @@ pub fn parse(input: &str) -> Option<SlashCommand> {
     let cmd = parts[0];
     let arg = parts.get(1).map(|s| s.trim()).filter(|s| !s.is_empty());
 
+    let (cmd, verbose) = match cmd.strip_suffix('+') {
+        Some(base @ ("\\d" | "\\dt" | "\\dv" | "\\di" | "\\df" | "\\ds" | "\\dn")) => (base, true),
+        _ => (cmd, false),
+    };
+
     match cmd {
         "\\d" => match arg {
-            Some(table) => Some(SlashCommand::Describe(table.to_string())),
-            None => Some(SlashCommand::ListAll),
+            Some(table) => Some(SlashCommand::Describe {
+                table: ObjectName::parse(table)?,
+                verbose,
+            }),
+            None => Some(SlashCommand::ListAll { verbose }),
+        },
+        "\\dt" => Some(SlashCommand::ListTables { verbose }),
+        "\\dv" => Some(SlashCommand::ListViews { verbose }),
+        "\\di" => Some(SlashCommand::ListIndexes {
+            table: match arg {
+                Some(table) => Some(ObjectName::parse(table)?),
+                None => None,
+            },
+            verbose,
+        }),
+        "\\df" => Some(SlashCommand::ListFunctions { verbose }),
+        "\\ds" => Some(SlashCommand::ListSchemas { verbose }),
+        "\\dn" => Some(SlashCommand::ListDatabases { verbose }),
+        "\\dT" => Some(SlashCommand::ListTypes(arg.map(|name| name.to_string()))),
+        "\\dS" => Some(SlashCommand::ListSynonyms),
+        "\\du" => match arg {
+            Some(db) => Some(SlashCommand::ListUsers(db.to_string())),
+            None => Some(SlashCommand::ListLogins),
+        },
+        "\\dp" => Some(SlashCommand::ListPermissions(arg.map(|o| o.to_string()))),
+        "\\dg" => Some(SlashCommand::ListRoles(arg.map(unquote_ident))),
+        "\\sf" => arg.map(|name| SlashCommand::ShowDefinition(name.to_string())),
+        "\\ef" => arg.map(|name| SlashCommand::EditDefinition(name.to_string())),
+        "\\script" => {
+            let arg = arg?;
+            let (target, rest) = arg.split_once(char::is_whitespace).unwrap_or((arg, ""));
+            let dir = unquote(rest.trim());
+            match target {
+                "db" => Some(SlashCommand::ScriptDatabase(
+                    Some(dir).filter(|d| !d.is_empty()).map(str::to_string),
+                )),
+                _ => Some(SlashCommand::ScriptTable(ObjectName::parse(arg)?)),
+            }
+        }
+        "\\frag" => Some(SlashCommand::Fragmentation(arg.map(|t| t.to_string()))),
+        "\\size" => Some(SlashCommand::TableSizes(arg.map(|db| db.to_string()))),
+        "\\jobs" => match arg {
+            None => Some(SlashCommand::ListJobs),
+            Some(arg) => match arg.split_once(char::is_whitespace)? {
+                ("history", name) => {
+                    Some(SlashCommand::JobHistory(unquote(name.trim()).to_string()))
+                }
+                _ => None,
+            },
+        },
+        "\\blocking" => Some(SlashCommand::Blocking),
+        "\\kill" => arg?.parse().ok().map(SlashCommand::Kill),
+        "\\session" => match arg {
+            Some(spid) => spid
+                .parse()
+                .ok()
+                .map(|spid| SlashCommand::Session(Some(spid))),
+            None => Some(SlashCommand::Session(None)),
         },
-        "\\dt" => Some(SlashCommand::ListTables),
-        "\\dv" => Some(SlashCommand::ListViews),
-        "\\di" => Some(SlashCommand::ListIndexes),
-        "\\df" => Some(SlashCommand::ListFunctions),
-        "\\ds" => Some(SlashCommand::ListSchemas),
-        "\\dn" => Some(SlashCommand::ListDatabases),
-        "\\c" => arg.map(|db| SlashCommand::UseDatabase(db.to_string())),
+        "\\c" => arg.map(|db| SlashCommand::UseDatabase(unquote_ident(db))),
         "\\conninfo" => Some(SlashCommand::ConnInfo),
+        "\\show" => {
+            let mut words = arg?.split_whitespace();
+            words
+                .next()
+                .filter(|w| w.eq_ignore_ascii_case("settings"))?;
+            Some(SlashCommand::ShowSettings(
+                words.map(|key| unquote(key).to_string()).collect(),
+            ))
+        }
         "\\x" => Some(SlashCommand::ToggleExpanded),
         "\\timing" => Some(SlashCommand::ToggleTiming),
+        "\\pset" => {
+            let arg = arg?;
+            let (option, value) = arg.split_once(char::is_whitespace).unwrap_or((arg, ""));
+            let value = unquote(value.trim());
+            match option {
+                "null" => Some(SlashCommand::NullDisplay(
+                    Some(value).filter(|v| !v.is_empty()).map(str::to_string),
+                )),
+                "datestyle" => Some(SlashCommand::DateStyle(
+                    Some(value).filter(|v| !v.is_empty()).map(str::to_string),
+                )),
+                "thousands" => parse_on_off(value).map(SlashCommand::Thousands),
+                "rownum" => parse_on_off(value).map(SlashCommand::RowNumbers),
+                "decimals" => match value {
+                    "" | "off" => Some(SlashCommand::Decimals(None)),
+                    n => n.parse().ok().map(|n| SlashCommand::Decimals(Some(n))),
+                },
+                "currency" => Some(SlashCommand::Currency(
+                    Some(value)
+                        .filter(|v| !v.is_empty() && *v != "off")
+                        .map(str::to_string),
+                )),
+                _ => None,
+            }
+        }
+        "\\pin" => match arg {
+            Some(n) => n.parse().ok().map(SlashCommand::PinColumns),
+            None => Some(SlashCommand::PinColumns(0)),
+        },
+        "\\chart" => {
+            let mut cols = arg?.split_whitespace();
+            match (cols.next(), cols.next(), cols.next()) {
+                (Some(label), Some(value), None) => Some(SlashCommand::Chart {
+                    label: label.to_string(),
+                    value: value.to_string(),
+                }),
+                _ => None,
+            }
+        }
+        "\\compare" => match arg {
+            None => Some(SlashCommand::Compare(None)),
+            Some(arg) => {
+                let source = |arg: &str| {
+                    let (previous, n) = match arg.strip_prefix('-') {
+                        Some(n) => (true, n),
+                        None => (false, arg),
+                    };
+                    match n.parse::<usize>().ok().filter(|&n| n > 0)? {
+                        n if previous => Some(CompareSource::Previous(n)),
+                        n => Some(CompareSource::ResultSet(n)),
+                    }
+                };
+                let mut sources = arg.split_whitespace().map(source);
+                match (sources.next(), sources.next(), sources.next()) {
+                    (Some(Some(a)), Some(Some(b)), None) => {
+                        Some(SlashCommand::Compare(Some((a, b))))
+                    }
+                    // One past result is compared with the current one
+                    (Some(Some(a @ CompareSource::Previous(_))), None, None) => {
+                        Some(SlashCommand::Compare(Some((a, CompareSource::Current))))
+                    }
+                    _ => None,
+                }
+            }
+        },
+        "\\explain" => Some(SlashCommand::Explain(arg.map(str::to_string))),
+        "\\snip" => parse_snippet(arg).map(SlashCommand::Snippet),
+        "\\ws" => parse_workspace(arg).map(SlashCommand::Workspace),
+        "\\e" => Some(SlashCommand::EditExternal),
+        "\\i" => arg.map(|path| SlashCommand::RunScript(unquote(path).to_string())),
+        "\\o" => Some(SlashCommand::Tee(arg.map(|path| unquote(path).to_string()))),
+        "\\!" => Some(SlashCommand::Shell(arg.unwrap_or("").to_string())),
+        "\\watch" => match arg {
+            Some(secs) => secs
+                .parse()
+                .ok()
+                .filter(|&s| s > 0)
+                .map(SlashCommand::Watch),
+            None => Some(SlashCommand::Watch(2)),
+        },
+        "\\errverbose" => Some(SlashCommand::ErrVerbose),
+        "\\gset" => Some(SlashCommand::GSet(arg.unwrap_or("").to_string())),
+        "\\set" => match arg {
+            Some(arg) => {
+                let (name, value) = arg.split_once(char::is_whitespace).unwrap_or((arg, ""));
+                if name.eq_ignore_ascii_case("option") {
+                    let spec = value.trim();
+                    return Some(SlashCommand::SetOption(
+                        (!spec.is_empty()).then(|| spec.to_string()),
+                    ));
+                }
+                Some(SlashCommand::SetVariable(Some((
+                    name.to_string(),
+                    unquote(value.trim()).to_string(),
+                ))))
+            }
+            None => Some(SlashCommand::SetVariable(None)),
+        },
+        "\\unset" => arg.map(|name| SlashCommand::UnsetVariable(name.to_string())),
+        "\\prompt" => {
+            let arg = arg?;
+            let (text, name) = match arg.rsplit_once(char::is_whitespace) {
+                Some((text, name)) => (Some(unquote(text.trim()).to_string()), name),
+                None => (None, arg),
+            };
+            Some(SlashCommand::Prompt {
+                text,
+                name: name.to_string(),
+            })
+        }
         "\\?" => Some(SlashCommand::Help),
         "\\q" => Some(SlashCommand::Quit),
         _ => None,
     }
 }
 
+/// Strip one pair of matching quotes, for paths with spaces.
+fn unquote(s: &str) -> &str {
+    for q in ['"', '\''] {
+        if let Some(inner) = s.strip_prefix(q).and_then(|s| s.strip_suffix(q)) {
+            return inner;
+        }
+    }
+    s
+}
+
+/// An identifier as typed, without `[...]` or `"..."` quoting.
+fn unquote_ident(name: &str) -> String {
+    if let Some(inner) = name.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        return inner.replace("]]", "]");
+    }
+    if let Some(inner) = name.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        return inner.replace("\"\"", "\"");
+    }
+    name.to_string()
+}
+
+/// A `\pset` switch: `on`, `off`, or empty (`None`, to toggle).
+fn parse_on_off(value: &str) -> Option<Option<bool>> {
+    match value {
+        "" => Some(None),
+        "on" => Some(Some(true)),
+        "off" => Some(Some(false)),
+        _ => None,
+    }
+}
+
+/// Turn the first `CREATE` of a module definition into `CREATE OR ALTER`,
+/// so running the edited text replaces the object. Comments are skipped.
+pub fn create_or_alter(definition: &str) -> String {
+    let bytes = definition.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if definition[i..].starts_with("--") {
+            i = definition[i..].find('\n').map_or(bytes.len(), |n| i + n);
+        } else if definition[i..].starts_with("/*") {
+            i = definition[i..]
+                .find("*/")
+                .map_or(bytes.len(), |n| i + n + 2);
+        } else if bytes[i].is_ascii_alphabetic() {
+            let end = definition[i..]
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .map_or(bytes.len(), |n| i + n);
+            if definition[i..end].eq_ignore_ascii_case("CREATE") {
+                return format!("{}CREATE OR ALTER{}", &definition[..i], &definition[end..]);
+            }
+            return definition.to_string();
+        } else {
+            i += definition[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    definition.to_string()
+}
+
+/// Run `sql`, whose only parameter is `@P1`, in `database` (the current
+/// one for `None`) through that database's `sp_executesql`.
+fn in_database(database: Option<&str>, sql: String) -> String {
+    match database {
+        Some(database) => format!(
+            "EXEC {}.sys.sp_executesql N'{}', N'@P1 nvarchar(4000)', @P1 = @P1",
+            quote_ident(database),
+            sql.replace('\'', "''")
+        ),
+        None => sql,
+    }
+}
+
+/// Batch for `\d <table>`, the table's `[schema].[name]` bound to `@P1`:
+/// one result set each for columns, indexes, keys, check constraints, and
+/// triggers; for a view its columns and definition; for a procedure or
+/// function its parameters and return type. Without a schema the object is
+/// found as `OBJECT_ID` finds it, in the user's default schema, then `dbo`.
+fn describe_table(verbose: bool) -> String {
+    let columns = if verbose {
+        "SELECT c.name AS COLUMN_NAME, TYPE_NAME(c.user_type_id) AS DATA_TYPE, c.max_length, c.precision, c.scale, c.is_nullable, c.is_identity, c.is_computed, dc.definition AS COLUMN_DEFAULT, c.collation_name, CAST(ep.value AS nvarchar(4000)) AS description FROM sys.columns c LEFT JOIN sys.default_constraints dc ON dc.object_id = c.default_object_id LEFT JOIN sys.extended_properties ep ON ep.class = 1 AND ep.major_id = c.object_id AND ep.minor_id = c.column_id AND ep.name = 'MS_Description' WHERE c.object_id = OBJECT_ID(@P1) ORDER BY c.column_id"
+            .to_string()
+    } else {
+        "SELECT COLUMN_NAME, DATA_TYPE, CHARACTER_MAXIMUM_LENGTH, IS_NULLABLE, COLUMN_DEFAULT FROM INFORMATION_SCHEMA.COLUMNS WHERE TABLE_SCHEMA = OBJECT_SCHEMA_NAME(OBJECT_ID(@P1)) AND TABLE_NAME = OBJECT_NAME(OBJECT_ID(@P1)) ORDER BY ORDINAL_POSITION"
+            .to_string()
+    };
+    let object = "OBJECT_ID(@P1)";
+    let key_columns = "SELECT STRING_AGG(c.name, ', ') WITHIN GROUP (ORDER BY ic.key_ordinal) FROM sys.index_columns ic JOIN sys.columns c ON c.object_id = ic.object_id AND c.column_id = ic.column_id";
+    let fk_columns = "SELECT STRING_AGG(c.name, ', ') WITHIN GROUP (ORDER BY fc.constraint_column_id) FROM sys.foreign_key_columns fc JOIN sys.columns c";
+    let table = [
+        columns.clone(),
+        table_indexes(false),
+        format!(
+            "SELECT k.name AS constraint_name, k.type_desc AS constraint_type, ({key_columns} WHERE ic.object_id = k.parent_object_id AND ic.index_id = k.unique_index_id AND ic.is_included_column = 0) AS columns, NULL AS referenced_table, NULL AS referenced_columns, NULL AS on_delete, NULL AS on_update FROM sys.key_constraints k WHERE k.parent_object_id = {object} \
+             UNION ALL SELECT f.name, f.type_desc, ({fk_columns} ON c.object_id = fc.parent_object_id AND c.column_id = fc.parent_column_id WHERE fc.constraint_object_id = f.object_id), OBJECT_SCHEMA_NAME(f.referenced_object_id) + '.' + OBJECT_NAME(f.referenced_object_id), ({fk_columns} ON c.object_id = fc.referenced_object_id AND c.column_id = fc.referenced_column_id WHERE fc.constraint_object_id = f.object_id), f.delete_referential_action_desc, f.update_referential_action_desc FROM sys.foreign_keys f WHERE f.parent_object_id = {object} \
+             ORDER BY constraint_type, constraint_name"
+        ),
+        format!(
+            "SELECT cc.name AS check_constraint, COL_NAME(cc.parent_object_id, cc.parent_column_id) AS column_name, cc.definition, cc.is_disabled FROM sys.check_constraints cc WHERE cc.parent_object_id = {object} ORDER BY cc.name"
+        ),
+        format!(
+            "SELECT t.name AS trigger_name, CASE WHEN t.is_instead_of_trigger = 1 THEN 'INSTEAD OF' ELSE 'AFTER' END AS timing, (SELECT STRING_AGG(te.type_desc, ', ') FROM sys.trigger_events te WHERE te.object_id = t.object_id) AS events, t.is_disabled FROM sys.triggers t WHERE t.parent_id = {object} ORDER BY t.name"
+        ),
+    ]
+    .join(";\n");
+    // `sys.parameters` holds a scalar function's return type as parameter 0.
+    // Its `default_value` is only filled for CLR routines, so T-SQL defaults
+    // aren't shown.
+    let routine = format!(
+        "SELECT p.name AS parameter_name, {} AS data_type, IIF(p.is_output = 1, 'OUTPUT', 'IN') AS direction FROM sys.parameters p WHERE p.object_id = {object} AND p.parameter_id > 0 ORDER BY p.parameter_id;\n\
+         SELECT o.type_desc AS routine_type, CASE WHEN o.type IN ('FN', 'FS') THEN (SELECT {} FROM sys.parameters r WHERE r.object_id = o.object_id AND r.parameter_id = 0) WHEN o.type IN ('IF', 'TF', 'FT') THEN 'TABLE' END AS return_type FROM sys.objects o WHERE o.object_id = {object}",
+        declared_type("p"),
+        declared_type("r")
+    );
+    format!(
+        "IF OBJECTPROPERTY({object}, 'IsView') = 1\nBEGIN\n{columns};\nSELECT OBJECT_DEFINITION({object}) AS definition\nEND\n\
+         ELSE IF EXISTS (SELECT 1 FROM sys.objects WHERE object_id = {object} AND type IN ('P', 'PC', 'X', 'FN', 'FS', 'IF', 'TF', 'FT'))\nBEGIN\n{routine}\nEND\n\
+         ELSE\nBEGIN\n{table}\nEND"
+    )
+}
+
+/// One session in detail (`@@SPID` without `spid`): its current statement,
+/// or the last one when idle, with open transactions, memory grant, and
+/// tempdb use; then its waits since it connected, longest first. A session
+/// that doesn't exist is an error.
+fn session_detail(spid: Option<u32>) -> String {
+    let spid = spid.map_or_else(|| "@@SPID".to_string(), |spid| spid.to_string());
+    format!(
+        "DECLARE @spid int = {spid};\nIF NOT EXISTS (SELECT 1 FROM sys.dm_exec_sessions WHERE session_id = @spid) RAISERROR(N'Session %d not found', 16, 1, @spid) ELSE BEGIN          SELECT s.session_id, s.login_name, s.host_name, s.program_name, DB_NAME(COALESCE(r.database_id, s.database_id)) AS database_name, COALESCE(r.status, s.status) AS status, r.command,          CASE WHEN r.session_id IS NULL THEN 'last' ELSE 'current' END AS statement_is,          CASE WHEN r.sql_handle IS NULL THEN t.text ELSE SUBSTRING(t.text, r.statement_start_offset / 2 + 1, (CASE r.statement_end_offset WHEN -1 THEN DATALENGTH(t.text) ELSE r.statement_end_offset END - r.statement_start_offset) / 2 + 1) END AS statement,          DATEDIFF(SECOND, COALESCE(r.start_time, s.last_request_start_time), SYSDATETIME()) AS duration_s, s.open_transaction_count, r.blocking_session_id AS blocked_by, r.wait_type, r.wait_time AS wait_ms, r.wait_resource, r.last_wait_type,          g.requested_memory_kb, g.granted_memory_kb, g.used_memory_kb, CAST(tu.pages * 8 / 1024.0 AS decimal(18, 2)) AS tempdb_mb,          s.cpu_time AS cpu_ms, s.logical_reads, s.reads, s.writes, r.percent_complete          FROM sys.dm_exec_sessions s          LEFT JOIN sys.dm_exec_requests r ON r.session_id = s.session_id          LEFT JOIN sys.dm_exec_connections c ON c.session_id = s.session_id AND c.parent_connection_id IS NULL          OUTER APPLY sys.dm_exec_sql_text(COALESCE(r.sql_handle, c.most_recent_sql_handle)) t          OUTER APPLY (SELECT SUM(mg.requested_memory_kb) AS requested_memory_kb, SUM(mg.granted_memory_kb) AS granted_memory_kb, SUM(mg.used_memory_kb) AS used_memory_kb FROM sys.dm_exec_query_memory_grants mg WHERE mg.session_id = s.session_id) g          OUTER APPLY (SELECT SUM(u.user_objects_alloc_page_count - u.user_objects_dealloc_page_count + u.internal_objects_alloc_page_count - u.internal_objects_dealloc_page_count) AS pages FROM (         SELECT user_objects_alloc_page_count, user_objects_dealloc_page_count, internal_objects_alloc_page_count, internal_objects_dealloc_page_count FROM sys.dm_db_session_space_usage WHERE session_id = s.session_id          UNION ALL SELECT user_objects_alloc_page_count, user_objects_dealloc_page_count, internal_objects_alloc_page_count, internal_objects_dealloc_page_count FROM sys.dm_db_task_space_usage WHERE session_id = s.session_id) u) tu          WHERE s.session_id = @spid;          SELECT wait_type, waiting_tasks_count, wait_time_ms, max_wait_time_ms, signal_wait_time_ms FROM sys.dm_exec_session_wait_stats WHERE session_id = @spid ORDER BY wait_time_ms DESC          END"
+    )
+}
+
+/// `@@OPTIONS` bits `\show settings` reports, by option name.
+const SET_OPTIONS: &[(&str, u32)] = &[
+    ("ANSI_NULLS", 32),
+    ("ANSI_NULL_DFLT_ON", 1024),
+    ("ANSI_PADDING", 16),
+    ("ANSI_WARNINGS", 8),
+    ("ARITHABORT", 64),
+    ("ARITHIGNORE", 128),
+    ("CONCAT_NULL_YIELDS_NULL", 4096),
+    ("CURSOR_CLOSE_ON_COMMIT", 4),
+    ("IMPLICIT_TRANSACTIONS", 2),
+    ("NOCOUNT", 512),
+    ("NUMERIC_ROUNDABORT", 8192),
+    ("QUOTED_IDENTIFIER", 256),
+    ("XACT_ABORT", 16384),
+];
+
+/// The session's isolation level, language, date settings, lock timeout,
+/// and `SET` options as setting/value rows, then the `SESSION_CONTEXT`
+/// values of `context_keys` keys, bound to `@P1`, `@P2`, ...
+fn show_settings(context_keys: usize) -> String {
+    let mut rows = vec![
+        "(N'TRANSACTION ISOLATION LEVEL', CASE s.transaction_isolation_level WHEN 1 THEN N'READ UNCOMMITTED' WHEN 2 THEN N'READ COMMITTED' WHEN 3 THEN N'REPEATABLE READ' WHEN 4 THEN N'SERIALIZABLE' WHEN 5 THEN N'SNAPSHOT' ELSE N'UNSPECIFIED' END)".to_string(),
+        "(N'LANGUAGE', @@LANGUAGE)".to_string(),
+        "(N'DATEFORMAT', s.date_format)".to_string(),
+        "(N'DATEFIRST', CAST(@@DATEFIRST AS nvarchar(10)))".to_string(),
+        "(N'LOCK_TIMEOUT', CAST(@@LOCK_TIMEOUT AS nvarchar(20)))".to_string(),
+        "(N'DEADLOCK_PRIORITY', CAST(s.deadlock_priority AS nvarchar(10)))".to_string(),
+        "(N'TEXTSIZE', CAST(@@TEXTSIZE AS nvarchar(20)))".to_string(),
+    ];
+    rows.extend(SET_OPTIONS.iter().map(|(name, bit)| {
+        format!("(N'{name}', CASE WHEN @@OPTIONS & {bit} <> 0 THEN N'ON' ELSE N'OFF' END)")
+    }));
+    rows.extend((1..=context_keys).map(|i| {
+        format!(
+            "(N'SESSION_CONTEXT(' + @P{i} + N')', CAST(SESSION_CONTEXT(@P{i}) AS nvarchar(4000)))"
+        )
+    }));
+    format!(
+        "SELECT v.setting, v.value FROM sys.dm_exec_sessions s CROSS APPLY (VALUES {}) v(setting, value) WHERE s.session_id = @@SPID",
+        rows.join(", ")
+    )
+}
+
+/// Tables and/or views (`types` is a list of `sys.objects` type codes) with
+/// row counts, sizes, dates, and `MS_Description`, for the `+` listings.
+fn verbose_object_listing(types: &str) -> String {
+    format!(
+        "SELECT s.name AS TABLE_SCHEMA, o.name AS TABLE_NAME, CASE o.type WHEN 'U' THEN 'BASE TABLE' ELSE 'VIEW' END AS TABLE_TYPE, st.row_count, CAST(st.used_kb / 1024.0 AS decimal(18, 2)) AS size_mb, o.create_date, o.modify_date, CAST(ep.value AS nvarchar(4000)) AS description FROM sys.objects o JOIN sys.schemas s ON s.schema_id = o.schema_id OUTER APPLY (SELECT SUM(CASE WHEN ps.index_id IN (0, 1) THEN ps.row_count ELSE 0 END) AS row_count, SUM(ps.used_page_count) * 8 AS used_kb FROM sys.dm_db_partition_stats ps WHERE ps.object_id = o.object_id) st LEFT JOIN sys.extended_properties ep ON ep.class = 1 AND ep.major_id = o.object_id AND ep.minor_id = 0 AND ep.name = 'MS_Description' WHERE o.type IN ({types}) AND o.is_ms_shipped = 0 ORDER BY s.name, o.name"
+    )
+}
+
+/// Batch for `\frag`: fragmentation and page counts from
+/// `sys.dm_db_index_physical_stats` (`LIMITED` scan), most fragmented
+/// first, with the usual maintenance statement: REBUILD from 30%,
+/// REORGANIZE from 5%. A named table that doesn't exist is an error rather
+/// than a scan of the whole database. The table name is bound to `@P1`.
+fn fragmentation(table: bool) -> String {
+    let (object, filter) = if table {
+        ("OBJECT_ID(@P1)", "")
+    } else {
+        ("NULL", " AND ps.page_count >= 1000")
+    };
+    let target = "QUOTENAME(i.name) + ' ON ' + QUOTENAME(s.name) + '.' + QUOTENAME(o.name)";
+    let query = format!(
+        "SELECT s.name AS schema_name, o.name AS table_name, i.name AS index_name, ps.index_type_desc, CAST(ps.avg_fragmentation_in_percent AS decimal(5, 1)) AS fragmentation_pct, ps.page_count, CASE WHEN ps.avg_fragmentation_in_percent >= 30 THEN 'ALTER INDEX ' + {target} + ' REBUILD;' WHEN ps.avg_fragmentation_in_percent >= 5 THEN 'ALTER INDEX ' + {target} + ' REORGANIZE;' ELSE '' END AS statement FROM sys.dm_db_index_physical_stats(DB_ID(), {object}, NULL, NULL, 'LIMITED') ps JOIN sys.indexes i ON i.object_id = ps.object_id AND i.index_id = ps.index_id JOIN sys.objects o ON o.object_id = ps.object_id JOIN sys.schemas s ON s.schema_id = o.schema_id WHERE ps.index_id > 0 AND ps.alloc_unit_type_desc = 'IN_ROW_DATA'{filter} ORDER BY ps.avg_fragmentation_in_percent DESC"
+    );
+    if table {
+        format!("IF {object} IS NULL RAISERROR(N'Table %s not found', 16, 1, @P1) ELSE {query}")
+    } else {
+        query
+    }
+}
+
+/// Query for the indexes of the table bound to `@P1`, with their key and
+/// included columns and filter; `verbose` adds row counts, sizes,
+/// statistics dates, and descriptions.
+fn table_indexes(verbose: bool) -> String {
+    let index_columns = |included: u8| {
+        format!(
+            "(SELECT STRING_AGG(c.name, ', ') WITHIN GROUP (ORDER BY ic.key_ordinal, ic.index_column_id) FROM sys.index_columns ic JOIN sys.columns c ON c.object_id = ic.object_id AND c.column_id = ic.column_id WHERE ic.object_id = i.object_id AND ic.index_id = i.index_id AND ic.is_included_column = {included})"
+        )
+    };
+    let (extra_columns, extra_joins) = if verbose {
+        (
+            ", st.row_count, CAST(st.used_kb / 1024.0 AS decimal(18, 2)) AS size_mb, STATS_DATE(i.object_id, i.index_id) AS stats_updated, CAST(ep.value AS nvarchar(4000)) AS description",
+            " OUTER APPLY (SELECT SUM(ps.row_count) AS row_count, SUM(ps.used_page_count) * 8 AS used_kb FROM sys.dm_db_partition_stats ps WHERE ps.object_id = i.object_id AND ps.index_id = i.index_id) st LEFT JOIN sys.extended_properties ep ON ep.class = 7 AND ep.major_id = i.object_id AND ep.minor_id = i.index_id AND ep.name = 'MS_Description'",
+        )
+    } else {
+        ("", "")
+    };
+    format!(
+        "SELECT i.name AS index_name, i.type_desc, i.is_unique, i.is_primary_key, {} AS key_columns, {} AS included_columns, i.filter_definition{extra_columns} FROM sys.indexes i{extra_joins} WHERE i.object_id = OBJECT_ID(@P1) AND i.name IS NOT NULL ORDER BY i.name",
+        index_columns(0),
+        index_columns(1)
+    )
+}
+
+/// `run_duration` and friends in `msdb` are `HHMMSS` integers; render them
+/// as `hh:mm:ss`.
+fn agent_duration(column: &str) -> String {
+    format!(
+        "STUFF(STUFF(RIGHT('000000' + CAST({} AS varchar(10)), 6), 5, 0, ':'), 3, 0, ':')",
+        column
+    )
+}
+
+/// Batch for `\jobs` and, with `history`, `\jobs history <name>` for the
+/// job named by `@P1`, from `msdb`'s job tables.
+fn jobs(history: bool) -> String {
+    let outcome = "CASE h.run_status WHEN 0 THEN 'Failed' WHEN 1 THEN 'Succeeded' WHEN 2 THEN 'Retry' WHEN 3 THEN 'Canceled' WHEN 4 THEN 'In progress' END";
+    let run_at = "msdb.dbo.agent_datetime(h.run_date, h.run_time)";
+    let duration = agent_duration("h.run_duration");
+    if history {
+        format!(
+            "IF NOT EXISTS (SELECT 1 FROM msdb.dbo.sysjobs WHERE name = @P1) RAISERROR(N'Job %s not found', 16, 1, @P1) ELSE SELECT TOP 200 {run_at} AS run_at, h.step_id, h.step_name, {outcome} AS outcome, {duration} AS duration, h.retries_attempted, h.message FROM msdb.dbo.sysjobhistory h JOIN msdb.dbo.sysjobs j ON j.job_id = h.job_id WHERE j.name = @P1 ORDER BY h.instance_id DESC"
+        )
+    } else {
+        format!(
+            "SELECT j.name, j.enabled, {outcome} AS last_outcome, {run_at} AS last_run, {duration} AS last_duration, CASE WHEN a.start_execution_date IS NOT NULL AND a.stop_execution_date IS NULL THEN 'Running' ELSE '' END AS activity, n.next_run FROM msdb.dbo.sysjobs j OUTER APPLY (SELECT TOP 1 h.run_status, h.run_date, h.run_time, h.run_duration FROM msdb.dbo.sysjobhistory h WHERE h.job_id = j.job_id AND h.step_id = 0 ORDER BY h.instance_id DESC) h OUTER APPLY (SELECT TOP 1 ja.start_execution_date, ja.stop_execution_date FROM msdb.dbo.sysjobactivity ja WHERE ja.job_id = j.job_id ORDER BY ja.session_id DESC) a OUTER APPLY (SELECT MIN(msdb.dbo.agent_datetime(js.next_run_date, js.next_run_time)) AS next_run FROM msdb.dbo.sysjobschedules js WHERE js.job_id = j.job_id AND js.next_run_date > 0) n ORDER BY j.name"
+        )
+    }
+}
+
+/// Bracket-quote an identifier for use in generated SQL, as `QUOTENAME`
+/// does: `Sales DB` becomes `[Sales DB]` and `a]b` becomes `[a]]b]`. Every
+/// database, schema, table, or column name spliced into SQL goes through
+/// this.
+pub fn quote_ident(name: &str) -> String {
+    format!("[{}]", name.replace(']', "]]"))
+}
+
+/// SQL expression for the declared type of a column or parameter row
+/// aliased `alias` (from `sys.columns` or `sys.parameters`), e.g.
+/// `nvarchar(50)` or `decimal(10,2)`.
+pub fn declared_type(alias: &str) -> String {
+    format!(
+        "TYPE_NAME({a}.user_type_id) + CASE \
+           WHEN TYPE_NAME({a}.user_type_id) IN ('varchar', 'char', 'varbinary', 'binary') \
+             THEN '(' + IIF({a}.max_length = -1, 'max', CAST({a}.max_length AS varchar(10))) + ')' \
+           WHEN TYPE_NAME({a}.user_type_id) IN ('nvarchar', 'nchar') \
+             THEN '(' + IIF({a}.max_length = -1, 'max', CAST({a}.max_length / 2 AS varchar(10))) + ')' \
+           WHEN TYPE_NAME({a}.user_type_id) IN ('decimal', 'numeric') \
+             THEN '(' + CAST({a}.precision AS varchar(10)) + ',' + CAST({a}.scale AS varchar(10)) + ')' \
+           ELSE '' END",
+        a = alias
+    )
+}
+
+/// Parse the argument of `\snip`. Lines after the first are a snippet body.
+fn parse_snippet(arg: Option<&str>) -> Option<SnippetCommand> {
+    let Some(arg) = arg else {
+        return Some(SnippetCommand::List);
+    };
+    let (first, rest) = arg.split_once('\n').unwrap_or((arg, ""));
+    let words: Vec<&str> = first.split_whitespace().collect();
+    let body = Some(rest.trim())
+        .filter(|b| !b.is_empty())
+        .map(|b| b.to_string());
+    match words.as_slice() {
+        ["save", name] => Some(SnippetCommand::Save {
+            name: name.to_string(),
+            body,
+        }),
+        ["rm" | "delete", name] => Some(SnippetCommand::Delete(name.to_string())),
+        [name] if body.is_none() => Some(SnippetCommand::Load(name.to_string())),
+        _ => None,
+    }
+}
+
+/// Parse the argument of `\ws`.
+fn parse_workspace(arg: Option<&str>) -> Option<WorkspaceCommand> {
+    let Some(arg) = arg else {
+        return Some(WorkspaceCommand::List);
+    };
+    let words: Vec<&str> = arg.split_whitespace().collect();
+    match words.as_slice() {
+        ["new"] => Some(WorkspaceCommand::New { database: None }),
+        ["new", db] => Some(WorkspaceCommand::New {
+            database: Some(db.to_string()),
+        }),
+        ["close"] => Some(WorkspaceCommand::Close),
+        [n] => n
+            .parse()
+            .ok()
+            .filter(|&n| n > 0)
+            .map(WorkspaceCommand::Switch),
+        _ => None,
+    }
+}
+
 /// Generate the action for a slash command.
 pub fn to_action(cmd: &SlashCommand, conn_info: &str, database: &str, user: &str) -> CommandAction {
     match cmd {
-        SlashCommand::ListAll => CommandAction::ExecuteSql(
+        SlashCommand::ListAll { verbose: true } => {
+            CommandAction::ExecuteSql(verbose_object_listing("'U', 'V'"))
+        }
+        SlashCommand::ListAll { verbose: false } => CommandAction::ExecuteSql(
             "SELECT TABLE_SCHEMA, TABLE_NAME, TABLE_TYPE FROM INFORMATION_SCHEMA.TABLES ORDER BY TABLE_SCHEMA, TABLE_NAME".to_string(),
         ),
-        SlashCommand::Describe(table) => CommandAction::ExecuteSql(format!(
-            "SELECT COLUMN_NAME, DATA_TYPE, CHARACTER_MAXIMUM_LENGTH, IS_NULLABLE, COLUMN_DEFAULT FROM INFORMATION_SCHEMA.COLUMNS WHERE TABLE_NAME = '{}' ORDER BY ORDINAL_POSITION",
-            table.replace('\'', "''")
-        )),
-        SlashCommand::ListTables => CommandAction::ExecuteSql(
+        SlashCommand::Describe { table, verbose } => CommandAction::ExecuteQuery {
+            sql: in_database(table.database.as_deref(), describe_table(*verbose)),
+            params: vec![table.local()],
+        },
+        SlashCommand::ListTables { verbose: true } => {
+            CommandAction::ExecuteSql(verbose_object_listing("'U'"))
+        }
+        SlashCommand::ListTables { verbose: false } => CommandAction::ExecuteSql(
             "SELECT TABLE_SCHEMA, TABLE_NAME, TABLE_TYPE FROM INFORMATION_SCHEMA.TABLES WHERE TABLE_TYPE = 'BASE TABLE' ORDER BY TABLE_SCHEMA, TABLE_NAME".to_string(),
         ),
-        SlashCommand::ListViews => CommandAction::ExecuteSql(
+        SlashCommand::ListViews { verbose: true } => {
+            CommandAction::ExecuteSql(verbose_object_listing("'V'"))
+        }
+        SlashCommand::ListViews { verbose: false } => CommandAction::ExecuteSql(
             "SELECT TABLE_SCHEMA, TABLE_NAME, TABLE_TYPE FROM INFORMATION_SCHEMA.TABLES WHERE TABLE_TYPE = 'VIEW' ORDER BY TABLE_SCHEMA, TABLE_NAME".to_string(),
         ),
-        SlashCommand::ListIndexes => CommandAction::ExecuteSql(
+        SlashCommand::ListIndexes {
+            table: Some(table),
+            verbose,
+        } => CommandAction::ExecuteQuery {
+            sql: in_database(
+                table.database.as_deref(),
+                format!(
+                    "IF OBJECT_ID(@P1) IS NULL RAISERROR(N'Table %s not found', 16, 1, @P1) ELSE {}",
+                    table_indexes(*verbose)
+                ),
+            ),
+            params: vec![table.local()],
+        },
+        SlashCommand::ListIndexes {
+            table: None,
+            verbose: true,
+        } => CommandAction::ExecuteSql(
+            "SELECT t.name AS table_name, i.name AS index_name, i.type_desc, i.is_unique, i.is_primary_key, st.row_count, CAST(st.used_kb / 1024.0 AS decimal(18, 2)) AS size_mb, STATS_DATE(i.object_id, i.index_id) AS stats_updated, CAST(ep.value AS nvarchar(4000)) AS description FROM sys.indexes i JOIN sys.tables t ON i.object_id = t.object_id OUTER APPLY (SELECT SUM(ps.row_count) AS row_count, SUM(ps.used_page_count) * 8 AS used_kb FROM sys.dm_db_partition_stats ps WHERE ps.object_id = i.object_id AND ps.index_id = i.index_id) st LEFT JOIN sys.extended_properties ep ON ep.class = 7 AND ep.major_id = i.object_id AND ep.minor_id = i.index_id AND ep.name = 'MS_Description' WHERE i.name IS NOT NULL ORDER BY t.name, i.name".to_string(),
+        ),
+        SlashCommand::ListIndexes {
+            table: None,
+            verbose: false,
+        } => CommandAction::ExecuteSql(
             "SELECT t.name AS table_name, i.name AS index_name, i.type_desc, i.is_unique, i.is_primary_key FROM sys.indexes i JOIN sys.tables t ON i.object_id = t.object_id WHERE i.name IS NOT NULL ORDER BY t.name, i.name".to_string(),
         ),
-        SlashCommand::ListFunctions => CommandAction::ExecuteSql(
+        SlashCommand::ListFunctions { verbose: true } => CommandAction::ExecuteSql(
+            "SELECT s.name AS ROUTINE_SCHEMA, o.name AS ROUTINE_NAME, o.type_desc AS ROUTINE_TYPE, o.create_date, o.modify_date, CAST(ep.value AS nvarchar(4000)) AS description FROM sys.objects o JOIN sys.schemas s ON s.schema_id = o.schema_id LEFT JOIN sys.extended_properties ep ON ep.class = 1 AND ep.major_id = o.object_id AND ep.minor_id = 0 AND ep.name = 'MS_Description' WHERE o.type IN ('P', 'PC', 'FN', 'IF', 'TF', 'FS', 'FT') AND o.is_ms_shipped = 0 ORDER BY s.name, o.name".to_string(),
+        ),
+        SlashCommand::ListFunctions { verbose: false } => CommandAction::ExecuteSql(
             "SELECT ROUTINE_SCHEMA, ROUTINE_NAME, ROUTINE_TYPE FROM INFORMATION_SCHEMA.ROUTINES ORDER BY ROUTINE_SCHEMA, ROUTINE_NAME".to_string(),
         ),
-        SlashCommand::ListSchemas => CommandAction::ExecuteSql(
+        SlashCommand::ListSchemas { verbose: true } => CommandAction::ExecuteSql(
+            "SELECT s.schema_id, s.name, USER_NAME(s.principal_id) AS owner, (SELECT COUNT(*) FROM sys.objects o WHERE o.schema_id = s.schema_id AND o.is_ms_shipped = 0) AS objects, CAST(ep.value AS nvarchar(4000)) AS description FROM sys.schemas s LEFT JOIN sys.extended_properties ep ON ep.class = 3 AND ep.major_id = s.schema_id AND ep.name = 'MS_Description' WHERE s.principal_id = 1 ORDER BY s.name".to_string(),
+        ),
+        SlashCommand::ListSchemas { verbose: false } => CommandAction::ExecuteSql(
             "SELECT schema_id, name FROM sys.schemas WHERE principal_id = 1 ORDER BY name".to_string(),
         ),
-        SlashCommand::ListDatabases => CommandAction::ExecuteSql(
+        SlashCommand::ListDatabases { verbose: true } => CommandAction::ExecuteSql(
+            "SELECT d.name, d.state_desc, d.recovery_model_desc, CAST(f.size_kb / 1024.0 AS decimal(18, 2)) AS size_mb, d.compatibility_level, d.collation_name, d.create_date FROM sys.databases d OUTER APPLY (SELECT SUM(CAST(mf.size AS bigint)) * 8 AS size_kb FROM sys.master_files mf WHERE mf.database_id = d.database_id) f ORDER BY d.name".to_string(),
+        ),
+        SlashCommand::ListDatabases { verbose: false } => CommandAction::ExecuteSql(
             "SELECT name, state_desc, recovery_model_desc FROM sys.databases ORDER BY name".to_string(),
         ),
-        SlashCommand::UseDatabase(db) => CommandAction::ExecuteSql(format!("USE {}", db)),
+        SlashCommand::ListTypes(None) => CommandAction::ExecuteSql(
+            "SELECT SCHEMA_NAME(t.schema_id) AS type_schema, t.name AS type_name, CASE WHEN t.is_table_type = 1 THEN 'TABLE TYPE' ELSE TYPE_NAME(t.system_type_id) END AS base_type, t.max_length, t.precision, t.scale, t.is_nullable FROM sys.types t WHERE t.is_user_defined = 1 ORDER BY type_schema, type_name".to_string(),
+        ),
+        SlashCommand::ListTypes(Some(name)) => CommandAction::ExecuteQuery {
+            sql: "SELECT c.name AS column_name, TYPE_NAME(c.user_type_id) AS data_type, c.max_length, c.precision, c.scale, c.is_nullable, c.is_identity FROM sys.table_types tt JOIN sys.columns c ON c.object_id = tt.type_table_object_id WHERE tt.name = @P1 OR SCHEMA_NAME(tt.schema_id) + '.' + tt.name = @P1 ORDER BY c.column_id".to_string(),
+            params: vec![name.clone()],
+        },
+        SlashCommand::ListSynonyms => CommandAction::ExecuteSql(
+            "SELECT SCHEMA_NAME(s.schema_id) AS synonym_schema, s.name AS synonym_name, s.base_object_name, o.type_desc AS target_type, s.create_date FROM sys.synonyms s LEFT JOIN sys.objects o ON o.object_id = OBJECT_ID(s.base_object_name) ORDER BY synonym_schema, synonym_name".to_string(),
+        ),
+        SlashCommand::ListLogins => CommandAction::ExecuteSql(
+            "SELECT name, type_desc, CASE WHEN is_disabled = 1 THEN 'yes' ELSE 'no' END AS disabled, default_database_name, create_date FROM sys.server_principals WHERE type IN ('S', 'U', 'G', 'E', 'X', 'C', 'K') AND name NOT LIKE '##%' ORDER BY name".to_string(),
+        ),
+        SlashCommand::ListUsers(db) => {
+            let db = quote_ident(db);
+            CommandAction::ExecuteSql(format!(
+                "SELECT u.name, u.type_desc, u.default_schema_name, ISNULL(STRING_AGG(r.name, ', ') WITHIN GROUP (ORDER BY r.name), '') AS roles FROM {db}.sys.database_principals u LEFT JOIN {db}.sys.database_role_members m ON m.member_principal_id = u.principal_id LEFT JOIN {db}.sys.database_principals r ON r.principal_id = m.role_principal_id WHERE u.type IN ('S', 'U', 'G', 'E', 'X', 'C', 'K') AND u.name NOT LIKE '##%' GROUP BY u.name, u.type_desc, u.default_schema_name ORDER BY u.name"
+            ))
+        }
+        SlashCommand::ListRoles(None) => CommandAction::ExecuteSql(
+            "SELECT r.name AS role, r.is_fixed_role, USER_NAME(r.owning_principal_id) AS owner, COUNT(m.member_principal_id) AS member_count, ISNULL(STRING_AGG(mp.name, ', ') WITHIN GROUP (ORDER BY mp.name), '') AS members FROM sys.database_principals r LEFT JOIN sys.database_role_members m ON m.role_principal_id = r.principal_id LEFT JOIN sys.database_principals mp ON mp.principal_id = m.member_principal_id WHERE r.type = 'R' GROUP BY r.name, r.is_fixed_role, r.owning_principal_id ORDER BY r.is_fixed_role, r.name".to_string(),
+        ),
+        SlashCommand::ListRoles(Some(role)) => CommandAction::ExecuteQuery {
+            sql: "IF NOT EXISTS (SELECT 1 FROM sys.database_principals WHERE name = @P1 AND type = 'R') RAISERROR(N'Role %s not found', 16, 1, @P1) ELSE SELECT mp.name AS member, mp.type_desc, mp.default_schema_name, SUSER_SNAME(mp.sid) AS login, mp.create_date FROM sys.database_role_members m JOIN sys.database_principals r ON r.principal_id = m.role_principal_id JOIN sys.database_principals mp ON mp.principal_id = m.member_principal_id WHERE r.name = @P1 ORDER BY mp.name".to_string(),
+            params: vec![role.clone()],
+        },
+        SlashCommand::ListPermissions(object) => {
+            let filter = match object {
+                Some(_) => "WHERE p.class = 1 AND (o.name = @P1 OR SCHEMA_NAME(o.schema_id) + '.' + o.name = @P1) ",
+                None => "",
+            };
+            let sql = format!(
+                "SELECT CASE p.class WHEN 0 THEN 'DATABASE' WHEN 1 THEN SCHEMA_NAME(o.schema_id) + '.' + o.name WHEN 3 THEN SCHEMA_NAME(p.major_id) ELSE p.class_desc END AS object_name, c.name AS column_name, pr.name AS principal, pr.type_desc AS principal_type, p.state_desc AS state, p.permission_name, g.name AS grantor FROM sys.database_permissions p JOIN sys.database_principals pr ON pr.principal_id = p.grantee_principal_id JOIN sys.database_principals g ON g.principal_id = p.grantor_principal_id LEFT JOIN sys.objects o ON p.class = 1 AND o.object_id = p.major_id LEFT JOIN sys.columns c ON p.class = 1 AND c.object_id = p.major_id AND c.column_id = p.minor_id {filter}ORDER BY object_name, principal, p.permission_name"
+            );
+            match object {
+                Some(name) => CommandAction::ExecuteQuery {
+                    sql,
+                    params: vec![name.clone()],
+                },
+                None => CommandAction::ExecuteSql(sql),
+            }
+        }
+        SlashCommand::ShowDefinition(name) => CommandAction::Definition {
+            name: name.clone(),
+            edit: false,
+        },
+        SlashCommand::EditDefinition(name) => CommandAction::Definition {
+            name: name.clone(),
+            edit: true,
+        },
+        SlashCommand::ScriptDatabase(dir) => CommandAction::ScriptDatabase(dir.clone()),
+        SlashCommand::ScriptTable(table) => CommandAction::ScriptTable(table.clone()),
+        SlashCommand::Fragmentation(None) => CommandAction::ExecuteSql(fragmentation(false)),
+        SlashCommand::Fragmentation(Some(table)) => CommandAction::ExecuteQuery {
+            sql: fragmentation(true),
+            params: vec![table.clone()],
+        },
+        SlashCommand::TableSizes(db) => {
+            let db = db.as_deref().map(|db| format!("{}.", quote_ident(db))).unwrap_or_default();
+            let data = "CASE WHEN ps.index_id IN (0, 1) THEN ps.in_row_data_page_count + ps.lob_used_page_count + ps.row_overflow_used_page_count ELSE 0 END";
+            let mb = |pages: &str| format!("CAST({} * 8 / 1024.0 AS decimal(18, 2))", pages);
+            CommandAction::ExecuteSql(format!(
+                "SELECT s.name AS schema_name, t.name AS table_name, SUM(CASE WHEN ps.index_id IN (0, 1) THEN ps.row_count ELSE 0 END) AS row_count, {} AS reserved_mb, {} AS data_mb, {} AS index_mb, {} AS unused_mb FROM {db}sys.dm_db_partition_stats ps JOIN {db}sys.tables t ON t.object_id = ps.object_id JOIN {db}sys.schemas s ON s.schema_id = t.schema_id GROUP BY s.name, t.name ORDER BY SUM(ps.reserved_page_count) DESC",
+                mb("SUM(ps.reserved_page_count)"),
+                mb(&format!("SUM({data})")),
+                mb(&format!("(SUM(ps.used_page_count) - SUM({data}))")),
+                mb("(SUM(ps.reserved_page_count) - SUM(ps.used_page_count))"),
+            ))
+        }
+        SlashCommand::ListJobs => CommandAction::ExecuteSql(jobs(false)),
+        SlashCommand::JobHistory(name) => CommandAction::ExecuteQuery {
+            sql: jobs(true),
+            params: vec![name.clone()],
+        },
+        SlashCommand::Blocking => CommandAction::Blocking,
+        SlashCommand::Kill(spid) => CommandAction::ExecuteSql(format!("KILL {}", spid)),
+        SlashCommand::Session(spid) => CommandAction::ExecuteSql(session_detail(*spid)),
+        SlashCommand::UseDatabase(db) => CommandAction::ExecuteSql(format!("USE {}", quote_ident(db))),
+        SlashCommand::ShowSettings(keys) if keys.is_empty() => {
+            CommandAction::ExecuteSql(show_settings(0))
+        }
+        SlashCommand::ShowSettings(keys) => CommandAction::ExecuteQuery {
+            sql: show_settings(keys.len()),
+            params: keys.clone(),
+        },
         SlashCommand::ConnInfo => CommandAction::DisplayMessage {
             columns: vec!["Property".to_string(), "Value".to_string()],
             rows: vec![
@@ -122,21 +1104,102 @@ pub fn to_action(cmd: &SlashCommand, conn_info: &str, database: &str, user: &str
         },
         SlashCommand::ToggleExpanded => CommandAction::ToggleExpanded,
         SlashCommand::ToggleTiming => CommandAction::ToggleTiming,
+        SlashCommand::NullDisplay(text) => CommandAction::NullDisplay(text.clone()),
+        SlashCommand::DateStyle(style) => CommandAction::DateStyle(style.clone()),
+        SlashCommand::Thousands(on) => CommandAction::Thousands(*on),
+        SlashCommand::Decimals(places) => CommandAction::Decimals(*places),
+        SlashCommand::Currency(symbol) => CommandAction::Currency(symbol.clone()),
+        SlashCommand::RowNumbers(on) => CommandAction::RowNumbers(*on),
+        SlashCommand::PinColumns(n) => CommandAction::PinColumns(*n),
+        SlashCommand::Chart { label, value } => CommandAction::ShowChart {
+            label: label.clone(),
+            value: value.clone(),
+        },
+        SlashCommand::Compare(sets) => CommandAction::Compare(*sets),
+        SlashCommand::Explain(sql) => CommandAction::Explain(sql.clone()),
+        SlashCommand::Snippet(snip) => CommandAction::Snippet(snip.clone()),
+        SlashCommand::Workspace(ws) => CommandAction::Workspace(ws.clone()),
+        SlashCommand::EditExternal => CommandAction::EditExternal,
+        SlashCommand::RunScript(path) => CommandAction::RunScript(path.clone()),
+        SlashCommand::Tee(path) => CommandAction::Tee(path.clone()),
+        SlashCommand::Shell(command) => CommandAction::Shell(command.clone()),
+        SlashCommand::Watch(secs) => CommandAction::Watch(*secs),
+        SlashCommand::ErrVerbose => CommandAction::ErrVerbose,
+        SlashCommand::GSet(prefix) => CommandAction::GSet(prefix.clone()),
+        SlashCommand::SetVariable(Some((name, value))) => CommandAction::SetVariable {
+            name: name.clone(),
+            value: value.clone(),
+        },
+        SlashCommand::SetVariable(None) => CommandAction::ListVariables,
+        SlashCommand::SetOption(Some(spec)) => CommandAction::SetOption(SessionOption::parse(spec)),
+        SlashCommand::SetOption(None) => CommandAction::ListOptions,
+        SlashCommand::UnsetVariable(name) => CommandAction::UnsetVariable(name.clone()),
+        SlashCommand::Prompt { text, name } => CommandAction::Prompt {
+            text: text.clone(),
+            name: name.clone(),
+        },
         SlashCommand::Help => CommandAction::DisplayMessage {
             columns: vec!["Command".to_string(), "Description".to_string()],
             rows: vec![
                 vec!["\\d".to_string(), "List all tables and views".to_string()],
-                vec!["\\d <table>".to_string(), "Describe table columns".to_string()],
+                vec!["\\d <table>".to_string(), "Describe a table (columns, indexes, keys, checks, triggers), view (columns, definition), or routine (parameters, return type)".to_string()],
                 vec!["\\dt".to_string(), "List tables only".to_string()],
                 vec!["\\dv".to_string(), "List views only".to_string()],
-                vec!["\\di".to_string(), "List indexes".to_string()],
+                vec!["\\di [table]".to_string(), "List indexes, or a table's with key and included columns".to_string()],
                 vec!["\\df".to_string(), "List procedures and functions".to_string()],
                 vec!["\\ds".to_string(), "List schemas".to_string()],
                 vec!["\\dn".to_string(), "List databases".to_string()],
+                vec!["\\d+, \\dt+, ...".to_string(), "Listings with sizes, row counts, dates, and descriptions".to_string()],
+                vec!["\\dT [type]".to_string(), "List user-defined types, or a table type's columns".to_string()],
+                vec!["\\dS".to_string(), "List synonyms and their targets".to_string()],
+                vec!["\\du".to_string(), "List server logins".to_string()],
+                vec!["\\du <db>".to_string(), "List database users and their roles".to_string()],
+                vec!["\\dp [object]".to_string(), "List GRANT/DENY permissions".to_string()],
+                vec!["\\dg [role]".to_string(), "List database roles and their members".to_string()],
+                vec!["\\sf <routine>".to_string(), "Show a procedure's or function's definition".to_string()],
+                vec!["\\ef <routine>".to_string(), "Load a definition into the editor as CREATE OR ALTER".to_string()],
+                vec!["\\script <table>".to_string(), "Load a table's CREATE TABLE with constraints and indexes into the editor".to_string()],
+                vec!["\\script db [dir]".to_string(), "Script the database's schema into the editor, or one file per object".to_string()],
+                vec!["\\frag [table]".to_string(), "Index fragmentation with REBUILD/REORGANIZE statements".to_string()],
+                vec!["\\size [db]".to_string(), "Tables by reserved space, with data/index/unused MB and rows".to_string()],
+                vec!["\\jobs".to_string(), "SQL Agent jobs with last outcome, duration, and next run".to_string()],
+                vec!["\\jobs history <name>".to_string(), "A job's run history".to_string()],
+                vec!["\\blocking".to_string(), "Blocking chains as a tree (k in results: \\kill the head blocker)".to_string()],
+                vec!["\\kill <spid>".to_string(), "End a session".to_string()],
+                vec!["\\session [spid]".to_string(), "A session's statement, transactions, memory grant, tempdb use, and waits".to_string()],
                 vec!["\\c <db>".to_string(), "Switch database".to_string()],
                 vec!["\\conninfo".to_string(), "Show connection info".to_string()],
+                vec!["\\show settings [key ...]".to_string(), "Show SET options, isolation level, language, date format, and SESSION_CONTEXT keys".to_string()],
                 vec!["\\x".to_string(), "Toggle expanded display".to_string()],
                 vec!["\\timing".to_string(), "Toggle query timing display".to_string()],
+                vec!["\\pset null [text]".to_string(), "Set the text shown for NULL values".to_string()],
+                vec!["\\pset datestyle [style]".to_string(), "Dates as raw, iso, locale, or epoch".to_string()],
+                vec!["\\pset thousands [on|off]".to_string(), "Group digits in the grid".to_string()],
+                vec!["\\pset currency [symbol|off]".to_string(), "Currency symbol for money values in the grid".to_string()],
+                vec!["\\pset decimals [n|off]".to_string(), "Decimal places for numbers in the grid".to_string()],
+                vec!["\\pset rownum [on|off]".to_string(), "Show row numbers in the grid".to_string()],
+                vec!["\\pin [n]".to_string(), "Pin first n result columns".to_string()],
+                vec!["\\chart <label> <value>".to_string(), "Bar chart of the current result".to_string()],
+                vec!["\\compare [a b]".to_string(), "Compare two result sets side by side (-n: n queries back)".to_string()],
+                vec!["\\explain [query]".to_string(), "Estimated plan tree of the query (default: last)".to_string()],
+                vec!["\\snip [name]".to_string(), "Browse or load saved snippets".to_string()],
+                vec!["\\snip save <name>".to_string(), "Save the last query as a snippet".to_string()],
+                vec!["\\snip rm <name>".to_string(), "Delete a snippet".to_string()],
+                vec!["\\ws".to_string(), "List workspaces (Ctrl+T opens one, Alt+1..9 switches)".to_string()],
+                vec!["\\ws new [db]".to_string(), "Open a workspace; with db, on its own connection".to_string()],
+                vec!["\\ws <n> / \\ws close".to_string(), "Switch to workspace n / close the current one".to_string()],
+                vec!["\\e".to_string(), "Edit the last query in $EDITOR".to_string()],
+                vec!["\\i <path>".to_string(), "Run a script file (GO-separated batches)".to_string()],
+                vec!["\\o [file]".to_string(), "Copy results to a file (no arg stops)".to_string()],
+                vec!["\\! [cmd]".to_string(), "Run a shell command (or start a shell)".to_string()],
+                vec!["\\watch [secs]".to_string(), "Re-run the last query every secs (default 2) until a key is pressed".to_string()],
+                vec!["\\errverbose".to_string(), "Show the last error in full".to_string()],
+                vec!["\\gset [prefix]".to_string(), "Store the single-row result as variables".to_string()],
+                vec!["\\set [name value]".to_string(), "Set a variable, used as :name, :'name', or $(name); no args lists them".to_string()],
+                vec!["\\set option <NAME> <value>".to_string(), "Apply a session option (ANSI_NULLS, ARITHABORT, NOCOUNT, LOCK_TIMEOUT, ISOLATION); kept across reconnects".to_string()],
+                vec!["\\unset <name>".to_string(), "Remove a variable".to_string()],
+                vec!["\\prompt [text] <name>".to_string(), "Ask for a variable's value".to_string()],
+                vec!["\\if, \\elif, \\else, \\endif".to_string(), "Conditional blocks in \\i scripts and the CLI prompt".to_string()],
                 vec!["\\?".to_string(), "Show this help".to_string()],
                 vec!["\\q".to_string(), "Quit".to_string()],
             ],
@@ -151,53 +1214,281 @@ mod tests {
 
     #[test]
     fn test_parse_list_all() {
-        assert_eq!(parse("\\d"), Some(SlashCommand::ListAll));
+        assert_eq!(parse("\\d"), Some(SlashCommand::ListAll { verbose: false }));
     }
 
     #[test]
     fn test_parse_describe() {
         assert_eq!(
             parse("\\d foo"),
-            Some(SlashCommand::Describe("foo".to_string()))
+            Some(SlashCommand::Describe {
+                table: ObjectName::parse("foo").unwrap(),
+                verbose: false
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_object_name() {
+        let name = |database: Option<&str>, schema: Option<&str>, name: &str| ObjectName {
+            database: database.map(String::from),
+            schema: schema.map(String::from),
+            name: name.to_string(),
+        };
+        assert_eq!(
+            ObjectName::parse("dbo.Users"),
+            Some(name(None, Some("dbo"), "Users"))
+        );
+        assert_eq!(
+            ObjectName::parse("[Sales DB].dbo.[Order.Lines]"),
+            Some(name(Some("Sales DB"), Some("dbo"), "Order.Lines"))
+        );
+        assert_eq!(
+            ObjectName::parse("otherdb..Users"),
+            Some(name(Some("otherdb"), None, "Users"))
+        );
+        assert_eq!(
+            ObjectName::parse("\"a]b\".[c]]d]"),
+            Some(name(None, Some("a]b"), "c]d"))
         );
+        assert_eq!(ObjectName::parse("a.b.c.d"), None);
+        assert_eq!(ObjectName::parse(".Users"), None);
+        assert_eq!(ObjectName::parse("dbo."), None);
+        assert_eq!(ObjectName::parse("[dbo.Users"), None);
+        assert_eq!(name(None, Some("a]b"), "c").local(), "[a]]b].[c]");
+        assert_eq!(
+            name(Some("Sales DB"), Some("dbo"), "t").qualified(),
+            "[Sales DB].[dbo].[t]"
+        );
+        assert_eq!(name(Some("db"), None, "t").qualified(), "[db]..[t]");
+        assert_eq!(name(None, None, "t").qualified(), "[t]");
     }
 
     #[test]
     fn test_parse_describe_with_whitespace() {
         assert_eq!(
             parse("  \\d  bar  "),
-            Some(SlashCommand::Describe("bar".to_string()))
+            Some(SlashCommand::Describe {
+                table: ObjectName::parse("bar").unwrap(),
+                verbose: false
+            })
         );
     }
 
     #[test]
     fn test_parse_list_tables() {
-        assert_eq!(parse("\\dt"), Some(SlashCommand::ListTables));
+        assert_eq!(
+            parse("\\dt"),
+            Some(SlashCommand::ListTables { verbose: false })
+        );
     }
 
     #[test]
     fn test_parse_list_views() {
-        assert_eq!(parse("\\dv"), Some(SlashCommand::ListViews));
+        assert_eq!(
+            parse("\\dv"),
+            Some(SlashCommand::ListViews { verbose: false })
+        );
     }
 
     #[test]
     fn test_parse_list_indexes() {
-        assert_eq!(parse("\\di"), Some(SlashCommand::ListIndexes));
+        assert_eq!(
+            parse("\\di"),
+            Some(SlashCommand::ListIndexes {
+                table: None,
+                verbose: false
+            })
+        );
+        assert_eq!(
+            parse("\\di+ sales.[Order Lines]"),
+            Some(SlashCommand::ListIndexes {
+                table: ObjectName::parse("sales.[Order Lines]"),
+                verbose: true
+            })
+        );
+        assert_eq!(parse("\\di a.b.c.d"), None);
+    }
+
+    #[test]
+    fn test_to_action_table_indexes() {
+        let cmd = parse("\\di dbo.O'Neil").unwrap();
+        let CommandAction::ExecuteQuery { sql, params } = to_action(&cmd, "", "", "") else {
+            panic!("expected ExecuteQuery");
+        };
+        assert_eq!(params, vec!["[dbo].[O'Neil]"]);
+        assert!(sql.starts_with("IF OBJECT_ID(@P1) IS NULL RAISERROR(N'Table %s not found'"));
+        for part in [
+            "AS key_columns",
+            "AS included_columns",
+            "i.filter_definition",
+            "i.is_unique",
+        ] {
+            assert!(sql.contains(part), "missing {}", part);
+        }
+        assert!(sql.contains("WHERE i.object_id = OBJECT_ID(@P1)"));
+        assert!(!sql.contains("size_mb"));
+        let cmd = parse("\\di+ dbo.orders").unwrap();
+        let CommandAction::ExecuteQuery { sql, .. } = to_action(&cmd, "", "", "") else {
+            panic!("expected ExecuteQuery");
+        };
+        assert!(sql.contains("AS size_mb") && sql.contains("AS included_columns"));
     }
 
     #[test]
     fn test_parse_list_functions() {
-        assert_eq!(parse("\\df"), Some(SlashCommand::ListFunctions));
+        assert_eq!(
+            parse("\\df"),
+            Some(SlashCommand::ListFunctions { verbose: false })
+        );
     }
 
     #[test]
     fn test_parse_list_schemas() {
-        assert_eq!(parse("\\ds"), Some(SlashCommand::ListSchemas));
+        assert_eq!(
+            parse("\\ds"),
+            Some(SlashCommand::ListSchemas { verbose: false })
+        );
     }
 
     #[test]
     fn test_parse_list_databases() {
-        assert_eq!(parse("\\dn"), Some(SlashCommand::ListDatabases));
+        assert_eq!(
+            parse("\\dn"),
+            Some(SlashCommand::ListDatabases { verbose: false })
+        );
+    }
+
+    #[test]
+    fn test_parse_list_types() {
+        assert_eq!(parse("\\dT"), Some(SlashCommand::ListTypes(None)));
+        assert_eq!(
+            parse("\\dT dbo.OrderLines"),
+            Some(SlashCommand::ListTypes(Some("dbo.OrderLines".to_string())))
+        );
+        // Case matters: \dt lists tables
+        assert_eq!(
+            parse("\\dt"),
+            Some(SlashCommand::ListTables { verbose: false })
+        );
+    }
+
+    #[test]
+    fn test_parse_list_synonyms() {
+        assert_eq!(parse("\\dS"), Some(SlashCommand::ListSynonyms));
+    }
+
+    #[test]
+    fn test_parse_list_users() {
+        assert_eq!(parse("\\du"), Some(SlashCommand::ListLogins));
+        assert_eq!(
+            parse("\\du sales"),
+            Some(SlashCommand::ListUsers("sales".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_list_permissions() {
+        assert_eq!(parse("\\dp"), Some(SlashCommand::ListPermissions(None)));
+        assert_eq!(
+            parse("\\dp dbo.orders"),
+            Some(SlashCommand::ListPermissions(Some(
+                "dbo.orders".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_list_roles() {
+        assert_eq!(parse("\\dg"), Some(SlashCommand::ListRoles(None)));
+        assert_eq!(
+            parse("\\dg [db_owner]"),
+            Some(SlashCommand::ListRoles(Some("db_owner".to_string())))
+        );
+        let CommandAction::ExecuteSql(sql) = to_action(&SlashCommand::ListRoles(None), "", "", "")
+        else {
+            panic!("expected ExecuteSql");
+        };
+        assert!(sql.contains("sys.database_role_members") && sql.contains("WHERE r.type = 'R'"));
+        let CommandAction::ExecuteQuery { sql, params } =
+            to_action(&parse("\\dg app'reader").unwrap(), "", "", "")
+        else {
+            panic!("expected ExecuteQuery");
+        };
+        assert_eq!(params, vec!["app'reader"]);
+        assert!(sql.contains("RAISERROR(N'Role %s not found', 16, 1, @P1)"));
+        assert!(sql.contains("WHERE r.name = @P1"));
+    }
+
+    #[test]
+    fn test_parse_verbose_listings() {
+        assert_eq!(parse("\\d+"), Some(SlashCommand::ListAll { verbose: true }));
+        assert_eq!(
+            parse("\\dt+"),
+            Some(SlashCommand::ListTables { verbose: true })
+        );
+        assert_eq!(
+            parse("\\dn+"),
+            Some(SlashCommand::ListDatabases { verbose: true })
+        );
+        assert_eq!(parse("\\q+"), None);
+    }
+
+    #[test]
+    fn test_parse_definition() {
+        assert_eq!(
+            parse("\\sf dbo.GetOrders"),
+            Some(SlashCommand::ShowDefinition("dbo.GetOrders".to_string()))
+        );
+        assert_eq!(
+            parse("\\ef GetOrders"),
+            Some(SlashCommand::EditDefinition("GetOrders".to_string()))
+        );
+        assert_eq!(parse("\\sf"), None);
+    }
+
+    #[test]
+    fn test_parse_script() {
+        assert_eq!(
+            parse("\\script db"),
+            Some(SlashCommand::ScriptDatabase(None))
+        );
+        assert_eq!(
+            parse("\\script db \"./schema dir\""),
+            Some(SlashCommand::ScriptDatabase(Some(
+                "./schema dir".to_string()
+            )))
+        );
+        assert_eq!(
+            parse("\\script [dbo].[Order Lines]"),
+            Some(SlashCommand::ScriptTable(ObjectName {
+                database: None,
+                schema: Some("dbo".to_string()),
+                name: "Order Lines".to_string(),
+            }))
+        );
+        assert_eq!(
+            parse("\\script sales.dbo.Orders"),
+            Some(SlashCommand::ScriptTable(ObjectName {
+                database: Some("sales".to_string()),
+                schema: Some("dbo".to_string()),
+                name: "Orders".to_string(),
+            }))
+        );
+        assert_eq!(parse("\\script a.b.c.d"), None);
+        assert_eq!(parse("\\script"), None);
+    }
+
+    #[test]
+    fn test_create_or_alter() {
+        assert_eq!(
+            create_or_alter("-- Created by ops\n/* create */\ncreate procedure p AS SELECT 1"),
+            "-- Created by ops\n/* create */\nCREATE OR ALTER procedure p AS SELECT 1"
+        );
+        assert_eq!(
+            create_or_alter("ALTER VIEW v AS SELECT 1"),
+            "ALTER VIEW v AS SELECT 1"
+        );
     }
 
     #[test]
@@ -206,6 +1497,22 @@ mod tests {
             parse("\\c mydb"),
             Some(SlashCommand::UseDatabase("mydb".to_string()))
         );
+        assert_eq!(
+            parse("\\c [Sales]]DB]"),
+            Some(SlashCommand::UseDatabase("Sales]DB".to_string()))
+        );
+        assert_eq!(
+            to_action(&parse("\\c Sales]DB").unwrap(), "", "", ""),
+            CommandAction::ExecuteSql("USE [Sales]]DB]".to_string())
+        );
+    }
+
+    #[test]
+    fn test_quote_ident() {
+        assert_eq!(quote_ident("Sales DB"), "[Sales DB]");
+        assert_eq!(quote_ident("my-db"), "[my-db]");
+        assert_eq!(quote_ident("a]b"), "[a]]b]");
+        assert_eq!(quote_ident("select"), "[select]");
     }
 
     #[test]
@@ -218,6 +1525,32 @@ mod tests {
         assert_eq!(parse("\\conninfo"), Some(SlashCommand::ConnInfo));
     }
 
+    #[test]
+    fn test_show_settings() {
+        assert_eq!(
+            parse("\\show settings"),
+            Some(SlashCommand::ShowSettings(Vec::new()))
+        );
+        assert_eq!(parse("\\show"), None);
+        assert_eq!(parse("\\show tables"), None);
+        let CommandAction::ExecuteSql(sql) =
+            to_action(&parse("\\show settings").unwrap(), "", "", "")
+        else {
+            panic!("expected ExecuteSql");
+        };
+        assert!(sql.contains("@@OPTIONS & 512") && !sql.contains("@P1"));
+        let CommandAction::ExecuteQuery { sql, params } = to_action(
+            &parse("\\show SETTINGS tenant_id \"region\"").unwrap(),
+            "",
+            "",
+            "",
+        ) else {
+            panic!("expected ExecuteQuery");
+        };
+        assert_eq!(params, vec!["tenant_id", "region"]);
+        assert!(sql.contains("SESSION_CONTEXT(@P2)") && !sql.contains("tenant_id"));
+    }
+
     #[test]
     fn test_parse_toggle_expanded() {
         assert_eq!(parse("\\x"), Some(SlashCommand::ToggleExpanded));
@@ -228,6 +1561,381 @@ mod tests {
         assert_eq!(parse("\\timing"), Some(SlashCommand::ToggleTiming));
     }
 
+    #[test]
+    fn test_parse_edit_external() {
+        assert_eq!(parse("\\e"), Some(SlashCommand::EditExternal));
+        assert_eq!(
+            to_action(&SlashCommand::EditExternal, "", "", ""),
+            CommandAction::EditExternal
+        );
+    }
+
+    #[test]
+    fn test_parse_run_script() {
+        assert_eq!(
+            parse("\\i migrations/001.sql"),
+            Some(SlashCommand::RunScript("migrations/001.sql".to_string()))
+        );
+        assert_eq!(
+            parse("\\i \"my scripts/a.sql\""),
+            Some(SlashCommand::RunScript("my scripts/a.sql".to_string()))
+        );
+        assert_eq!(parse("\\i"), None);
+    }
+
+    #[test]
+    fn test_parse_tee() {
+        assert_eq!(
+            parse("\\o results.txt"),
+            Some(SlashCommand::Tee(Some("results.txt".to_string())))
+        );
+        assert_eq!(parse("\\o"), Some(SlashCommand::Tee(None)));
+    }
+
+    #[test]
+    fn test_parse_shell() {
+        assert_eq!(
+            parse("\\! git diff setup.sql"),
+            Some(SlashCommand::Shell("git diff setup.sql".to_string()))
+        );
+        assert_eq!(parse("\\!"), Some(SlashCommand::Shell(String::new())));
+    }
+
+    #[test]
+    fn test_parse_watch() {
+        assert_eq!(parse("\\watch 5"), Some(SlashCommand::Watch(5)));
+        assert_eq!(parse("\\watch"), Some(SlashCommand::Watch(2)));
+        assert_eq!(parse("\\watch 0"), None);
+        assert_eq!(parse("\\watch soon"), None);
+    }
+
+    #[test]
+    fn test_parse_pin_columns() {
+        assert_eq!(parse("\\pin 2"), Some(SlashCommand::PinColumns(2)));
+        assert_eq!(parse("\\pin"), Some(SlashCommand::PinColumns(0)));
+        assert_eq!(parse("\\pin two"), None);
+    }
+
+    #[test]
+    fn test_fragmentation() {
+        let CommandAction::ExecuteQuery { sql, params } =
+            to_action(&parse("\\frag dbo.O'Neil").unwrap(), "", "", "")
+        else {
+            panic!("expected a parameterized query");
+        };
+        assert_eq!(params, vec!["dbo.O'Neil"]);
+        assert!(sql.starts_with("IF OBJECT_ID(@P1) IS NULL RAISERROR"));
+        assert!(sql.contains("dm_db_index_physical_stats(DB_ID(), OBJECT_ID(@P1)"));
+        assert!(!sql.contains("O'Neil"));
+        let CommandAction::ExecuteSql(sql) = to_action(&parse("\\frag").unwrap(), "", "", "")
+        else {
+            panic!("expected SQL");
+        };
+        assert!(sql.contains("(DB_ID(), NULL,") && sql.contains("page_count >= 1000"));
+    }
+
+    #[test]
+    fn test_table_sizes() {
+        let CommandAction::ExecuteSql(sql) =
+            to_action(&parse("\\size Sales]DB").unwrap(), "", "", "")
+        else {
+            panic!("expected SQL");
+        };
+        assert!(sql.contains("FROM [Sales]]DB].sys.dm_db_partition_stats ps"));
+        let CommandAction::ExecuteSql(sql) = to_action(&parse("\\size").unwrap(), "", "", "")
+        else {
+            panic!("expected SQL");
+        };
+        assert!(sql.contains("FROM sys.dm_db_partition_stats ps JOIN sys.tables t"));
+    }
+
+    #[test]
+    fn test_parse_jobs() {
+        assert_eq!(parse("\\jobs"), Some(SlashCommand::ListJobs));
+        assert_eq!(
+            parse("\\jobs history 'Nightly backup'"),
+            Some(SlashCommand::JobHistory("Nightly backup".to_string()))
+        );
+        assert_eq!(parse("\\jobs history"), None);
+        assert_eq!(parse("\\jobs nightly"), None);
+        let CommandAction::ExecuteQuery { sql, params } =
+            to_action(&SlashCommand::JobHistory("O'Neil".to_string()), "", "", "")
+        else {
+            panic!("expected a parameterized query");
+        };
+        assert_eq!(params, vec!["O'Neil"]);
+        assert!(sql.contains("WHERE j.name = @P1 ORDER BY h.instance_id DESC"));
+    }
+
+    #[test]
+    fn test_parse_kill() {
+        assert_eq!(parse("\\kill 57"), Some(SlashCommand::Kill(57)));
+        assert_eq!(parse("\\kill 57; DROP TABLE t"), None);
+        assert_eq!(
+            to_action(&SlashCommand::Kill(57), "", "", ""),
+            CommandAction::ExecuteSql("KILL 57".to_string())
+        );
+    }
+
+    #[test]
+    fn test_session() {
+        assert_eq!(parse("\\session 57"), Some(SlashCommand::Session(Some(57))));
+        assert_eq!(parse("\\session"), Some(SlashCommand::Session(None)));
+        assert_eq!(parse("\\session 57; DROP TABLE t"), None);
+        let CommandAction::ExecuteSql(sql) =
+            to_action(&SlashCommand::Session(Some(57)), "", "", "")
+        else {
+            panic!("expected ExecuteSql");
+        };
+        assert!(
+            sql.starts_with("DECLARE @spid int = 57;")
+                && sql.contains("sys.dm_exec_session_wait_stats")
+        );
+        let CommandAction::ExecuteSql(sql) = to_action(&SlashCommand::Session(None), "", "", "")
+        else {
+            panic!("expected ExecuteSql");
+        };
+        assert!(sql.starts_with("DECLARE @spid int = @@SPID;"));
+    }
+
+    #[test]
+    fn test_parse_workspace() {
+        assert_eq!(
+            parse("\\ws"),
+            Some(SlashCommand::Workspace(WorkspaceCommand::List))
+        );
+        assert_eq!(
+            parse("\\ws new Sales"),
+            Some(SlashCommand::Workspace(WorkspaceCommand::New {
+                database: Some("Sales".to_string())
+            }))
+        );
+        assert_eq!(
+            parse("\\ws 2"),
+            Some(SlashCommand::Workspace(WorkspaceCommand::Switch(2)))
+        );
+        assert_eq!(parse("\\ws 0"), None);
+        assert_eq!(parse("\\ws open x"), None);
+    }
+
+    #[test]
+    fn test_parse_pset_null() {
+        assert_eq!(
+            parse("\\pset null '(null)'"),
+            Some(SlashCommand::NullDisplay(Some("(null)".to_string())))
+        );
+        assert_eq!(parse("\\pset null"), Some(SlashCommand::NullDisplay(None)));
+        assert_eq!(
+            parse("\\pset datestyle iso"),
+            Some(SlashCommand::DateStyle(Some("iso".to_string())))
+        );
+        assert_eq!(
+            parse("\\pset datestyle"),
+            Some(SlashCommand::DateStyle(None))
+        );
+        assert_eq!(
+            parse("\\pset thousands on"),
+            Some(SlashCommand::Thousands(Some(true)))
+        );
+        assert_eq!(
+            parse("\\pset thousands"),
+            Some(SlashCommand::Thousands(None))
+        );
+        assert_eq!(
+            parse("\\pset decimals 2"),
+            Some(SlashCommand::Decimals(Some(2)))
+        );
+        assert_eq!(
+            parse("\\pset decimals off"),
+            Some(SlashCommand::Decimals(None))
+        );
+        assert_eq!(parse("\\pset decimals two"), None);
+        assert_eq!(
+            parse("\\pset currency '€ '"),
+            Some(SlashCommand::Currency(Some("€ ".to_string())))
+        );
+        assert_eq!(
+            parse("\\pset currency off"),
+            Some(SlashCommand::Currency(None))
+        );
+        assert_eq!(
+            parse("\\pset rownum off"),
+            Some(SlashCommand::RowNumbers(Some(false)))
+        );
+        assert_eq!(parse("\\pset rownum maybe"), None);
+        assert_eq!(parse("\\pset border 2"), None);
+    }
+
+    #[test]
+    fn test_parse_chart() {
+        assert_eq!(
+            parse("\\chart day  total"),
+            Some(SlashCommand::Chart {
+                label: "day".to_string(),
+                value: "total".to_string()
+            })
+        );
+        assert_eq!(parse("\\chart day"), None);
+        assert_eq!(parse("\\chart a b c"), None);
+    }
+
+    #[test]
+    fn test_parse_compare() {
+        assert_eq!(parse("\\compare"), Some(SlashCommand::Compare(None)));
+        assert_eq!(
+            parse("\\compare 1 3"),
+            Some(SlashCommand::Compare(Some((
+                CompareSource::ResultSet(1),
+                CompareSource::ResultSet(3)
+            ))))
+        );
+        assert_eq!(
+            parse("\\compare -3 -1"),
+            Some(SlashCommand::Compare(Some((
+                CompareSource::Previous(3),
+                CompareSource::Previous(1)
+            ))))
+        );
+        assert_eq!(
+            parse("\\compare -2"),
+            Some(SlashCommand::Compare(Some((
+                CompareSource::Previous(2),
+                CompareSource::Current
+            ))))
+        );
+        assert_eq!(parse("\\compare 1"), None);
+        assert_eq!(parse("\\compare 0 2"), None);
+        assert_eq!(parse("\\compare -0"), None);
+    }
+
+    #[test]
+    fn test_parse_explain() {
+        assert_eq!(parse("\\explain"), Some(SlashCommand::Explain(None)));
+        assert_eq!(
+            parse("\\explain SELECT *\nFROM t"),
+            Some(SlashCommand::Explain(Some("SELECT *\nFROM t".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_parse_snippet() {
+        assert_eq!(
+            parse("\\snip"),
+            Some(SlashCommand::Snippet(SnippetCommand::List))
+        );
+        assert_eq!(
+            parse("\\snip daily-report"),
+            Some(SlashCommand::Snippet(SnippetCommand::Load(
+                "daily-report".to_string()
+            )))
+        );
+        assert_eq!(
+            parse("\\snip save daily-report"),
+            Some(SlashCommand::Snippet(SnippetCommand::Save {
+                name: "daily-report".to_string(),
+                body: None
+            }))
+        );
+        assert_eq!(
+            parse("\\snip rm old"),
+            Some(SlashCommand::Snippet(SnippetCommand::Delete(
+                "old".to_string()
+            )))
+        );
+        assert_eq!(parse("\\snip a b c"), None);
+    }
+
+    #[test]
+    fn test_parse_snippet_save_with_body() {
+        assert_eq!(
+            parse("\\snip save q\nSELECT 1\nFROM t"),
+            Some(SlashCommand::Snippet(SnippetCommand::Save {
+                name: "q".to_string(),
+                body: Some("SELECT 1\nFROM t".to_string())
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_errverbose() {
+        assert_eq!(parse("\\errverbose"), Some(SlashCommand::ErrVerbose));
+    }
+
+    #[test]
+    fn test_parse_gset() {
+        assert_eq!(parse("\\gset"), Some(SlashCommand::GSet(String::new())));
+        assert_eq!(
+            parse("\\gset order_"),
+            Some(SlashCommand::GSet("order_".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_set_variable() {
+        assert_eq!(
+            parse("\\set region 'North West'"),
+            Some(SlashCommand::SetVariable(Some((
+                "region".to_string(),
+                "North West".to_string()
+            ))))
+        );
+        assert_eq!(
+            parse("\\set flag"),
+            Some(SlashCommand::SetVariable(Some((
+                "flag".to_string(),
+                String::new()
+            ))))
+        );
+        assert_eq!(parse("\\set"), Some(SlashCommand::SetVariable(None)));
+        assert_eq!(
+            parse("\\unset region"),
+            Some(SlashCommand::UnsetVariable("region".to_string()))
+        );
+        assert_eq!(parse("\\unset"), None);
+    }
+
+    #[test]
+    fn test_parse_set_option() {
+        assert_eq!(
+            parse("\\set option nocount on"),
+            Some(SlashCommand::SetOption(Some("nocount on".to_string())))
+        );
+        assert_eq!(parse("\\set option"), Some(SlashCommand::SetOption(None)));
+        let option = SessionOption::parse("isolation read_committed").unwrap();
+        assert_eq!(
+            option.sql(),
+            "SET TRANSACTION ISOLATION LEVEL READ COMMITTED"
+        );
+        assert_eq!(
+            SessionOption::parse("LOCK_TIMEOUT 5000").unwrap().sql(),
+            "SET LOCK_TIMEOUT 5000"
+        );
+        assert!(SessionOption::parse("nocount maybe").is_err());
+        assert!(SessionOption::parse("lock_timeout -2").is_err());
+        assert!(SessionOption::parse("isolation chaos").is_err());
+        assert!(SessionOption::parse("xact_abort on").is_err());
+        assert!(SessionOption::parse("nocount on; DROP TABLE t").is_err());
+    }
+
+    #[test]
+    fn test_parse_prompt() {
+        assert_eq!(
+            parse("\\prompt 'Customer id: ' cust"),
+            Some(SlashCommand::Prompt {
+                text: Some("Customer id: ".to_string()),
+                name: "cust".to_string()
+            })
+        );
+        assert_eq!(
+            parse("\\prompt cust"),
+            Some(SlashCommand::Prompt {
+                text: None,
+                name: "cust".to_string()
+            })
+        );
+        assert_eq!(parse("\\prompt"), None);
+    }
+
     #[test]
     fn test_parse_help() {
         assert_eq!(parse("\\?"), Some(SlashCommand::Help));
@@ -250,7 +1958,7 @@ mod tests {
 
     #[test]
     fn test_to_action_list_all_sql() {
-        let cmd = SlashCommand::ListAll;
+        let cmd = SlashCommand::ListAll { verbose: false };
         let action = to_action(&cmd, "", "", "");
         match action {
             CommandAction::ExecuteSql(sql) => {
@@ -261,29 +1969,153 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_to_action_verbose_tables_sql() {
+        match to_action(&SlashCommand::ListTables { verbose: true }, "", "", "") {
+            CommandAction::ExecuteSql(sql) => {
+                assert!(sql.contains("o.type IN ('U')"));
+                assert!(sql.contains("MS_Description"));
+                assert!(sql.contains("row_count"));
+            }
+            _ => panic!("expected ExecuteSql"),
+        }
+    }
+
     #[test]
     fn test_to_action_describe_sql() {
-        let action = to_action(&SlashCommand::Describe("users".to_string()), "", "", "");
+        let cmd = SlashCommand::Describe {
+            table: ObjectName::parse("users").unwrap(),
+            verbose: false,
+        };
+        let action = to_action(&cmd, "", "", "");
         match action {
-            CommandAction::ExecuteSql(sql) => {
+            CommandAction::ExecuteQuery { sql, params } => {
                 assert!(sql.contains("INFORMATION_SCHEMA.COLUMNS"));
-                assert!(sql.contains("'users'"));
+                assert!(sql.contains("TABLE_NAME = OBJECT_NAME(OBJECT_ID(@P1))"));
+                assert!(sql.contains("TABLE_SCHEMA = OBJECT_SCHEMA_NAME(OBJECT_ID(@P1))"));
+                assert_eq!(params, vec!["[users]"]);
             }
-            _ => panic!("expected ExecuteSql"),
+            _ => panic!("expected ExecuteQuery"),
+        }
+    }
+
+    #[test]
+    fn test_to_action_describe_view() {
+        let cmd = parse("\\d dbo.active_users").unwrap();
+        let CommandAction::ExecuteQuery { sql, .. } = to_action(&cmd, "", "", "") else {
+            panic!("expected ExecuteQuery");
+        };
+        let (view, table) = sql.split_once("\nELSE\n").unwrap();
+        assert!(view.starts_with("IF OBJECTPROPERTY(OBJECT_ID(@P1), 'IsView') = 1"));
+        assert!(view.contains("INFORMATION_SCHEMA.COLUMNS"));
+        assert!(view.contains("SELECT OBJECT_DEFINITION(OBJECT_ID(@P1)) AS definition"));
+        assert!(!view.contains("sys.indexes"));
+        assert!(table.contains("sys.indexes") && table.contains("sys.triggers"));
+    }
+
+    #[test]
+    fn test_to_action_describe_routine() {
+        let cmd = parse("\\d dbo.GetOrders").unwrap();
+        let CommandAction::ExecuteQuery { sql, .. } = to_action(&cmd, "", "", "") else {
+            panic!("expected ExecuteQuery");
+        };
+        let routine = sql.split("\nELSE").nth(1).unwrap();
+        assert!(routine.starts_with(" IF EXISTS (SELECT 1 FROM sys.objects WHERE object_id = OBJECT_ID(@P1) AND type IN ('P'"));
+        assert!(routine.contains(
+            "FROM sys.parameters p WHERE p.object_id = OBJECT_ID(@P1) AND p.parameter_id > 0"
+        ));
+        assert!(routine.contains("AS direction") && !routine.contains("default_value"));
+        assert!(routine.contains(
+            "r.parameter_id = 0) WHEN o.type IN ('IF', 'TF', 'FT') THEN 'TABLE' END AS return_type"
+        ));
+        assert!(!routine.contains("sys.indexes"));
+    }
+
+    #[test]
+    fn test_to_action_describe_other_database() {
+        let cmd = parse("\\d [Sales DB].dbo.O'Neil").unwrap();
+        match to_action(&cmd, "", "", "") {
+            CommandAction::ExecuteQuery { sql, params } => {
+                assert!(sql.starts_with("EXEC [Sales DB].sys.sp_executesql N'IF OBJECTPROPERTY("));
+                assert!(sql.ends_with("', N'@P1 nvarchar(4000)', @P1 = @P1"));
+                assert!(sql.contains("ORDER BY i.name"));
+                assert_eq!(params, vec!["[dbo].[O'Neil]"]);
+            }
+            _ => panic!("expected ExecuteQuery"),
+        }
+    }
+
+    #[test]
+    fn test_to_action_describe_result_sets() {
+        let cmd = parse("\\d+ dbo.users").unwrap();
+        match to_action(&cmd, "", "", "") {
+            CommandAction::ExecuteQuery { sql, params } => {
+                assert_eq!(params, vec!["[dbo].[users]"]);
+                for part in [
+                    "MS_Description",
+                    "sys.indexes",
+                    "sys.foreign_keys",
+                    "sys.check_constraints",
+                    "sys.triggers",
+                ] {
+                    assert!(sql.contains(part), "missing {}", part);
+                }
+                // Every result set is scoped to the object, in every branch
+                assert_eq!(sql.matches("OBJECT_ID(@P1)").count(), 12);
+            }
+            _ => panic!("expected ExecuteQuery"),
         }
     }
 
     #[test]
     fn test_to_action_describe_sql_injection() {
-        let action = to_action(&SlashCommand::Describe("a'b".to_string()), "", "", "");
+        let cmd = SlashCommand::Describe {
+            table: ObjectName::parse("a'b").unwrap(),
+            verbose: false,
+        };
+        let action = to_action(&cmd, "", "", "");
+        match action {
+            CommandAction::ExecuteQuery { sql, params } => {
+                assert!(!sql.contains("a'b") && !sql.contains("a''b"));
+                assert_eq!(params, vec!["[a'b]"]);
+            }
+            _ => panic!("expected ExecuteQuery"),
+        }
+    }
+
+    #[test]
+    fn test_to_action_list_users_quotes_database() {
+        let action = to_action(&SlashCommand::ListUsers("a]b".to_string()), "", "", "");
         match action {
             CommandAction::ExecuteSql(sql) => {
-                assert!(sql.contains("'a''b'"));
+                assert!(sql.contains("[a]]b].sys.database_principals"));
             }
             _ => panic!("expected ExecuteSql"),
         }
     }
 
+    #[test]
+    fn test_to_action_list_permissions_filter() {
+        let action = to_action(
+            &SlashCommand::ListPermissions(Some("o'x".to_string())),
+            "",
+            "",
+            "",
+        );
+        match action {
+            CommandAction::ExecuteQuery { sql, params } => {
+                assert!(sql.contains("sys.database_permissions"));
+                assert!(sql.contains("o.name = @P1"));
+                assert_eq!(params, vec!["o'x"]);
+            }
+            _ => panic!("expected ExecuteQuery"),
+        }
+        match to_action(&SlashCommand::ListPermissions(None), "", "", "") {
+            CommandAction::ExecuteSql(sql) => assert!(!sql.contains("WHERE")),
+            _ => panic!("expected ExecuteSql"),
+        }
+    }
+
     #[test]
     fn test_to_action_conninfo() {
         let action = to_action(&SlashCommand::ConnInfo, "localhost:1433", "mydb", "sa");
@@ -0,0 +1,223 @@
+//! Locations of meow's per-user configuration and data files, and the
+//! settings read from the `config` file there.
+
+use std::path::PathBuf;
+
+/// Directory holding meow's config and data files, created on demand.
+///
+/// Honors `MEOW_CONFIG_DIR`, then the platform convention
+/// (`%APPDATA%\meow` on Windows, `$XDG_CONFIG_HOME/meow` or `~/.config/meow`
+/// elsewhere). Returns `None` if no home directory can be determined.
+pub fn config_dir() -> Option<PathBuf> {
+    let dir = if let Some(dir) = std::env::var_os("MEOW_CONFIG_DIR") {
+        PathBuf::from(dir)
+    } else if cfg!(windows) {
+        PathBuf::from(std::env::var_os("APPDATA")?).join("meow")
+    } else if let Some(xdg) = std::env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        PathBuf::from(xdg).join("meow")
+    } else {
+        PathBuf::from(std::env::var_os("HOME")?)
+            .join(".config")
+            .join("meow")
+    };
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+/// Path of a file inside the config directory.
+pub fn config_file(name: &str) -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(name))
+}
+
+/// The startup file run after connecting: `~/.meowrc`, or `meowrc` in the
+/// config directory. `None` if neither exists.
+pub fn rc_file() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".meowrc"));
+    home.into_iter()
+        .chain(config_file("meowrc"))
+        .find(|path| path.is_file())
+}
+
+/// Editor key bindings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Keymap {
+    /// tui-textarea's default bindings.
+    #[default]
+    Default,
+    /// Modal Vim emulation.
+    Vim,
+    /// Emacs bindings with a mark and kill/yank.
+    Emacs,
+}
+
+impl Keymap {
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "default" => Some(Keymap::Default),
+            "vim" => Some(Keymap::Vim),
+            "emacs" => Some(Keymap::Emacs),
+            _ => None,
+        }
+    }
+}
+
+/// User settings from `<config dir>/config`.
+///
+/// The file holds `key = value` lines; blank lines and lines starting with
+/// `#` are ignored, as are unknown keys and invalid values.
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    /// Editor key bindings (`keymap = default|vim|emacs`).
+    pub keymap: Keymap,
+    /// Show row counts and sizes next to sidebar tables
+    /// (`sidebar_stats = true`).
+    pub sidebar_stats: bool,
+    /// Sidebar width in columns (`sidebar_width = 30`); saved when the
+    /// sidebar is resized.
+    pub sidebar_width: Option<u16>,
+    /// Use plain ASCII instead of Unicode glyphs in the sidebar
+    /// (`ascii_icons = true`), for fonts without them.
+    pub ascii_icons: bool,
+    /// Color theme (`theme = gruvbox`): `default`, `gruvbox`, `solarized`,
+    /// `dracula`, or `plain` for the terminal's 16 colors.
+    pub theme: Option<String>,
+    /// Seconds between server health probes shown in the status bar
+    /// (`health_interval = 30`); off when unset or 0.
+    pub health_interval: Option<u64>,
+    /// Seconds a connection may sit idle before a `SELECT 1` checks it and
+    /// reconnects if it has died (`keepalive_interval = 300`); `0` turns
+    /// the check off, unset uses the default.
+    pub keepalive_interval: Option<u64>,
+    /// How dates and times are written (`datestyle = iso`): `raw`, `iso`,
+    /// `locale`, or `epoch`, as with `\pset datestyle`.
+    pub date_style: Option<String>,
+}
+
+impl Settings {
+    /// Load settings, falling back to defaults if the file is missing.
+    pub fn load() -> Self {
+        config_file("config")
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|contents| Self::parse(&contents))
+            .unwrap_or_default()
+    }
+
+    fn parse(contents: &str) -> Self {
+        let mut settings = Self::default();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "keymap" => {
+                    if let Some(keymap) = Keymap::parse(value) {
+                        settings.keymap = keymap;
+                    }
+                }
+                "sidebar_stats" => {
+                    if let Some(on) = parse_bool(value) {
+                        settings.sidebar_stats = on;
+                    }
+                }
+                "ascii_icons" => {
+                    if let Some(on) = parse_bool(value) {
+                        settings.ascii_icons = on;
+                    }
+                }
+                "theme" => settings.theme = Some(value.to_string()),
+                "datestyle" => settings.date_style = Some(value.to_string()),
+                "health_interval" => {
+                    settings.health_interval = value.parse().ok().filter(|&secs| secs > 0);
+                }
+                "keepalive_interval" => {
+                    if let Ok(secs) = value.parse() {
+                        settings.keepalive_interval = Some(secs);
+                    }
+                }
+                "sidebar_width" => {
+                    if let Ok(width) = value.parse() {
+                        settings.sidebar_width = Some(width);
+                    }
+                }
+                _ => {}
+            }
+        }
+        settings
+    }
+}
+
+/// Set `key = value` in the config file, replacing an existing line for the
+/// key or appending one. Other lines, including comments, are kept.
+pub fn save_setting(key: &str, value: &str) -> std::io::Result<()> {
+    let Some(path) = config_file("config") else {
+        return Ok(());
+    };
+    let contents = std::fs::read_to_string(&path).unwrap_or_default();
+    std::fs::write(path, set_line(&contents, key, value))
+}
+
+fn set_line(contents: &str, key: &str, value: &str) -> String {
+    let mut found = false;
+    let mut out = String::new();
+    for line in contents.lines() {
+        let is_key = !line.trim_start().starts_with('#')
+            && line.split_once('=').is_some_and(|(k, _)| k.trim() == key);
+        if is_key && !found {
+            out.push_str(&format!("{} = {}\n", key, value));
+            found = true;
+        } else if !is_key {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    if !found {
+        out.push_str(&format!("{} = {}\n", key, value));
+    }
+    out
+}
+
+/// Read a setting as a boolean: `true`/`false`, `on`/`off`, `yes`/`no`, or
+/// `1`/`0`, in any case.
+pub fn parse_bool(s: &str) -> Option<bool> {
+    match s.to_ascii_lowercase().as_str() {
+        "true" | "on" | "yes" | "1" => Some(true),
+        "false" | "off" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let settings = Settings::parse(
+            "# comment\nkeymap = vim\nsidebar_width = 30\nhealth_interval = 0\nkeepalive_interval = 0\nbogus = 1\n",
+        );
+        assert_eq!(settings.keepalive_interval, Some(0));
+        assert_eq!(settings.keymap, Keymap::Vim);
+        assert_eq!(settings.sidebar_width, Some(30));
+        assert_eq!(settings.health_interval, None);
+        assert!(!settings.sidebar_stats);
+    }
+
+    #[test]
+    fn test_set_line() {
+        assert_eq!(
+            set_line("keymap = vim\nsidebar_width = 22\n", "sidebar_width", "30"),
+            "keymap = vim\nsidebar_width = 30\n"
+        );
+        assert_eq!(
+            set_line("# sidebar_width = 1\nkeymap = vim", "sidebar_width", "30"),
+            "# sidebar_width = 1\nkeymap = vim\nsidebar_width = 30\n"
+        );
+    }
+}
@@ -1,31 +1,111 @@
 //! Database connection management and query execution.
 
 pub mod query;
+pub mod script;
 
 use claw::{AuthMethod, Config, TcpClient};
+use std::time::Duration;
 
 /// A handle wrapping the claw client.
 pub type ConnectionHandle = TcpClient;
 
-/// Connect to SQL Server using the given parameters.
+/// Server errors that clear up on their own, mostly from Azure SQL: the
+/// database is unavailable, being moved, or failing over (40197, 40613,
+/// ...), or the server is throttling (10928, 10929, 40501, ...). Not 4060,
+/// which a mistyped or dropped database raises too.
+const TRANSIENT_ERRORS: &[u32] = &[
+    233, 4221, 10053, 10054, 10060, 10928, 10929, 40143, 40197, 40501, 40540, 40613, 49918, 49919,
+    49920,
+];
+
+/// Routing redirects followed during one login, as Azure SQL's gateway
+/// sends one to the node hosting the database.
+const MAX_REDIRECTS: usize = 3;
+
+/// Most connection attempts made however many retries are asked for.
+const MAX_ATTEMPTS: u32 = 10;
+
+/// Longest pause between connection attempts.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// How many times to try connecting, and how long to wait in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Connection attempts in all; 1 never retries. At most `MAX_ATTEMPTS`
+    /// are made.
+    pub attempts: u32,
+    /// Pause before the first retry; each later retry waits twice as long,
+    /// up to `MAX_RETRY_DELAY`.
+    pub delay: Duration,
+}
+
+/// Server, credentials, and connection options: everything needed to
+/// connect but the database.
+#[derive(Debug, Clone)]
+pub struct Login {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: String,
+    pub trust_cert: bool,
+    /// Declare `ApplicationIntent=ReadOnly`, so an availability group
+    /// listener routes the connection to a readable secondary.
+    pub read_only: bool,
+    pub retry: RetryPolicy,
+}
+
+/// Whether connecting again may succeed where `e` failed: a network error
+/// (the server is unreachable or still starting) or a transient server
+/// error. Login failures and the like are final.
+fn is_transient(e: &claw::Error) -> bool {
+    match e {
+        claw::Error::Io { .. } => true,
+        claw::Error::Server(token) => TRANSIENT_ERRORS.contains(&token.code()),
+        _ => false,
+    }
+}
+
+/// Connect to SQL Server with `login`, in `database`.
+///
+/// A routing redirect is followed to the server it names. A network error
+/// or transient server error is retried as `login.retry` allows, backing
+/// off between attempts.
 pub async fn connect(
-    host: &str,
-    port: u16,
-    user: &str,
-    password: &str,
+    login: &Login,
     database: &str,
-    trust_cert: bool,
 ) -> Result<ConnectionHandle, Box<dyn std::error::Error>> {
-    let mut config = Config::new();
-    config.host(host);
-    config.port(port);
-    config.authentication(AuthMethod::sql_server(user, password));
-    config.database(database);
-
-    if trust_cert {
-        config.trust_cert();
-    }
+    let mut target = (login.host.clone(), login.port);
+    let mut redirects = 0;
+    let mut attempt = 1;
+    let attempts = login.retry.attempts.min(MAX_ATTEMPTS);
+    let mut delay = login.retry.delay.min(MAX_RETRY_DELAY);
+    loop {
+        let mut config = Config::new();
+        config.host(&target.0);
+        config.port(target.1);
+        config.authentication(AuthMethod::sql_server(&login.user, &login.password));
+        config.database(database);
+        config.readonly(login.read_only);
+
+        if login.trust_cert {
+            config.trust_cert();
+        }
 
-    let client = claw::connect(config).await?;
-    Ok(client)
+        match claw::connect(config).await {
+            Ok(client) => return Ok(client),
+            Err(claw::Error::Routing { host, port }) if redirects < MAX_REDIRECTS => {
+                redirects += 1;
+                target = (host, port);
+            }
+            Err(e) if is_transient(&e) && attempt < attempts => {
+                attempt += 1;
+                tokio::time::sleep(delay).await;
+                delay = delay.saturating_mul(2).min(MAX_RETRY_DELAY);
+                // Start over at the server asked for, not a node it redirected to
+                target = (login.host.clone(), login.port);
+                redirects = 0;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
 }
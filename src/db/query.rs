@@ -1,23 +1,111 @@
 //! Query execution and result formatting.
 
-use crate::app::{ObjectNode, QueryResult, ResultSet};
+use crate::app::{ForeignKey, NodeKind, ObjectNode, ProcParam, QueryError, QueryResult, ResultSet};
+use crate::commands::{ObjectName, declared_type, quote_ident};
 use crate::db::ConnectionHandle;
-use claw::{ResultItem, SqlValue};
+use crate::scripts::{Branch, Conditions};
+use crate::variables;
+use claw::{Column, ColumnType, ResultItem, SqlValue, ToSql};
 use futures_util::TryStreamExt;
-use std::time::Instant;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// Capture the details of an error from running `sql`. Server errors carry
+/// their number, severity, state, line, and procedure.
+pub fn query_error(e: &(dyn std::error::Error + 'static), sql: &str) -> QueryError {
+    match e.downcast_ref::<claw::Error>() {
+        Some(claw::Error::Server(token)) => QueryError {
+            message: token.message().to_string(),
+            number: Some(token.code()),
+            severity: Some(token.class()),
+            state: Some(token.state()),
+            line: Some(token.line()).filter(|&l| l > 0),
+            procedure: Some(token.procedure().to_string()).filter(|p| !p.is_empty()),
+            server: Some(token.server().to_string()).filter(|s| !s.is_empty()),
+            sql: sql.to_string(),
+        },
+        _ => QueryError {
+            message: e.to_string(),
+            sql: sql.to_string(),
+            ..Default::default()
+        },
+    }
+}
+
+/// Evaluate the condition of a `\if` or `\elif`, if `conditions` says it
+/// matters (`false` otherwise). Variables in the condition have already
+/// been replaced, so it is a boolean literal (`true`, `off`, `1`, ...) or a
+/// query whose first value is read as one. A NULL or empty result is false, so
+/// `\if SELECT OBJECT_ID('dbo.t')` tests for existence.
+pub async fn eval_branch(
+    client: &mut ConnectionHandle,
+    conditions: &Conditions,
+    branch: &Branch,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let Some(condition) = conditions.condition(branch) else {
+        return Ok(false);
+    };
+    if let Some(value) = variables::truthy(condition) {
+        return Ok(value);
+    }
+    let result = execute_query(client, condition).await?;
+    match result.value(0, 0, 0) {
+        None => Ok(false),
+        Some(value) => variables::truthy(value)
+            .ok_or_else(|| format!("condition value {} is not a boolean", value).into()),
+    }
+}
+
+/// How often [`execute_query_with_progress`] reports the row count.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
 
 /// Execute a SQL query and return structured results.
 pub async fn execute_query(
     client: &mut ConnectionHandle,
     sql: &str,
+) -> Result<QueryResult, Box<dyn std::error::Error>> {
+    execute_query_with_progress(client, sql, |_| {}).await
+}
+
+/// Execute a SQL query with `params` bound to `@P1`, `@P2`, ... as
+/// `nvarchar` values and return structured results.
+pub async fn execute_query_params(
+    client: &mut ConnectionHandle,
+    sql: &str,
+    params: &[String],
+) -> Result<QueryResult, Box<dyn std::error::Error>> {
+    let params: Vec<&dyn ToSql> = params.iter().map(|p| p as &dyn ToSql).collect();
+    run_query(client, sql, &params, |_| {}).await
+}
+
+/// Execute a SQL query and return structured results, calling `on_progress`
+/// with the number of rows fetched so far (across all result sets) every
+/// [`PROGRESS_INTERVAL`] while rows arrive.
+pub async fn execute_query_with_progress(
+    client: &mut ConnectionHandle,
+    sql: &str,
+    on_progress: impl FnMut(usize),
+) -> Result<QueryResult, Box<dyn std::error::Error>> {
+    run_query(client, sql, &[], on_progress).await
+}
+
+async fn run_query(
+    client: &mut ConnectionHandle,
+    sql: &str,
+    params: &[&dyn ToSql],
+    mut on_progress: impl FnMut(usize),
 ) -> Result<QueryResult, Box<dyn std::error::Error>> {
     let start = Instant::now();
+    let mut last_progress = start;
+    let mut fetched = 0;
 
-    let mut stream = client.execute(sql, &[]).await?;
+    let mut stream = client.execute(sql, params).await?;
 
     let mut result_sets = Vec::new();
     let mut current_columns: Vec<String> = Vec::new();
+    let mut current_types: Vec<String> = Vec::new();
     let mut current_rows: Vec<Vec<String>> = Vec::new();
+    let mut current_nulls = HashSet::new();
 
     while let Some(item) = stream.try_next().await? {
         match item {
@@ -26,7 +114,9 @@ pub async fn execute_query(
                 if !current_columns.is_empty() || !current_rows.is_empty() {
                     result_sets.push(ResultSet {
                         columns: std::mem::take(&mut current_columns),
+                        column_types: std::mem::take(&mut current_types),
                         rows: std::mem::take(&mut current_rows),
+                        nulls: std::mem::take(&mut current_nulls),
                     });
                 }
                 current_columns = schema
@@ -34,14 +124,32 @@ pub async fn execute_query(
                     .iter()
                     .map(|c| c.name().to_string())
                     .collect();
+                current_types = column_type_names(schema.columns());
             }
             ResultItem::Row(row) => {
                 // If we haven't seen metadata yet, get columns from the row
                 if current_columns.is_empty() {
                     current_columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+                    current_types = column_type_names(row.columns());
                 }
-                let vals: Vec<String> = row.into_iter().map(|val| format_sql_value(&val)).collect();
+                let r = current_rows.len();
+                let vals: Vec<String> = row
+                    .into_iter()
+                    .enumerate()
+                    .map(|(c, val)| {
+                        let ty = current_types.get(c).map_or("", String::as_str);
+                        format_value(&val, ty).unwrap_or_else(|| {
+                            current_nulls.insert((r, c));
+                            "NULL".to_string()
+                        })
+                    })
+                    .collect();
                 current_rows.push(vals);
+                fetched += 1;
+                if last_progress.elapsed() >= PROGRESS_INTERVAL {
+                    on_progress(fetched);
+                    last_progress = Instant::now();
+                }
             }
             ResultItem::Message(_) => {} // skip info messages
         }
@@ -51,7 +159,9 @@ pub async fn execute_query(
     if !current_columns.is_empty() || !current_rows.is_empty() {
         result_sets.push(ResultSet {
             columns: current_columns,
+            column_types: current_types,
             rows: current_rows,
+            nulls: current_nulls,
         });
     }
 
@@ -61,50 +171,175 @@ pub async fn execute_query(
         result_sets,
         elapsed_ms,
         error: None,
+        sql: None,
     })
 }
 
-/// Format a SqlValue into a display string.
-fn format_sql_value(val: &SqlValue<'_>) -> String {
-    match val {
+/// Execute a SQL query and hand each row to `on_row` as it arrives, with the
+/// column names and SQL types of its result set, instead of collecting the
+/// results. NULL values are `None`. `on_row` is also called with `None`
+/// when a result set's columns arrive, before any of its rows, so even an
+/// empty result's columns are seen. Returns the elapsed time in
+/// milliseconds.
+pub async fn stream_query(
+    client: &mut ConnectionHandle,
+    sql: &str,
+    mut on_row: impl FnMut(
+        &[String],
+        &[String],
+        Option<Vec<Option<String>>>,
+    ) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<u128, Box<dyn std::error::Error>> {
+    let start = Instant::now();
+
+    let mut stream = client.execute(sql, &[]).await?;
+    let mut columns: Vec<String> = Vec::new();
+    let mut types: Vec<String> = Vec::new();
+
+    while let Some(item) = stream.try_next().await? {
+        match item {
+            ResultItem::Metadata(schema) => {
+                columns = schema
+                    .columns()
+                    .iter()
+                    .map(|c| c.name().to_string())
+                    .collect();
+                types = column_type_names(schema.columns());
+                on_row(&columns, &types, None)?;
+            }
+            ResultItem::Row(row) => {
+                if columns.is_empty() {
+                    columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+                    types = column_type_names(row.columns());
+                    on_row(&columns, &types, None)?;
+                }
+                let vals = row
+                    .into_iter()
+                    .enumerate()
+                    .map(|(c, val)| format_value(&val, types.get(c).map_or("", String::as_str)))
+                    .collect();
+                on_row(&columns, &types, Some(vals))?;
+            }
+            ResultItem::Message(_) => {}
+        }
+    }
+
+    Ok(start.elapsed().as_millis())
+}
+
+/// Map result column metadata to SQL type names.
+fn column_type_names(columns: &[Column]) -> Vec<String> {
+    columns
+        .iter()
+        .map(|c| sql_type_name(c.column_type()).to_string())
+        .collect()
+}
+
+/// SQL Server type name for a TDS column type.
+///
+/// Variable-length TDS types (`Intn`, `Floatn`, ...) only carry the family,
+/// so they map to the most common member of it.
+pub fn sql_type_name(ty: ColumnType) -> &'static str {
+    match ty {
+        ColumnType::Null => "null",
+        ColumnType::Bit | ColumnType::Bitn => "bit",
+        ColumnType::Int1 => "tinyint",
+        ColumnType::Int2 => "smallint",
+        ColumnType::Int4 | ColumnType::Intn => "int",
+        ColumnType::Int8 => "bigint",
+        ColumnType::Float4 => "real",
+        ColumnType::Float8 | ColumnType::Floatn => "float",
+        ColumnType::Money => "money",
+        ColumnType::Money4 => "smallmoney",
+        ColumnType::Datetime4 => "smalldatetime",
+        ColumnType::Datetime | ColumnType::Datetimen => "datetime",
+        ColumnType::Daten => "date",
+        ColumnType::Timen => "time",
+        ColumnType::Datetime2 => "datetime2",
+        ColumnType::DatetimeOffsetn => "datetimeoffset",
+        ColumnType::Guid => "uniqueidentifier",
+        ColumnType::Decimaln => "decimal",
+        ColumnType::Numericn => "numeric",
+        ColumnType::BigVarBin => "varbinary",
+        ColumnType::BigBinary => "binary",
+        ColumnType::BigVarChar => "varchar",
+        ColumnType::BigChar => "char",
+        ColumnType::NVarchar => "nvarchar",
+        ColumnType::NChar => "nchar",
+        ColumnType::Text => "text",
+        ColumnType::NText => "ntext",
+        ColumnType::Image => "image",
+        ColumnType::Xml => "xml",
+        ColumnType::Udt => "udt",
+        ColumnType::SSVariant => "sql_variant",
+    }
+}
+
+/// Format a value of a column of SQL type `type_name`, `None` for NULL.
+/// Money arrives as a float, so it is written with its four fixed decimal
+/// places rather than the float's shortest form.
+fn format_value(val: &SqlValue<'_>, type_name: &str) -> Option<String> {
+    match (type_name, val) {
+        ("money" | "smallmoney", SqlValue::F64(Some(n))) => Some(format!("{:.4}", n)),
+        ("money" | "smallmoney", SqlValue::F32(Some(n))) => Some(format!("{:.4}", n)),
+        _ => format_sql_value(val),
+    }
+}
+
+/// Format a SqlValue into a display string, `None` for NULL.
+fn format_sql_value(val: &SqlValue<'_>) -> Option<String> {
+    Some(match val {
         SqlValue::U8(Some(n)) => n.to_string(),
-        SqlValue::U8(None) => "NULL".to_string(),
+        SqlValue::U8(None) => return None,
         SqlValue::I16(Some(n)) => n.to_string(),
-        SqlValue::I16(None) => "NULL".to_string(),
+        SqlValue::I16(None) => return None,
         SqlValue::I32(Some(n)) => n.to_string(),
-        SqlValue::I32(None) => "NULL".to_string(),
+        SqlValue::I32(None) => return None,
         SqlValue::I64(Some(n)) => n.to_string(),
-        SqlValue::I64(None) => "NULL".to_string(),
+        SqlValue::I64(None) => return None,
         SqlValue::F32(Some(n)) => n.to_string(),
-        SqlValue::F32(None) => "NULL".to_string(),
+        SqlValue::F32(None) => return None,
         SqlValue::F64(Some(n)) => n.to_string(),
-        SqlValue::F64(None) => "NULL".to_string(),
+        SqlValue::F64(None) => return None,
         SqlValue::Bit(Some(b)) => b.to_string(),
-        SqlValue::Bit(None) => "NULL".to_string(),
+        SqlValue::Bit(None) => return None,
         SqlValue::String(Some(s)) => s.to_string(),
-        SqlValue::String(None) => "NULL".to_string(),
+        SqlValue::String(None) => return None,
         SqlValue::Guid(Some(g)) => g.to_string(),
-        SqlValue::Guid(None) => "NULL".to_string(),
+        SqlValue::Guid(None) => return None,
         SqlValue::Binary(Some(b)) => format!("0x{}", hex_encode(b)),
-        SqlValue::Binary(None) => "NULL".to_string(),
+        SqlValue::Binary(None) => return None,
         SqlValue::Numeric(Some(n)) => format!("{}", n),
-        SqlValue::Numeric(None) => "NULL".to_string(),
-        SqlValue::Xml(Some(x)) => format!("{:?}", x),
-        SqlValue::Xml(None) => "NULL".to_string(),
+        SqlValue::Numeric(None) => return None,
+        SqlValue::Xml(Some(x)) => {
+            let xml: &str = (**x).as_ref();
+            xml.to_string()
+        }
+        SqlValue::Xml(None) => return None,
         SqlValue::DateTime(Some(dt)) => {
-            // Days since 1900-01-01, seconds_fragments in 1/300s
+            // Days since 1900-01-01, seconds_fragments in 1/300s, which
+            // round to the milliseconds SQL Server shows (.000, .003, .007)
             let unix_days = -25567i64 + dt.days() as i64;
             let (year, month, day) = days_to_ymd(unix_days);
-            let total_secs = dt.seconds_fragments() as f64 / 300.0;
-            let hours = (total_secs / 3600.0) as u32;
-            let mins = ((total_secs % 3600.0) / 60.0) as u32;
-            let secs = (total_secs % 60.0) as u32;
-            format!(
-                "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
-                year, month, day, hours, mins, secs
-            )
+            let total_ms = (dt.seconds_fragments() as u64 * 1000 + 150) / 300;
+            let total_secs = total_ms / 1000;
+            let hours = total_secs / 3600;
+            let mins = (total_secs % 3600) / 60;
+            let secs = total_secs % 60;
+            let millis = total_ms % 1000;
+            if millis > 0 {
+                format!(
+                    "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03}",
+                    year, month, day, hours, mins, secs, millis
+                )
+            } else {
+                format!(
+                    "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                    year, month, day, hours, mins, secs
+                )
+            }
         }
-        SqlValue::DateTime(None) => "NULL".to_string(),
+        SqlValue::DateTime(None) => return None,
         SqlValue::SmallDateTime(Some(dt)) => {
             let unix_days = -25567i64 + dt.days() as i64;
             let (year, month, day) = days_to_ymd(unix_days);
@@ -116,12 +351,12 @@ fn format_sql_value(val: &SqlValue<'_>) -> String {
                 year, month, day, hours, mins
             )
         }
-        SqlValue::SmallDateTime(None) => "NULL".to_string(),
+        SqlValue::SmallDateTime(None) => return None,
         SqlValue::Date(Some(d)) => {
             let (year, month, day) = days_to_ymd(d.days() as i64 - 719163);
             format!("{:04}-{:02}-{:02}", year, month, day)
         }
-        SqlValue::Date(None) => "NULL".to_string(),
+        SqlValue::Date(None) => return None,
         SqlValue::Time(Some(t)) => {
             let nanos = t.increments() as f64 * 10f64.powi(9 - t.scale() as i32);
             let total_secs = (nanos / 1_000_000_000.0) as u64;
@@ -135,7 +370,7 @@ fn format_sql_value(val: &SqlValue<'_>) -> String {
                 format!("{:02}:{:02}:{:02}", hours, mins, secs)
             }
         }
-        SqlValue::Time(None) => "NULL".to_string(),
+        SqlValue::Time(None) => return None,
         SqlValue::DateTime2(Some(dt2)) => {
             let (year, month, day) = days_to_ymd(dt2.date().days() as i64 - 719163);
             let t = dt2.time();
@@ -163,7 +398,7 @@ fn format_sql_value(val: &SqlValue<'_>) -> String {
                 )
             }
         }
-        SqlValue::DateTime2(None) => "NULL".to_string(),
+        SqlValue::DateTime2(None) => return None,
         SqlValue::DateTimeOffset(Some(dto)) => {
             let dt2 = dto.datetime2();
             let (year, month, day) = days_to_ymd(dt2.date().days() as i64 - 719163);
@@ -173,30 +408,37 @@ fn format_sql_value(val: &SqlValue<'_>) -> String {
             let hours = total_secs / 3600;
             let mins = (total_secs % 3600) / 60;
             let secs = total_secs % 60;
+            let frac = (nanos % 1_000_000_000.0) as u64;
+            let fraction = if frac > 0 {
+                format!(".{:07}", frac / 100)
+            } else {
+                String::new()
+            };
             let offset_mins = dto.offset();
             let sign = if offset_mins >= 0 { '+' } else { '-' };
             let abs_offset = offset_mins.unsigned_abs();
             format!(
-                "{:04}-{:02}-{:02} {:02}:{:02}:{:02} {}{:02}:{:02}",
+                "{:04}-{:02}-{:02} {:02}:{:02}:{:02}{} {}{:02}:{:02}",
                 year,
                 month,
                 day,
                 hours,
                 mins,
                 secs,
+                fraction,
                 sign,
                 abs_offset / 60,
                 abs_offset % 60
             )
         }
-        SqlValue::DateTimeOffset(None) => "NULL".to_string(),
+        SqlValue::DateTimeOffset(None) => return None,
         other => format!("{:?}", other),
-    }
+    })
 }
 
 /// Convert days since Unix epoch (1970-01-01) to (year, month, day).
 /// Uses Howard Hinnant's civil calendar algorithm.
-fn days_to_ymd(z: i64) -> (i64, u32, u32) {
+pub(crate) fn days_to_ymd(z: i64) -> (i64, u32, u32) {
     let z = z + 719468; // shift to 0000-03-01 epoch
     let era = if z >= 0 {
         z / 146097
@@ -232,30 +474,172 @@ pub async fn fetch_object_tree(
     let mut databases = Vec::new();
     for row in &db_rows {
         let db_name: &str = row.get(0usize).unwrap_or("?");
-        databases.push(ObjectNode {
-            name: db_name.to_string(),
-            depth: 0,
-            expanded: false,
-            children: Vec::new(),
-        });
+        databases.push(ObjectNode::new(db_name, NodeKind::Database, 0));
     }
 
     // For the current database, pre-load schemas and tables
     if let Some(db) = databases.iter_mut().find(|d| d.name == "master") {
-        load_schemas_and_tables(client, db).await.ok();
+        load_database(client, db).await.ok();
     }
 
     Ok(databases)
 }
 
-/// Load schemas and tables for a specific database node.
-pub async fn load_schemas_and_tables(
+/// Fetch the names of all stored procedures in the current database.
+pub async fn fetch_procedures(
+    client: &mut ConnectionHandle,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let stream = client
+        .execute("SELECT name FROM sys.procedures ORDER BY name", &[])
+        .await?;
+    let rows = stream.into_first_result().await?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| row.get::<&str, _>(0usize).map(str::to_string))
+        .collect())
+}
+
+/// Fetch a table's column names in column order.
+pub async fn fetch_columns(
+    client: &mut ConnectionHandle,
+    table: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let sql = format!(
+        "SELECT name FROM sys.columns WHERE object_id = OBJECT_ID(N'{}') ORDER BY column_id",
+        table.replace('\'', "''")
+    );
+    let stream = client.execute(&sql, &[]).await?;
+    let rows = stream.into_first_result().await?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| row.get::<&str, _>(0usize).map(str::to_string))
+        .collect())
+}
+
+/// Fetch the foreign keys where `table` is the referencing or the
+/// referenced table.
+pub async fn fetch_foreign_keys(
+    client: &mut ConnectionHandle,
+    table: &str,
+) -> Result<Vec<ForeignKey>, Box<dyn std::error::Error>> {
+    let sql = format!(
+        "SELECT OBJECT_NAME(fkc.constraint_object_id), \
+         OBJECT_NAME(fkc.parent_object_id), pc.name, \
+         OBJECT_NAME(fkc.referenced_object_id), rc.name \
+         FROM sys.foreign_key_columns fkc \
+         JOIN sys.columns pc ON pc.object_id = fkc.parent_object_id AND pc.column_id = fkc.parent_column_id \
+         JOIN sys.columns rc ON rc.object_id = fkc.referenced_object_id AND rc.column_id = fkc.referenced_column_id \
+         WHERE OBJECT_ID(N'{0}') IN (fkc.parent_object_id, fkc.referenced_object_id) \
+         ORDER BY fkc.constraint_object_id, fkc.constraint_column_id",
+        table.replace('\'', "''")
+    );
+    let stream = client.execute(&sql, &[]).await?;
+    let rows = stream.into_first_result().await?;
+
+    // One row per column pair; group them by constraint
+    let mut keys: Vec<(String, ForeignKey)> = Vec::new();
+    for row in &rows {
+        let text = |i: usize| row.get::<&str, _>(i).unwrap_or("").to_string();
+        let name = text(0);
+        if keys.last().is_none_or(|(n, _)| *n != name) {
+            keys.push((
+                name,
+                ForeignKey {
+                    table: text(1),
+                    columns: Vec::new(),
+                    ref_table: text(3),
+                    ref_columns: Vec::new(),
+                },
+            ));
+        }
+        if let Some((_, fk)) = keys.last_mut() {
+            fk.columns.push(text(2));
+            fk.ref_columns.push(text(4));
+        }
+    }
+    Ok(keys.into_iter().map(|(_, fk)| fk).collect())
+}
+
+/// The primary key columns of `table`, in key order; empty if it has none.
+pub async fn fetch_primary_key(
+    client: &mut ConnectionHandle,
+    table: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let sql = format!(
+        "SELECT c.name FROM sys.indexes i \
+         JOIN sys.index_columns ic ON ic.object_id = i.object_id AND ic.index_id = i.index_id \
+         JOIN sys.columns c ON c.object_id = ic.object_id AND c.column_id = ic.column_id \
+         WHERE i.object_id = OBJECT_ID(N'{}') AND i.is_primary_key = 1 \
+         ORDER BY ic.key_ordinal",
+        table.replace('\'', "''")
+    );
+    let stream = client.execute(&sql, &[]).await?;
+    let rows = stream.into_first_result().await?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| row.get::<&str, _>(0usize).map(str::to_string))
+        .collect())
+}
+
+/// The table column each column of the first result set of `sql` reads, as
+/// SQL Server describes it without running the batch: `None` for
+/// expressions and computed columns, and the column's own name where the
+/// result gives it an alias.
+pub async fn fetch_source_columns(
+    client: &mut ConnectionHandle,
+    sql: &str,
+) -> Result<Vec<Option<String>>, Box<dyn std::error::Error>> {
+    let describe = "SELECT IIF(is_computed_column = 1, NULL, source_column) \
+                    FROM sys.dm_exec_describe_first_result_set(@P1, NULL, 1) \
+                    WHERE is_hidden = 0 ORDER BY column_ordinal";
+    let result = execute_query_params(client, describe, &[sql.to_string()]).await?;
+    let Some(rs) = result.result_sets.first() else {
+        return Ok(Vec::new());
+    };
+    Ok((0..rs.rows.len())
+        .map(|r| rs.value(r, 0).map(str::to_string))
+        .collect())
+}
+
+/// Fetch a stored procedure's parameters from `sys.parameters`.
+///
+/// Returns an empty list when the procedure doesn't exist.
+pub async fn fetch_proc_params(
+    client: &mut ConnectionHandle,
+    proc: &str,
+) -> Result<Vec<ProcParam>, Box<dyn std::error::Error>> {
+    let sql = format!(
+        "SELECT p.name, {}, \
+         IIF(p.is_output = 1, 'YES', 'NO'), \
+         IIF(p.has_default_value = 1, CONVERT(nvarchar(4000), p.default_value), NULL) \
+         FROM sys.parameters p \
+         WHERE p.object_id = OBJECT_ID(N'{}') AND p.parameter_id > 0 \
+         ORDER BY p.parameter_id",
+        declared_type("p"),
+        proc.replace('\'', "''")
+    );
+    let stream = client.execute(&sql, &[]).await?;
+    let rows = stream.into_first_result().await?;
+    Ok(rows
+        .iter()
+        .map(|row| ProcParam {
+            name: row.get::<&str, _>(0usize).unwrap_or("?").to_string(),
+            type_name: row.get::<&str, _>(1usize).unwrap_or("").to_string(),
+            output: row.get::<&str, _>(2usize) == Some("YES"),
+            default: row.get::<&str, _>(3usize).map(str::to_string),
+        })
+        .collect())
+}
+
+/// Load a database node's children: its schemas with their tables, then
+/// Views, Stored Procedures, Functions, and Triggers folders.
+pub async fn load_database(
     client: &mut ConnectionHandle,
     db_node: &mut ObjectNode,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let sql = format!(
         "SELECT TABLE_SCHEMA, TABLE_NAME FROM {}.INFORMATION_SCHEMA.TABLES ORDER BY TABLE_SCHEMA, TABLE_NAME",
-        db_node.name
+        quote_ident(&db_node.name)
     );
     let stream = client.execute(&sql, &[]).await?;
     let rows = stream.into_first_result().await?;
@@ -275,20 +659,401 @@ pub async fn load_schemas_and_tables(
     db_node.children = schemas
         .into_iter()
         .map(|(schema, tables)| ObjectNode {
-            name: schema,
-            depth: 1,
-            expanded: false,
             children: tables
                 .into_iter()
-                .map(|t| ObjectNode {
-                    name: t,
-                    depth: 2,
-                    expanded: false,
-                    children: Vec::new(),
-                })
+                .map(|t| ObjectNode::new(t, NodeKind::Table, 2))
                 .collect(),
+            ..ObjectNode::new(schema, NodeKind::Schema, 1)
         })
         .collect();
+    db_node
+        .children
+        .extend(load_object_folders(client, &db_node.name).await?);
+    db_node.loaded = true;
 
     Ok(())
 }
+
+/// The SQL definition of a view, procedure, function, or trigger, in its
+/// own database if `object` names one, else in `database`. `None` for
+/// objects without one, such as tables or encrypted modules.
+pub async fn fetch_definition(
+    client: &mut ConnectionHandle,
+    database: &str,
+    object: &ObjectName,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let db = quote_ident(object.database.as_deref().unwrap_or(database));
+    let qualified = match object.schema {
+        Some(_) => format!("{}.{}", db, object.local()),
+        None => format!("{}..{}", db, object.local()),
+    };
+    let sql = format!(
+        "SELECT m.definition FROM {db}.sys.sql_modules m WHERE m.object_id = OBJECT_ID(N'{}')",
+        qualified.replace('\'', "''")
+    );
+    let stream = client.execute(&sql, &[]).await?;
+    let rows = stream.into_first_result().await?;
+    Ok(rows
+        .first()
+        .and_then(|row| row.get::<&str, _>(0usize))
+        .map(str::to_string))
+}
+
+/// Server and session details for the status bar.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionInfo {
+    /// `SERVERPROPERTY('ProductVersion')`, e.g. `16.0.4135.4`.
+    pub version: String,
+    /// Edition without the `Edition (64-bit)` suffix, e.g. `Developer`.
+    pub edition: String,
+    /// Server process ID of the session.
+    pub spid: String,
+    /// Whether the connection is encrypted; `None` without permission to see
+    /// `sys.dm_exec_connections`.
+    pub encrypted: Option<bool>,
+    /// Open transactions (`@@TRANCOUNT`) after the last query.
+    pub transactions: u32,
+    /// `SERVERPROPERTY('ServerName')`: the instance that served the
+    /// connection, which behind a listener isn't the host connected to.
+    pub replica: String,
+    /// Whether the database was read-only when connecting, as on a readable
+    /// secondary.
+    pub read_only: bool,
+    /// First line of `@@VERSION`, e.g. `Microsoft SQL Server 2022 (RTM-CU12)
+    /// (KB5033663) - 16.0.4115.5 (X64)`.
+    pub server_version: String,
+    /// TDS version and transport, e.g. `TDS 7.4 over TCP`.
+    pub protocol: String,
+    /// Network packet size in bytes; `None` without permission to see
+    /// `sys.dm_exec_connections`.
+    pub packet_size: Option<u32>,
+}
+
+/// A `protocol_version` connection property as a TDS version, e.g.
+/// `0x74000004` as `7.4`.
+fn tds_version(raw: u32) -> String {
+    match raw >> 24 {
+        0x74 => "7.4".to_string(),
+        0x73 if (raw >> 16) & 0xFF == 0x0B => "7.3B".to_string(),
+        0x73 => "7.3A".to_string(),
+        0x72 => "7.2".to_string(),
+        0x71 => "7.1".to_string(),
+        0x70 => "7.0".to_string(),
+        0x08 => "8.0".to_string(),
+        _ => format!("0x{:08X}", raw),
+    }
+}
+
+/// Fetch the server version, edition, SPID, encryption, transaction count,
+/// serving replica, protocol, and packet size of the session. Meant to run
+/// once after connecting; see [`fetch_transaction_count`] for keeping the
+/// count current.
+pub async fn fetch_session_info(
+    client: &mut ConnectionHandle,
+) -> Result<SessionInfo, Box<dyn std::error::Error>> {
+    // dm_exec_connections needs VIEW SERVER STATE; without it, leave it NULL
+    let sql = "DECLARE @encrypt nvarchar(40), @packet int;                BEGIN TRY SELECT @encrypt = encrypt_option, @packet = net_packet_size FROM sys.dm_exec_connections WHERE session_id = @@SPID AND parent_connection_id IS NULL; END TRY                BEGIN CATCH END CATCH;                SELECT CAST(SERVERPROPERTY('ProductVersion') AS nvarchar(128)),                CAST(SERVERPROPERTY('Edition') AS nvarchar(128)),                CAST(@@SPID AS varchar(10)), @encrypt, CAST(@@TRANCOUNT AS varchar(10)),                CAST(SERVERPROPERTY('ServerName') AS nvarchar(128)),                CAST(DATABASEPROPERTYEX(DB_NAME(), 'Updateability') AS nvarchar(20)),                CAST(@@VERSION AS nvarchar(4000)), CAST(CAST(CONNECTIONPROPERTY('protocol_version') AS bigint) AS varchar(20)),                CAST(CONNECTIONPROPERTY('net_transport') AS nvarchar(40)), CAST(@packet AS varchar(10))";
+    let stream = client.execute(sql, &[]).await?;
+    let rows = stream.into_first_result().await?;
+    let Some(row) = rows.first() else {
+        return Ok(SessionInfo::default());
+    };
+    let text = |i: usize| row.get::<&str, _>(i).unwrap_or("").to_string();
+    let edition = text(1);
+    Ok(SessionInfo {
+        version: text(0),
+        edition: edition
+            .split(" Edition")
+            .next()
+            .unwrap_or(&edition)
+            .to_string(),
+        spid: text(2),
+        encrypted: match text(3).as_str() {
+            "TRUE" => Some(true),
+            "FALSE" => Some(false),
+            _ => None,
+        },
+        transactions: text(4).parse().unwrap_or(0),
+        replica: text(5),
+        read_only: text(6) == "READ_ONLY",
+        server_version: text(7).lines().next().unwrap_or("").trim().to_string(),
+        protocol: match text(8).parse::<u32>() {
+            Ok(raw) => format!("TDS {} over {}", tds_version(raw), text(9)),
+            Err(_) => text(9),
+        },
+        packet_size: text(10).parse().ok(),
+    })
+}
+
+/// The estimated plan of `sql` as `SET SHOWPLAN_ALL` rows, one result set
+/// per statement. `sql` is compiled but not run.
+pub async fn fetch_plan(
+    client: &mut ConnectionHandle,
+    sql: &str,
+) -> Result<QueryResult, Box<dyn std::error::Error>> {
+    // SHOWPLAN_ALL must be the only statement in its batch
+    client
+        .execute("SET SHOWPLAN_ALL ON", &[])
+        .await?
+        .into_results()
+        .await?;
+    let result = execute_query(client, sql).await;
+    // Even when compiling failed, or every later query would only be planned
+    client
+        .execute("SET SHOWPLAN_ALL OFF", &[])
+        .await?
+        .into_results()
+        .await?;
+    result
+}
+
+/// How long [`ping`] waits for an answer.
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Check that the connection still works with a `SELECT 1`, failing if it
+/// errors or the server doesn't answer within [`PING_TIMEOUT`].
+pub async fn ping(client: &mut ConnectionHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let check = async {
+        client
+            .execute("SELECT 1", &[])
+            .await?
+            .into_results()
+            .await?;
+        Ok::<_, Box<dyn std::error::Error>>(())
+    };
+    match tokio::time::timeout(PING_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("no answer within {}s", PING_TIMEOUT.as_secs()).into()),
+    }
+}
+
+/// The session's open transaction count (`@@TRANCOUNT`).
+pub async fn fetch_transaction_count(
+    client: &mut ConnectionHandle,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let stream = client
+        .execute("SELECT CAST(@@TRANCOUNT AS varchar(10))", &[])
+        .await?;
+    let rows = stream.into_first_result().await?;
+    Ok(rows
+        .first()
+        .and_then(|row| row.get::<&str, _>(0usize))
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0))
+}
+
+/// Server load figures for the status bar's health probe.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HealthSnapshot {
+    /// User requests currently running or waiting, other than this session's.
+    pub active_sessions: u64,
+    /// Requests blocked by another session.
+    pub blocked_sessions: u64,
+    /// Space allocated in tempdb, in MB.
+    pub tempdb_used_mb: u64,
+}
+
+/// Take a health snapshot. Needs `VIEW SERVER STATE`.
+pub async fn fetch_health(
+    client: &mut ConnectionHandle,
+) -> Result<HealthSnapshot, Box<dyn std::error::Error>> {
+    let sql = "SELECT CAST((SELECT COUNT(*) FROM sys.dm_exec_requests r JOIN sys.dm_exec_sessions s ON s.session_id = r.session_id WHERE s.is_user_process = 1 AND r.session_id <> @@SPID) AS varchar(20)), \
+               CAST((SELECT COUNT(*) FROM sys.dm_exec_requests WHERE blocking_session_id <> 0) AS varchar(20)), \
+               CAST((SELECT SUM(allocated_extent_page_count) * 8 / 1024 FROM tempdb.sys.dm_db_file_space_usage) AS varchar(20))";
+    let stream = client.execute(sql, &[]).await?;
+    let rows = stream.into_first_result().await?;
+    let number = |i: usize| {
+        rows.first()
+            .and_then(|row| row.get::<&str, _>(i))
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(0)
+    };
+    Ok(HealthSnapshot {
+        active_sessions: number(0),
+        blocked_sessions: number(1),
+        tempdb_used_mb: number(2),
+    })
+}
+
+/// How a table's columns take part in an INSERT.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InsertColumns {
+    /// Computed and `rowversion` columns, which can't be written.
+    pub generated: Vec<String>,
+    /// The identity column, written with `SET IDENTITY_INSERT` on.
+    pub identity: Option<String>,
+}
+
+/// The generated and identity columns of `table`; none if there's no such
+/// table.
+pub async fn fetch_insert_columns(
+    client: &mut ConnectionHandle,
+    table: &str,
+) -> Result<InsertColumns, Box<dyn std::error::Error>> {
+    let sql = "SELECT c.name, CAST(c.is_identity AS char(1)) \
+               FROM sys.columns c \
+               WHERE c.object_id = OBJECT_ID(@P1) \
+                 AND (c.is_identity = 1 OR c.is_computed = 1 OR TYPE_NAME(c.system_type_id) = 'timestamp')";
+    let result = execute_query_params(client, sql, &[table.to_string()]).await?;
+    let mut columns = InsertColumns::default();
+    for row in result.rows_for(0) {
+        if row[1] == "1" {
+            columns.identity = Some(row[0].clone());
+        } else {
+            columns.generated.push(row[0].clone());
+        }
+    }
+    Ok(columns)
+}
+
+/// Approximate row count and used size in KB of each table in `database`,
+/// keyed by (schema, table), from `sys.dm_db_partition_stats`.
+pub async fn fetch_table_stats(
+    client: &mut ConnectionHandle,
+    database: &str,
+) -> Result<HashMap<(String, String), (u64, u64)>, Box<dyn std::error::Error>> {
+    let db = quote_ident(database);
+    let sql = format!(
+        "SELECT s.name, t.name, \
+         CAST(SUM(IIF(ps.index_id IN (0, 1), ps.row_count, 0)) AS varchar(20)), \
+         CAST(SUM(ps.used_page_count) * 8 AS varchar(20)) \
+         FROM {db}.sys.tables t \
+         JOIN {db}.sys.schemas s ON s.schema_id = t.schema_id \
+         JOIN {db}.sys.dm_db_partition_stats ps ON ps.object_id = t.object_id \
+         GROUP BY s.name, t.name"
+    );
+    let stream = client.execute(&sql, &[]).await?;
+    let rows = stream.into_first_result().await?;
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let text = |i: usize| row.get::<&str, _>(i).unwrap_or("").to_string();
+            let number = |i: usize| text(i).parse().unwrap_or(0);
+            ((text(0), text(1)), (number(2), number(3)))
+        })
+        .collect())
+}
+
+/// Views, Stored Procedures, Functions, and Triggers folders for a
+/// database, from `sys.objects`. Objects are labelled `schema.name`.
+async fn load_object_folders(
+    client: &mut ConnectionHandle,
+    database: &str,
+) -> Result<Vec<ObjectNode>, Box<dyn std::error::Error>> {
+    let db = quote_ident(database);
+    let sql = format!(
+        "SELECT RTRIM(o.type), s.name + '.' + o.name \
+         FROM {db}.sys.objects o JOIN {db}.sys.schemas s ON s.schema_id = o.schema_id \
+         WHERE o.type IN ('V', 'P', 'FN', 'IF', 'TF', 'FS', 'FT', 'TR') AND o.is_ms_shipped = 0 \
+         ORDER BY s.name, o.name"
+    );
+    let stream = client.execute(&sql, &[]).await?;
+    let rows = stream.into_first_result().await?;
+
+    let folders = [
+        ("Views", NodeKind::View),
+        ("Stored Procedures", NodeKind::Procedure),
+        ("Functions", NodeKind::Function),
+        ("Triggers", NodeKind::Trigger),
+    ];
+    Ok(folders
+        .iter()
+        .map(|&(label, kind)| {
+            let children: Vec<ObjectNode> = rows
+                .iter()
+                .filter(|row| object_kind(row.get::<&str, _>(0usize).unwrap_or("")) == Some(kind))
+                .map(|row| ObjectNode::new(row.get::<&str, _>(1usize).unwrap_or("?"), kind, 2))
+                .collect();
+            ObjectNode {
+                name: format!("{} ({})", label, children.len()),
+                children,
+                ..ObjectNode::new("", NodeKind::Folder, 1)
+            }
+        })
+        .collect())
+}
+
+/// Map a `sys.objects` type code to a node kind.
+fn object_kind(code: &str) -> Option<NodeKind> {
+    match code {
+        "V" => Some(NodeKind::View),
+        "P" => Some(NodeKind::Procedure),
+        "FN" | "IF" | "TF" | "FS" | "FT" => Some(NodeKind::Function),
+        "TR" => Some(NodeKind::Trigger),
+        _ => None,
+    }
+}
+
+/// Load a table or view node's children, given its database, schema, and
+/// name: Columns, Indexes, and Foreign Keys folders. Each entry's `info` is
+/// its type and nullability, index type, or referenced table.
+pub async fn load_table_details(
+    client: &mut ConnectionHandle,
+    database: &str,
+    schema: &str,
+    name: &str,
+    table_node: &mut ObjectNode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let db = quote_ident(database);
+    let object =
+        format!("{}.{}.{}", db, quote_ident(schema), quote_ident(name)).replace('\'', "''");
+    let sql = format!(
+        "SELECT c.name, {ty} + IIF(c.is_nullable = 1, ', null', ', not null') \
+         FROM {db}.sys.columns c WHERE c.object_id = OBJECT_ID(N'{object}') ORDER BY c.column_id; \
+         SELECT i.name, LOWER(i.type_desc) \
+           + IIF(i.is_primary_key = 1, ', primary key', IIF(i.is_unique = 1, ', unique', '')) \
+         FROM {db}.sys.indexes i WHERE i.object_id = OBJECT_ID(N'{object}') AND i.name IS NOT NULL \
+         ORDER BY i.index_id; \
+         SELECT fk.name, '→ ' + OBJECT_SCHEMA_NAME(fk.referenced_object_id, DB_ID(N'{dbname}')) \
+           + '.' + OBJECT_NAME(fk.referenced_object_id, DB_ID(N'{dbname}')) \
+         FROM {db}.sys.foreign_keys fk WHERE fk.parent_object_id = OBJECT_ID(N'{object}') \
+         ORDER BY fk.name",
+        ty = declared_type("c"),
+        dbname = database.replace('\'', "''"),
+    );
+    let stream = client.execute(&sql, &[]).await?;
+    let results = stream.into_results().await?;
+
+    let depth = table_node.depth;
+    let folders = ["Columns", "Indexes", "Foreign Keys"];
+    table_node.children = folders
+        .iter()
+        .enumerate()
+        .map(|(i, folder)| {
+            let rows = results.get(i).map(Vec::as_slice).unwrap_or_default();
+            ObjectNode {
+                name: format!("{} ({})", folder, rows.len()),
+                children: rows
+                    .iter()
+                    .map(|row| ObjectNode {
+                        info: row.get::<&str, _>(1usize).map(str::to_string),
+                        ..ObjectNode::new(
+                            row.get::<&str, _>(0usize).unwrap_or("?"),
+                            NodeKind::Detail,
+                            depth + 2,
+                        )
+                    })
+                    .collect(),
+                ..ObjectNode::new("", NodeKind::Folder, depth + 1)
+            }
+        })
+        .collect();
+    table_node.loaded = true;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_kind() {
+        assert_eq!(object_kind("V"), Some(NodeKind::View));
+        assert_eq!(object_kind("P"), Some(NodeKind::Procedure));
+        assert_eq!(object_kind("IF"), Some(NodeKind::Function));
+        assert_eq!(object_kind("FT"), Some(NodeKind::Function));
+        assert_eq!(object_kind("TR"), Some(NodeKind::Trigger));
+        assert_eq!(object_kind("U"), None);
+    }
+}
@@ -0,0 +1,585 @@
+//! Schema scripting: CREATE statements rebuilt from the catalog views, for
+//! `meow script-schema`, `\script db`, and `\script <table>`.
+
+use super::ConnectionHandle;
+use crate::commands::{ObjectName, declared_type, quote_ident};
+use std::collections::BTreeMap;
+
+/// One scripted object: a table with its constraints and indexes, a table's
+/// foreign keys, or a view, procedure, or function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptedObject {
+    pub schema: String,
+    pub name: String,
+    /// `Table`, `ForeignKeys`, `View`, `Procedure`, or `Function`.
+    pub kind: &'static str,
+    /// The statements, each followed by `GO`.
+    pub ddl: String,
+}
+
+impl ScriptedObject {
+    /// File name for the object, `schema.name.Kind.sql`, with characters
+    /// file systems reject replaced by `_`.
+    pub fn file_name(&self) -> String {
+        let clean = |s: &str| {
+            s.chars()
+                .map(|c| match c {
+                    '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+                    c if c.is_control() => '_',
+                    c => c,
+                })
+                .collect::<String>()
+        };
+        format!(
+            "{}.{}.{}.sql",
+            clean(&self.schema),
+            clean(&self.name),
+            self.kind
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct Table {
+    schema: String,
+    name: String,
+    columns: Vec<Column>,
+    /// Primary key and unique constraints.
+    keys: Vec<Key>,
+    foreign_keys: Vec<ForeignKey>,
+    /// Check constraints: name and definition.
+    checks: Vec<(String, String)>,
+    /// Indexes other than those backing key constraints.
+    indexes: Vec<Index>,
+}
+
+#[derive(Debug, Default)]
+struct Column {
+    name: String,
+    type_name: String,
+    nullable: bool,
+    /// Seed and increment.
+    identity: Option<(String, String)>,
+    /// Default constraint name and definition.
+    default: Option<(String, String)>,
+    /// Definition and whether it's persisted.
+    computed: Option<(String, bool)>,
+}
+
+#[derive(Debug, Default)]
+struct Key {
+    name: String,
+    primary: bool,
+    /// `CLUSTERED` or `NONCLUSTERED`.
+    type_desc: String,
+    /// Column names and whether they're descending.
+    columns: Vec<(String, bool)>,
+}
+
+#[derive(Debug, Default)]
+struct ForeignKey {
+    name: String,
+    columns: Vec<String>,
+    ref_table: String,
+    ref_columns: Vec<String>,
+    /// `ON DELETE` and `ON UPDATE` actions, e.g. `NO_ACTION` or `CASCADE`.
+    on_delete: String,
+    on_update: String,
+}
+
+#[derive(Debug, Default)]
+struct Index {
+    name: String,
+    unique: bool,
+    type_desc: String,
+    columns: Vec<(String, bool)>,
+    included: Vec<String>,
+    filter: Option<String>,
+}
+
+/// Script the tables, views, procedures, and functions of `database`,
+/// ordered by kind, then schema and name. Foreign keys come after all the
+/// tables, so the tables they reference already exist.
+pub async fn script_schema(
+    client: &mut ConnectionHandle,
+    database: &str,
+) -> Result<Vec<ScriptedObject>, Box<dyn std::error::Error>> {
+    let tables = fetch_tables(client, database, None).await?;
+    let mut objects: Vec<ScriptedObject> = tables
+        .iter()
+        .map(|table| ScriptedObject {
+            schema: table.schema.clone(),
+            name: table.name.clone(),
+            kind: "Table",
+            ddl: create_table(table),
+        })
+        .collect();
+    objects.extend(tables.iter().filter_map(|table| {
+        Some(ScriptedObject {
+            schema: table.schema.clone(),
+            name: table.name.clone(),
+            kind: "ForeignKeys",
+            ddl: add_foreign_keys(table)?,
+        })
+    }));
+
+    let db = quote_ident(database);
+    let sql = format!(
+        "SELECT s.name, o.name, RTRIM(o.type), m.definition \
+         FROM {db}.sys.sql_modules m \
+         JOIN {db}.sys.objects o ON o.object_id = m.object_id \
+         JOIN {db}.sys.schemas s ON s.schema_id = o.schema_id \
+         WHERE o.type IN ('V', 'P', 'FN', 'IF', 'TF') AND o.is_ms_shipped = 0 \
+         ORDER BY CASE o.type WHEN 'V' THEN 0 WHEN 'P' THEN 1 ELSE 2 END, s.name, o.name"
+    );
+    let stream = client.execute(&sql, &[]).await?;
+    let rows = stream.into_first_result().await?;
+    for row in &rows {
+        let text = |i: usize| row.get::<&str, _>(i).unwrap_or("").to_string();
+        let (schema, name) = (text(0), text(1));
+        let kind = match text(2).as_str() {
+            "V" => "View",
+            "P" => "Procedure",
+            _ => "Function",
+        };
+        // Encrypted modules have no definition
+        let ddl = match row.get::<&str, _>(3usize) {
+            Some(definition) => format!("{}\nGO\n", definition.trim()),
+            None => format!(
+                "-- {}.{} is encrypted; its definition is not available\n",
+                quote_ident(&schema),
+                quote_ident(&name)
+            ),
+        };
+        objects.push(ScriptedObject {
+            schema,
+            name,
+            kind,
+            ddl,
+        });
+    }
+    Ok(objects)
+}
+
+/// The CREATE TABLE script for `table`, in its own database if it names
+/// one and `database` otherwise, with its constraints and indexes. `None`
+/// if there's no such table.
+pub async fn script_table(
+    client: &mut ConnectionHandle,
+    database: &str,
+    table: &ObjectName,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let database = table.database.as_deref().unwrap_or(database);
+    let tables = fetch_tables(client, database, Some(table)).await?;
+    Ok(tables.first().map(|table| {
+        let mut ddl = create_table(table);
+        ddl.push_str(&add_foreign_keys(table).unwrap_or_default());
+        ddl
+    }))
+}
+
+/// Fetch the user tables of `database` with their columns, constraints, and
+/// indexes: all of them, or just `only`.
+async fn fetch_tables(
+    client: &mut ConnectionHandle,
+    database: &str,
+    only: Option<&ObjectName>,
+) -> Result<Vec<Table>, Box<dyn std::error::Error>> {
+    let db = quote_ident(database);
+    // OBJECT_ID resolves names in the current database unless qualified
+    let filter = |column: &str| match only {
+        Some(table) => {
+            let qualified = match table.schema {
+                Some(_) => format!("{}.{}", db, table.local()),
+                None => format!("{}..{}", db, table.local()),
+            };
+            format!(
+                " AND {} = OBJECT_ID(N'{}')",
+                column,
+                qualified.replace('\'', "''")
+            )
+        }
+        None => String::new(),
+    };
+    let id = |column: &str| format!("CAST({} AS varchar(20))", column);
+    let sql = format!(
+        "SELECT {t_id}, s.name, t.name, c.name, {ty}, CAST(c.is_nullable AS char(1)), \
+           CAST(ic.seed_value AS varchar(40)), CAST(ic.increment_value AS varchar(40)), \
+           dc.name, dc.definition, cc.definition, CAST(cc.is_persisted AS char(1)) \
+         FROM {db}.sys.tables t \
+         JOIN {db}.sys.schemas s ON s.schema_id = t.schema_id \
+         JOIN {db}.sys.columns c ON c.object_id = t.object_id \
+         LEFT JOIN {db}.sys.identity_columns ic ON ic.object_id = c.object_id AND ic.column_id = c.column_id \
+         LEFT JOIN {db}.sys.default_constraints dc ON dc.parent_object_id = c.object_id AND dc.parent_column_id = c.column_id \
+         LEFT JOIN {db}.sys.computed_columns cc ON cc.object_id = c.object_id AND cc.column_id = c.column_id \
+         WHERE t.is_ms_shipped = 0{t_filter} \
+         ORDER BY s.name, t.name, c.column_id; \
+         SELECT {kc_id}, kc.name, kc.type, i.type_desc, c.name, CAST(xc.is_descending_key AS char(1)) \
+         FROM {db}.sys.key_constraints kc \
+         JOIN {db}.sys.indexes i ON i.object_id = kc.parent_object_id AND i.index_id = kc.unique_index_id \
+         JOIN {db}.sys.index_columns xc ON xc.object_id = i.object_id AND xc.index_id = i.index_id \
+         JOIN {db}.sys.columns c ON c.object_id = xc.object_id AND c.column_id = xc.column_id \
+         WHERE xc.is_included_column = 0{kc_filter} \
+         ORDER BY kc.parent_object_id, kc.type, kc.name, xc.key_ordinal; \
+         SELECT {fk_id}, fk.name, pc.name, \
+           QUOTENAME(rs.name) + '.' + QUOTENAME(rt.name), rc.name, \
+           fk.delete_referential_action_desc, fk.update_referential_action_desc \
+         FROM {db}.sys.foreign_keys fk \
+         JOIN {db}.sys.foreign_key_columns fkc ON fkc.constraint_object_id = fk.object_id \
+         JOIN {db}.sys.columns pc ON pc.object_id = fkc.parent_object_id AND pc.column_id = fkc.parent_column_id \
+         JOIN {db}.sys.tables rt ON rt.object_id = fk.referenced_object_id \
+         JOIN {db}.sys.schemas rs ON rs.schema_id = rt.schema_id \
+         JOIN {db}.sys.columns rc ON rc.object_id = fkc.referenced_object_id AND rc.column_id = fkc.referenced_column_id \
+         WHERE 1 = 1{fk_filter} \
+         ORDER BY fk.parent_object_id, fk.name, fkc.constraint_column_id; \
+         SELECT {ck_id}, ck.name, ck.definition FROM {db}.sys.check_constraints ck \
+         WHERE 1 = 1{ck_filter} \
+         ORDER BY ck.parent_object_id, ck.name; \
+         SELECT {i_id}, i.name, CAST(i.is_unique AS char(1)), i.type_desc, c.name, \
+           CAST(xc.is_descending_key AS char(1)), CAST(xc.is_included_column AS char(1)), i.filter_definition \
+         FROM {db}.sys.indexes i \
+         JOIN {db}.sys.tables t ON t.object_id = i.object_id \
+         JOIN {db}.sys.index_columns xc ON xc.object_id = i.object_id AND xc.index_id = i.index_id \
+         JOIN {db}.sys.columns c ON c.object_id = xc.object_id AND c.column_id = xc.column_id \
+         WHERE t.is_ms_shipped = 0 AND i.is_primary_key = 0 AND i.is_unique_constraint = 0 AND i.type IN (1, 2){i_filter} \
+         ORDER BY i.object_id, i.name, xc.is_included_column, xc.key_ordinal, xc.index_column_id",
+        t_id = id("t.object_id"),
+        kc_id = id("kc.parent_object_id"),
+        fk_id = id("fk.parent_object_id"),
+        ck_id = id("ck.parent_object_id"),
+        i_id = id("i.object_id"),
+        ty = declared_type("c"),
+        t_filter = filter("t.object_id"),
+        kc_filter = filter("kc.parent_object_id"),
+        fk_filter = filter("fk.parent_object_id"),
+        ck_filter = filter("ck.parent_object_id"),
+        i_filter = filter("i.object_id"),
+    );
+    let stream = client.execute(&sql, &[]).await?;
+    let results = stream.into_results().await?;
+    let result = |i: usize| results.get(i).map(Vec::as_slice).unwrap_or_default();
+
+    // Keyed by object_id, in schema and name order
+    let mut order = Vec::new();
+    let mut tables: BTreeMap<String, Table> = BTreeMap::new();
+    for row in result(0) {
+        let text = |i: usize| row.get::<&str, _>(i).map(str::to_string);
+        let id = text(0).unwrap_or_default();
+        let table = tables.entry(id.clone()).or_insert_with(|| {
+            order.push(id);
+            Table {
+                schema: text(1).unwrap_or_default(),
+                name: text(2).unwrap_or_default(),
+                ..Table::default()
+            }
+        });
+        table.columns.push(Column {
+            name: text(3).unwrap_or_default(),
+            type_name: text(4).unwrap_or_default(),
+            nullable: text(5).as_deref() == Some("1"),
+            identity: text(6).zip(text(7)),
+            default: text(8).zip(text(9)),
+            computed: text(10).map(|d| (d, text(11).as_deref() == Some("1"))),
+        });
+    }
+
+    for row in result(1) {
+        let text = |i: usize| row.get::<&str, _>(i).unwrap_or("").to_string();
+        let Some(table) = tables.get_mut(&text(0)) else {
+            continue;
+        };
+        let name = text(1);
+        if table.keys.last().is_none_or(|k| k.name != name) {
+            table.keys.push(Key {
+                name,
+                primary: text(2).trim() == "PK",
+                type_desc: text(3),
+                columns: Vec::new(),
+            });
+        }
+        if let Some(key) = table.keys.last_mut() {
+            key.columns.push((text(4), text(5) == "1"));
+        }
+    }
+
+    for row in result(2) {
+        let text = |i: usize| row.get::<&str, _>(i).unwrap_or("").to_string();
+        let Some(table) = tables.get_mut(&text(0)) else {
+            continue;
+        };
+        let name = text(1);
+        if table.foreign_keys.last().is_none_or(|fk| fk.name != name) {
+            table.foreign_keys.push(ForeignKey {
+                name,
+                ref_table: text(3),
+                on_delete: text(5),
+                on_update: text(6),
+                ..ForeignKey::default()
+            });
+        }
+        if let Some(fk) = table.foreign_keys.last_mut() {
+            fk.columns.push(text(2));
+            fk.ref_columns.push(text(4));
+        }
+    }
+
+    for row in result(3) {
+        let text = |i: usize| row.get::<&str, _>(i).unwrap_or("").to_string();
+        if let Some(table) = tables.get_mut(&text(0)) {
+            table.checks.push((text(1), text(2)));
+        }
+    }
+
+    for row in result(4) {
+        let text = |i: usize| row.get::<&str, _>(i).map(str::to_string);
+        let Some(table) = tables.get_mut(&text(0).unwrap_or_default()) else {
+            continue;
+        };
+        let name = text(1).unwrap_or_default();
+        if table.indexes.last().is_none_or(|i| i.name != name) {
+            table.indexes.push(Index {
+                name,
+                unique: text(2).as_deref() == Some("1"),
+                type_desc: text(3).unwrap_or_default(),
+                filter: text(7),
+                ..Index::default()
+            });
+        }
+        if let Some(index) = table.indexes.last_mut() {
+            let column = text(4).unwrap_or_default();
+            if text(6).as_deref() == Some("1") {
+                index.included.push(column);
+            } else {
+                index
+                    .columns
+                    .push((column, text(5).as_deref() == Some("1")));
+            }
+        }
+    }
+
+    Ok(order
+        .into_iter()
+        .filter_map(|id| tables.remove(&id))
+        .collect())
+}
+
+/// `[a], [b] DESC`
+fn key_list(columns: &[(String, bool)]) -> String {
+    columns
+        .iter()
+        .map(|(name, desc)| {
+            if *desc {
+                format!("{} DESC", quote_ident(name))
+            } else {
+                quote_ident(name)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn name_list(columns: &[String]) -> String {
+    columns
+        .iter()
+        .map(|c| quote_ident(c))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `[schema].[name]` of `table`.
+fn qualified_name(table: &Table) -> String {
+    format!(
+        "{}.{}",
+        quote_ident(&table.schema),
+        quote_ident(&table.name)
+    )
+}
+
+/// The CREATE TABLE statement for `table`, with its key and check
+/// constraints inline, followed by CREATE INDEX for its other indexes.
+/// Foreign keys are left to [`add_foreign_keys`].
+fn create_table(table: &Table) -> String {
+    let qualified = qualified_name(table);
+    let mut lines = Vec::new();
+    for c in &table.columns {
+        let mut line = format!("    {} ", quote_ident(&c.name));
+        if let Some((ref definition, persisted)) = c.computed {
+            line.push_str(&format!("AS {}", definition));
+            if persisted {
+                line.push_str(" PERSISTED");
+            }
+            lines.push(line);
+            continue;
+        }
+        line.push_str(&c.type_name);
+        if let Some((ref seed, ref increment)) = c.identity {
+            line.push_str(&format!(" IDENTITY({}, {})", seed, increment));
+        }
+        line.push_str(if c.nullable { " NULL" } else { " NOT NULL" });
+        if let Some((ref name, ref definition)) = c.default {
+            line.push_str(&format!(
+                " CONSTRAINT {} DEFAULT {}",
+                quote_ident(name),
+                definition
+            ));
+        }
+        lines.push(line);
+    }
+    for key in &table.keys {
+        lines.push(format!(
+            "    CONSTRAINT {} {} {} ({})",
+            quote_ident(&key.name),
+            if key.primary { "PRIMARY KEY" } else { "UNIQUE" },
+            key.type_desc,
+            key_list(&key.columns)
+        ));
+    }
+    for (name, definition) in &table.checks {
+        lines.push(format!(
+            "    CONSTRAINT {} CHECK {}",
+            quote_ident(name),
+            definition
+        ));
+    }
+
+    let mut out = format!(
+        "CREATE TABLE {} (\n{}\n);\nGO\n",
+        qualified,
+        lines.join(",\n")
+    );
+    for index in &table.indexes {
+        out.push_str(&format!(
+            "CREATE {}{} INDEX {} ON {} ({})",
+            if index.unique { "UNIQUE " } else { "" },
+            index.type_desc,
+            quote_ident(&index.name),
+            qualified,
+            key_list(&index.columns)
+        ));
+        if !index.included.is_empty() {
+            out.push_str(&format!(" INCLUDE ({})", name_list(&index.included)));
+        }
+        if let Some(ref filter) = index.filter {
+            out.push_str(&format!(" WHERE {}", filter));
+        }
+        out.push_str(";\nGO\n");
+    }
+    out
+}
+
+/// `ALTER TABLE ... ADD CONSTRAINT` for each foreign key of `table`, or
+/// `None` if it has none.
+fn add_foreign_keys(table: &Table) -> Option<String> {
+    if table.foreign_keys.is_empty() {
+        return None;
+    }
+    let qualified = qualified_name(table);
+    let mut out = String::new();
+    for fk in &table.foreign_keys {
+        out.push_str(&format!(
+            "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({})",
+            qualified,
+            quote_ident(&fk.name),
+            name_list(&fk.columns),
+            fk.ref_table,
+            name_list(&fk.ref_columns)
+        ));
+        for (event, action) in [("DELETE", &fk.on_delete), ("UPDATE", &fk.on_update)] {
+            if !action.is_empty() && action != "NO_ACTION" {
+                out.push_str(&format!(" ON {} {}", event, action.replace('_', " ")));
+            }
+        }
+        out.push_str(";\nGO\n");
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_table() {
+        let table = Table {
+            schema: "dbo".into(),
+            name: "Order Lines".into(),
+            columns: vec![
+                Column {
+                    name: "id".into(),
+                    type_name: "int".into(),
+                    identity: Some(("1".into(), "1".into())),
+                    ..Column::default()
+                },
+                Column {
+                    name: "status".into(),
+                    type_name: "nvarchar(20)".into(),
+                    default: Some(("DF_status".into(), "('new')".into())),
+                    ..Column::default()
+                },
+                Column {
+                    name: "total".into(),
+                    computed: Some(("([qty]*[price])".into(), true)),
+                    ..Column::default()
+                },
+                Column {
+                    name: "note".into(),
+                    type_name: "nvarchar(max)".into(),
+                    nullable: true,
+                    ..Column::default()
+                },
+            ],
+            keys: vec![Key {
+                name: "PK_Lines".into(),
+                primary: true,
+                type_desc: "CLUSTERED".into(),
+                columns: vec![("id".into(), false)],
+            }],
+            foreign_keys: vec![ForeignKey {
+                name: "FK_Lines_Orders".into(),
+                columns: vec!["order_id".into()],
+                ref_table: "[dbo].[Orders]".into(),
+                ref_columns: vec!["id".into()],
+                on_delete: "CASCADE".into(),
+                on_update: "NO_ACTION".into(),
+            }],
+            checks: vec![("CK_qty".into(), "([qty]>(0))".into())],
+            indexes: vec![Index {
+                name: "IX_status".into(),
+                unique: false,
+                type_desc: "NONCLUSTERED".into(),
+                columns: vec![("status".into(), false), ("id".into(), true)],
+                included: vec!["note".into()],
+                filter: Some("([note] IS NOT NULL)".into()),
+            }],
+        };
+        assert_eq!(
+            create_table(&table),
+            "CREATE TABLE [dbo].[Order Lines] (\n\
+             \x20   [id] int IDENTITY(1, 1) NOT NULL,\n\
+             \x20   [status] nvarchar(20) NOT NULL CONSTRAINT [DF_status] DEFAULT ('new'),\n\
+             \x20   [total] AS ([qty]*[price]) PERSISTED,\n\
+             \x20   [note] nvarchar(max) NULL,\n\
+             \x20   CONSTRAINT [PK_Lines] PRIMARY KEY CLUSTERED ([id]),\n\
+             \x20   CONSTRAINT [CK_qty] CHECK ([qty]>(0))\n\
+             );\nGO\n\
+             CREATE NONCLUSTERED INDEX [IX_status] ON [dbo].[Order Lines] ([status], [id] DESC) INCLUDE ([note]) WHERE ([note] IS NOT NULL);\nGO\n"
+        );
+        assert_eq!(
+            add_foreign_keys(&table).unwrap(),
+            "ALTER TABLE [dbo].[Order Lines] ADD CONSTRAINT [FK_Lines_Orders] FOREIGN KEY ([order_id]) \
+             REFERENCES [dbo].[Orders] ([id]) ON DELETE CASCADE;\nGO\n"
+        );
+        assert_eq!(add_foreign_keys(&Table::default()), None);
+    }
+
+    #[test]
+    fn test_file_name() {
+        let object = ScriptedObject {
+            schema: "dbo".into(),
+            name: "a/b:c".into(),
+            kind: "View",
+            ddl: String::new(),
+        };
+        assert_eq!(object.file_name(), "dbo.a_b_c.View.sql");
+    }
+}
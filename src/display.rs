@@ -0,0 +1,518 @@
+//! Display formats for result values: how dates and times are written
+//! (`\pset datestyle`, `datestyle` in the config file), and how numbers are
+//! shown in the grid (`\pset thousands`, `\pset decimals`,
+//! `\pset currency`), and `datetimeoffset` values shown in another time zone
+//! (`\set timezone`).
+//!
+//! Values arrive as text in SQL Server's own style (`2024-01-31 14:05:00`,
+//! with ` +02:00` for `datetimeoffset`); these functions rewrite that text
+//! for the column's SQL type and leave anything else alone.
+
+use crate::app::QueryResult;
+use crate::db::query::days_to_ymd;
+use crate::timezone::Zone;
+use std::borrow::Cow;
+
+/// How date and time values are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateStyle {
+    /// As SQL Server writes them: `2024-01-31 14:05:00 +02:00`.
+    #[default]
+    Raw,
+    /// ISO 8601: `2024-01-31T14:05:00+02:00`.
+    Iso,
+    /// The locale's day order: `01/31/2024 14:05:00` for US locales,
+    /// `31/01/2024 14:05:00` otherwise.
+    Locale { month_first: bool },
+    /// Seconds since 1970-01-01 UTC; values without an offset are taken as
+    /// UTC. Times of day are left as they are.
+    Epoch,
+}
+
+impl DateStyle {
+    /// Parse a style name: `raw`, `iso`, `locale`, or `epoch`. `locale`
+    /// takes its day order from `LC_ALL`, `LC_TIME`, or `LANG`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "raw" => Some(Self::Raw),
+            "iso" => Some(Self::Iso),
+            "locale" => Some(Self::Locale {
+                month_first: locale_is_us(),
+            }),
+            "epoch" => Some(Self::Epoch),
+            _ => None,
+        }
+    }
+
+    /// The name [`DateStyle::parse`] accepts.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::Iso => "iso",
+            Self::Locale { .. } => "locale",
+            Self::Epoch => "epoch",
+        }
+    }
+}
+
+/// Whether the environment's locale is a US one, which writes the month
+/// before the day.
+fn locale_is_us() -> bool {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .is_some_and(|locale| {
+            locale
+                .split(['.', '@'])
+                .next()
+                .is_some_and(|l| l.ends_with("_US"))
+        })
+}
+
+/// A date/time value split into its parts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DateTime<'a> {
+    date: Option<(i64, u32, u32)>,
+    /// Hours, minutes, seconds, and the fraction's digits (possibly empty).
+    time: Option<(u32, u32, u32, &'a str)>,
+    /// Offset from UTC in minutes.
+    offset: Option<i32>,
+}
+
+/// `value` of SQL type `type_name` written in `style`. Values of other
+/// types, and text that doesn't parse, are returned unchanged.
+pub fn format_date<'a>(value: &'a str, type_name: &str, style: DateStyle) -> Cow<'a, str> {
+    if style == DateStyle::Raw || !is_date_type(type_name) {
+        return Cow::Borrowed(value);
+    }
+    match parse_date_time(value) {
+        Some(dt) => Cow::Owned(write_date_time(&dt, style)),
+        None => Cow::Borrowed(value),
+    }
+}
+
+/// A streamed row (`None` for NULL) with its dates written in `style`.
+pub fn format_row<'a>(
+    row: &'a [Option<String>],
+    types: &[String],
+    style: DateStyle,
+) -> Vec<Option<Cow<'a, str>>> {
+    row.iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let ty = types.get(i).map(String::as_str).unwrap_or("");
+            value.as_deref().map(|v| format_date(v, ty, style))
+        })
+        .collect()
+}
+
+/// A copy of `result` with its dates written in `style`, for exports.
+pub fn restyle_dates(result: &QueryResult, style: DateStyle) -> QueryResult {
+    let mut result = result.clone();
+    for rs in &mut result.result_sets {
+        for (i, ty) in rs.column_types.iter().enumerate() {
+            if !is_date_type(ty) {
+                continue;
+            }
+            for (r, row) in rs.rows.iter_mut().enumerate() {
+                if let Some(value) = row.get_mut(i)
+                    && !rs.nulls.contains(&(r, i))
+                {
+                    *value = format_date(value, ty, style).into_owned();
+                }
+            }
+        }
+    }
+    result
+}
+
+/// A `datetimeoffset` value (or with `datetime2`, a `datetime2` value taken
+/// as UTC) converted to `zone`, as `datetimeoffset` text. `None` for other
+/// values.
+pub fn to_zone(value: &str, type_name: &str, zone: &Zone, datetime2: bool) -> Option<String> {
+    let dt = parse_date_time(value)?;
+    let offset = match (type_name, dt.offset) {
+        ("datetimeoffset", Some(offset)) => offset,
+        ("datetime2", None) if datetime2 => 0,
+        _ => return None,
+    };
+    let (y, m, d) = dt.date?;
+    let (h, min, s, fraction) = dt.time?;
+    let utc = days_from_civil(y, m, d) * 86_400 + i64::from(h * 3600 + min * 60 + s)
+        - i64::from(offset) * 60;
+    let zone_offset = zone.offset_at(utc);
+    let local = utc + i64::from(zone_offset);
+    let secs = local.rem_euclid(86_400) as u32;
+    let converted = DateTime {
+        date: Some(days_to_ymd(local.div_euclid(86_400))),
+        time: Some((secs / 3600, secs % 3600 / 60, secs % 60, fraction)),
+        offset: Some(zone_offset / 60),
+    };
+    Some(write_date_time(&converted, DateStyle::Raw))
+}
+
+/// Whether values of SQL type `type_name` are dates or times.
+pub fn is_date_type(type_name: &str) -> bool {
+    matches!(
+        type_name,
+        "date" | "time" | "datetime" | "smalldatetime" | "datetime2" | "datetimeoffset"
+    )
+}
+
+fn parse_date_time(value: &str) -> Option<DateTime<'_>> {
+    let mut parts = value.split(' ');
+    let mut dt = DateTime {
+        date: None,
+        time: None,
+        offset: None,
+    };
+    let mut next = parts.next()?;
+    if next.contains('-') {
+        let mut ymd = next.splitn(3, '-');
+        dt.date = Some((
+            ymd.next()?.parse().ok()?,
+            ymd.next()?.parse().ok()?,
+            ymd.next()?.parse().ok()?,
+        ));
+        next = match parts.next() {
+            Some(next) => next,
+            None => return Some(dt),
+        };
+    }
+    let (clock, fraction) = next.split_once('.').unwrap_or((next, ""));
+    let mut hms = clock.split(':');
+    dt.time = Some((
+        hms.next()?.parse().ok()?,
+        hms.next()?.parse().ok()?,
+        hms.next().map_or(Some(0), |s| s.parse().ok())?,
+        fraction,
+    ));
+    if let Some(offset) = parts.next() {
+        let (sign, offset) = match offset.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, offset.strip_prefix('+')?),
+        };
+        let (h, m) = offset.split_once(':')?;
+        dt.offset = Some(sign * (h.parse::<i32>().ok()? * 60 + m.parse::<i32>().ok()?));
+    }
+    (parts.next().is_none()).then_some(dt)
+}
+
+fn write_date_time(dt: &DateTime, style: DateStyle) -> String {
+    if style == DateStyle::Epoch
+        && let Some((y, m, d)) = dt.date
+    {
+        let (h, min, s, fraction) = dt.time.unwrap_or((0, 0, 0, ""));
+        let seconds = days_from_civil(y, m, d) * 86_400 + i64::from(h * 3600 + min * 60 + s)
+            - i64::from(dt.offset.unwrap_or(0)) * 60;
+        return epoch_text(seconds, fraction);
+    }
+
+    let date = dt.date.map(|(y, m, d)| match style {
+        DateStyle::Locale { month_first: true } => format!("{:02}/{:02}/{:04}", m, d, y),
+        DateStyle::Locale { month_first: false } => format!("{:02}/{:02}/{:04}", d, m, y),
+        _ => format!("{:04}-{:02}-{:02}", y, m, d),
+    });
+    let time = dt.time.map(|(h, m, s, fraction)| {
+        let mut time = format!("{:02}:{:02}:{:02}", h, m, s);
+        if !fraction.is_empty() {
+            time.push('.');
+            time.push_str(fraction);
+        }
+        time
+    });
+    let offset = dt.offset.map(|minutes| {
+        let sign = if minutes < 0 { '-' } else { '+' };
+        let minutes = minutes.unsigned_abs();
+        format!("{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+    });
+
+    let iso = style == DateStyle::Iso;
+    let mut out = date.unwrap_or_default();
+    if let Some(time) = time {
+        if !out.is_empty() {
+            out.push(if iso { 'T' } else { ' ' });
+        }
+        out.push_str(&time);
+    }
+    if let Some(offset) = offset {
+        if !iso {
+            out.push(' ');
+        }
+        out.push_str(&offset);
+    }
+    out
+}
+
+/// How numbers are shown in the grid. Exports keep the values as returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NumberFormat {
+    /// Group integer digits in threes with commas.
+    pub thousands: bool,
+    /// Round or pad non-integer values to this many decimal places.
+    pub decimals: Option<usize>,
+}
+
+/// `value` of SQL type `type_name` written with `format`. Floats are
+/// always written out in full rather than in scientific notation. Values
+/// of other types, and text that isn't a plain number, are returned
+/// unchanged.
+pub fn format_number<'a>(value: &'a str, type_name: &str, format: NumberFormat) -> Cow<'a, str> {
+    let fractional = match type_name {
+        "tinyint" | "smallint" | "int" | "bigint" => false,
+        "real" | "float" | "decimal" | "numeric" | "money" | "smallmoney" => true,
+        _ => return Cow::Borrowed(value),
+    };
+    let plain = format == NumberFormat::default() && !value.contains(['e', 'E']);
+    if plain {
+        return Cow::Borrowed(value);
+    }
+    let Some((negative, mut digits, mut point)) = parse_number(value) else {
+        return Cow::Borrowed(value);
+    };
+    if fractional && let Some(places) = format.decimals {
+        round_digits(&mut digits, &mut point, places);
+    }
+
+    let (int, frac) = digits.split_at(point);
+    let int = if int.is_empty() { "0" } else { int };
+    let mut out = String::with_capacity(value.len() + int.len() / 3 + 1);
+    if negative && digits.bytes().any(|d| d != b'0') {
+        out.push('-');
+    }
+    for (i, c) in int.chars().enumerate() {
+        if format.thousands && i > 0 && (int.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    if !frac.is_empty() {
+        out.push('.');
+        out.push_str(frac);
+    }
+    Cow::Owned(out)
+}
+
+/// A money value with `symbol` in front, after any minus sign: `-12.5000`
+/// with `$` is `-$12.5000`. Values of other types are returned unchanged.
+pub fn with_currency<'a>(value: Cow<'a, str>, type_name: &str, symbol: &str) -> Cow<'a, str> {
+    if !matches!(type_name, "money" | "smallmoney") {
+        return value;
+    }
+    match value.strip_prefix('-') {
+        Some(amount) => Cow::Owned(format!("-{}{}", symbol, amount)),
+        None => Cow::Owned(format!("{}{}", symbol, value)),
+    }
+}
+
+/// Split a number into its sign, its digits without the point, and the
+/// point's position in them, applying any exponent. `1.5e3` is
+/// `(false, "1500", 4)`; `2.5e-3` is `(false, "00025", 1)`.
+fn parse_number(value: &str) -> Option<(bool, String, usize)> {
+    let (negative, rest) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value),
+    };
+    let (mantissa, exponent) = match rest.split_once(['e', 'E']) {
+        Some((m, e)) => (m, e.trim_start_matches('+').parse::<i64>().ok()?),
+        None => (rest, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int.is_empty() && frac.is_empty()
+        || !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let mut digits = format!("{}{}", int, frac);
+    let mut point = int.len() as i64 + exponent;
+    if point < 0 {
+        digits.insert_str(0, &"0".repeat(point.unsigned_abs() as usize));
+        point = 0;
+    }
+    let point = point as usize;
+    if point > digits.len() {
+        digits.push_str(&"0".repeat(point - digits.len()));
+    }
+    Some((negative, digits, point))
+}
+
+/// Round (half away from zero) or zero-pad `digits` to `places` after the
+/// point.
+fn round_digits(digits: &mut String, point: &mut usize, places: usize) {
+    let keep = *point + places;
+    if digits.len() <= keep {
+        digits.push_str(&"0".repeat(keep - digits.len()));
+        return;
+    }
+    let round_up = digits.as_bytes()[keep] >= b'5';
+    digits.truncate(keep);
+    if round_up {
+        let mut bytes = std::mem::take(digits).into_bytes();
+        let mut i = bytes.len();
+        loop {
+            if i == 0 {
+                bytes.insert(0, b'1');
+                *point += 1;
+                break;
+            }
+            i -= 1;
+            if bytes[i] == b'9' {
+                bytes[i] = b'0';
+            } else {
+                bytes[i] += 1;
+                break;
+            }
+        }
+        *digits = String::from_utf8(bytes).unwrap_or_default();
+    }
+}
+
+/// Whole seconds plus a fraction's digits, e.g. `-1.5` for half a second
+/// before the epoch.
+fn epoch_text(seconds: i64, fraction: &str) -> String {
+    let digits = fraction.trim_end_matches('0');
+    if digits.is_empty() {
+        return seconds.to_string();
+    }
+    if seconds >= 0 {
+        return format!("{}.{}", seconds, digits);
+    }
+    // -2 + 0.25 is -1.75: borrow a second and complement the fraction
+    let scale = 10i64.pow(digits.len() as u32);
+    let complement = scale - digits.parse::<i64>().unwrap_or(0);
+    let whole = seconds + 1;
+    let sign = if whole == 0 { "-" } else { "" };
+    format!(
+        "{}{}.{:0width$}",
+        sign,
+        whole,
+        complement,
+        width = digits.len()
+    )
+}
+
+/// Days since 1970-01-01 for a civil date (Howard Hinnant's algorithm; the
+/// inverse of `days_to_ymd` in `db::query`).
+pub(crate) fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (i64::from(m) + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + i64::from(d) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_date() {
+        let offset = "2024-01-31 14:05:00 +02:00";
+        assert_eq!(
+            format_date(offset, "datetimeoffset", DateStyle::Iso),
+            "2024-01-31T14:05:00+02:00"
+        );
+        assert_eq!(
+            format_date(offset, "datetimeoffset", DateStyle::Epoch),
+            "1706702700"
+        );
+        assert_eq!(
+            format_date(
+                "2024-01-31 14:05:00.1250000",
+                "datetime2",
+                DateStyle::Locale { month_first: true }
+            ),
+            "01/31/2024 14:05:00.1250000"
+        );
+        assert_eq!(
+            format_date(
+                "2024-01-31",
+                "date",
+                DateStyle::Locale { month_first: false }
+            ),
+            "31/01/2024"
+        );
+        assert_eq!(
+            format_date("14:05:00", "time", DateStyle::Epoch),
+            "14:05:00"
+        );
+        assert_eq!(format_date(offset, "nvarchar", DateStyle::Iso), offset);
+        assert_eq!(
+            format_date(offset, "datetimeoffset", DateStyle::Raw),
+            offset
+        );
+    }
+
+    #[test]
+    fn test_to_zone() {
+        let berlin = Zone::Fixed(3600);
+        assert_eq!(
+            to_zone(
+                "2024-01-31 23:30:00 -05:00",
+                "datetimeoffset",
+                &berlin,
+                false
+            )
+            .as_deref(),
+            Some("2024-02-01 05:30:00 +01:00")
+        );
+        assert_eq!(
+            to_zone(
+                "2024-01-31 23:30:00.5",
+                "datetime2",
+                &Zone::Fixed(-3600),
+                true
+            )
+            .as_deref(),
+            Some("2024-01-31 22:30:00.5 -01:00")
+        );
+        assert_eq!(
+            to_zone("2024-01-31 23:30:00", "datetime2", &berlin, false),
+            None
+        );
+    }
+
+    #[test]
+    fn test_format_number() {
+        let grouped = NumberFormat {
+            thousands: true,
+            decimals: Some(2),
+        };
+        assert_eq!(
+            format_number("1234567890", "bigint", grouped),
+            "1,234,567,890"
+        );
+        assert_eq!(format_number("-1234.567", "decimal", grouped), "-1,234.57");
+        assert_eq!(format_number("999.995", "float", grouped), "1,000.00");
+        assert_eq!(format_number("-0.001", "float", grouped), "0.00");
+        assert_eq!(format_number("12", "money", grouped), "12.00");
+        assert_eq!(format_number("1234", "nvarchar", grouped), "1234");
+        let raw = NumberFormat::default();
+        assert_eq!(
+            format_number("1.5E+20", "float", raw),
+            "150000000000000000000"
+        );
+        assert_eq!(format_number("2.5e-3", "real", raw), "0.0025");
+        assert_eq!(format_number("NaN", "float", grouped), "NaN");
+    }
+
+    #[test]
+    fn test_with_currency() {
+        let money = |value: &'static str, ty| with_currency(Cow::Borrowed(value), ty, "$");
+        assert_eq!(money("1234.5000", "money"), "$1234.5000");
+        assert_eq!(money("-12.5000", "smallmoney"), "-$12.5000");
+        assert_eq!(money("12.5", "decimal"), "12.5");
+    }
+
+    #[test]
+    fn test_epoch_text() {
+        assert_eq!(epoch_text(5, "2500000"), "5.25");
+        assert_eq!(epoch_text(-2, "25"), "-1.75");
+        assert_eq!(epoch_text(-1, "5"), "-0.5");
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+    }
+}
@@ -0,0 +1,246 @@
+//! Generated data-modification statements: SQL literals for result values
+//! and the INSERT, UPDATE, and DELETE statements built from them.
+
+use crate::commands::quote_ident;
+
+/// SQL literal for a result value of SQL type `type_name` (as in
+/// [`crate::app::ResultSet::column_types`]): `None` as `NULL`, numbers and
+/// binary as they are, `bit` as `1`/`0`, everything else as an `N'...'`
+/// string. Dates with a time are written in ISO 8601 form, which SQL Server
+/// reads the same under any `DATEFORMAT`.
+pub fn literal(value: Option<&str>, type_name: &str) -> String {
+    let Some(value) = value else {
+        return "NULL".to_string();
+    };
+    match type_name {
+        "bit" => match value {
+            "true" => "1".to_string(),
+            "false" => "0".to_string(),
+            other => other.to_string(),
+        },
+        "tinyint" | "smallint" | "int" | "bigint" | "real" | "float" | "decimal" | "numeric"
+        | "money" | "smallmoney" | "binary" | "varbinary" | "image" => value.to_string(),
+        "datetime" | "datetime2" | "smalldatetime" | "datetimeoffset" => {
+            format!("N'{}'", iso_date_time(value).replace('\'', "''"))
+        }
+        _ => format!("N'{}'", value.replace('\'', "''")),
+    }
+}
+
+/// `2024-01-31 09:30:00.123 +01:00` as `2024-01-31T09:30:00.123+01:00`,
+/// with seconds added to a `smalldatetime`'s `09:30`. Text already in that
+/// form is returned as it is.
+fn iso_date_time(value: &str) -> String {
+    let Some((date, rest)) = value.split_once(' ') else {
+        return value.to_string();
+    };
+    let (time, offset) = rest.split_once(' ').unwrap_or((rest, ""));
+    let seconds = if time.matches(':').count() == 1 {
+        ":00"
+    } else {
+        ""
+    };
+    format!("{}T{}{}{}", date, time, seconds, offset)
+}
+
+/// Select-list entry that reads column `name` of SQL type `type_name`
+/// without loss: date and time types as ISO 8601 text (style 126), which
+/// keeps the fractional seconds the display format drops and converts back
+/// the same under any `DATEFORMAT`; other types as the column itself.
+pub fn lossless_column(name: &str, type_name: &str) -> String {
+    let column = quote_ident(name);
+    match type_name {
+        "date" | "time" | "datetime" | "datetime2" | "smalldatetime" | "datetimeoffset" => {
+            format!("CONVERT(nvarchar(40), {c}, 126) AS {c}", c = column)
+        }
+        _ => column,
+    }
+}
+
+/// `INSERT INTO table ([a], [b]) VALUES (1, N'x');` from column names and
+/// literals. `table` is used as given.
+pub fn insert(table: &str, values: &[(&str, String)]) -> String {
+    let (columns, literals): (Vec<String>, Vec<&str>) = values
+        .iter()
+        .map(|(column, literal)| (quote_ident(column), literal.as_str()))
+        .unzip();
+    format!(
+        "INSERT INTO {} ({}) VALUES ({});",
+        table,
+        columns.join(", "),
+        literals.join(", ")
+    )
+}
+
+/// `UPDATE table SET [a] = 1 WHERE [id] = 5;`
+pub fn update(table: &str, set: &[(&str, String)], key: &[(&str, String)]) -> String {
+    let set: Vec<String> = set
+        .iter()
+        .map(|(column, literal)| format!("{} = {}", quote_ident(column), literal))
+        .collect();
+    format!(
+        "UPDATE {} SET {}{};",
+        table,
+        set.join(", "),
+        where_clause(key)
+    )
+}
+
+/// `DELETE FROM table WHERE [id] = 5;`
+pub fn delete(table: &str, key: &[(&str, String)]) -> String {
+    format!("DELETE FROM {}{};", table, where_clause(key))
+}
+
+/// `SELECT [a], [b] FROM table WHERE [id] = 5;`
+pub fn select(table: &str, columns: &[&str], key: &[(&str, String)]) -> String {
+    let columns: Vec<String> = columns.iter().map(|c| quote_ident(c)).collect();
+    format!(
+        "SELECT {} FROM {}{};",
+        columns.join(", "),
+        table,
+        where_clause(key)
+    )
+}
+
+/// ` WHERE [a] = 1 AND [b] IS NULL`
+fn where_clause(key: &[(&str, String)]) -> String {
+    let terms: Vec<String> = key
+        .iter()
+        .map(|(column, literal)| match literal.as_str() {
+            "NULL" => format!("{} IS NULL", quote_ident(column)),
+            _ => format!("{} = {}", quote_ident(column), literal),
+        })
+        .collect();
+    format!(" WHERE {}", terms.join(" AND "))
+}
+
+/// The table a simple single-table `SELECT ... FROM <table> ...` reads, as
+/// written. `None` for joins, subqueries, unions, and comma-separated
+/// tables.
+pub fn source_table(sql: &str) -> Option<String> {
+    let upper = sql.to_ascii_uppercase();
+    let words: Vec<&str> = upper
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .collect();
+    if !upper.trim_start().starts_with("SELECT")
+        || words
+            .iter()
+            .filter(|w| **w == "FROM" || **w == "SELECT")
+            .count()
+            != 2
+        || words
+            .iter()
+            .any(|w| matches!(*w, "JOIN" | "UNION" | "APPLY" | "INTERSECT" | "EXCEPT"))
+    {
+        return None;
+    }
+    let from = upper.match_indices("FROM").map(|(i, _)| i).find(|&i| {
+        let before = upper[..i].chars().next_back();
+        let after = upper[i + 4..].chars().next();
+        before.is_some_and(char::is_whitespace) && after.is_some_and(char::is_whitespace)
+    })?;
+    // Up to the first space, comma, or semicolon outside brackets
+    let rest = sql[from + 4..].trim_start();
+    let mut in_brackets = false;
+    let end = rest
+        .char_indices()
+        .find(|&(_, c)| {
+            match c {
+                '[' => in_brackets = true,
+                ']' => in_brackets = false,
+                _ => {}
+            }
+            !in_brackets && (c.is_whitespace() || c == ',' || c == ';' || c == ')')
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(rest.len());
+    let (table, after) = rest.split_at(end);
+    if table.is_empty() || table.starts_with('(') || after.trim_start().starts_with(',') {
+        return None;
+    }
+    Some(table.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal() {
+        assert_eq!(literal(Some("42"), "int"), "42");
+        assert_eq!(literal(Some("true"), "bit"), "1");
+        assert_eq!(literal(Some("0x0A0B"), "varbinary"), "0x0A0B");
+        assert_eq!(literal(Some("O'Neil"), "nvarchar"), "N'O''Neil'");
+        assert_eq!(literal(Some("2024-01-31"), "date"), "N'2024-01-31'");
+        assert_eq!(
+            literal(Some("2024-01-31 09:30:00.123"), "datetime"),
+            "N'2024-01-31T09:30:00.123'"
+        );
+        assert_eq!(
+            literal(Some("2024-01-31 09:30"), "smalldatetime"),
+            "N'2024-01-31T09:30:00'"
+        );
+        assert_eq!(
+            literal(Some("2024-01-31 09:30:00.5000000 -05:00"), "datetimeoffset"),
+            "N'2024-01-31T09:30:00.5000000-05:00'"
+        );
+        assert_eq!(
+            literal(Some("2024-01-31T09:30:00.123"), "datetime"),
+            "N'2024-01-31T09:30:00.123'"
+        );
+        assert_eq!(literal(None, "nvarchar"), "NULL");
+        assert_eq!(literal(Some("NULL"), "nvarchar"), "N'NULL'");
+    }
+
+    #[test]
+    fn test_lossless_column() {
+        assert_eq!(lossless_column("id", "int"), "[id]");
+        assert_eq!(
+            lossless_column("at", "datetime"),
+            "CONVERT(nvarchar(40), [at], 126) AS [at]"
+        );
+    }
+
+    #[test]
+    fn test_source_table() {
+        assert_eq!(
+            source_table("SELECT * FROM dbo.Orders WHERE id > 5").as_deref(),
+            Some("dbo.Orders")
+        );
+        assert_eq!(
+            source_table("select top 10 a, b\nfrom [dbo].[Order Lines];").as_deref(),
+            Some("[dbo].[Order Lines]")
+        );
+        assert_eq!(source_table("SELECT * FROM a JOIN b ON a.id = b.id"), None);
+        assert_eq!(source_table("SELECT * FROM a, b"), None);
+        assert_eq!(
+            source_table("SELECT * FROM a WHERE id IN (SELECT id FROM b)"),
+            None
+        );
+        assert_eq!(source_table("EXEC sp_who"), None);
+    }
+
+    #[test]
+    fn test_statements() {
+        let key = [("id", "5".to_string())];
+        assert_eq!(
+            insert(
+                "dbo.Config",
+                &[("id", "5".into()), ("key]x", "N'a'".into())]
+            ),
+            "INSERT INTO dbo.Config ([id], [key]]x]) VALUES (5, N'a');"
+        );
+        assert_eq!(
+            update("dbo.Config", &[("value", "NULL".into())], &key),
+            "UPDATE dbo.Config SET [value] = NULL WHERE [id] = 5;"
+        );
+        assert_eq!(
+            delete("t", &[("a", "1".into()), ("b", "NULL".into())]),
+            "DELETE FROM t WHERE [a] = 1 AND [b] IS NULL;"
+        );
+        assert_eq!(
+            select("t", &["v"], &key),
+            "SELECT [v] FROM t WHERE [id] = 5;"
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Persistent query history, stored one entry per line in the config directory.
+
+use crate::config;
+use std::io::Write;
+
+/// Maximum number of entries kept on disk and in memory.
+pub const MAX_ENTRIES: usize = 1000;
+
+const HISTORY_FILE: &str = "history";
+
+/// Load saved history, oldest first. Missing or unreadable files yield an
+/// empty history; an oversized file is compacted to `MAX_ENTRIES`.
+pub fn load() -> Vec<String> {
+    let Some(path) = config::config_file(HISTORY_FILE) else {
+        return Vec::new();
+    };
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<String> = contents
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(decode)
+        .collect();
+    if entries.len() > MAX_ENTRIES {
+        entries.drain(..entries.len() - MAX_ENTRIES);
+        let body: String = entries.iter().map(|e| encode(e) + "\n").collect();
+        std::fs::write(&path, body).ok();
+    }
+    entries
+}
+
+/// Append an entry to the history file.
+pub fn append(entry: &str) -> std::io::Result<()> {
+    let Some(path) = config::config_file(HISTORY_FILE) else {
+        return Ok(());
+    };
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", encode(entry))
+}
+
+/// Escape backslashes and newlines so an entry fits on one line.
+pub fn encode(entry: &str) -> String {
+    entry
+        .replace('\\', "\\\\")
+        .replace('\r', "")
+        .replace('\n', "\\n")
+}
+
+/// Reverse `encode`.
+pub fn decode(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
@@ -5,13 +5,26 @@
 #![allow(unused)]
 
 mod app;
+mod blocking;
+mod bookmarks;
 mod cli;
 mod commands;
+mod config;
 mod db;
+mod display;
+mod dml;
+mod history;
+mod params;
+mod scripts;
+mod snippets;
+mod state;
+mod timezone;
 mod tui;
+mod variables;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser, Subcommand};
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// 🐱 meow — TUI SQL Server client
 #[derive(Parser, Debug, Clone)]
@@ -33,32 +46,201 @@ pub struct Args {
     #[arg(short = 'P', long = "password")]
     pub password: Option<String>,
 
-    /// Initial database
-    #[arg(short = 'd', long = "database", default_value = "master")]
-    pub database: String,
+    /// Initial database [default: master, or the TUI's last database]
+    #[arg(short = 'd', long = "database", global = true)]
+    pub database: Option<String>,
 
     /// Trust server certificate
     #[arg(long = "trust-cert")]
     pub trust_cert: bool,
 
+    /// Application intent: readwrite, or readonly to be routed to a readable
+    /// secondary by an availability group listener
+    #[arg(
+        long = "application-intent",
+        default_value = "readwrite",
+        value_parser = ["readwrite", "readonly"],
+        ignore_case = true
+    )]
+    pub application_intent: String,
+
+    /// Times to retry connecting after a network error or a transient
+    /// server error (at most 9)
+    #[arg(long = "connect-retries", default_value_t = 2)]
+    pub connect_retries: u32,
+
+    /// Seconds to wait before the first connect retry; each later retry
+    /// waits twice as long, up to 30 seconds
+    #[arg(long = "connect-retry-delay", default_value_t = 1)]
+    pub connect_retry_delay: u64,
+
     /// Non-interactive CLI mode
     #[arg(long = "cli")]
     pub cli_mode: bool,
 
-    /// Execute SQL from file
+    /// Execute SQL from file; repeat or use a wildcard to run several in order
     #[arg(short = 'i', long = "input")]
-    pub input: Option<PathBuf>,
+    pub input: Vec<PathBuf>,
 
     /// Write results to file
     #[arg(short = 'o', long = "output")]
     pub output: Option<PathBuf>,
 
-    /// Output format: table, csv, json
-    #[arg(long = "format", default_value = "table")]
-    pub format: String,
+    /// Output format: table, csv, json, ndjson, markdown [default: from the
+    /// -o file extension, else table]
+    #[arg(long = "format")]
+    pub format: Option<String>,
+
+    /// Don't run the ~/.meowrc startup file
+    #[arg(long = "no-meowrc")]
+    pub no_meowrc: bool,
+
+    /// Stop at the first failed batch (CLI mode)
+    #[arg(short = 'b', long = "abort-on-error")]
+    pub abort_on_error: bool,
+
+    /// Leave $(name) in scripts as written instead of substituting variables
+    #[arg(short = 'x', long = "no-variables")]
+    pub no_variables: bool,
+
+    /// Widest a table column may be (default: 50 on a terminal, no limit in files)
+    #[arg(long = "max-col-width")]
+    pub max_col_width: Option<usize>,
+
+    /// Cut values wider than the column limit short with "…"
+    #[arg(long = "truncate", conflicts_with = "wrap")]
+    pub truncate: bool,
+
+    /// Wrap values wider than the column limit onto extra lines
+    #[arg(long = "wrap")]
+    pub wrap: bool,
+
+    /// Omit column headers from CLI output
+    #[arg(long = "no-headers")]
+    pub no_headers: bool,
+
+    /// Omit row count and timing trailers from CLI output
+    #[arg(long = "no-footer")]
+    pub no_footer: bool,
+
+    /// Print only results: no prompt, messages, or trailers
+    #[arg(short = 'q', long = "quiet")]
+    pub quiet: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Subcommands; without one meow starts the TUI or CLI.
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Apply the .sql scripts in a directory that haven't been applied yet,
+    /// in lexical order, recording them in dbo.meow_migrations
+    Migrate {
+        /// Directory of migration scripts
+        dir: PathBuf,
+    },
+    /// Write CREATE statements for the tables (with their constraints and
+    /// indexes), views, procedures, and functions of the -d database, one
+    /// file per object
+    ScriptSchema {
+        /// Directory to write the scripts to, created if missing
+        #[arg(long = "out")]
+        out: PathBuf,
+    },
+    /// Compare a table's rows in two databases by primary key; exits 1 if
+    /// they differ
+    DiffData {
+        /// Table to compare, e.g. dbo.Config
+        #[arg(long = "table")]
+        table: String,
+        /// Database with the wanted rows: a database on the -S server, or
+        /// host[,port]/database
+        #[arg(long = "source")]
+        source: String,
+        /// Database to compare against it, in the same form
+        #[arg(long = "target")]
+        target: String,
+        /// Print the DELETE, UPDATE, and INSERT statements that make the
+        /// target match the source instead of a report
+        #[arg(long = "sql")]
+        sql: bool,
+    },
+    /// Stream a whole table to a CSV or NDJSON file in constant memory
+    Export {
+        /// Table to export, e.g. dbo.Orders
+        #[arg(long = "table")]
+        table: String,
+        /// csv or ndjson [default: from the --out extension, else csv]
+        #[arg(long = "format")]
+        format: Option<String>,
+        /// File to write
+        #[arg(long = "out")]
+        out: PathBuf,
+    },
 }
 
 impl Args {
+    /// The output format: `--format` if given, else `table`.
+    pub fn format(&self) -> &str {
+        self.format.as_deref().unwrap_or("table")
+    }
+
+    /// The `-d` database, defaulting to `master`.
+    pub fn database(&self) -> &str {
+        self.database.as_deref().unwrap_or("master")
+    }
+
+    /// Server, login, and connection options for [`db::connect`].
+    pub fn login(&self) -> db::Login {
+        let (host, port) = self.parse_server();
+        db::Login {
+            host,
+            port,
+            user: self.user.clone().unwrap_or_else(|| "sa".to_string()),
+            password: self.password.clone().unwrap_or_default(),
+            trust_cert: self.trust_cert,
+            read_only: self.application_intent.eq_ignore_ascii_case("readonly"),
+            retry: db::RetryPolicy {
+                attempts: self.connect_retries.saturating_add(1),
+                delay: Duration::from_secs(self.connect_retry_delay),
+            },
+        }
+    }
+
+    /// Pick the output format from the `-o` file extension when `--format`
+    /// isn't given. Fails if the two disagree or the extension names a format
+    /// meow can't write.
+    pub fn resolve_format(&mut self) -> Result<(), String> {
+        let Some(ref path) = self.output else {
+            return Ok(());
+        };
+        let Some(ext) = path.extension().map(|e| e.to_string_lossy().to_lowercase()) else {
+            return Ok(());
+        };
+        if ext == "xlsx" {
+            return Err(format!(
+                "cannot write {}: xlsx output is not supported (use .csv)",
+                path.display()
+            ));
+        }
+        let Some(inferred) = cli::format_for_extension(&ext) else {
+            return Ok(());
+        };
+        match self.format {
+            Some(ref format) if format != inferred => Err(format!(
+                "--format {} conflicts with the .{} extension of {}",
+                format,
+                ext,
+                path.display()
+            )),
+            _ => {
+                self.format = Some(inferred.to_string());
+                Ok(())
+            }
+        }
+    }
+
     /// Parse the server string into (host, port).
     pub fn parse_server(&self) -> (String, u16) {
         if let Some((host, port_str)) = self.server.split_once(',') {
@@ -75,15 +257,40 @@ impl Args {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+    let mut args = Args::parse();
+    if let Err(e) = args.resolve_format() {
+        Args::command()
+            .error(clap::error::ErrorKind::ArgumentConflict, e)
+            .exit();
+    }
 
     // Determine if we should run in CLI mode:
     // --cli flag, piped stdin, or -i flag
     let is_piped = atty_check();
-    if args.cli_mode || is_piped || args.input.is_some() {
-        cli::run(args).await?;
-    } else {
-        tui::run(args).await?;
+    let code = match args.command.clone() {
+        Some(Command::Migrate { dir }) => cli::migrate::run(args, &dir).await?,
+        Some(Command::ScriptSchema { out }) => {
+            cli::script_schema::run(args, &out).await?;
+            0
+        }
+        Some(Command::DiffData {
+            table,
+            source,
+            target,
+            sql,
+        }) => cli::diff_data::run(args, &table, &source, &target, sql).await?,
+        Some(Command::Export { table, format, out }) => {
+            cli::export::run(args, &table, format.as_deref(), &out).await?;
+            0
+        }
+        None if args.cli_mode || is_piped || !args.input.is_empty() => cli::run(args).await?,
+        None => {
+            tui::run(args).await?;
+            0
+        }
+    };
+    if code != 0 {
+        std::process::exit(code);
     }
 
     Ok(())
@@ -0,0 +1,318 @@
+//! Query parameters: finding the `@name`s a query uses without declaring
+//! them, and running it through `sp_executesql` with values for them.
+
+use crate::variables::quoted_end;
+
+/// Words that start a statement, ending a `DECLARE` list written without
+/// a semicolon.
+const STATEMENT_WORDS: &[&str] = &[
+    "BEGIN", "DECLARE", "DELETE", "EXEC", "EXECUTE", "IF", "INSERT", "MERGE", "PRINT", "RETURN",
+    "SELECT", "SET", "UPDATE", "WHILE", "WITH",
+];
+
+/// A parameter and the value to run the query with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    /// Name with its `@`.
+    pub name: String,
+    /// SQL type, e.g. `int` or `nvarchar(50)`.
+    pub type_name: String,
+    /// The value as typed; `None` for NULL.
+    pub value: Option<String>,
+}
+
+/// A query waiting for its parameter values, asked for one at a time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamForm {
+    /// The query, after variable interpolation.
+    pub sql: String,
+    /// Every undeclared parameter, with its `@`.
+    pub names: Vec<String>,
+    /// Values given so far, in `names` order.
+    pub values: Vec<Param>,
+}
+
+/// The `@name`s used in `sql` but not declared in it, in order of first
+/// use. Names in strings, quoted identifiers, and comments don't count, nor
+/// do `@@` functions, `@` inside a word (`x@y`), or the named arguments of
+/// a procedure call (`EXEC p @name = 1`). Empty for `CREATE PROCEDURE` and the like, whose
+/// parameters are declared in the header.
+pub fn undeclared(sql: &str) -> Vec<String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut used: Vec<String> = Vec::new();
+    let mut declared: Vec<String> = Vec::new();
+    // Paren depth of the DECLARE list being read, and whether the next
+    // variable in it is being declared
+    let mut declare: Option<(usize, bool)> = None;
+    let mut depth = 0usize;
+    // Inside an EXEC statement, and whether the procedure name has been read
+    let mut exec: Option<bool> = None;
+    let mut previous_word = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\'' | '"' | '[' => {
+                if c != '\'' && exec == Some(false) {
+                    exec = Some(true);
+                }
+                let close = if c == '[' { ']' } else { c };
+                i = quoted_end(&chars, i + 1, close);
+                continue;
+            }
+            '-' if next == Some('-') => {
+                i = (i..chars.len())
+                    .find(|&j| chars[j] == '\n')
+                    .unwrap_or(chars.len());
+                continue;
+            }
+            '/' if next == Some('*') => {
+                i = (i + 2..chars.len())
+                    .find(|&j| chars[j] == '*' && chars.get(j + 1) == Some(&'/'))
+                    .map_or(chars.len(), |j| j + 2);
+                continue;
+            }
+            '@' if next == Some('@') => {
+                i += 2 + word_len(&chars, i + 2);
+                continue;
+            }
+            '@' => {
+                let len = word_len(&chars, i + 1);
+                let in_word = i > 0 && word_len(&chars, i - 1) > 0;
+                let argument = exec == Some(true)
+                    && chars[i + 1 + len..].iter().find(|c| !c.is_whitespace()) == Some(&'=');
+                if len > 0 && !in_word && !argument {
+                    let name: String = chars[i..i + 1 + len].iter().collect();
+                    let name = name.to_lowercase();
+                    match declare {
+                        Some((d, true)) if d == depth => {
+                            declared.push(name);
+                            declare = Some((d, false));
+                        }
+                        _ => {
+                            if !used.iter().any(|u| u.to_lowercase() == name) {
+                                used.push(chars[i..i + 1 + len].iter().collect());
+                            }
+                        }
+                    }
+                }
+                i += 1 + len;
+                continue;
+            }
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' => {
+                if let Some((d, _)) = declare
+                    && d == depth
+                {
+                    declare = Some((d, true));
+                }
+            }
+            ';' => {
+                declare = None;
+                exec = None;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let len = word_len(&chars, i);
+                let word: String = chars[i..i + len].iter().collect::<String>().to_uppercase();
+                if word == "DECLARE" {
+                    declare = Some((depth, true));
+                } else if STATEMENT_WORDS.contains(&word.as_str()) {
+                    declare = None;
+                }
+                if matches!(word.as_str(), "EXEC" | "EXECUTE") {
+                    exec = Some(false);
+                } else if exec == Some(false) {
+                    exec = Some(true);
+                } else if STATEMENT_WORDS.contains(&word.as_str()) {
+                    exec = None;
+                }
+                if matches!(previous_word.as_str(), "CREATE" | "ALTER")
+                    && matches!(word.as_str(), "PROC" | "PROCEDURE" | "FUNCTION" | "TRIGGER")
+                {
+                    return Vec::new();
+                }
+                previous_word = word;
+                i += len;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    used.retain(|name| !declared.contains(&name.to_lowercase()));
+    used
+}
+
+/// Read a parameter answer written as `type = value`. `NULL` (any case) is
+/// NULL; the type may hold only letters, digits, `_`, `(`, `)`, `,`, and
+/// spaces.
+pub fn parse_answer(name: &str, input: &str) -> Result<Param, String> {
+    let (type_name, value) = input
+        .split_once('=')
+        .ok_or_else(|| format!("Write {} as type = value, e.g. int = 42", name))?;
+    let type_name = type_name.trim();
+    if type_name.is_empty()
+        || !type_name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '(' | ')' | ',' | ' '))
+    {
+        return Err(format!("Not a type: {}", type_name));
+    }
+    let value = value.trim();
+    Ok(Param {
+        name: name.to_string(),
+        type_name: type_name.to_string(),
+        value: (!value.eq_ignore_ascii_case("null")).then(|| value.to_string()),
+    })
+}
+
+/// `EXEC sp_executesql N'<sql>', N'@a int, ...', @a = 42, ...;` — the query
+/// with its parameters bound to the given values.
+pub fn sp_executesql(sql: &str, params: &[Param]) -> String {
+    let declarations: Vec<String> = params
+        .iter()
+        .map(|p| format!("{} {}", p.name, p.type_name))
+        .collect();
+    let mut exec = format!(
+        "EXEC sp_executesql N'{}', N'{}'",
+        sql.replace('\'', "''"),
+        declarations.join(", ")
+    );
+    for param in params {
+        exec.push_str(&format!(", {} = {}", param.name, value_literal(param)));
+    }
+    exec.push(';');
+    exec
+}
+
+/// A value as a literal: numbers for numeric types and `0x...` for binary
+/// types as they are, anything else as an `N'...'` string for the server to
+/// convert to the parameter type.
+fn value_literal(param: &Param) -> String {
+    let Some(ref value) = param.value else {
+        return "NULL".to_string();
+    };
+    let base_type = param
+        .type_name
+        .split('(')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_lowercase();
+    let number = matches!(
+        base_type.as_str(),
+        "tinyint"
+            | "smallint"
+            | "int"
+            | "bigint"
+            | "real"
+            | "float"
+            | "decimal"
+            | "numeric"
+            | "money"
+            | "smallmoney"
+            | "bit"
+    ) && value.parse::<f64>().is_ok_and(f64::is_finite)
+        && value
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'));
+    let binary = matches!(base_type.as_str(), "binary" | "varbinary")
+        && value.get(..2).is_some_and(|p| p.eq_ignore_ascii_case("0x"))
+        && value[2..].chars().all(|c| c.is_ascii_hexdigit());
+    if number || binary {
+        value.clone()
+    } else {
+        format!("N'{}'", value.replace('\'', "''"))
+    }
+}
+
+/// Length of the identifier (letters, digits, `_`, `#`, `$`) at `start`.
+fn word_len(chars: &[char], start: usize) -> usize {
+    chars[start.min(chars.len())..]
+        .iter()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '#' | '$'))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undeclared() {
+        assert_eq!(
+            undeclared("SELECT * FROM t WHERE id = @Id AND name = @name OR id = @id"),
+            vec!["@Id", "@name"]
+        );
+        assert_eq!(
+            undeclared("SELECT '@x', [@y], @@ROWCOUNT -- @z\n/* @w */ FROM t"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            undeclared("DECLARE @a int = 1, @b TABLE (x int, y int)\nSELECT @a, @c FROM @b"),
+            vec!["@c"]
+        );
+        assert_eq!(
+            undeclared("CREATE PROCEDURE p @a int AS SELECT @a"),
+            Vec::<String>::new()
+        );
+        assert_eq!(undeclared("SELECT x@y FROM t"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_undeclared_skips_named_arguments() {
+        assert_eq!(
+            undeclared("EXEC sp_who @loginame = 'sa'"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            undeclared("EXECUTE [dbo].[GetOrders] @CustomerId = 5, @Since = @from"),
+            vec!["@from"]
+        );
+        assert_eq!(
+            undeclared("EXEC sp_executesql N'SELECT @p', N'@p int', @p = 1"),
+            Vec::<String>::new()
+        );
+        assert_eq!(undeclared("EXEC @rc = dbo.p @a = 1"), vec!["@rc"]);
+        assert_eq!(
+            undeclared("EXEC p @a = 1\nSELECT * FROM t WHERE id = @a"),
+            vec!["@a"]
+        );
+    }
+
+    #[test]
+    fn test_parse_answer() {
+        assert_eq!(
+            parse_answer("@id", "int = 42"),
+            Ok(Param {
+                name: "@id".to_string(),
+                type_name: "int".to_string(),
+                value: Some("42".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_answer("@d", "decimal(10, 2) = null").unwrap().value,
+            None
+        );
+        assert!(parse_answer("@id", "42").is_err());
+        assert!(parse_answer("@id", "int'); DROP TABLE t -- = 1").is_err());
+    }
+
+    #[test]
+    fn test_sp_executesql() {
+        let params = [
+            parse_answer("@id", "int = 42").unwrap(),
+            parse_answer("@name", "nvarchar(50) = O'Neil").unwrap(),
+            parse_answer("@n", "int = 1; DROP TABLE t").unwrap(),
+            parse_answer("@code", "varchar(10) = 007").unwrap(),
+        ];
+        assert_eq!(
+            sp_executesql("SELECT * FROM t WHERE name = @name AND s = 'x'", &params),
+            "EXEC sp_executesql N'SELECT * FROM t WHERE name = @name AND s = ''x''', \
+             N'@id int, @name nvarchar(50), @n int, @code varchar(10)', @id = 42, \
+             @name = N'O''Neil', @n = N'1; DROP TABLE t', @code = N'007';"
+        );
+    }
+}
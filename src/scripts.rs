@@ -0,0 +1,654 @@
+//! Reading, writing, and finding `.sql` script files, and splitting scripts
+//! into batches the way sqlcmd does.
+
+use crate::variables;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// How deep `find_sql_files` descends below the starting directory.
+const MAX_DEPTH: usize = 3;
+
+/// Cap on the number of files `find_sql_files` returns.
+const MAX_FILES: usize = 500;
+
+/// Read a script, normalising CRLF line endings.
+pub fn read(path: &Path) -> io::Result<String> {
+    Ok(std::fs::read_to_string(path)?.replace("\r\n", "\n"))
+}
+
+/// Write a script with a trailing newline.
+pub fn write(path: &Path, text: &str) -> io::Result<()> {
+    std::fs::write(path, with_newline(text))
+}
+
+/// Write a script to a new file in the temp directory, `meow-<pid>-<n>.sql`,
+/// readable only by the user. The file is always created, never an
+/// existing one (or a symlink) found at the name reused.
+pub fn write_temp(text: &str) -> io::Result<PathBuf> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    for n in 0..100u32 {
+        let name = format!("meow-{}-{}.sql", std::process::id(), nanos.wrapping_add(n));
+        let path = std::env::temp_dir().join(name);
+        match options.open(&path) {
+            Ok(mut file) => {
+                io::Write::write_all(&mut file, with_newline(text).as_bytes())?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "no unused temp file name",
+    ))
+}
+
+fn with_newline(text: &str) -> String {
+    let mut body = text.to_string();
+    if !body.ends_with('\n') {
+        body.push('\n');
+    }
+    body
+}
+
+/// `.sql` files under `root`, sorted, skipping hidden and build directories.
+pub fn find_sql_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    walk(root, 0, &mut files);
+    files.sort();
+    files
+}
+
+/// Expand `*` and `?` wildcards in the file name of each path (for quoted
+/// `-i` arguments the shell didn't expand), keeping the given order and
+/// sorting the matches of each pattern. A pattern that matches nothing is an
+/// error.
+pub fn expand_paths(paths: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut expanded = Vec::new();
+    for path in paths {
+        let pattern = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if !pattern.contains(['*', '?']) {
+            expanded.push(path.clone());
+            continue;
+        }
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let entries = std::fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        let mut matches: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_file())
+            .filter(|e| wildcard_match(&pattern, &e.file_name().to_string_lossy()))
+            .map(|e| dir.join(e.file_name()))
+            .collect();
+        if matches.is_empty() {
+            return Err(format!("no files match {}", path.display()));
+        }
+        matches.sort();
+        expanded.extend(matches);
+    }
+    Ok(expanded)
+}
+
+/// Match `name` against a pattern where `*` is any run of characters and `?`
+/// any one character.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name position it was tried at
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    p = sp + 1;
+                    n = sn + 1;
+                    star = Some((sp, sn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn walk(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        if files.len() >= MAX_FILES {
+            return;
+        }
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            if depth < MAX_DEPTH && !matches!(name.as_ref(), "target" | "node_modules") {
+                walk(&path, depth + 1, files);
+            }
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("sql"))
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// One batch of a script: the SQL between `GO` separators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Batch {
+    pub sql: String,
+    /// 1-based line number where the batch starts.
+    pub line: usize,
+    /// How many times to run it (`GO 5`).
+    pub repeat: usize,
+    /// Set for `\if`, `\elif`, `\else`, and `\endif` lines, which carry no
+    /// SQL.
+    pub branch: Option<Branch>,
+}
+
+/// A psql-style conditional line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Branch {
+    /// `\if <condition>`
+    If(String),
+    /// `\elif <condition>`
+    Elif(String),
+    /// `\else`
+    Else,
+    /// `\endif`
+    Endif,
+}
+
+impl Branch {
+    /// The branch with its condition, if any, rewritten by `f`.
+    pub fn map_condition(
+        self,
+        f: impl FnOnce(&str) -> Result<String, String>,
+    ) -> Result<Branch, String> {
+        Ok(match self {
+            Branch::If(cond) => Branch::If(f(&cond)?),
+            Branch::Elif(cond) => Branch::Elif(f(&cond)?),
+            other => other,
+        })
+    }
+}
+
+/// Parse a `\if`-family line. A condition is a boolean literal (`true`,
+/// `off`, `1`, ...) or a query, usually after variable interpolation.
+pub fn branch(line: &str) -> Result<Option<Branch>, String> {
+    let line = line.trim();
+    let (cmd, arg) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let arg = arg.trim();
+    let branch = match cmd {
+        "\\if" | "\\elif" if arg.is_empty() => {
+            return Err(format!("{} needs a condition", cmd));
+        }
+        "\\if" => Branch::If(arg.to_string()),
+        "\\elif" => Branch::Elif(arg.to_string()),
+        "\\else" => Branch::Else,
+        "\\endif" => Branch::Endif,
+        _ => return Ok(None),
+    };
+    Ok(Some(branch))
+}
+
+/// Which `\if` branches are active while running a script or the REPL.
+#[derive(Debug, Default)]
+pub struct Conditions {
+    frames: Vec<Frame>,
+}
+
+#[derive(Debug)]
+struct Frame {
+    /// Whether the enclosing block is active.
+    parent_active: bool,
+    /// Whether some branch of this `\if` has been taken.
+    taken: bool,
+    /// Whether the current branch is active.
+    active: bool,
+    /// Whether `\else` has been seen.
+    in_else: bool,
+}
+
+impl Conditions {
+    /// Whether statements should run at this point.
+    pub fn active(&self) -> bool {
+        self.frames.last().is_none_or(|f| f.active)
+    }
+
+    /// Whether an `\if` is still waiting for its `\endif`.
+    pub fn is_open(&self) -> bool {
+        !self.frames.is_empty()
+    }
+
+    /// The condition that must be evaluated before `apply`ing `branch`, or
+    /// `None` if its value can't matter (e.g. inside a skipped block).
+    pub fn condition<'a>(&self, branch: &'a Branch) -> Option<&'a str> {
+        match branch {
+            Branch::If(cond) if self.active() => Some(cond),
+            Branch::Elif(cond) => self
+                .frames
+                .last()
+                .filter(|f| f.parent_active && !f.taken && !f.in_else)
+                .map(|_| cond.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Enter, switch, or leave a branch. `value` is the evaluated condition,
+    /// ignored when `condition` returned `None`.
+    pub fn apply(&mut self, branch: &Branch, value: bool) -> Result<(), String> {
+        match branch {
+            Branch::If(_) => {
+                let parent_active = self.active();
+                self.frames.push(Frame {
+                    parent_active,
+                    taken: parent_active && value,
+                    active: parent_active && value,
+                    in_else: false,
+                });
+            }
+            Branch::Elif(_) => {
+                let f = self.frames.last_mut().ok_or("\\elif without \\if")?;
+                if f.in_else {
+                    return Err("\\elif after \\else".to_string());
+                }
+                f.active = f.parent_active && !f.taken && value;
+                f.taken |= f.active;
+            }
+            Branch::Else => {
+                let f = self.frames.last_mut().ok_or("\\else without \\if")?;
+                if f.in_else {
+                    return Err("\\else after \\else".to_string());
+                }
+                f.in_else = true;
+                f.active = f.parent_active && !f.taken;
+                f.taken = true;
+            }
+            Branch::Endif => {
+                self.frames.pop().ok_or("\\endif without \\if")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split a script into batches, sqlcmd style.
+///
+/// `GO` on a line of its own (optionally `GO <count>`) ends a batch.
+/// `:setvar name value` lines define variables, and each batch's references
+/// are replaced as [`variables::interpolate_script`] does: `:name` and
+/// `$(name)` outside literals and comments, `$(name)` falling back to the
+/// environment variable of that name. `vars` supplies initial values. With
+/// `variables` false, references are left as written (sqlcmd `-x`).
+/// Batches that are only whitespace are dropped.
+///
+/// `\if`, `\elif`, `\else`, and `\endif` lines end the current batch and
+/// become batches of their own with `branch` set, to be evaluated as the
+/// script runs.
+pub fn prepare(
+    text: &str,
+    vars: &HashMap<String, String>,
+    variables: bool,
+) -> Result<Vec<Batch>, String> {
+    let mut vars = vars.clone();
+    // Only checks nesting; conditions are evaluated when the script runs
+    let mut nesting = Conditions::default();
+    let mut batches = Vec::new();
+    let mut current = String::new();
+    let mut start = 1;
+
+    for (i, line) in text.lines().enumerate() {
+        let lineno = i + 1;
+        let trimmed = line.trim();
+        if let Some(rest) = strip_prefix_ci(trimmed, ":setvar") {
+            let rest = rest.trim();
+            let (name, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if name.is_empty() {
+                return Err(format!("line {}: :setvar needs a variable name", lineno));
+            }
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+            vars.insert(name.to_string(), value.to_string());
+            if current.is_empty() {
+                start = lineno + 1;
+            }
+            continue;
+        }
+        let at_line = |e: String| format!("line {}: {}", lineno, e);
+        if let Some(branch) = branch(trimmed).map_err(at_line)? {
+            nesting.apply(&branch, false).map_err(at_line)?;
+            if !current.trim().is_empty() {
+                batches.push(batch(&current, start, 1, &vars, variables)?);
+            }
+            let branch = branch
+                .map_condition(|cond| expand(cond, &vars, variables))
+                .map_err(at_line)?;
+            batches.push(Batch {
+                sql: String::new(),
+                line: lineno,
+                repeat: 1,
+                branch: Some(branch),
+            });
+            current.clear();
+            start = lineno + 1;
+            continue;
+        }
+        if let Some(repeat) = go_separator(trimmed) {
+            if !current.trim().is_empty() {
+                batches.push(batch(&current, start, repeat, &vars, variables)?);
+            }
+            current.clear();
+            start = lineno + 1;
+            continue;
+        }
+        if current.is_empty() && trimmed.is_empty() {
+            start = lineno + 1;
+            continue;
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    if !current.trim().is_empty() {
+        batches.push(batch(&current, start, 1, &vars, variables)?);
+    }
+    if nesting.is_open() {
+        return Err("\\if without \\endif".to_string());
+    }
+    Ok(batches)
+}
+
+/// A batch of `sql` starting at `line`, with its variables expanded. The
+/// whole batch is expanded at once so literals and comments that span
+/// lines are left alone.
+fn batch(
+    sql: &str,
+    line: usize,
+    repeat: usize,
+    vars: &HashMap<String, String>,
+    variables: bool,
+) -> Result<Batch, String> {
+    Ok(Batch {
+        sql: expand(sql, vars, variables).map_err(|e| format!("line {}: {}", line, e))?,
+        line,
+        repeat,
+        branch: None,
+    })
+}
+
+/// [`variables::interpolate_script`] if `variables` is on, else `text` as
+/// it is.
+fn expand(text: &str, vars: &HashMap<String, String>, variables: bool) -> Result<String, String> {
+    if variables {
+        variables::interpolate_script(text, vars)
+    } else {
+        Ok(text.to_string())
+    }
+}
+
+/// Split a startup file into the commands to run: each slash command line
+/// on its own, and SQL statements ended by `;`, a `GO` line, or the end of
+/// the file. Blank lines and comment lines between commands are dropped.
+pub fn rc_commands(text: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut buffer = String::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if buffer.is_empty() {
+            if trimmed.is_empty() || trimmed.starts_with("--") {
+                continue;
+            }
+            if trimmed.starts_with('\\') {
+                commands.push(trimmed.to_string());
+                continue;
+            }
+        }
+        if go_separator(trimmed).is_some() {
+            commands.push(std::mem::take(&mut buffer).trim().to_string());
+            continue;
+        }
+        buffer.push_str(line);
+        buffer.push('\n');
+        if ends_statement(&buffer) {
+            commands.push(std::mem::take(&mut buffer).trim().to_string());
+        }
+    }
+    commands.push(buffer.trim().to_string());
+    commands.retain(|c| !c.is_empty());
+    commands
+}
+
+/// Whether `text` ends with a `;` that terminates a statement: outside
+/// string literals, quoted identifiers, and comments, with nothing but
+/// whitespace and comments after it.
+pub fn ends_statement(text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    let mut last = None;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\'' | '"' | '[' => {
+                let close = if c == '[' { ']' } else { c };
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        // Unterminated: the statement continues on the next line
+                        None => return false,
+                        Some(&d) if d == close && chars.get(i + 1) == Some(&close) => i += 2,
+                        Some(&d) if d == close => break,
+                        Some(_) => i += 1,
+                    }
+                }
+                last = Some(close);
+            }
+            '-' if next == Some('-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if next == Some('*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return false;
+                }
+                i += 1;
+            }
+            c if !c.is_whitespace() => last = Some(c),
+            _ => {}
+        }
+        i += 1;
+    }
+    last == Some(';')
+}
+
+/// If `line` is a batch separator, the repeat count it asks for.
+pub fn go_separator(line: &str) -> Option<usize> {
+    // Allow a trailing comment: `GO -- end of batch`
+    let line = line.split("--").next().unwrap_or("").trim();
+    let rest = strip_prefix_ci(line, "go")?;
+    if rest.is_empty() {
+        return Some(1);
+    }
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    rest.trim().parse().ok().filter(|&n| n > 0)
+}
+
+fn strip_prefix_ci<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    s.get(..prefix.len())
+        .filter(|head| head.eq_ignore_ascii_case(prefix))
+        .map(|_| &s[prefix.len()..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_temp() {
+        let first = write_temp("SELECT 1").unwrap();
+        let second = write_temp("SELECT 2").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(read(&first).unwrap(), "SELECT 1\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&first).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_file(first).ok();
+        std::fs::remove_file(second).ok();
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.sql", "01_schema.sql"));
+        assert!(wildcard_match("0?_*.sql", "02_seed.sql"));
+        assert!(wildcard_match("a*b*c", "aXbYbZc"));
+        assert!(!wildcard_match("*.sql", "notes.txt"));
+        assert!(!wildcard_match("0?_*.sql", "10_grants.sql"));
+    }
+
+    #[test]
+    fn test_rc_commands() {
+        let rc = "-- startup\n\\timing\nSET LOCK_TIMEOUT 5000;\n\nSET NOCOUNT ON\nGO\n\\c shop\nSELECT 1\n";
+        assert_eq!(
+            rc_commands(rc),
+            vec![
+                "\\timing",
+                "SET LOCK_TIMEOUT 5000;",
+                "SET NOCOUNT ON",
+                "\\c shop",
+                "SELECT 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ends_statement() {
+        assert!(ends_statement("SELECT 1;"));
+        assert!(ends_statement("SELECT *\nFROM t; -- done\n"));
+        assert!(!ends_statement("SELECT 1"));
+        assert!(!ends_statement("SELECT 'a;"));
+        assert!(!ends_statement("SELECT 'a;'"));
+        assert!(!ends_statement("SELECT [x;]"));
+        assert!(!ends_statement("SELECT 1 /* ; */"));
+        assert!(!ends_statement("SELECT 1; /* still"));
+    }
+
+    #[test]
+    fn test_prepare_splits_on_go() {
+        let script = "CREATE TABLE t (id int)\nGO\n\nINSERT t VALUES (1)\ngo 3\nSELECT * FROM t\n";
+        let batches = prepare(script, &HashMap::new(), true).unwrap();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0].sql, "CREATE TABLE t (id int)\n");
+        assert_eq!((batches[1].line, batches[1].repeat), (4, 3));
+        assert_eq!((batches[2].line, batches[2].repeat), (6, 1));
+    }
+
+    #[test]
+    fn test_prepare_does_not_split_on_go_prefix() {
+        let batches = prepare("SELECT 1\nGOTO label\nGOx", &HashMap::new(), true).unwrap();
+        assert_eq!(batches.len(), 1);
+    }
+
+    #[test]
+    fn test_prepare_substitutes_variables() {
+        let script = ":setvar db \"Sales\"\nUSE $(db)\nSELECT :who, '$(who)\n:who' /* $(db)\n*/";
+        let vars = HashMap::from([("who".to_string(), "me".to_string())]);
+        let batches = prepare(script, &vars, true).unwrap();
+        // Not in literals or comments, even across lines
+        assert_eq!(
+            batches[0].sql,
+            "USE Sales\nSELECT me, '$(who)\n:who' /* $(db)\n*/\n"
+        );
+        assert_eq!(batches[0].line, 2);
+
+        let err = prepare("SELECT $(meow_surely_undefined)", &HashMap::new(), true).unwrap_err();
+        assert!(err.contains("line 1"));
+
+        let batches = prepare("USE $(db)\nSELECT :who", &vars, false).unwrap();
+        assert_eq!(batches[0].sql, "USE $(db)\nSELECT :who\n");
+    }
+
+    #[test]
+    fn test_prepare_branches() {
+        let script = "\\if $(seed)\nINSERT t VALUES (1)\n\\else\nSELECT 1\n\\endif\n";
+        let vars = HashMap::from([("seed".to_string(), "true".to_string())]);
+        let batches = prepare(script, &vars, true).unwrap();
+        let branches: Vec<_> = batches.iter().map(|b| b.branch.clone()).collect();
+        assert_eq!(
+            branches,
+            vec![
+                Some(Branch::If("true".to_string())),
+                None,
+                Some(Branch::Else),
+                None,
+                Some(Branch::Endif),
+            ]
+        );
+        assert_eq!(batches[1].line, 2);
+
+        assert!(prepare("\\if true\nSELECT 1", &HashMap::new(), true).is_err());
+        let err = prepare("SELECT 1\n\\endif", &HashMap::new(), true).unwrap_err();
+        assert!(err.contains("line 2"));
+    }
+
+    #[test]
+    fn test_conditions() {
+        let mut c = Conditions::default();
+        let cond = |s: &str| Branch::If(s.to_string());
+        c.apply(&cond("a"), false).unwrap();
+        assert!(!c.active());
+        // A nested \if in a skipped block needs no evaluation and stays off
+        assert_eq!(c.condition(&cond("b")), None);
+        c.apply(&cond("b"), true).unwrap();
+        assert!(!c.active());
+        c.apply(&Branch::Endif, false).unwrap();
+        let elif = Branch::Elif("c".to_string());
+        assert_eq!(c.condition(&elif), Some("c"));
+        c.apply(&elif, true).unwrap();
+        assert!(c.active());
+        c.apply(&Branch::Else, false).unwrap();
+        assert!(!c.active());
+        assert!(c.apply(&Branch::Else, false).is_err());
+        c.apply(&Branch::Endif, false).unwrap();
+        assert!(c.active() && !c.is_open());
+    }
+}
@@ -0,0 +1,74 @@
+//! Named SQL snippets, stored as `<name>.sql` files under the config directory.
+
+use crate::config;
+use std::io;
+use std::path::PathBuf;
+
+/// Snippet names are limited to characters that are safe in file names.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Save `body` under `name`, replacing any existing snippet.
+pub fn save(name: &str, body: &str) -> io::Result<PathBuf> {
+    let path = path_for(name)?;
+    std::fs::write(&path, body)?;
+    Ok(path)
+}
+
+/// Load the snippet called `name`.
+pub fn load(name: &str) -> io::Result<String> {
+    std::fs::read_to_string(path_for(name)?)
+}
+
+/// Delete the snippet called `name`.
+pub fn delete(name: &str) -> io::Result<()> {
+    std::fs::remove_file(path_for(name)?)
+}
+
+/// All snippets as (name, body), sorted by name.
+pub fn list() -> Vec<(String, String)> {
+    let Some(dir) = dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut snippets: Vec<(String, String)> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "sql"))
+        .filter_map(|p| {
+            let name = p.file_stem()?.to_str()?.to_string();
+            let body = std::fs::read_to_string(&p).ok()?;
+            Some((name, body))
+        })
+        .collect();
+    snippets.sort_by(|a, b| a.0.cmp(&b.0));
+    snippets
+}
+
+fn dir() -> Option<PathBuf> {
+    let dir = config::config_dir()?.join("snippets");
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+fn path_for(name: &str) -> io::Result<PathBuf> {
+    if !is_valid_name(name) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "invalid snippet name '{}' (use letters, digits, - _ .)",
+                name
+            ),
+        ));
+    }
+    let dir = dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory available"))?;
+    Ok(dir.join(format!("{}.sql", name)))
+}
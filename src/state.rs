@@ -0,0 +1,107 @@
+//! Display state kept between TUI sessions: expanded mode, timing, sidebar
+//! visibility, the null display string, and the last database used.
+//!
+//! Stored as `key = value` lines in `<config dir>/state`, separate from the
+//! hand-edited `config` file (which already keeps the sidebar width).
+//! Missing or invalid entries keep the defaults.
+
+use crate::config::config_file;
+
+/// Saved display state. `None` fields weren't saved and keep the default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct State {
+    /// `\x` expanded display.
+    pub expanded: Option<bool>,
+    /// `\timing` display.
+    pub timing: Option<bool>,
+    /// Whether the sidebar is shown.
+    pub sidebar_visible: Option<bool>,
+    /// Text shown for NULL values (`\pset null`).
+    pub null_display: Option<String>,
+    /// Database in use when the last session ended.
+    pub database: Option<String>,
+}
+
+impl State {
+    /// Load the saved state, or defaults if there is none.
+    pub fn load() -> Self {
+        config_file("state")
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|contents| Self::parse(&contents))
+            .unwrap_or_default()
+    }
+
+    /// Write the state file.
+    pub fn save(&self) -> std::io::Result<()> {
+        match config_file("state") {
+            Some(path) => std::fs::write(path, self.render()),
+            None => Ok(()),
+        }
+    }
+
+    fn parse(contents: &str) -> Self {
+        let mut state = Self::default();
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            // Values are written quoted so leading and trailing spaces survive
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+            match key.trim() {
+                "expanded" => state.expanded = value.parse().ok(),
+                "timing" => state.timing = value.parse().ok(),
+                "sidebar_visible" => state.sidebar_visible = value.parse().ok(),
+                "null_display" => state.null_display = Some(value.to_string()),
+                "database" if !value.is_empty() => state.database = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        state
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let mut line = |key: &str, value: String| out.push_str(&format!("{} = {}\n", key, value));
+        if let Some(on) = self.expanded {
+            line("expanded", on.to_string());
+        }
+        if let Some(on) = self.timing {
+            line("timing", on.to_string());
+        }
+        if let Some(on) = self.sidebar_visible {
+            line("sidebar_visible", on.to_string());
+        }
+        if let Some(ref text) = self.null_display {
+            line("null_display", format!("\"{}\"", text));
+        }
+        if let Some(ref database) = self.database {
+            line("database", format!("\"{}\"", database));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let state = State {
+            expanded: Some(true),
+            timing: Some(false),
+            sidebar_visible: Some(false),
+            null_display: Some(" ∅ ".to_string()),
+            database: Some("Sales DB".to_string()),
+        };
+        assert_eq!(State::parse(&state.render()), state);
+        assert_eq!(
+            State::parse("timing = maybe\nbogus = 1\n"),
+            State::default()
+        );
+    }
+}
@@ -0,0 +1,434 @@
+//! Time zones for showing `datetimeoffset` values in another zone
+//! (`\set timezone`): fixed offsets, `UTC`, `local`, and IANA names read
+//! from the system's zoneinfo files.
+
+use crate::db::query::days_to_ymd;
+use crate::display::days_from_civil;
+use std::path::{Path, PathBuf};
+
+/// A time zone: its UTC offset at any moment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Zone {
+    /// A fixed offset from UTC, in seconds.
+    Fixed(i32),
+    /// Offsets from a zoneinfo file: the offset before the first
+    /// transition, then each transition's UTC time and the offset from it,
+    /// and the rule for times after the last transition.
+    Rules {
+        initial: i32,
+        transitions: Vec<(i64, i32)>,
+        rule: Option<PosixRule>,
+    },
+}
+
+impl Zone {
+    /// A zone from `UTC`, an offset (`+02:00`, `-0530`, `+5`), `local`
+    /// (`TZ`, else `/etc/localtime`), or an IANA name such as
+    /// `Europe/Berlin`, looked up under `TZDIR` or `/usr/share/zoneinfo`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        if spec.eq_ignore_ascii_case("utc") || spec.eq_ignore_ascii_case("z") {
+            return Ok(Self::Fixed(0));
+        }
+        if let Some(offset) = parse_offset(spec) {
+            return Ok(Self::Fixed(offset));
+        }
+        let path = if spec.eq_ignore_ascii_case("local") {
+            match std::env::var("TZ") {
+                Ok(tz) if !tz.is_empty() => return Self::parse(tz.trim_start_matches(':')),
+                _ => PathBuf::from("/etc/localtime"),
+            }
+        } else if spec.split('/').any(|part| part.is_empty() || part == "..") {
+            return Err(format!("Unknown time zone {}", spec));
+        } else {
+            zoneinfo_dir().join(spec)
+        };
+        load(&path).ok_or_else(|| format!("Unknown time zone {}", spec))
+    }
+
+    /// The offset from UTC, in seconds, at `utc` seconds since the epoch.
+    pub fn offset_at(&self, utc: i64) -> i32 {
+        match self {
+            Self::Fixed(offset) => *offset,
+            Self::Rules {
+                initial,
+                transitions,
+                rule,
+            } => {
+                let i = transitions.partition_point(|&(at, _)| at <= utc);
+                if i == transitions.len()
+                    && let Some(rule) = rule
+                {
+                    return rule.offset_at(utc);
+                }
+                match i {
+                    0 => *initial,
+                    i => transitions[i - 1].1,
+                }
+            }
+        }
+    }
+}
+
+/// A POSIX TZ rule from the footer of a zoneinfo file, such as
+/// `CET-1CEST,M3.5.0,M10.5.0/3`: the standard offset and, for zones with
+/// summer time, when it starts and ends each year.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PosixRule {
+    /// Standard offset, in seconds east of UTC.
+    std_offset: i32,
+    dst: Option<DstRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DstRule {
+    /// Summer time offset, in seconds east of UTC.
+    offset: i32,
+    /// Start and end: the day, and seconds after local midnight.
+    start: (RuleDate, i32),
+    end: (RuleDate, i32),
+}
+
+/// `Mm.w.d`: weekday `d` (0 is Sunday) of week `w` (5 is the last) of
+/// month `m`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RuleDate {
+    month: u32,
+    week: u32,
+    weekday: u32,
+}
+
+impl PosixRule {
+    /// Parse a rule. Only `Mm.w.d` dates are understood, which is what
+    /// zoneinfo files use; others give `None`.
+    fn parse(spec: &str) -> Option<Self> {
+        let rest = tz_name(spec)?;
+        let (std, rest) = tz_time(rest)?;
+        // POSIX offsets count west of UTC
+        let std_offset = -std;
+        if rest.is_empty() {
+            return Some(Self {
+                std_offset,
+                dst: None,
+            });
+        }
+        let rest = tz_name(rest)?;
+        let (offset, rest) = match rest.strip_prefix(',') {
+            Some(rest) => (std_offset + 3600, rest),
+            None => {
+                let (dst, rest) = tz_time(rest)?;
+                (-dst, rest.strip_prefix(',')?)
+            }
+        };
+        let (start, end) = rest.split_once(',')?;
+        Some(Self {
+            std_offset,
+            dst: Some(DstRule {
+                offset,
+                start: RuleDate::parse_transition(start)?,
+                end: RuleDate::parse_transition(end)?,
+            }),
+        })
+    }
+
+    /// The offset from UTC, in seconds, at `utc` seconds since the epoch.
+    fn offset_at(&self, utc: i64) -> i32 {
+        let Some(ref dst) = self.dst else {
+            return self.std_offset;
+        };
+        let (year, _, _) = days_to_ymd((utc + i64::from(self.std_offset)).div_euclid(86_400));
+        // Start is given in standard time, end in summer time
+        let at = |(date, time): (RuleDate, i32), offset: i32| {
+            date.day(year) * 86_400 + i64::from(time) - i64::from(offset)
+        };
+        let start = at(dst.start, self.std_offset);
+        let end = at(dst.end, dst.offset);
+        // Southern zones have summer time across the new year
+        let summer = if start < end {
+            start <= utc && utc < end
+        } else {
+            utc < end || start <= utc
+        };
+        if summer { dst.offset } else { self.std_offset }
+    }
+}
+
+impl RuleDate {
+    /// `Mm.w.d[/time]`, the time defaulting to 02:00.
+    fn parse_transition(spec: &str) -> Option<(Self, i32)> {
+        let (date, time) = match spec.split_once('/') {
+            Some((date, time)) => match tz_time(time)? {
+                (time, "") => (date, time),
+                _ => return None,
+            },
+            None => (spec, 7200),
+        };
+        let mut parts = date.strip_prefix('M')?.split('.').map(|p| p.parse::<u32>());
+        let date = Self {
+            month: parts.next()?.ok().filter(|m| (1..=12).contains(m))?,
+            week: parts.next()?.ok().filter(|w| (1..=5).contains(w))?,
+            weekday: parts.next()?.ok().filter(|d| *d <= 6)?,
+        };
+        parts.next().is_none().then_some((date, time))
+    }
+
+    /// The day in `year`, as days since the epoch.
+    fn day(self, year: i64) -> i64 {
+        let first = days_from_civil(year, self.month, 1);
+        // 1970-01-01 was a Thursday
+        let first_weekday = (first + 4).rem_euclid(7);
+        let mut day = first
+            + (i64::from(self.weekday) - first_weekday).rem_euclid(7)
+            + 7 * i64::from(self.week - 1);
+        let next_month = match self.month {
+            12 => days_from_civil(year + 1, 1, 1),
+            m => days_from_civil(year, m + 1, 1),
+        };
+        while day >= next_month {
+            day -= 7;
+        }
+        day
+    }
+}
+
+/// The rest of `spec` after a zone abbreviation: letters, or anything in
+/// `<...>`.
+fn tz_name(spec: &str) -> Option<&str> {
+    if let Some(rest) = spec.strip_prefix('<') {
+        return rest.split_once('>').map(|(_, rest)| rest);
+    }
+    let len = spec
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(spec.len());
+    (len >= 3).then(|| &spec[len..])
+}
+
+/// `[+-]hh[:mm[:ss]]` as seconds, and the rest of `spec`.
+fn tz_time(spec: &str) -> Option<(i32, &str)> {
+    let (sign, spec) = match spec.as_bytes().first()? {
+        b'-' => (-1, &spec[1..]),
+        b'+' => (1, &spec[1..]),
+        _ => (1, spec),
+    };
+    let len = spec
+        .find(|c: char| !c.is_ascii_digit() && c != ':')
+        .unwrap_or(spec.len());
+    let mut seconds = 0;
+    for (i, part) in spec[..len].split(':').enumerate() {
+        let n: i32 = part.parse().ok()?;
+        let (max, scale) = match i {
+            0 => (167, 3600),
+            1 => (59, 60),
+            2 => (59, 1),
+            _ => return None,
+        };
+        if n > max {
+            return None;
+        }
+        seconds += n * scale;
+    }
+    Some((sign * seconds, &spec[len..]))
+}
+
+/// `+02:00`, `-0530`, or `+5` as seconds east of UTC.
+fn parse_offset(spec: &str) -> Option<i32> {
+    let (sign, rest) = match spec.as_bytes().first()? {
+        b'+' => (1, &spec[1..]),
+        b'-' => (-1, &spec[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "0"),
+    };
+    let hours: i32 = hours.parse().ok().filter(|h| *h <= 14)?;
+    let minutes: i32 = minutes.parse().ok().filter(|m| *m < 60)?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+fn zoneinfo_dir() -> PathBuf {
+    std::env::var_os("TZDIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/usr/share/zoneinfo"))
+}
+
+fn load(path: &Path) -> Option<Zone> {
+    parse_tzif(&std::fs::read(path).ok()?)
+}
+
+/// Read a TZif (zoneinfo) file's transitions, preferring the 64-bit data
+/// of version 2 and later files. Times past the last transition follow the
+/// POSIX TZ rule in the footer of those files, or else keep its offset.
+fn parse_tzif(data: &[u8]) -> Option<Zone> {
+    let (header, body) = tzif_header(data)?;
+    let version = data[4];
+    let (counts, body, time_size) = if version >= b'2' {
+        let v1_len = header.timecnt * 5
+            + header.typecnt * 6
+            + header.charcnt
+            + header.leapcnt * 8
+            + header.isstdcnt
+            + header.isutcnt;
+        let (header, body) = tzif_header(body.get(v1_len..)?)?;
+        (header, body, 8)
+    } else {
+        (header, body, 4)
+    };
+
+    let times_len = counts.timecnt * time_size;
+    let times = body.get(..times_len)?;
+    let indexes = body.get(times_len..times_len + counts.timecnt)?;
+    let types = body.get(times_len + counts.timecnt..)?;
+    let offset_of = |index: usize| -> Option<i32> {
+        let entry = types.get(index * 6..index * 6 + 4)?;
+        Some(i32::from_be_bytes(entry.try_into().ok()?))
+    };
+    if counts.typecnt == 0 {
+        return None;
+    }
+
+    let transitions = times
+        .chunks(time_size)
+        .zip(indexes)
+        .map(|(time, &index)| {
+            let at = match time_size {
+                8 => i64::from_be_bytes(time.try_into().ok()?),
+                _ => i64::from(i32::from_be_bytes(time.try_into().ok()?)),
+            };
+            Some((at, offset_of(index as usize)?))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    // The footer follows the version 2 data: `\n<rule>\n`
+    let rule = (time_size == 8)
+        .then(|| {
+            let data_len = times_len
+                + counts.timecnt
+                + counts.typecnt * 6
+                + counts.charcnt
+                + counts.leapcnt * 12
+                + counts.isstdcnt
+                + counts.isutcnt;
+            let footer = body.get(data_len..)?.strip_prefix(b"\n")?;
+            let end = footer.iter().position(|&b| b == b'\n')?;
+            PosixRule::parse(std::str::from_utf8(&footer[..end]).ok()?)
+        })
+        .flatten();
+    Some(Zone::Rules {
+        initial: offset_of(0)?,
+        transitions,
+        rule,
+    })
+}
+
+/// Counts from a TZif header.
+struct TzifHeader {
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+/// Parse the 44-byte header at the start of `data`; returns it and the
+/// bytes after it.
+fn tzif_header(data: &[u8]) -> Option<(TzifHeader, &[u8])> {
+    if data.get(..4)? != b"TZif" {
+        return None;
+    }
+    let count = |i: usize| -> Option<usize> {
+        let bytes = data.get(20 + i * 4..24 + i * 4)?;
+        Some(u32::from_be_bytes(bytes.try_into().ok()?) as usize)
+    };
+    let header = TzifHeader {
+        isutcnt: count(0)?,
+        isstdcnt: count(1)?,
+        leapcnt: count(2)?,
+        timecnt: count(3)?,
+        typecnt: count(4)?,
+        charcnt: count(5)?,
+    };
+    Some((header, data.get(44..)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(Zone::parse("UTC"), Ok(Zone::Fixed(0)));
+        assert_eq!(Zone::parse("+02:00"), Ok(Zone::Fixed(7200)));
+        assert_eq!(Zone::parse("-0530"), Ok(Zone::Fixed(-19800)));
+        assert_eq!(Zone::parse("+5"), Ok(Zone::Fixed(18000)));
+        assert!(Zone::parse("../etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_parse_tzif() {
+        // Version 1: standard time at +1h, summer time at +2h from t=1000
+        let mut data = b"TZif".to_vec();
+        data.extend([0u8; 16]);
+        for count in [0u32, 0, 0, 1, 2, 4] {
+            data.extend(count.to_be_bytes());
+        }
+        data.extend(1000i32.to_be_bytes());
+        data.push(1);
+        data.extend(3600i32.to_be_bytes());
+        data.extend([0, 0]);
+        data.extend(7200i32.to_be_bytes());
+        data.extend([1, 0]);
+        data.extend(b"CET\0");
+
+        let zone = parse_tzif(&data).unwrap();
+        assert_eq!(zone.offset_at(999), 3600);
+        assert_eq!(zone.offset_at(1000), 7200);
+        assert_eq!(parse_tzif(b"nope"), None);
+    }
+
+    #[test]
+    fn test_parse_tzif_footer() {
+        // A slim version 2 Europe/Berlin: one transition to CET in 1893,
+        // then the footer rule
+        let mut data = b"TZif2".to_vec();
+        data.extend([0u8; 39]);
+        data.extend(b"TZif2");
+        data.extend([0u8; 15]);
+        for count in [0u32, 0, 0, 1, 2, 9] {
+            data.extend(count.to_be_bytes());
+        }
+        data.extend((-2_422_054_408i64).to_be_bytes());
+        data.push(1);
+        data.extend(3208i32.to_be_bytes());
+        data.extend([0, 0]);
+        data.extend(3600i32.to_be_bytes());
+        data.extend([0, 4]);
+        data.extend(b"LMT\0CET\0\0");
+        data.extend(b"\nCET-1CEST,M3.5.0,M10.5.0/3\n");
+
+        let zone = parse_tzif(&data).unwrap();
+        let at = |y, m, d, h: i64| days_from_civil(y, m, d) * 86_400 + h * 3600;
+        assert_eq!(zone.offset_at(at(1850, 1, 1, 0)), 3208);
+        assert_eq!(zone.offset_at(at(2030, 1, 15, 12)), 3600);
+        assert_eq!(zone.offset_at(at(2030, 7, 1, 12)), 7200);
+        // 2030: summer time from 31 March 01:00 UTC to 27 October 01:00 UTC
+        assert_eq!(zone.offset_at(at(2030, 3, 31, 1) - 1), 3600);
+        assert_eq!(zone.offset_at(at(2030, 3, 31, 1)), 7200);
+        assert_eq!(zone.offset_at(at(2030, 10, 27, 1) - 1), 7200);
+        assert_eq!(zone.offset_at(at(2030, 10, 27, 1)), 3600);
+    }
+
+    #[test]
+    fn test_posix_rule() {
+        let sydney = PosixRule::parse("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        let at = |y, m, d| days_from_civil(y, m, d) * 86_400;
+        assert_eq!(sydney.offset_at(at(2031, 1, 10)), 39_600);
+        assert_eq!(sydney.offset_at(at(2031, 7, 10)), 36_000);
+
+        let fixed = PosixRule::parse("<+0530>-5:30").unwrap();
+        assert_eq!(fixed.offset_at(0), 19_800);
+        assert_eq!(PosixRule::parse("EST5EDT,J60,J300"), None);
+        assert_eq!(PosixRule::parse("CET-1CEST,M13.5.0,M10.5.0"), None);
+    }
+}
@@ -1,4 +1,8 @@
-//! SQL keyword autocomplete state and matching logic.
+//! SQL keyword and object name autocomplete state and fuzzy matching.
+
+use super::fuzzy;
+use crate::app::ForeignKey;
+use std::collections::HashMap;
 
 /// Comprehensive T-SQL keywords for autocomplete.
 const SQL_KEYWORDS: &[&str] = &[
@@ -166,17 +170,95 @@ const SQL_KEYWORDS: &[&str] = &[
     "sys",
 ];
 
+/// How many accepted suggestions are remembered for ranking.
+const RECENT_LIMIT: usize = 20;
+
+/// Score bonus for schema matches (tables, columns, procedures) over keywords.
+const SCHEMA_BONUS: i64 = 10;
+
+/// Score bonus for phrases from history: below schema names, above keywords.
+const HISTORY_BONUS: i64 = 5;
+
+/// Clauses remembered from history as whole phrases.
+const PHRASE_CLAUSES: &[&str] = &["SELECT", "WHERE"];
+
+/// Words that end a remembered clause.
+const PHRASE_ENDS: &[&str] = &[
+    "FROM",
+    "WHERE",
+    "GROUP",
+    "ORDER",
+    "HAVING",
+    "UNION",
+    "EXCEPT",
+    "INTERSECT",
+    "OPTION",
+    "INTO",
+];
+
+/// One entry in the popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    /// Text inserted when accepted.
+    pub text: String,
+    /// How many chars before the cursor it replaces: the word being typed,
+    /// or the whole clause for a phrase from history.
+    pub replace: usize,
+}
+
+/// What kind of name belongs at the cursor, from the clause it is in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompletionContext {
+    /// Anywhere else: keywords and table names.
+    Keyword,
+    /// After `FROM`, `JOIN`, `UPDATE`, or `INTO`.
+    Table,
+    /// After `EXEC`/`EXECUTE`.
+    Procedure,
+    /// In a `SELECT` list, `WHERE`, `ON`, `BY`, `HAVING`, or `SET` clause.
+    /// `tables` are the statement's tables; `qualified` means the word
+    /// follows `alias.`, so only that table's columns fit.
+    Column {
+        tables: Vec<String>,
+        qualified: bool,
+    },
+    /// Right after `JOIN <table> [alias] ON`: join predicates from foreign
+    /// keys between `joined` and the tables before it, then columns.
+    JoinOn {
+        tables: Vec<String>,
+        joined: TableRef,
+        others: Vec<TableRef>,
+    },
+}
+
+/// A table reference in a statement: (table as written, alias).
+pub type TableRef = (String, Option<String>);
+
 /// Autocomplete popup state.
 #[derive(Debug, Clone, Default)]
 pub struct Autocomplete {
     /// Whether the popup is currently visible.
     pub active: bool,
-    /// Current list of matching suggestions.
-    pub suggestions: Vec<&'static str>,
+    /// Current list of matching suggestions, best first.
+    pub suggestions: Vec<Suggestion>,
     /// Currently selected index in suggestions.
     pub selected: usize,
-    /// The prefix being matched (the partial word the user typed).
+    /// The partial word the user typed, matched as a fuzzy pattern.
     pub prefix: String,
+    /// Table names from the object browser.
+    pub objects: Vec<String>,
+    /// Stored procedure names.
+    pub procedures: Vec<String>,
+    /// Column names by table, keyed by [`table_key`]; filled in on demand.
+    pub columns: HashMap<String, Vec<String>>,
+    /// Foreign keys on either side of a table, keyed by [`table_key`];
+    /// filled in on demand.
+    pub foreign_keys: HashMap<String, Vec<ForeignKey>>,
+    /// `SELECT` lists and `WHERE` clauses from query history, most recent
+    /// first.
+    pub phrases: Vec<String>,
+    /// Recently accepted suggestions, most recent first.
+    pub recent: Vec<String>,
 }
 
 impl Autocomplete {
@@ -184,24 +266,164 @@ impl Autocomplete {
     /// Call this after every keystroke in the editor.
     pub fn update(&mut self, lines: &[String], cursor_row: usize, cursor_col: usize) {
         let prefix = extract_current_word(lines, cursor_row, cursor_col);
-        if prefix.len() < 2 {
+        let context = context(lines, cursor_row, cursor_col);
+        let qualified = matches!(
+            context,
+            CompletionContext::Column {
+                qualified: true,
+                ..
+            }
+        );
+        let before = text_before(lines, cursor_row, cursor_col);
+        let mut matches = self.rank_phrases(&before, &prefix);
+        if let CompletionContext::JoinOn { joined, others, .. } = &context {
+            matches.extend(self.rank_joins(joined, others, &prefix));
+        }
+        if prefix.len() >= 2 || qualified {
+            matches.extend(self.rank(&prefix, &context));
+        }
+        if matches.is_empty() {
             self.dismiss();
             return;
         }
-        let upper = prefix.to_ascii_uppercase();
-        let matches: Vec<&'static str> = SQL_KEYWORDS
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.text.len().cmp(&b.1.text.len())));
+        self.prefix = prefix;
+        self.suggestions = matches.into_iter().map(|(_, s)| s).collect();
+        self.selected = self.selected.min(self.suggestions.len().saturating_sub(1));
+        self.active = true;
+    }
+
+    /// Candidates for `context` matching `pattern` as a subsequence, scored
+    /// by fuzzy match plus bonuses for schema names and recent picks.
+    fn rank(&self, pattern: &str, context: &CompletionContext) -> Vec<(i64, Suggestion)> {
+        let (schema, keywords): (Vec<&str>, bool) = match context {
+            CompletionContext::Keyword => (strs(&self.objects), true),
+            CompletionContext::Table => (strs(&self.objects), false),
+            CompletionContext::Procedure => (strs(&self.procedures), false),
+            CompletionContext::JoinOn { tables, .. } => (
+                tables
+                    .iter()
+                    .filter_map(|t| self.columns.get(&table_key(t)))
+                    .flat_map(|cols| cols.iter().map(String::as_str))
+                    .collect(),
+                true,
+            ),
+            CompletionContext::Column { tables, qualified } => (
+                tables
+                    .iter()
+                    .filter_map(|t| self.columns.get(&table_key(t)))
+                    .flat_map(|cols| cols.iter().map(String::as_str))
+                    .collect(),
+                !qualified,
+            ),
+        };
+        let keywords: &[&str] = if keywords { SQL_KEYWORDS } else { &[] };
+
+        let mut seen = std::collections::HashSet::new();
+        schema
+            .into_iter()
+            .map(|c| (SCHEMA_BONUS, c))
+            .chain(keywords.iter().map(|&c| (0, c)))
+            .filter(|(_, c)| seen.insert(c.to_ascii_uppercase()))
+            .filter_map(|(bonus, c)| {
+                let (score, _) = fuzzy::fuzzy_match(pattern, c)?;
+                let suggestion = Suggestion {
+                    text: c.to_string(),
+                    replace: pattern.chars().count(),
+                };
+                Some((score + bonus + self.recency(c), suggestion))
+            })
+            .collect()
+    }
+
+    /// History phrases continuing the `SELECT` list or `WHERE` clause typed
+    /// so far. They score like an exact match of the current word, plus
+    /// [`HISTORY_BONUS`].
+    fn rank_phrases(&self, before: &str, word: &str) -> Vec<(i64, Suggestion)> {
+        let statement = before.rsplit(';').next().unwrap_or("");
+        let Some(typed) = open_clause(statement) else {
+            return Vec::new();
+        };
+        let typed_norm = normalize(typed).to_ascii_uppercase();
+        let exact = fuzzy::fuzzy_match(word, word).map_or(0, |(score, _)| score);
+        self.phrases
+            .iter()
+            .filter(|p| {
+                let upper = p.to_ascii_uppercase();
+                upper.len() > typed_norm.len() && upper.starts_with(&typed_norm)
+            })
+            .map(|p| {
+                let suggestion = Suggestion {
+                    text: p.clone(),
+                    replace: typed.chars().count(),
+                };
+                (exact + HISTORY_BONUS + self.recency(p), suggestion)
+            })
+            .collect()
+    }
+
+    /// `ON` predicates for foreign keys between `joined` and `others`,
+    /// qualified with aliases where the statement has them. They rank above
+    /// everything else.
+    fn rank_joins(
+        &self,
+        joined: &TableRef,
+        others: &[TableRef],
+        word: &str,
+    ) -> Vec<(i64, Suggestion)> {
+        let mut seen = std::collections::HashSet::new();
+        join_predicates(self.foreign_keys.get(&table_key(&joined.0)), joined, others)
+            .into_iter()
+            .filter(|p| seen.insert(p.clone()))
+            .filter_map(|p| {
+                let (score, _) = fuzzy::fuzzy_match(word, &p)?;
+                let bonus = 2 * SCHEMA_BONUS + self.recency(&p);
+                let suggestion = Suggestion {
+                    replace: word.chars().count(),
+                    text: p,
+                };
+                Some((score + bonus, suggestion))
+            })
+            .collect()
+    }
+
+    /// Ranking bonus for a recently accepted suggestion.
+    fn recency(&self, text: &str) -> i64 {
+        self.recent
             .iter()
-            .filter(|kw| kw.to_ascii_uppercase().starts_with(&upper))
-            .copied()
+            .position(|r| r == text)
+            .map_or(0, |i| (RECENT_LIMIT - i) as i64)
+    }
+
+    /// Rebuild the history phrases from `history` (oldest first).
+    pub fn set_history(&mut self, history: &[String]) {
+        let mut seen = std::collections::HashSet::new();
+        self.phrases = history
+            .iter()
+            .rev()
+            .flat_map(|q| clause_phrases(q))
+            .filter(|p| seen.insert(p.to_ascii_uppercase()))
             .collect();
-        if matches.is_empty() {
-            self.dismiss();
-        } else {
-            self.prefix = prefix;
-            self.suggestions = matches;
-            self.selected = self.selected.min(self.suggestions.len().saturating_sub(1));
-            self.active = true;
+    }
+
+    /// Replace the table and procedure names offered for completion.
+    pub fn set_objects(&mut self, objects: Vec<String>, procedures: Vec<String>) {
+        self.objects = objects;
+        self.procedures = procedures;
+        self.columns.clear();
+    }
+
+    /// Take the selected suggestion, remembering it so it ranks higher next
+    /// time. Dismisses the popup.
+    pub fn accept(&mut self) -> Option<Suggestion> {
+        let chosen = self.suggestions.get(self.selected).cloned();
+        if let Some(ref chosen) = chosen {
+            self.recent.retain(|r| *r != chosen.text);
+            self.recent.insert(0, chosen.text.clone());
+            self.recent.truncate(RECENT_LIMIT);
         }
+        self.dismiss();
+        chosen
     }
 
     /// Dismiss the autocomplete popup.
@@ -228,30 +450,492 @@ impl Autocomplete {
             self.selected = (self.selected + 1) % self.suggestions.len();
         }
     }
+}
 
-    /// Get the currently selected suggestion, if any.
-    pub fn selected_keyword(&self) -> Option<&'static str> {
-        self.suggestions.get(self.selected).copied()
+/// Build `other.col = joined.col` predicates from the foreign keys of the
+/// joined table, for each earlier table on the other end of a key.
+fn join_predicates(
+    foreign_keys: Option<&Vec<ForeignKey>>,
+    joined: &TableRef,
+    others: &[TableRef],
+) -> Vec<String> {
+    let name = |r: &TableRef| r.1.clone().unwrap_or_else(|| r.0.clone());
+    let joined_key = table_key(&joined.0);
+    let mut predicates = Vec::new();
+    for fk in foreign_keys.into_iter().flatten() {
+        // The joined table may be either end of the key
+        let sides = [
+            (&fk.table, &fk.columns, &fk.ref_table, &fk.ref_columns),
+            (&fk.ref_table, &fk.ref_columns, &fk.table, &fk.columns),
+        ];
+        for (this, this_cols, other, other_cols) in sides {
+            if table_key(this) != joined_key {
+                continue;
+            }
+            for r in others
+                .iter()
+                .filter(|r| table_key(&r.0) == table_key(other))
+            {
+                let pairs: Vec<String> = other_cols
+                    .iter()
+                    .zip(this_cols)
+                    .map(|(oc, jc)| format!("{}.{} = {}.{}", name(r), oc, name(joined), jc))
+                    .collect();
+                predicates.push(pairs.join(" AND "));
+            }
+        }
     }
+    predicates
 }
 
-/// Extract the current word being typed at the cursor position.
-/// Scans backward from cursor to find the word start.
-fn extract_current_word(lines: &[String], row: usize, col: usize) -> String {
+/// Editor text from the start up to the cursor, at character column `col`.
+fn text_before(lines: &[String], row: usize, col: usize) -> String {
     if row >= lines.len() {
         return String::new();
     }
+    let mut before: String = lines[..row].iter().map(|l| format!("{}\n", l)).collect();
     let line = &lines[row];
-    let bytes = line.as_bytes();
-    let col = col.min(bytes.len());
-    let mut start = col;
-    while start > 0 {
-        let ch = bytes[start - 1];
-        if ch.is_ascii_alphanumeric() || ch == b'_' {
-            start -= 1;
+    before.push_str(&line[..byte_offset(line, col)]);
+    before
+}
+
+/// Byte offset of character column `col` in `line`, clamped to its end.
+fn byte_offset(line: &str, col: usize) -> usize {
+    line.char_indices().nth(col).map_or(line.len(), |(i, _)| i)
+}
+
+/// Collapse runs of whitespace to single spaces.
+fn normalize(sql: &str) -> String {
+    sql.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_phrase_end(word: &str) -> bool {
+    let word = word.trim_end_matches(';');
+    PHRASE_ENDS.iter().any(|e| e.eq_ignore_ascii_case(word))
+}
+
+/// The `SELECT` list or `WHERE` clause still being typed at the end of
+/// `statement`, from its keyword on, if the user has started its body.
+fn open_clause(statement: &str) -> Option<&str> {
+    let upper = statement.to_ascii_uppercase();
+    let start = PHRASE_CLAUSES
+        .iter()
+        .filter_map(|kw| {
+            upper.rmatch_indices(kw).find(|&(i, _)| {
+                let before_ok = upper[..i]
+                    .chars()
+                    .next_back()
+                    .is_none_or(char::is_whitespace);
+                let after_ok = upper[i + kw.len()..].starts_with(char::is_whitespace);
+                before_ok && after_ok
+            })
+        })
+        .map(|(i, _)| i)
+        .max()?;
+    let clause = &statement[start..];
+    let mut words = clause.split_whitespace();
+    words.next();
+    let body: Vec<&str> = words.collect();
+    (!body.is_empty() && !body.iter().any(|w| is_phrase_end(w))).then_some(clause)
+}
+
+/// `SELECT` lists and `WHERE` clauses in a query, whitespace-normalized.
+fn clause_phrases(sql: &str) -> Vec<String> {
+    let mut phrases = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+    for word in sql.split_whitespace() {
+        if let Some(ref mut words) = current {
+            if is_phrase_end(word) {
+                if words.len() > 1 {
+                    phrases.push(words.join(" "));
+                }
+                current = None;
+            } else if let Some(last) = word.strip_suffix(';') {
+                if !last.is_empty() {
+                    words.push(last);
+                }
+                phrases.push(words.join(" "));
+                current = None;
+                continue;
+            } else {
+                words.push(word);
+                continue;
+            }
+        }
+        if PHRASE_CLAUSES.iter().any(|c| c.eq_ignore_ascii_case(word)) {
+            current = Some(vec![word]);
+        }
+    }
+    if let Some(words) = current
+        && words.len() > 1
+    {
+        phrases.push(words.join(" "));
+    }
+    phrases
+}
+
+fn strs(list: &[String]) -> Vec<&str> {
+    list.iter().map(String::as_str).collect()
+}
+
+/// Normalize a table reference for the column cache: drop brackets and any
+/// schema/database qualifier, and ignore case.
+pub fn table_key(table: &str) -> String {
+    let name = table.rsplit('.').next().unwrap_or(table);
+    name.trim_matches(|c| c == '[' || c == ']')
+        .to_ascii_lowercase()
+}
+
+/// Clause keywords after which column names are expected.
+const COLUMN_CLAUSES: &[&str] = &["SELECT", "WHERE", "ON", "AND", "OR", "BY", "HAVING", "SET"];
+
+/// Keywords that introduce a table reference.
+const TABLE_CLAUSES: &[&str] = &["FROM", "JOIN", "UPDATE", "INTO"];
+
+/// Work out what kind of name belongs at the cursor.
+///
+/// Looks at the statement around the cursor (between `;` separators): the
+/// tokens before the word being typed pick the clause, and the table
+/// references anywhere in the statement give the columns in scope.
+pub fn context(lines: &[String], row: usize, col: usize) -> CompletionContext {
+    if row >= lines.len() {
+        return CompletionContext::Keyword;
+    }
+    let mut before = text_before(lines, row, col);
+    let cursor_line = &lines[row];
+    let word = extract_current_word(lines, row, col);
+    before.truncate(before.len() - word.len());
+    let mut after = cursor_line[byte_offset(cursor_line, col)..].to_string();
+    for l in &lines[row + 1..] {
+        after.push('\n');
+        after.push_str(l);
+    }
+
+    let before_stmt = before.rsplit(';').next().unwrap_or("");
+    let after_stmt = after.split(';').next().unwrap_or("");
+    let tokens = tokenize(before_stmt);
+    let statement_tables = || {
+        let mut all = tokens.clone();
+        all.push(word.clone());
+        all.extend(tokenize(after_stmt));
+        table_refs(&all)
+    };
+
+    // `alias.` or `table.` right before the word
+    if let Some(qualifier) = tokens.last().and_then(|t| t.strip_suffix('.')) {
+        let qualifier = table_key(qualifier);
+        let refs = statement_tables();
+        return match refs.iter().find(|(t, alias)| {
+            alias.as_deref().map(str::to_ascii_lowercase) == Some(qualifier.clone())
+                || table_key(t) == qualifier
+        }) {
+            Some((table, _)) => CompletionContext::Column {
+                tables: vec![table.clone()],
+                qualified: true,
+            },
+            // Probably a schema name, as in `dbo.`
+            None => match upper_at(&tokens, tokens.len().wrapping_sub(2)).as_deref() {
+                Some("EXEC" | "EXECUTE") => CompletionContext::Procedure,
+                _ => CompletionContext::Table,
+            },
+        };
+    }
+
+    let upper: Vec<String> = tokens.iter().map(|t| t.to_ascii_uppercase()).collect();
+    let last_clause = upper
+        .iter()
+        .rev()
+        .find(|t| COLUMN_CLAUSES.contains(&t.as_str()) || TABLE_CLAUSES.contains(&t.as_str()));
+    match upper.last().map(String::as_str) {
+        Some("EXEC" | "EXECUTE") => CompletionContext::Procedure,
+        Some("ON") => {
+            let mut others = table_refs(&tokens);
+            match others.pop() {
+                Some(joined) => CompletionContext::JoinOn {
+                    tables: statement_tables().into_iter().map(|(t, _)| t).collect(),
+                    joined,
+                    others,
+                },
+                None => CompletionContext::Keyword,
+            }
+        }
+        Some(t) if TABLE_CLAUSES.contains(&t) => CompletionContext::Table,
+        Some(",") if last_clause.is_some_and(|c| c == "FROM") => CompletionContext::Table,
+        _ if last_clause.is_some_and(|c| COLUMN_CLAUSES.contains(&c.as_str())) => {
+            CompletionContext::Column {
+                tables: statement_tables().into_iter().map(|(t, _)| t).collect(),
+                qualified: false,
+            }
+        }
+        _ => CompletionContext::Keyword,
+    }
+}
+
+fn upper_at(tokens: &[String], i: usize) -> Option<String> {
+    tokens.get(i).map(|t| t.to_ascii_uppercase())
+}
+
+/// Split SQL into words (keeping `.`-qualified names together) and single
+/// punctuation characters, skipping string literals and comments. A trailing
+/// `.` stays on its word, marking a qualifier.
+fn tokenize(sql: &str) -> Vec<String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == '\'' {
+            i += 1;
+            while i < chars.len() && chars[i] != '\'' {
+                i += 1;
+            }
+            i += 1;
+        } else if c == '-' && next == Some('-') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+        } else if c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '[') {
+            let start = i;
+            while i < chars.len() {
+                let ch = chars[i];
+                let word_char =
+                    ch.is_alphanumeric() || matches!(ch, '_' | '@' | '#' | '.' | '[' | ']');
+                if !(word_char || ch == ' ' && in_brackets(&chars[start..i])) {
+                    break;
+                }
+                i += 1;
+            }
+            tokens.push(chars[start..i].iter().collect());
         } else {
-            break;
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+            i += 1;
+        }
+    }
+    tokens
+}
+
+/// Whether a `[` in `word` is still open (spaces are allowed inside).
+fn in_brackets(word: &[char]) -> bool {
+    word.iter().filter(|&&c| c == '[').count() > word.iter().filter(|&&c| c == ']').count()
+}
+
+/// Table references in a token stream as (table, alias): names following
+/// `FROM`, `JOIN`, `UPDATE`, or `INTO`, and comma-separated `FROM` lists.
+fn table_refs(tokens: &[String]) -> Vec<TableRef> {
+    let is_keyword = |t: &str| {
+        let upper = t.to_ascii_uppercase();
+        SQL_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(&upper))
+    };
+    let is_name =
+        |t: &str| t.starts_with(|c: char| c.is_alphanumeric() || matches!(c, '_' | '#' | '['));
+
+    let mut refs = Vec::new();
+    let mut in_from = false;
+    let mut i = 0;
+    while i < tokens.len() {
+        let upper = tokens[i].to_ascii_uppercase();
+        let starts_ref = TABLE_CLAUSES.contains(&upper.as_str()) || (in_from && upper == ",");
+        if TABLE_CLAUSES.contains(&upper.as_str()) {
+            in_from = upper == "FROM";
+        } else if upper != "," && is_keyword(&upper) {
+            in_from = false;
+        }
+        i += 1;
+        if !starts_ref {
+            continue;
+        }
+        let Some(table) = tokens.get(i).filter(|t| is_name(t) && !is_keyword(t)) else {
+            continue;
+        };
+        i += 1;
+        let mut alias_at = i;
+        if tokens.get(i).is_some_and(|t| t.eq_ignore_ascii_case("AS")) {
+            alias_at += 1;
+        }
+        let alias = tokens
+            .get(alias_at)
+            .filter(|t| is_name(t) && !is_keyword(t))
+            .cloned();
+        if alias.is_some() {
+            i = alias_at + 1;
+        }
+        refs.push((table.clone(), alias));
+    }
+    refs
+}
+
+/// Extract the current word being typed at the cursor position (a
+/// character column). Scans backward from cursor to find the word start.
+fn extract_current_word(lines: &[String], row: usize, col: usize) -> String {
+    if row >= lines.len() {
+        return String::new();
+    }
+    let line = &lines[row];
+    let end = byte_offset(line, col);
+    let start = line[..end]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| c.is_alphanumeric() || c == '_')
+        .last()
+        .map_or(end, |(i, _)| i);
+    line[start..end].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn complete(ac: &mut Autocomplete, word: &str) -> Vec<String> {
+        ac.update(&[word.to_string()], 0, word.len());
+        ac.suggestions.iter().map(|s| s.text.clone()).collect()
+    }
+
+    #[test]
+    fn test_fuzzy_object_names() {
+        let mut ac = Autocomplete::default();
+        ac.set_objects(vec!["Customers".into(), "OrderDetails".into()], Vec::new());
+        assert_eq!(
+            complete(&mut ac, "ordt").first().map(String::as_str),
+            Some("OrderDetails")
+        );
+        assert!(complete(&mut ac, "zzq").is_empty());
+        assert!(!ac.active);
+    }
+
+    #[test]
+    fn test_recent_ranks_first() {
+        let mut ac = Autocomplete::default();
+        let before = complete(&mut ac, "se");
+        assert!(before.len() > 1);
+        ac.selected = 1;
+        assert_eq!(ac.accept().map(|s| s.text).as_ref(), Some(&before[1]));
+        assert_eq!(complete(&mut ac, "se")[0], before[1]);
+    }
+
+    fn ctx(sql: &str) -> CompletionContext {
+        let lines: Vec<String> = sql.lines().map(str::to_string).collect();
+        let row = lines.len() - 1;
+        context(&lines, row, lines[row].len())
+    }
+
+    fn columns(tables: &[&str], qualified: bool) -> CompletionContext {
+        CompletionContext::Column {
+            tables: tables.iter().map(|t| t.to_string()).collect(),
+            qualified,
         }
     }
-    line[start..col].to_string()
+
+    #[test]
+    fn test_context_by_clause() {
+        assert_eq!(ctx("SELECT * FROM Ord"), CompletionContext::Table);
+        assert_eq!(ctx("SELECT * FROM a JOIN "), CompletionContext::Table);
+        assert_eq!(ctx("SELECT * FROM a, b"), CompletionContext::Table);
+        assert_eq!(ctx("EXEC dbo.Get"), CompletionContext::Procedure);
+        assert_eq!(ctx("SELECT * FROM dbo.Ord"), CompletionContext::Table);
+        assert_eq!(ctx("EXEC Get"), CompletionContext::Procedure);
+        assert_eq!(ctx("SELECT * FROM Orders o WH"), CompletionContext::Keyword);
+        assert_eq!(
+            ctx("SELECT * FROM Orders o JOIN Customers AS c ON o.Id = c.Ord"),
+            columns(&["Customers"], true)
+        );
+        assert_eq!(
+            ctx("SELECT * FROM Orders WHERE Sta"),
+            columns(&["Orders"], false)
+        );
+    }
+
+    #[test]
+    fn test_context_sees_tables_after_cursor() {
+        let lines = vec!["SELECT Na FROM dbo.[Order Details] d".to_string()];
+        assert_eq!(
+            context(&lines, 0, 9),
+            columns(&["dbo.[Order Details]"], false)
+        );
+        assert_eq!(table_key("dbo.[Order Details]"), "order details");
+    }
+
+    #[test]
+    fn test_columns_for_context() {
+        let mut ac = Autocomplete::default();
+        ac.columns
+            .insert("orders".into(), vec!["OrderDate".into(), "Status".into()]);
+        assert_eq!(
+            complete(&mut ac, "SELECT * FROM Orders o WHERE o."),
+            vec!["Status", "OrderDate"]
+        );
+    }
+
+    #[test]
+    fn test_clause_phrases() {
+        assert_eq!(
+            clause_phrases(
+                "SELECT o.Id,\n  o.Total FROM Orders o\nWHERE o.Status = 'open' ORDER BY 1;"
+            ),
+            vec!["SELECT o.Id, o.Total", "WHERE o.Status = 'open'"]
+        );
+        assert_eq!(
+            clause_phrases("SELECT 1; SELECT * FROM t WHERE x = 2;"),
+            vec!["SELECT 1", "SELECT *", "WHERE x = 2"]
+        );
+    }
+
+    #[test]
+    fn test_history_phrases_rank_between_schema_and_keywords() {
+        let mut ac = Autocomplete::default();
+        ac.set_history(&["SELECT * FROM Orders WHERE Status = 'open' AND Total > 100".to_string()]);
+        ac.columns.insert("orders".into(), vec!["Status".into()]);
+        let typed = "SELECT * FROM Orders WHERE St";
+        let got = complete(&mut ac, typed);
+        assert_eq!(
+            got[..2],
+            ["Status", "WHERE Status = 'open' AND Total > 100"]
+        );
+        assert!(got.len() > 2, "keywords follow");
+        assert_eq!(ac.suggestions[1].replace, "WHERE St".len());
+    }
+
+    #[test]
+    fn test_join_predicates_from_foreign_keys() {
+        let mut ac = Autocomplete::default();
+        ac.foreign_keys.insert(
+            "orderitems".into(),
+            vec![ForeignKey {
+                table: "OrderItems".into(),
+                columns: vec!["OrderID".into()],
+                ref_table: "Orders".into(),
+                ref_columns: vec!["OrderID".into()],
+            }],
+        );
+        let sql = "SELECT * FROM dbo.Orders o JOIN OrderItems oi ON ";
+        let lines = vec![sql.to_string()];
+        assert!(matches!(
+            context(&lines, 0, sql.len()),
+            CompletionContext::JoinOn { ref joined, ref others, .. }
+                if joined.1.as_deref() == Some("oi") && others.len() == 1
+        ));
+        assert_eq!(complete(&mut ac, sql), vec!["o.OrderID = oi.OrderID"]);
+    }
+
+    #[test]
+    fn test_multibyte_text_before_cursor() {
+        let lines = vec!["SELECT 'é', café".to_string(), "FROM é".to_string()];
+        // Columns count characters, not bytes
+        assert_eq!(extract_current_word(&lines, 0, 16), "café");
+        assert_eq!(extract_current_word(&lines, 0, 15), "caf");
+        assert_eq!(extract_current_word(&lines, 0, 99), "café");
+        assert_eq!(text_before(&lines, 0, 10), "SELECT 'é'");
+        assert_eq!(text_before(&lines, 1, 6), "SELECT 'é', café\nFROM é");
+        let mut ac = Autocomplete::default();
+        ac.update(&lines, 1, 6);
+        ac.update(&lines, 0, 9);
+    }
 }
@@ -0,0 +1,127 @@
+//! Bracket pairing for the SQL editor: `(`/`)` and `[`/`]`, ignoring
+//! brackets inside string literals, quoted identifiers, and comments.
+
+use std::collections::HashMap;
+
+/// A (row, column) position in the editor, columns counted in chars.
+pub type Pos = (usize, usize);
+
+/// Bracket pairs and unbalanced brackets found in a buffer.
+#[derive(Debug, Default)]
+pub struct Brackets {
+    /// Each bracket mapped to its partner (both directions).
+    pub pairs: HashMap<Pos, Pos>,
+    /// Brackets with no partner, in buffer order.
+    pub unbalanced: Vec<Pos>,
+}
+
+impl Brackets {
+    /// The partner of the bracket at the cursor, or just before it.
+    /// Returns `(bracket, partner)`.
+    pub fn match_at(&self, cursor: Pos) -> Option<(Pos, Pos)> {
+        let before = cursor.1.checked_sub(1).map(|c| (cursor.0, c));
+        std::iter::once(cursor)
+            .chain(before)
+            .find_map(|p| self.pairs.get(&p).map(|&m| (p, m)))
+    }
+}
+
+/// Scan `lines` for bracket pairs.
+pub fn scan(lines: &[String]) -> Brackets {
+    enum State {
+        Code,
+        Str(char),
+        BlockComment,
+    }
+
+    let mut result = Brackets::default();
+    let mut stack: Vec<(char, Pos)> = Vec::new();
+    let mut state = State::Code;
+
+    for (row, line) in lines.iter().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut col = 0;
+        while col < chars.len() {
+            let c = chars[col];
+            let next = chars.get(col + 1).copied();
+            match state {
+                State::Str(quote) => {
+                    if c == quote {
+                        if next == Some(quote) {
+                            col += 1; // doubled quote escape
+                        } else {
+                            state = State::Code;
+                        }
+                    }
+                }
+                State::BlockComment => {
+                    if c == '*' && next == Some('/') {
+                        state = State::Code;
+                        col += 1;
+                    }
+                }
+                State::Code => match c {
+                    '\'' | '"' => state = State::Str(c),
+                    '-' if next == Some('-') => break,
+                    '/' if next == Some('*') => {
+                        state = State::BlockComment;
+                        col += 1;
+                    }
+                    '(' | '[' => stack.push((c, (row, col))),
+                    ')' | ']' => {
+                        let open = if c == ')' { '(' } else { '[' };
+                        match stack.last() {
+                            Some(&(o, pos)) if o == open => {
+                                stack.pop();
+                                result.pairs.insert(pos, (row, col));
+                                result.pairs.insert((row, col), pos);
+                            }
+                            _ => result.unbalanced.push((row, col)),
+                        }
+                    }
+                    _ => {}
+                },
+            }
+            col += 1;
+        }
+    }
+
+    result
+        .unbalanced
+        .extend(stack.into_iter().map(|(_, pos)| pos));
+    result.unbalanced.sort();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(s: &str) -> Vec<String> {
+        s.lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_nested_pairs_across_lines() {
+        let b = scan(&lines("SELECT (a + (b\n* [c]))"));
+        assert_eq!(b.pairs.get(&(0, 7)), Some(&(1, 6)));
+        assert_eq!(b.pairs.get(&(0, 12)), Some(&(1, 5)));
+        assert_eq!(b.pairs.get(&(1, 2)), Some(&(1, 4)));
+        assert!(b.unbalanced.is_empty());
+        // Cursor just after the closing paren still matches
+        assert_eq!(b.match_at((1, 7)), Some(((1, 6), (0, 7))));
+    }
+
+    #[test]
+    fn test_ignores_strings_and_comments() {
+        let b = scan(&lines("SELECT ')' -- (\n/* ( */ (1"));
+        assert!(b.pairs.is_empty());
+        assert_eq!(b.unbalanced, vec![(1, 8)]);
+    }
+
+    #[test]
+    fn test_mismatched_close() {
+        let b = scan(&lines("(a]"));
+        assert_eq!(b.unbalanced, vec![(0, 0), (0, 2)]);
+    }
+}
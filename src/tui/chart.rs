@@ -0,0 +1,231 @@
+//! Horizontal bar chart overlay for a numeric result column.
+
+use super::theme::Theme;
+use crate::app::ResultSet;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::prelude::*;
+use ratatui::widgets::{Bar, BarChart, BarGroup, Block, Borders, Clear, Paragraph};
+use std::cell::Cell;
+
+/// Resolution of bar lengths; values are scaled to `0..=SCALE`.
+const SCALE: u64 = 1000;
+
+/// A bar chart built from two columns of a result set.
+pub struct ChartView {
+    /// Overlay title.
+    pub title: String,
+    /// (label, value, value as displayed) for each charted row.
+    pub bars: Vec<(String, f64, String)>,
+    /// Rows skipped because the value was not numeric (e.g. NULL).
+    pub skipped: usize,
+    /// Error building the chart, shown instead of bars.
+    pub error: Option<String>,
+    /// First visible bar.
+    pub scroll: usize,
+    /// Inner height of the overlay from the last draw.
+    pub viewport_height: Cell<u16>,
+}
+
+impl ChartView {
+    /// Build a chart from the given label and value columns (names or 1-based indexes).
+    pub fn from_result(rs: Option<&ResultSet>, label_col: &str, value_col: &str) -> Self {
+        let mut view = Self {
+            title: format!("{} by {}", value_col, label_col),
+            bars: Vec::new(),
+            skipped: 0,
+            error: None,
+            scroll: 0,
+            viewport_height: Cell::new(0),
+        };
+        let Some(rs) = rs.filter(|rs| !rs.columns.is_empty()) else {
+            view.error = Some("No result set to chart. Run a query first.".to_string());
+            return view;
+        };
+        let (Some(label_idx), Some(value_idx)) = (
+            resolve_column(&rs.columns, label_col),
+            resolve_column(&rs.columns, value_col),
+        ) else {
+            view.error = Some(format!(
+                "Unknown column. Available: {}",
+                rs.columns.join(", ")
+            ));
+            return view;
+        };
+        view.title = format!("{} by {}", rs.columns[value_idx], rs.columns[label_idx]);
+
+        for row in &rs.rows {
+            let label = row.get(label_idx).cloned().unwrap_or_default();
+            let raw = row.get(value_idx).map(|s| s.as_str()).unwrap_or("");
+            match raw.trim().parse::<f64>() {
+                Ok(v) if v.is_finite() => view.bars.push((label, v, raw.to_string())),
+                _ => view.skipped += 1,
+            }
+        }
+        if view.bars.is_empty() {
+            view.error = Some(format!(
+                "Column '{}' has no numeric values",
+                rs.columns[value_idx]
+            ));
+        }
+        view
+    }
+
+    /// Handle a key press. Returns true when the chart should close.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let page = (self.viewport_height.get() as usize).max(1);
+        let max_scroll = self.bars.len().saturating_sub(page);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return true,
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => self.scroll = (self.scroll + 1).min(max_scroll),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(page),
+            KeyCode::PageDown => self.scroll = (self.scroll + page).min(max_scroll),
+            _ => {}
+        }
+        false
+    }
+}
+
+/// Find a column by case-insensitive name or 1-based position.
+fn resolve_column(columns: &[String], spec: &str) -> Option<usize> {
+    columns
+        .iter()
+        .position(|c| c.eq_ignore_ascii_case(spec))
+        .or_else(|| {
+            spec.parse::<usize>()
+                .ok()
+                .filter(|&n| n >= 1 && n <= columns.len())
+                .map(|n| n - 1)
+        })
+}
+
+/// Draw the chart overlay into the given area.
+pub fn draw(frame: &mut Frame, chart: &ChartView, area: Rect, theme: &Theme) {
+    frame.render_widget(Clear, area);
+
+    let skipped = if chart.skipped > 0 {
+        format!(", {} skipped", chart.skipped)
+    } else {
+        String::new()
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(
+            " 📊 {} — {} rows{} ",
+            chart.title,
+            chart.bars.len(),
+            skipped
+        ))
+        .title_bottom(" ↑/↓ Scroll │ Esc: Close ")
+        .border_style(Style::default().fg(theme.accent))
+        .style(Style::default().bg(theme.background));
+    let inner = block.inner(area);
+    chart.viewport_height.set(inner.height);
+
+    if let Some(ref err) = chart.error {
+        let msg = Paragraph::new(err.as_str())
+            .block(block)
+            .style(Style::default().fg(theme.muted));
+        frame.render_widget(msg, area);
+        return;
+    }
+
+    // Bars grow from zero; negative values render as empty bars with their value shown
+    let max = chart.bars.iter().map(|(_, v, _)| *v).fold(0.0f64, f64::max);
+    let bars: Vec<Bar> = chart
+        .bars
+        .iter()
+        .skip(chart.scroll)
+        .take(inner.height as usize)
+        .map(|(label, value, shown)| {
+            let scaled = if max > 0.0 {
+                (value.max(0.0) / max * SCALE as f64).round() as u64
+            } else {
+                0
+            };
+            Bar::default()
+                .label(Line::from(label.clone()))
+                .value(scaled)
+                .text_value(shown.clone())
+        })
+        .collect();
+
+    let barchart = BarChart::default()
+        .block(block)
+        .direction(Direction::Horizontal)
+        .bar_width(1)
+        .bar_gap(0)
+        .max(SCALE)
+        .bar_style(Style::default().fg(theme.accent))
+        .value_style(Style::default().fg(theme.on_accent).bg(theme.accent))
+        .label_style(Style::default().fg(theme.highlight))
+        .data(BarGroup::default().bars(&bars));
+    frame.render_widget(barchart, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result_set(columns: &[&str], rows: &[&[&str]]) -> ResultSet {
+        ResultSet {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: rows
+                .iter()
+                .map(|row| row.iter().map(|v| v.to_string()).collect())
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_from_result() {
+        let rs = result_set(
+            &["Day", "n"],
+            &[
+                &["mon", "3"],
+                &["tue", " 1.5 "],
+                &["wed", "NULL"],
+                &["thu", "x"],
+            ],
+        );
+        // Columns by name, any case, or by position
+        let view = ChartView::from_result(Some(&rs), "day", "2");
+        assert_eq!(view.error, None);
+        assert_eq!(view.title, "n by Day");
+        assert_eq!(
+            view.bars,
+            vec![
+                ("mon".to_string(), 3.0, "3".to_string()),
+                ("tue".to_string(), 1.5, " 1.5 ".to_string()),
+            ]
+        );
+        assert_eq!(view.skipped, 2);
+    }
+
+    #[test]
+    fn test_from_result_errors() {
+        let view = ChartView::from_result(None, "a", "b");
+        assert_eq!(
+            view.error.as_deref(),
+            Some("No result set to chart. Run a query first.")
+        );
+
+        let rs = result_set(&["day", "n"], &[&["mon", "inf"], &["tue", "NaN"]]);
+        let view = ChartView::from_result(Some(&rs), "day", "total");
+        assert_eq!(
+            view.error.as_deref(),
+            Some("Unknown column. Available: day, n")
+        );
+        let view = ChartView::from_result(Some(&rs), "day", "3");
+        assert!(view.error.is_some());
+
+        // Infinite and NaN values aren't charted
+        let view = ChartView::from_result(Some(&rs), "day", "n");
+        assert_eq!(
+            view.error.as_deref(),
+            Some("Column 'n' has no numeric values")
+        );
+        assert_eq!(view.skipped, 2);
+    }
+}
@@ -0,0 +1,314 @@
+//! Side-by-side comparison overlay for two result sets (`\compare`), with
+//! synchronized scrolling and differing cells highlighted.
+
+use super::theme::Theme;
+use crate::app::ResultSet;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, Row, Table};
+use std::cell::Cell;
+
+/// Widest a column is drawn, in characters.
+const MAX_COLUMN_WIDTH: usize = 30;
+
+/// One side of a comparison: a copy of a result set.
+pub struct CompareSide {
+    /// Pane title.
+    pub title: String,
+    /// Column names.
+    pub columns: Vec<String>,
+    /// Cell values, `None` for NULL.
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+impl CompareSide {
+    /// Copy `rs` as a side titled `title`.
+    pub fn new(title: impl Into<String>, rs: &ResultSet) -> Self {
+        Self {
+            title: title.into(),
+            columns: rs.columns.clone(),
+            rows: (0..rs.rows.len())
+                .map(|r| {
+                    rs.row_values(r)
+                        .into_iter()
+                        .map(|v| v.map(str::to_string))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// The cell at `row`, `col`: `None` past the end of the result,
+    /// `Some(None)` for NULL.
+    fn cell(&self, row: usize, col: usize) -> Option<Option<&str>> {
+        self.rows.get(row)?.get(col).map(|v| v.as_deref())
+    }
+}
+
+/// Two result sets shown side by side, compared cell by cell by position.
+pub struct CompareView {
+    pub left: CompareSide,
+    pub right: CompareSide,
+    /// Rows with at least one differing cell.
+    pub differing_rows: Vec<usize>,
+    /// Number of differing cells, including cells only one side has.
+    pub differing_cells: usize,
+    /// First visible row, shared by both panes.
+    pub scroll: usize,
+    /// First visible column, shared by both panes.
+    pub col_scroll: usize,
+    /// Inner height of a pane from the last draw.
+    pub viewport_height: Cell<u16>,
+}
+
+impl CompareView {
+    /// Compare `left` with `right`.
+    pub fn new(left: CompareSide, right: CompareSide) -> Self {
+        let mut view = Self {
+            left,
+            right,
+            differing_rows: Vec::new(),
+            differing_cells: 0,
+            scroll: 0,
+            col_scroll: 0,
+            viewport_height: Cell::new(0),
+        };
+        for row in 0..view.row_count() {
+            let cells = (0..view.column_count())
+                .filter(|&col| view.differs(row, col))
+                .count();
+            if cells > 0 {
+                view.differing_rows.push(row);
+                view.differing_cells += cells;
+            }
+        }
+        view
+    }
+
+    fn row_count(&self) -> usize {
+        self.left.rows.len().max(self.right.rows.len())
+    }
+
+    fn column_count(&self) -> usize {
+        self.left.columns.len().max(self.right.columns.len())
+    }
+
+    /// Whether the two sides differ at `row`, `col`.
+    pub fn differs(&self, row: usize, col: usize) -> bool {
+        self.left.cell(row, col) != self.right.cell(row, col)
+    }
+
+    /// Handle a key press. Returns true when the comparison should close.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let page = (self.viewport_height.get() as usize)
+            .saturating_sub(1)
+            .max(1);
+        let last_row = self.row_count().saturating_sub(1);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return true,
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => self.scroll = (self.scroll + 1).min(last_row),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(page),
+            KeyCode::PageDown => self.scroll = (self.scroll + page).min(last_row),
+            KeyCode::Home => self.scroll = 0,
+            KeyCode::End => self.scroll = last_row,
+            KeyCode::Left => self.col_scroll = self.col_scroll.saturating_sub(1),
+            KeyCode::Right => {
+                self.col_scroll = (self.col_scroll + 1).min(self.column_count().saturating_sub(1))
+            }
+            KeyCode::Char('n') => {
+                if let Some(&row) = self.differing_rows.iter().find(|&&r| r > self.scroll) {
+                    self.scroll = row;
+                }
+            }
+            KeyCode::Char('N') => {
+                if let Some(&row) = self.differing_rows.iter().rfind(|&&r| r < self.scroll) {
+                    self.scroll = row;
+                }
+            }
+            _ => {}
+        }
+        false
+    }
+}
+
+/// Draw the comparison overlay into the given area.
+pub fn draw(frame: &mut Frame, view: &CompareView, area: Rect, theme: &Theme) {
+    frame.render_widget(Clear, area);
+    let summary = if view.differing_cells == 0 {
+        "identical".to_string()
+    } else {
+        format!(
+            "{} differing cells in {} rows",
+            view.differing_cells,
+            view.differing_rows.len()
+        )
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" Compare — {} ", summary))
+        .title_bottom(" ↑/↓ ←/→ Scroll │ n/N: Next/prev difference │ Esc: Close ")
+        .border_style(Style::default().fg(theme.accent))
+        .style(Style::default().bg(theme.background));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Both panes use the wider of each column so they line up
+    let widths: Vec<u16> = (view.col_scroll..view.column_count())
+        .map(|col| {
+            let sides = [&view.left, &view.right];
+            let header = sides
+                .iter()
+                .filter_map(|s| s.columns.get(col))
+                .map(|c| c.len())
+                .max()
+                .unwrap_or(0);
+            let data = sides
+                .iter()
+                .flat_map(|s| s.rows.iter())
+                .filter_map(|row| row.get(col))
+                .map(|v| v.as_deref().map_or(4, str::len))
+                .max()
+                .unwrap_or(0);
+            header.max(data).min(MAX_COLUMN_WIDTH) as u16 + 1
+        })
+        .collect();
+
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(inner);
+    for (side, pane) in [(&view.left, panes[0]), (&view.right, panes[1])] {
+        draw_side(frame, view, side, &widths, pane, theme);
+    }
+}
+
+/// Draw one side of the comparison.
+fn draw_side(
+    frame: &mut Frame,
+    view: &CompareView,
+    side: &CompareSide,
+    widths: &[u16],
+    area: Rect,
+    theme: &Theme,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} — {} rows ", side.title, side.rows.len()))
+        .border_style(Style::default().fg(theme.muted));
+    let inner = block.inner(area);
+    view.viewport_height.set(inner.height);
+
+    let header = Row::new(
+        side.columns
+            .iter()
+            .skip(view.col_scroll)
+            .map(|c| Span::styled(c.clone(), Style::default().fg(theme.accent).bold())),
+    );
+    let rows: Vec<Row> = (view.scroll..view.row_count())
+        .take(inner.height as usize)
+        .map(|r| {
+            Row::new((view.col_scroll..side.columns.len()).map(|c| {
+                let span = match side.cell(r, c) {
+                    Some(Some(value)) => Span::raw(value.to_string()),
+                    Some(None) => Span::styled("NULL", Style::default().fg(theme.muted).italic()),
+                    None => Span::raw(""),
+                };
+                if view.differs(r, c) {
+                    span.style(Style::default().fg(theme.background).bg(theme.error))
+                } else {
+                    span
+                }
+            }))
+        })
+        .collect();
+    let constraints: Vec<Constraint> = widths.iter().map(|&w| Constraint::Length(w)).collect();
+    let table = Table::new(rows, constraints).header(header).block(block);
+    frame.render_widget(table, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn side(columns: &[&str], rows: &[&[Option<&str>]]) -> CompareSide {
+        CompareSide {
+            title: String::new(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: rows
+                .iter()
+                .map(|row| row.iter().map(|v| v.map(str::to_string)).collect())
+                .collect(),
+        }
+    }
+
+    fn press(view: &mut CompareView, c: char) {
+        view.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn test_compare_view_new() {
+        let view = CompareView::new(
+            side(&["a", "b"], &[&[Some("1"), Some("x")], &[Some("2"), None]]),
+            side(&["a", "b"], &[&[Some("1"), Some("x")], &[Some("2"), None]]),
+        );
+        assert!(view.differing_rows.is_empty());
+        assert_eq!(view.differing_cells, 0);
+
+        // The right side has an extra column and an extra row
+        let view = CompareView::new(
+            side(&["a"], &[&[Some("1")], &[Some("2")]]),
+            side(
+                &["a", "b"],
+                &[
+                    &[Some("1"), Some("x")],
+                    &[Some("2")],
+                    &[Some("3"), Some("y")],
+                ],
+            ),
+        );
+        assert_eq!(view.differing_rows, vec![0, 2]);
+        assert_eq!(view.differing_cells, 3);
+    }
+
+    #[test]
+    fn test_differs() {
+        let view = CompareView::new(
+            side(&["a", "b"], &[&[None, Some("")], &[Some("1")]]),
+            side(&["a", "b"], &[&[Some(""), Some("")], &[Some("1"), None]]),
+        );
+        // NULL and '' differ; '' and '' don't
+        assert!(view.differs(0, 0));
+        assert!(!view.differs(0, 1));
+        // A cell only one side has differs, even from NULL
+        assert!(!view.differs(1, 0));
+        assert!(view.differs(1, 1));
+        // Past both sides' ends nothing differs
+        assert!(!view.differs(5, 0));
+    }
+
+    #[test]
+    fn test_next_difference() {
+        let rows: Vec<Vec<Option<&str>>> = (0..6)
+            .map(|r| vec![Some(if r == 2 || r == 4 { "x" } else { "1" })])
+            .collect();
+        let rows: Vec<&[Option<&str>]> = rows.iter().map(Vec::as_slice).collect();
+        let same: [&[Option<&str>]; 6] = [&[Some("1")]; 6];
+        let mut view = CompareView::new(side(&["a"], &rows), side(&["a"], &same));
+        assert_eq!(view.differing_rows, vec![2, 4]);
+
+        press(&mut view, 'n');
+        assert_eq!(view.scroll, 2);
+        press(&mut view, 'n');
+        assert_eq!(view.scroll, 4);
+        // No difference further down: stays put
+        press(&mut view, 'n');
+        assert_eq!(view.scroll, 4);
+        press(&mut view, 'N');
+        assert_eq!(view.scroll, 2);
+        press(&mut view, 'N');
+        assert_eq!(view.scroll, 2);
+        assert!(view.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)));
+    }
+}
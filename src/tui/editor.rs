@@ -1,8 +1,12 @@
 //! SQL query editor pane with syntax highlighting.
 
+use super::brackets::{self, Pos};
+use super::vim::VimMode;
 use crate::app::{App, FocusPane};
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders};
+use tui_textarea::{CursorMove, TextArea};
+use unicode_width::UnicodeWidthChar;
 
 /// SQL keywords for basic syntax highlighting.
 const SQL_KEYWORDS: &[&str] = &[
@@ -16,24 +20,253 @@ const SQL_KEYWORDS: &[&str] = &[
 
 /// Draw the SQL editor pane.
 pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let focused = app.focus == FocusPane::Editor;
     let border_style = if focused {
-        Style::default().fg(Color::Cyan)
+        Style::default().fg(theme.accent)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme.muted)
     };
 
-    let block = Block::default()
+    let title = match app.file {
+        Some(ref file) => format!(
+            " SQL Editor — {}{} ",
+            file.path.display(),
+            if app.is_modified() { " [+]" } else { "" }
+        ),
+        None => " SQL Editor ".to_string(),
+    };
+    let mut block = Block::default()
         .borders(Borders::ALL)
-        .title(" SQL Editor ")
+        .title(title)
         .border_style(border_style);
+    if let Some(ref vim) = app.vim {
+        block = block.title(
+            Line::from(format!(" -- {} -- ", vim.mode_label()))
+                .right_aligned()
+                .style(Style::default().fg(theme.highlight)),
+        );
+        if vim.mode == VimMode::Command {
+            block = block.title_bottom(format!(":{}█", vim.command));
+        }
+    }
+
+    let brackets = brackets::scan(app.editor.lines());
+    if let Some(&(row, col)) = brackets.unbalanced.first() {
+        let c = app.editor.lines()[row].chars().nth(col).unwrap_or(' ');
+        block = block.title_bottom(
+            Line::from(format!(" unbalanced {} at {}:{} ", c, row + 1, col + 1))
+                .right_aligned()
+                .style(Style::default().fg(theme.error)),
+        );
+    }
 
     let inner = block.inner(area);
     frame.render_widget(block, area);
     frame.render_widget(&app.editor, inner);
+
+    if !focused {
+        return;
+    }
+    let matched = brackets.match_at(app.editor.cursor());
+    let bracket_style = Style::default().fg(theme.on_accent).bg(theme.highlight);
+    let unbalanced_style = Style::default().fg(theme.text).bg(theme.error);
+    let marks = matched
+        .into_iter()
+        .flat_map(|(a, b)| [(a, bracket_style), (b, bracket_style)])
+        .chain(brackets.unbalanced.iter().map(|&p| (p, unbalanced_style)));
+    for (pos, style) in marks {
+        if pos == app.editor.cursor() {
+            continue; // leave the cursor visible
+        }
+        if let Some((x, y)) = screen_pos(frame.buffer_mut(), app, inner, pos) {
+            frame.buffer_mut()[(x, y)].set_style(style);
+        }
+    }
+}
+
+/// Screen position of a buffer position inside the rendered editor.
+///
+/// tui-textarea keeps its scroll offset private, so this finds the rendered
+/// cursor cell and offsets from it; rows and columns scroll together for all
+/// lines, so the offset carries over.
+fn screen_pos(buf: &Buffer, app: &App, inner: Rect, pos: Pos) -> Option<(u16, u16)> {
+    let (cx, cy) = (inner.top()..inner.bottom())
+        .flat_map(|y| (inner.left()..inner.right()).map(move |x| (x, y)))
+        .find(|&(x, y)| buf[(x, y)].modifier.contains(Modifier::REVERSED))?;
+    let lines = app.editor.lines();
+    let (row, col) = app.editor.cursor();
+    let tab = app.editor.tab_length() as usize;
+    let x = cx as i64 + display_col(&lines[pos.0], pos.1, tab) as i64
+        - display_col(&lines[row], col, tab) as i64;
+    let y = cy as i64 + pos.0 as i64 - row as i64;
+    let inside = |v: i64, lo: u16, hi: u16| v >= lo as i64 && v < hi as i64;
+    (inside(x, inner.left(), inner.right()) && inside(y, inner.top(), inner.bottom()))
+        .then_some((x as u16, y as u16))
+}
+
+/// Display column of char index `col` in `line`, expanding tabs the way
+/// tui-textarea renders them.
+fn display_col(line: &str, col: usize, tab: usize) -> usize {
+    line.chars().take(col).fold(0, |w, c| {
+        if c == '\t' && tab > 0 {
+            w + tab - w % tab
+        } else if c == '\t' {
+            w
+        } else {
+            w + c.width().unwrap_or(0)
+        }
+    })
+}
+
+/// Rows covered by the selection, or the cursor row. A selection ending at
+/// the start of a line doesn't include that line.
+fn selected_rows(ta: &TextArea) -> (usize, usize) {
+    match ta.selection_range() {
+        Some(((first, _), (last, 0))) if last > first => (first, last - 1),
+        Some(((first, _), (last, _))) => (first, last),
+        None => (ta.cursor().0, ta.cursor().0),
+    }
+}
+
+/// Run a line edit with the selection lifted, then restore the cursor and
+/// selection shifted down by `shift` rows (negative is up). The yank
+/// register is left as it was.
+fn shifting_selection(ta: &mut TextArea, shift: isize, edit: impl FnOnce(&mut TextArea)) {
+    let cursor = ta.cursor();
+    let anchor = ta
+        .selection_range()
+        .map(|(a, b)| if a == cursor { b } else { a });
+    let yank = ta.yank_text();
+    ta.cancel_selection();
+    edit(ta);
+    ta.set_yank_text(yank);
+    // The start of the line after the last one is the end of the last one
+    let moved = |ta: &TextArea, (row, col): (usize, usize)| {
+        let row = row.saturating_add_signed(shift);
+        let last = ta.lines().len() - 1;
+        if row > last {
+            CursorMove::Jump(last as u16, ta.lines()[last].chars().count() as u16)
+        } else {
+            CursorMove::Jump(row as u16, col as u16)
+        }
+    };
+    if let Some(anchor) = anchor {
+        ta.move_cursor(moved(ta, anchor));
+        ta.start_selection();
+    }
+    ta.move_cursor(moved(ta, cursor));
+}
+
+/// Move the current line, or the selected lines, one line up or down
+/// (Alt+Up/Down).
+pub fn move_lines(ta: &mut TextArea, up: bool) {
+    let (first, last) = selected_rows(ta);
+    let lines = ta.lines();
+    if up && first == 0 || !up && last + 1 >= lines.len() {
+        return;
+    }
+    if up {
+        let prev = lines[first - 1].clone();
+        let last_len = lines[last].chars().count();
+        shifting_selection(ta, -1, |ta| {
+            ta.move_cursor(CursorMove::Jump(first as u16 - 1, 0));
+            ta.delete_str(prev.chars().count() + 1);
+            ta.move_cursor(CursorMove::Jump(last as u16 - 1, last_len as u16));
+            ta.insert_str(format!("\n{}", prev));
+        });
+    } else {
+        let next = lines[last + 1].clone();
+        let last_len = lines[last].chars().count();
+        shifting_selection(ta, 1, |ta| {
+            ta.move_cursor(CursorMove::Jump(last as u16, last_len as u16));
+            ta.delete_str(next.chars().count() + 1);
+            ta.move_cursor(CursorMove::Jump(first as u16, 0));
+            ta.insert_str(format!("{}\n", next));
+        });
+    }
+}
+
+/// Duplicate the current line, or the selected lines, below themselves and
+/// move to the copy (Alt+Shift+Down).
+pub fn duplicate_lines(ta: &mut TextArea) {
+    let (first, last) = selected_rows(ta);
+    let lines = ta.lines();
+    let text = lines[first..=last].join("\n");
+    let last_len = lines[last].chars().count();
+    let count = (last - first + 1) as isize;
+    shifting_selection(ta, count, |ta| {
+        ta.move_cursor(CursorMove::Jump(last as u16, last_len as u16));
+        ta.insert_str(format!("\n{}", text));
+    });
 }
 
 /// Check if a word is a SQL keyword (case-insensitive).
 pub fn is_sql_keyword(word: &str) -> bool {
     SQL_KEYWORDS.iter().any(|kw| kw.eq_ignore_ascii_case(word))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str) -> TextArea<'static> {
+        TextArea::from(text.lines().map(str::to_string))
+    }
+
+    #[test]
+    fn test_move_lines() {
+        let mut ta = editor("a\nb\nc");
+        ta.move_cursor(CursorMove::Jump(1, 1));
+        move_lines(&mut ta, true);
+        assert_eq!(ta.lines(), ["b", "a", "c"]);
+        assert_eq!(ta.cursor(), (0, 1));
+        // Already at the top
+        move_lines(&mut ta, true);
+        assert_eq!(ta.lines(), ["b", "a", "c"]);
+
+        move_lines(&mut ta, false);
+        move_lines(&mut ta, false);
+        assert_eq!(ta.lines(), ["a", "c", "b"]);
+        assert_eq!(ta.cursor(), (2, 1));
+        move_lines(&mut ta, false);
+        assert_eq!(ta.lines(), ["a", "c", "b"]);
+    }
+
+    #[test]
+    fn test_move_selected_lines() {
+        let mut ta = editor("a\nb\nc\nd");
+        ta.set_yank_text("kept");
+        // A selection ending at the start of a line leaves that line out
+        ta.move_cursor(CursorMove::Jump(1, 0));
+        ta.start_selection();
+        ta.move_cursor(CursorMove::Jump(3, 0));
+        move_lines(&mut ta, true);
+        assert_eq!(ta.lines(), ["b", "c", "a", "d"]);
+        assert_eq!(ta.selection_range(), Some(((0, 0), (2, 0))));
+
+        move_lines(&mut ta, false);
+        move_lines(&mut ta, false);
+        assert_eq!(ta.lines(), ["a", "d", "b", "c"]);
+        assert_eq!(ta.selection_range(), Some(((2, 0), (3, 1))));
+        move_lines(&mut ta, true);
+        assert_eq!(ta.lines(), ["a", "b", "c", "d"]);
+        assert_eq!(ta.yank_text(), "kept");
+    }
+
+    #[test]
+    fn test_duplicate_lines() {
+        let mut ta = editor("a\nb");
+        ta.move_cursor(CursorMove::Jump(0, 1));
+        duplicate_lines(&mut ta);
+        assert_eq!(ta.lines(), ["a", "a", "b"]);
+        assert_eq!(ta.cursor(), (1, 1));
+
+        ta.move_cursor(CursorMove::Jump(1, 0));
+        ta.start_selection();
+        ta.move_cursor(CursorMove::Jump(2, 1));
+        duplicate_lines(&mut ta);
+        assert_eq!(ta.lines(), ["a", "a", "b", "a", "b"]);
+        assert_eq!(ta.selection_range(), Some(((3, 0), (4, 1))));
+    }
+}
@@ -0,0 +1,212 @@
+//! Emacs-style key bindings for the SQL editor.
+//!
+//! tui-textarea's defaults already cover much of Emacs, but its motions drop
+//! the selection and it has no mark. This layer adds the mark (`Ctrl+Space`),
+//! region kill/copy, undo on `Ctrl+_`, and recentering on `Ctrl+L`; anything
+//! it doesn't handle goes to the default bindings. `Ctrl+D` and `Ctrl+L` reach
+//! it before the global keys while the editor has focus.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui_textarea::{CursorMove, Scrolling, TextArea};
+
+/// Handle an Emacs binding. Returns `false` if the key should go to the
+/// default editor bindings instead.
+pub fn handle_key(key: KeyEvent, ta: &mut TextArea) -> bool {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+    // Motions keep the region while the mark is set
+    let motion = match (ctrl, alt, key.code) {
+        (true, false, KeyCode::Char('f')) | (false, false, KeyCode::Right) => {
+            Some(CursorMove::Forward)
+        }
+        (true, false, KeyCode::Char('b')) | (false, false, KeyCode::Left) => Some(CursorMove::Back),
+        (true, false, KeyCode::Char('n')) | (false, false, KeyCode::Down) => Some(CursorMove::Down),
+        (true, false, KeyCode::Char('p')) | (false, false, KeyCode::Up) => Some(CursorMove::Up),
+        (true, false, KeyCode::Char('a')) | (false, false, KeyCode::Home) => Some(CursorMove::Head),
+        (true, false, KeyCode::Char('e')) | (false, false, KeyCode::End) => Some(CursorMove::End),
+        (false, true, KeyCode::Char('f')) => Some(CursorMove::WordForward),
+        (false, true, KeyCode::Char('b')) => Some(CursorMove::WordBack),
+        (false, true, KeyCode::Char('<')) => Some(CursorMove::Top),
+        (false, true, KeyCode::Char('>')) => Some(CursorMove::Bottom),
+        _ => None,
+    };
+    if let Some(motion) = motion {
+        ta.move_cursor(motion);
+        return true;
+    }
+
+    match (ctrl, alt, key.code) {
+        // Set mark
+        (true, false, KeyCode::Char(' ')) => {
+            ta.cancel_selection();
+            ta.start_selection();
+        }
+        // Quit: drop the mark
+        (true, false, KeyCode::Char('g')) => ta.cancel_selection(),
+        // Kill region, or the previous word when no mark is set
+        (true, false, KeyCode::Char('w')) => {
+            if ta.is_selecting() {
+                ta.cut();
+            } else {
+                ta.delete_word();
+            }
+        }
+        // Copy region
+        (false, true, KeyCode::Char('w')) => {
+            ta.copy();
+            ta.cancel_selection();
+        }
+        // Kill to end of line; at the end, join the next line
+        (true, false, KeyCode::Char('k')) => {
+            ta.cancel_selection();
+            if !ta.delete_line_by_end() {
+                ta.delete_next_char();
+            }
+        }
+        (true, false, KeyCode::Char('y')) => {
+            ta.cancel_selection();
+            ta.paste();
+        }
+        (true, false, KeyCode::Char('d')) => {
+            ta.cancel_selection();
+            ta.delete_next_char();
+        }
+        (true, false, KeyCode::Char('l')) => recenter(ta),
+        (false, true, KeyCode::Char('d')) => {
+            ta.cancel_selection();
+            ta.delete_next_word();
+        }
+        (false, true, KeyCode::Backspace) => {
+            ta.cancel_selection();
+            ta.delete_word();
+        }
+        // Undo: terminals send Ctrl+_ and Ctrl+/ as the same byte, which
+        // crossterm reports as Ctrl+7
+        (true, false, KeyCode::Char('_' | '/' | '7')) => {
+            ta.cancel_selection();
+            ta.undo();
+        }
+        (true, false, KeyCode::Char('v')) => ta.scroll(Scrolling::PageDown),
+        (false, true, KeyCode::Char('v')) => ta.scroll(Scrolling::PageUp),
+        _ => {
+            // Any other key deactivates the mark, as in Emacs
+            ta.cancel_selection();
+            return false;
+        }
+    }
+    true
+}
+
+/// Scroll so the cursor's line is in the middle of the editor, leaving the
+/// cursor where it is.
+fn recenter(ta: &mut TextArea) {
+    let (row, col) = ta.cursor();
+    // Scrolling clamps the cursor into view, so put the viewport's top on
+    // the cursor's line first, then back off half a page
+    ta.scroll((-i16::MAX, 0));
+    ta.scroll((row.min(i16::MAX as usize) as i16, 0));
+    ta.scroll(Scrolling::HalfPageUp);
+    ta.move_cursor(CursorMove::Jump(row as u16, col as u16));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str) -> TextArea<'static> {
+        TextArea::from(text.lines().map(str::to_string))
+    }
+
+    fn key(ta: &mut TextArea, modifiers: KeyModifiers, code: KeyCode) -> bool {
+        handle_key(KeyEvent::new(code, modifiers), ta)
+    }
+
+    fn ctrl(ta: &mut TextArea, c: char) -> bool {
+        key(ta, KeyModifiers::CONTROL, KeyCode::Char(c))
+    }
+
+    fn alt(ta: &mut TextArea, c: char) -> bool {
+        key(ta, KeyModifiers::ALT, KeyCode::Char(c))
+    }
+
+    #[test]
+    fn test_mark_and_region() {
+        let mut ta = editor("select id from users");
+        // Motions extend the region from the mark
+        assert!(ctrl(&mut ta, ' '));
+        assert!(alt(&mut ta, 'f'));
+        assert!(ta.is_selecting());
+        assert!(ctrl(&mut ta, 'w'));
+        assert_eq!(ta.lines()[0], "id from users");
+
+        ctrl(&mut ta, 'e');
+        assert!(ctrl(&mut ta, 'y'));
+        assert_eq!(ta.lines()[0], "id from usersselect ");
+
+        // Alt+W copies the region and drops the mark
+        ctrl(&mut ta, 'a');
+        ctrl(&mut ta, ' ');
+        ctrl(&mut ta, 'f');
+        ctrl(&mut ta, 'f');
+        ctrl(&mut ta, 'f');
+        assert!(alt(&mut ta, 'w'));
+        assert!(!ta.is_selecting());
+        assert_eq!(ta.yank_text(), "id ");
+        assert_eq!(ta.lines()[0], "id from usersselect ");
+
+        // Ctrl+G drops the mark; keys left to the editor drop it too
+        ctrl(&mut ta, ' ');
+        ctrl(&mut ta, 'g');
+        assert!(!ta.is_selecting());
+        ctrl(&mut ta, ' ');
+        assert!(!key(&mut ta, KeyModifiers::NONE, KeyCode::Char('x')));
+        assert!(!ta.is_selecting());
+    }
+
+    #[test]
+    fn test_kill_and_undo() {
+        let mut ta = editor("select id\nfrom users");
+        // Ctrl+K kills to the end of the line, then joins the next one
+        ctrl(&mut ta, 'f');
+        assert!(ctrl(&mut ta, 'k'));
+        assert_eq!(ta.lines(), ["s", "from users"]);
+        ctrl(&mut ta, 'k');
+        assert_eq!(ta.lines(), ["sfrom users"]);
+
+        assert!(ctrl(&mut ta, '_'));
+        assert_eq!(ta.lines(), ["s", "from users"]);
+
+        ctrl(&mut ta, 'n');
+        ctrl(&mut ta, 'a');
+        assert!(alt(&mut ta, 'd'));
+        assert_eq!(ta.lines()[1], " users");
+        assert!(ctrl(&mut ta, 'd'));
+        assert_eq!(ta.lines()[1], "users");
+    }
+
+    #[test]
+    fn test_recenter() {
+        use ratatui::buffer::Buffer;
+        use ratatui::layout::Rect;
+        use ratatui::widgets::Widget;
+
+        let text: Vec<String> = (0..50).map(|i| i.to_string()).collect();
+        let mut ta = editor(&text.join("\n"));
+        let area = Rect::new(0, 0, 10, 10);
+        // The first line drawn
+        let top = |ta: &TextArea| {
+            let mut buf = Buffer::empty(area);
+            ta.render(area, &mut buf);
+            buf.cell((0, 0)).unwrap().symbol().to_string() + buf.cell((1, 0)).unwrap().symbol()
+        };
+        ctrl(&mut ta, ' ');
+        key(&mut ta, KeyModifiers::ALT, KeyCode::Char('>'));
+        ctrl(&mut ta, 'p');
+        assert_eq!(top(&ta), "39");
+        assert!(ctrl(&mut ta, 'l'));
+        assert_eq!(ta.cursor(), (48, 0));
+        assert!(ta.is_selecting());
+        assert_eq!(top(&ta), "43");
+    }
+}
@@ -0,0 +1,65 @@
+//! Subsequence-based fuzzy matching shared by search and completion.
+
+/// Score `candidate` against `pattern` (case-insensitive subsequence match).
+///
+/// Returns `None` when the pattern isn't a subsequence of the candidate,
+/// otherwise a score (higher is better) and the matched char positions.
+/// Consecutive matches and matches at word starts are rewarded; gaps cost.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    if pattern.is_empty() {
+        return Some((0, Vec::new()));
+    }
+    let chars: Vec<char> = candidate.chars().collect();
+
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut score = 0i64;
+    let mut pi = 0;
+    let mut last_match: Option<usize> = None;
+    for (i, &c) in chars.iter().enumerate() {
+        if pi == pattern.len() {
+            break;
+        }
+        if c.to_lowercase().eq(std::iter::once(pattern[pi])) {
+            score += 1;
+            let prev = i.checked_sub(1).map(|p| chars[p]);
+            let word_start = match prev {
+                None => true,
+                Some(p) => !p.is_alphanumeric() || (p.is_lowercase() && c.is_uppercase()),
+            };
+            if word_start {
+                score += 8;
+            }
+            match last_match {
+                Some(last) if last + 1 == i => score += 5,
+                Some(last) => score -= (i - last - 1).min(5) as i64,
+                None => score -= i.min(10) as i64,
+            }
+            last_match = Some(i);
+            positions.push(i);
+            pi += 1;
+        }
+    }
+    (pi == pattern.len()).then_some((score, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subsequence_required() {
+        assert!(fuzzy_match("ordt", "OrderDetails").is_some());
+        assert!(fuzzy_match("xyz", "OrderDetails").is_none());
+        assert_eq!(fuzzy_match("", "anything"), Some((0, Vec::new())));
+    }
+
+    #[test]
+    fn test_prefers_word_starts_and_runs() {
+        let (tight, _) = fuzzy_match("od", "OrderDetails").unwrap();
+        let (loose, _) = fuzzy_match("od", "xxoxxxxxxxd").unwrap();
+        assert!(tight > loose);
+        let (_, pos) = fuzzy_match("sel", "SELECT 1").unwrap();
+        assert_eq!(pos, vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,166 @@
+//! Incremental fuzzy search over query history (Ctrl+R).
+
+use super::fuzzy;
+use super::theme::Theme;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+/// Outcome of a key press in the search overlay.
+pub enum SearchOutcome {
+    /// Keep searching.
+    Continue,
+    /// Close without loading anything.
+    Cancel,
+    /// Load this history entry into the editor.
+    Accept(String),
+    /// Star or unstar this history entry.
+    ToggleStar(String),
+}
+
+/// State of the reverse history search overlay.
+#[derive(Default)]
+pub struct HistorySearch {
+    /// Text typed so far.
+    pub query: String,
+    /// Matching history indexes with matched char positions, best first.
+    pub matches: Vec<(usize, Vec<usize>)>,
+    /// Selected match.
+    pub selected: usize,
+}
+
+impl HistorySearch {
+    /// Start a search over `history` (oldest first).
+    pub fn new(history: &[String]) -> Self {
+        let mut search = Self::default();
+        search.refresh(history);
+        search
+    }
+
+    /// Recompute matches: by fuzzy score, then most recent first.
+    fn refresh(&mut self, history: &[String]) {
+        let mut scored: Vec<(i64, usize, Vec<usize>)> = history
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| {
+                fuzzy::fuzzy_match(&self.query, entry).map(|(score, pos)| (score, i, pos))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+        // Drop repeats of the same query, keeping the best-ranked one
+        let mut seen = std::collections::HashSet::new();
+        self.matches = scored
+            .into_iter()
+            .filter(|(_, i, _)| seen.insert(history[*i].as_str()))
+            .map(|(_, i, pos)| (i, pos))
+            .collect();
+        self.selected = 0;
+    }
+
+    /// Handle a key press against `history`.
+    pub fn handle_key(&mut self, key: KeyEvent, history: &[String]) -> SearchOutcome {
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc) | (KeyModifiers::CONTROL, KeyCode::Char('g')) => {
+                return SearchOutcome::Cancel;
+            }
+            (_, KeyCode::Enter) => {
+                return match self.matches.get(self.selected) {
+                    Some((i, _)) => SearchOutcome::Accept(history[*i].clone()),
+                    None => SearchOutcome::Cancel,
+                };
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('s')) => {
+                if let Some((i, _)) = self.matches.get(self.selected) {
+                    return SearchOutcome::ToggleStar(history[*i].clone());
+                }
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('r')) | (_, KeyCode::Down) => {
+                self.selected = (self.selected + 1).min(self.matches.len().saturating_sub(1));
+            }
+            (_, KeyCode::Up) => self.selected = self.selected.saturating_sub(1),
+            (_, KeyCode::Backspace) => {
+                self.query.pop();
+                self.refresh(history);
+            }
+            (KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char(c)) => {
+                self.query.push(c);
+                self.refresh(history);
+            }
+            _ => {}
+        }
+        SearchOutcome::Continue
+    }
+}
+
+/// Draw the search overlay into the given area. Entries in `starred` are
+/// marked with a star.
+pub fn draw(
+    frame: &mut Frame,
+    search: &HistorySearch,
+    history: &[String],
+    starred: &[String],
+    area: Rect,
+    theme: &Theme,
+) {
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(
+            " (reverse-i-search) {} of {} ",
+            search.matches.len(),
+            history.len()
+        ))
+        .title_bottom(
+            " Type to filter │ ↑/↓ or Ctrl+R: Select │ Enter: Load │ Ctrl+S: Star │ Esc: Cancel ",
+        )
+        .border_style(Style::default().fg(theme.highlight))
+        .style(Style::default().bg(theme.background));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("search: ", Style::default().fg(theme.muted)),
+            Span::styled(
+                format!("{}█", search.query),
+                Style::default().fg(theme.text),
+            ),
+        ]),
+        Line::from(""),
+    ];
+
+    let height = inner.height.saturating_sub(2) as usize;
+    let first = search.selected.saturating_sub(height.saturating_sub(1));
+    for (row, (idx, positions)) in search.matches.iter().enumerate().skip(first).take(height) {
+        let entry = &history[*idx];
+        // Show multi-line queries on one line
+        let flat: Vec<char> = entry
+            .chars()
+            .map(|c| if c == '\n' { '⏎' } else { c })
+            .collect();
+        let selected = row == search.selected;
+        let base = if selected {
+            Style::default().fg(theme.on_accent).bg(theme.accent)
+        } else {
+            Style::default().fg(theme.text)
+        };
+        let marker = if starred.contains(entry) {
+            "★ "
+        } else {
+            "  "
+        };
+        let mut spans = vec![Span::styled(marker, base.fg(theme.highlight))];
+        spans.extend(flat.iter().enumerate().map(|(i, c)| {
+            let style = if positions.contains(&i) {
+                base.fg(theme.highlight).bold()
+            } else {
+                base
+            };
+            Span::styled(c.to_string(), style)
+        }));
+        lines.push(Line::from(spans));
+    }
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
@@ -0,0 +1,84 @@
+//! Classic offset / hex / ASCII dump for binary values.
+
+use ratatui::prelude::*;
+
+/// Bytes shown per dump line.
+pub const BYTES_PER_LINE: usize = 16;
+
+/// Decode a `0x`-prefixed hex string (as produced for binary columns).
+pub fn decode(value: &str) -> Option<Vec<u8>> {
+    let hex = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))?;
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Number of dump lines for `len` bytes.
+pub fn line_count(len: usize) -> usize {
+    len.div_ceil(BYTES_PER_LINE).max(1)
+}
+
+/// Render dump line `index` (each line covers `BYTES_PER_LINE` bytes).
+pub fn render_line(bytes: &[u8], index: usize) -> Line<'static> {
+    let start = index * BYTES_PER_LINE;
+    let chunk = &bytes[start.min(bytes.len())..(start + BYTES_PER_LINE).min(bytes.len())];
+
+    let mut hex = String::with_capacity(BYTES_PER_LINE * 3 + 1);
+    for i in 0..BYTES_PER_LINE {
+        if i == BYTES_PER_LINE / 2 {
+            hex.push(' ');
+        }
+        match chunk.get(i) {
+            Some(b) => hex.push_str(&format!("{:02x} ", b)),
+            None => hex.push_str("   "),
+        }
+    }
+    let ascii: String = chunk
+        .iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        })
+        .collect();
+
+    Line::from(vec![
+        Span::styled(
+            format!("{:08x}  ", start),
+            Style::default().fg(Color::DarkGray),
+        ),
+        Span::styled(hex, Style::default().fg(Color::Yellow)),
+        Span::styled(format!(" |{}|", ascii), Style::default().fg(Color::Green)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("0x00FFa1"), Some(vec![0x00, 0xff, 0xa1]));
+        assert_eq!(decode("0x"), Some(vec![]));
+        assert_eq!(decode("0xABC"), None);
+        assert_eq!(decode("ABCD"), None);
+    }
+
+    #[test]
+    fn test_render_line() {
+        let bytes = b"Hello, world!\x00\x01";
+        let line = render_line(bytes, 0).to_string();
+        assert!(line.starts_with("00000000  48 65 6c"));
+        assert!(line.ends_with("|Hello, world!..|"));
+        assert_eq!(line_count(bytes.len()), 1);
+        assert_eq!(line_count(17), 2);
+    }
+}
@@ -0,0 +1,424 @@
+//! Minimal JSON parser and pretty-printer with foldable containers.
+
+use ratatui::prelude::*;
+use std::collections::HashSet;
+
+/// A parsed JSON value. Numbers keep their source text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+/// How deeply arrays and objects may nest before parsing gives up, so a
+/// hostile value can't overflow the stack while parsing or rendering.
+const MAX_DEPTH: usize = 128;
+
+/// A rendered line, tagged with the container it opens (if any).
+pub struct JsonLine {
+    /// The highlighted display line.
+    pub line: Line<'static>,
+    /// Container id when this line opens an object/array (foldable).
+    pub fold_id: Option<usize>,
+}
+
+/// Parse a JSON document. Trailing non-whitespace is an error.
+pub fn parse(input: &str) -> Result<JsonValue, String> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_ws();
+    if parser.pos < parser.chars.len() {
+        return Err(format!("unexpected trailing data at {}", parser.pos));
+    }
+    Ok(value)
+}
+
+/// Pretty-print a value into highlighted lines, collapsing folded containers.
+pub fn render(value: &JsonValue, folded: &HashSet<usize>) -> Vec<JsonLine> {
+    let mut out = Vec::new();
+    let mut next_id = 0;
+    render_value(value, 0, None, false, folded, &mut next_id, true, &mut out);
+    out
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// Containers currently open.
+    depth: usize,
+}
+
+impl Parser {
+    fn skip_ws(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, ch: char) -> Result<(), String> {
+        self.skip_ws();
+        if self.peek() == Some(ch) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected '{}' at {}", ch, self.pos))
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: JsonValue) -> Result<JsonValue, String> {
+        let end = self.pos + word.chars().count();
+        if end <= self.chars.len() && self.chars[self.pos..end].iter().copied().eq(word.chars()) {
+            self.pos = end;
+            Ok(value)
+        } else {
+            Err(format!("invalid literal at {}", self.pos))
+        }
+    }
+
+    fn value(&mut self) -> Result<JsonValue, String> {
+        self.skip_ws();
+        match self.peek() {
+            Some('{') | Some('[') => {
+                if self.depth == MAX_DEPTH {
+                    return Err(format!("nested too deeply at {}", self.pos));
+                }
+                self.depth += 1;
+                let value = if self.peek() == Some('{') {
+                    self.object()
+                } else {
+                    self.array()
+                };
+                self.depth -= 1;
+                value
+            }
+            Some('"') => self.string().map(JsonValue::String),
+            Some('t') => self.literal("true", JsonValue::Bool(true)),
+            Some('f') => self.literal("false", JsonValue::Bool(false)),
+            Some('n') => self.literal("null", JsonValue::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            _ => Err(format!("unexpected character at {}", self.pos)),
+        }
+    }
+
+    fn object(&mut self) -> Result<JsonValue, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.skip_ws();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(JsonValue::Object(members));
+        }
+        loop {
+            self.skip_ws();
+            let key = self.string()?;
+            self.expect(':')?;
+            members.push((key, self.value()?));
+            self.skip_ws();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(members));
+                }
+                _ => return Err(format!("expected ',' or '}}' at {}", self.pos)),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<JsonValue, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_ws();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_ws();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(items));
+                }
+                _ => return Err(format!("expected ',' or ']' at {}", self.pos)),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.peek() != Some('"') {
+            return Err(format!("expected string at {}", self.pos));
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        while let Some(ch) = self.peek() {
+            self.pos += 1;
+            match ch {
+                '"' => return Ok(out),
+                '\\' => {
+                    let esc = self.peek().ok_or("unterminated escape")?;
+                    self.pos += 1;
+                    match esc {
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        'r' => out.push('\r'),
+                        'b' => out.push('\u{8}'),
+                        'f' => out.push('\u{c}'),
+                        'u' => out.push(self.unicode_escape()?),
+                        other => out.push(other),
+                    }
+                }
+                other => out.push(other),
+            }
+        }
+        Err("unterminated string".to_string())
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let end = self.pos + 4;
+        if end > self.chars.len() {
+            return Err("truncated \\u escape".to_string());
+        }
+        let hex: String = self.chars[self.pos..end].iter().collect();
+        self.pos = end;
+        u32::from_str_radix(&hex, 16).map_err(|e| e.to_string())
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high)
+            && self.chars.get(self.pos) == Some(&'\\')
+            && self.chars.get(self.pos + 1) == Some(&'u')
+        {
+            self.pos += 2;
+            let low = self.hex4()?;
+            0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF)
+        } else {
+            high
+        };
+        Ok(char::from_u32(code).unwrap_or('\u{FFFD}'))
+    }
+
+    fn number(&mut self) -> Result<JsonValue, String> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        if text.parse::<f64>().is_err() {
+            return Err(format!("invalid number at {}", start));
+        }
+        Ok(JsonValue::Number(text))
+    }
+}
+
+/// Escape a string for display as a JSON literal.
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn scalar_span(value: &JsonValue) -> Span<'static> {
+    match value {
+        JsonValue::Null => Span::styled("null", Style::default().fg(Color::Magenta)),
+        JsonValue::Bool(b) => Span::styled(b.to_string(), Style::default().fg(Color::Magenta)),
+        JsonValue::Number(n) => Span::styled(n.clone(), Style::default().fg(Color::Yellow)),
+        JsonValue::String(s) => Span::styled(quote(s), Style::default().fg(Color::Green)),
+        JsonValue::Array(_) | JsonValue::Object(_) => Span::raw(""),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_value(
+    value: &JsonValue,
+    depth: usize,
+    key: Option<&str>,
+    comma: bool,
+    folded: &HashSet<usize>,
+    next_id: &mut usize,
+    visible: bool,
+    out: &mut Vec<JsonLine>,
+) {
+    let mut prefix = vec![Span::raw("  ".repeat(depth))];
+    if let Some(k) = key {
+        prefix.push(Span::styled(quote(k), Style::default().fg(Color::Cyan)));
+        prefix.push(Span::raw(": "));
+    }
+    let trailer = if comma { "," } else { "" };
+
+    let (open, close, len) = match value {
+        JsonValue::Array(items) => ("[", "]", items.len()),
+        JsonValue::Object(members) => ("{", "}", members.len()),
+        scalar => {
+            if visible {
+                prefix.push(scalar_span(scalar));
+                prefix.push(Span::raw(trailer));
+                out.push(JsonLine {
+                    line: Line::from(prefix),
+                    fold_id: None,
+                });
+            }
+            return;
+        }
+    };
+
+    let id = *next_id;
+    *next_id += 1;
+    let is_folded = folded.contains(&id);
+
+    if visible {
+        let mut spans = prefix;
+        if len == 0 {
+            spans.push(Span::raw(format!("{}{}{}", open, close, trailer)));
+        } else if is_folded {
+            spans.push(Span::raw(open));
+            spans.push(Span::styled(
+                format!(" … {} item{} ", len, if len == 1 { "" } else { "s" }),
+                Style::default().fg(Color::DarkGray),
+            ));
+            spans.push(Span::raw(format!("{}{}", close, trailer)));
+        } else {
+            spans.push(Span::raw(open));
+        }
+        out.push(JsonLine {
+            line: Line::from(spans),
+            fold_id: (len > 0).then_some(id),
+        });
+    }
+
+    let children_visible = visible && !is_folded && len > 0;
+    match value {
+        JsonValue::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                let comma = i + 1 < len;
+                render_value(
+                    item,
+                    depth + 1,
+                    None,
+                    comma,
+                    folded,
+                    next_id,
+                    children_visible,
+                    out,
+                );
+            }
+        }
+        JsonValue::Object(members) => {
+            for (i, (k, v)) in members.iter().enumerate() {
+                let comma = i + 1 < len;
+                render_value(
+                    v,
+                    depth + 1,
+                    Some(k),
+                    comma,
+                    folded,
+                    next_id,
+                    children_visible,
+                    out,
+                );
+            }
+        }
+        _ => {}
+    }
+
+    if children_visible {
+        out.push(JsonLine {
+            line: Line::from(format!("{}{}{}", "  ".repeat(depth), close, trailer)),
+            fold_id: None,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nested() {
+        let v = parse(r#"{"a": [1, -2.5e3, true, null], "b": {"c": "x\"y"}}"#).unwrap();
+        assert_eq!(
+            v,
+            JsonValue::Object(vec![
+                (
+                    "a".to_string(),
+                    JsonValue::Array(vec![
+                        JsonValue::Number("1".to_string()),
+                        JsonValue::Number("-2.5e3".to_string()),
+                        JsonValue::Bool(true),
+                        JsonValue::Null,
+                    ])
+                ),
+                (
+                    "b".to_string(),
+                    JsonValue::Object(vec![(
+                        "c".to_string(),
+                        JsonValue::String("x\"y".to_string())
+                    )])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_unicode_escape() {
+        assert_eq!(
+            parse(r#""\u00e9\ud83d\ude00""#).unwrap(),
+            JsonValue::String("é😀".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_trailing_data() {
+        assert!(parse("{} x").is_err());
+        assert!(parse("[1,]").is_err());
+    }
+
+    #[test]
+    fn test_parse_depth_limit() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(parse(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(parse(&"[".repeat(100_000)).is_err());
+    }
+
+    #[test]
+    fn test_render_folding() {
+        let v = parse(r#"{"a": [1, 2], "b": 3}"#).unwrap();
+        assert_eq!(render(&v, &HashSet::new()).len(), 7);
+        let folded: HashSet<usize> = [1].into_iter().collect();
+        let lines = render(&v, &folded);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1].fold_id, Some(1));
+    }
+}
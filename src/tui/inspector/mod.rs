@@ -0,0 +1,417 @@
+//! Cell value inspector overlay with format-aware rendering.
+
+pub mod hex;
+pub mod json;
+pub mod sql;
+pub mod xml;
+
+use super::theme::Theme;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use std::cell::Cell;
+use std::collections::HashSet;
+
+/// What the inspector is showing.
+pub enum InspectorContent {
+    /// Plain text, one entry per line.
+    Text(Vec<String>),
+    /// A parsed JSON document with folded container ids.
+    Json {
+        value: json::JsonValue,
+        folded: HashSet<usize>,
+    },
+    /// Pretty-printed, highlighted XML.
+    Xml(Vec<Line<'static>>),
+    /// Decoded binary data shown as a hex dump.
+    Hex(Vec<u8>),
+    /// Highlighted SQL, e.g. a view or procedure definition.
+    Sql(Vec<Line<'static>>),
+}
+
+/// Outcome of a key press in the inspector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InspectorOutcome {
+    /// Keep the inspector open.
+    Continue,
+    /// Close the inspector.
+    Close,
+    /// Close and insert the raw text into the editor (SQL content only).
+    ToEditor,
+}
+
+impl InspectorContent {
+    /// Default file extension when saving this kind of content.
+    fn extension(&self) -> &'static str {
+        match self {
+            InspectorContent::Text(_) => "txt",
+            InspectorContent::Json { .. } => "json",
+            InspectorContent::Xml(_) => "xml",
+            InspectorContent::Hex(_) => "bin",
+            InspectorContent::Sql(_) => "sql",
+        }
+    }
+}
+
+/// State of the value inspector overlay.
+pub struct Inspector {
+    /// Overlay title (usually the column name and detected format).
+    pub title: String,
+    /// The content being inspected.
+    pub content: InspectorContent,
+    /// Column name the value came from.
+    pub column: String,
+    /// The original cell value, written verbatim on save (binary values are
+    /// written decoded).
+    pub raw: String,
+    /// File path being typed while the save prompt is open.
+    pub save_path: Option<String>,
+    /// A save target that already exists, while asking whether to replace it.
+    pub overwrite_path: Option<String>,
+    /// Result of the last save, shown in the footer.
+    pub status: Option<String>,
+    /// Selected line.
+    pub cursor: usize,
+    /// First visible line.
+    pub scroll: usize,
+    /// Inner height of the overlay from the last draw.
+    pub viewport_height: Cell<u16>,
+}
+
+impl Inspector {
+    /// Build an inspector for a cell value, detecting its format from the
+    /// column's SQL type and the value itself. Only a binary column's value
+    /// is decoded as hex; text that happens to start with `0x` stays text.
+    pub fn for_value(column: &str, column_type: &str, value: &str) -> Self {
+        if matches!(column_type, "binary" | "varbinary" | "image")
+            && let Some(bytes) = hex::decode(value)
+        {
+            let format = format!("{} bytes", bytes.len());
+            return Self::new(column, &format, value, InspectorContent::Hex(bytes));
+        }
+        let trimmed = value.trim_start();
+        if (trimmed.starts_with('{') || trimmed.starts_with('['))
+            && let Ok(parsed) = json::parse(value)
+        {
+            return Self::new(
+                column,
+                "JSON",
+                value,
+                InspectorContent::Json {
+                    value: parsed,
+                    folded: HashSet::new(),
+                },
+            );
+        }
+        if (column_type == "xml" || xml::looks_like_xml(value))
+            && let Ok(lines) = xml::render(value)
+        {
+            return Self::new(column, "XML", value, InspectorContent::Xml(lines));
+        }
+        Self::new(
+            column,
+            "",
+            value,
+            InspectorContent::Text(value.lines().map(|l| l.to_string()).collect()),
+        )
+    }
+
+    /// Build an inspector showing the SQL definition of object `name`.
+    pub fn for_definition(name: &str, definition: &str) -> Self {
+        Self::new(
+            name,
+            "definition",
+            definition,
+            InspectorContent::Sql(sql::render(definition)),
+        )
+    }
+
+    fn new(column: &str, format: &str, raw: &str, content: InspectorContent) -> Self {
+        let title = if format.is_empty() {
+            column.to_string()
+        } else {
+            format!("{} ({})", column, format)
+        };
+        Self {
+            title,
+            content,
+            column: column.to_string(),
+            raw: raw.to_string(),
+            save_path: None,
+            overwrite_path: None,
+            status: None,
+            cursor: 0,
+            scroll: 0,
+            viewport_height: Cell::new(0),
+        }
+    }
+
+    /// Write the raw value to `path`, recording the outcome in `status`. An
+    /// existing file is only replaced with `overwrite`; otherwise it's kept
+    /// and the user is asked.
+    fn save(&mut self, path: &str, overwrite: bool) {
+        let bytes = match &self.content {
+            InspectorContent::Hex(bytes) => bytes.as_slice(),
+            _ => self.raw.as_bytes(),
+        };
+        let written = if overwrite {
+            std::fs::write(path, bytes)
+        } else {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)
+                .and_then(|mut file| std::io::Write::write_all(&mut file, bytes))
+        };
+        let len = bytes.len();
+        self.status = match written {
+            Ok(()) => Some(format!("Saved {} bytes to {}", len, path)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                self.overwrite_path = Some(path.to_string());
+                None
+            }
+            Err(e) => Some(format!("Save failed: {}", e)),
+        };
+    }
+
+    /// Handle a key while asking whether to replace an existing file: `y`
+    /// replaces it, anything else keeps it.
+    fn handle_overwrite_key(&mut self, key: KeyEvent) {
+        let Some(path) = self.overwrite_path.take() else {
+            return;
+        };
+        if matches!(key.code, KeyCode::Char('y' | 'Y')) {
+            self.save(&path, true);
+        } else {
+            self.status = Some(format!("Not saved: {} exists", path));
+        }
+    }
+
+    /// Handle a key while the save prompt is open.
+    fn handle_save_key(&mut self, key: KeyEvent) {
+        let Some(ref mut path) = self.save_path else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.save_path = None,
+            KeyCode::Backspace => {
+                path.pop();
+            }
+            KeyCode::Char(c) => path.push(c),
+            KeyCode::Enter => {
+                let path = path.clone();
+                self.save_path = None;
+                if !path.trim().is_empty() {
+                    self.save(path.trim(), false);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Render the content into display lines with their fold ids.
+    /// Hex dumps are rendered lazily via `lines_in` instead.
+    fn lines(&self) -> Vec<(Line<'static>, Option<usize>)> {
+        match &self.content {
+            InspectorContent::Text(lines) => lines
+                .iter()
+                .map(|l| (Line::from(l.clone()), None))
+                .collect(),
+            InspectorContent::Json { value, folded } => json::render(value, folded)
+                .into_iter()
+                .map(|jl| (jl.line, jl.fold_id))
+                .collect(),
+            InspectorContent::Xml(lines) | InspectorContent::Sql(lines) => {
+                lines.iter().map(|l| (l.clone(), None)).collect()
+            }
+            InspectorContent::Hex(_) => Vec::new(),
+        }
+    }
+
+    /// Total number of display lines.
+    fn line_count(&self) -> usize {
+        match &self.content {
+            InspectorContent::Hex(bytes) => hex::line_count(bytes.len()),
+            _ => self.lines().len(),
+        }
+    }
+
+    /// Display lines `start..start + count` with their fold ids.
+    fn lines_in(&self, start: usize, count: usize) -> Vec<(Line<'static>, Option<usize>)> {
+        match &self.content {
+            InspectorContent::Hex(bytes) => (start..self.line_count().min(start + count))
+                .map(|i| (hex::render_line(bytes, i), None))
+                .collect(),
+            _ => self.lines().into_iter().skip(start).take(count).collect(),
+        }
+    }
+
+    /// Handle a key press.
+    pub fn handle_key(&mut self, key: KeyEvent) -> InspectorOutcome {
+        if self.overwrite_path.is_some() {
+            self.handle_overwrite_key(key);
+            return InspectorOutcome::Continue;
+        }
+        if self.save_path.is_some() {
+            self.handle_save_key(key);
+            return InspectorOutcome::Continue;
+        }
+        let page = (self.viewport_height.get() as usize).max(1);
+        let last = self.line_count().saturating_sub(1);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return InspectorOutcome::Close,
+            KeyCode::Char('e') if matches!(self.content, InspectorContent::Sql(_)) => {
+                return InspectorOutcome::ToEditor;
+            }
+            KeyCode::Char('s') => {
+                let stem: String = self
+                    .column
+                    .chars()
+                    .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                    .collect();
+                let stem = if stem.is_empty() { "value" } else { &stem };
+                self.save_path = Some(format!("{}.{}", stem, self.content.extension()));
+                self.status = None;
+            }
+            KeyCode::Up => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Down => self.cursor = (self.cursor + 1).min(last),
+            KeyCode::PageUp => self.cursor = self.cursor.saturating_sub(page),
+            KeyCode::PageDown => self.cursor = (self.cursor + page).min(last),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = last,
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                let fold_id = self.lines_in(self.cursor, 1).pop().and_then(|(_, id)| id);
+                if let (Some(id), InspectorContent::Json { folded, .. }) =
+                    (fold_id, &mut self.content)
+                    && !folded.remove(&id)
+                {
+                    folded.insert(id);
+                }
+            }
+            _ => {}
+        }
+        // Keep the cursor within the viewport
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if self.cursor >= self.scroll + page {
+            self.scroll = self.cursor + 1 - page;
+        }
+        InspectorOutcome::Continue
+    }
+}
+
+/// Draw the inspector overlay into the given area.
+pub fn draw(frame: &mut Frame, inspector: &Inspector, area: Rect, theme: &Theme) {
+    frame.render_widget(Clear, area);
+
+    let total = inspector.line_count();
+    let foldable = matches!(inspector.content, InspectorContent::Json { .. });
+    let hint = if let Some(ref path) = inspector.overwrite_path {
+        format!(
+            " {} exists. Overwrite? (y: Overwrite │ any other key: Cancel) ",
+            path
+        )
+    } else if let Some(ref path) = inspector.save_path {
+        format!(" Save to: {}█  (Enter: Save │ Esc: Cancel) ", path)
+    } else if let Some(ref status) = inspector.status {
+        format!(" {} ", status)
+    } else if foldable {
+        " ↑/↓ Move │ Enter: Fold │ s: Save │ Esc: Close ".to_string()
+    } else if matches!(inspector.content, InspectorContent::Sql(_)) {
+        " ↑/↓ Move │ e: Copy to Editor │ s: Save │ Esc: Close ".to_string()
+    } else {
+        " ↑/↓ Move │ s: Save │ Esc: Close ".to_string()
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(
+            " {} — line {}/{} ",
+            inspector.title,
+            (inspector.cursor + 1).min(total),
+            total
+        ))
+        .title_bottom(hint)
+        .border_style(Style::default().fg(theme.accent))
+        .style(Style::default().bg(theme.background));
+    let inner = block.inner(area);
+    inspector.viewport_height.set(inner.height);
+
+    let visible: Vec<Line> = inspector
+        .lines_in(inspector.scroll, inner.height as usize)
+        .into_iter()
+        .zip(inspector.scroll..)
+        .map(|((line, _), i)| {
+            if i == inspector.cursor {
+                line.style(Style::default().bg(theme.selection))
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(visible).block(block), area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn press(inspector: &mut Inspector, code: KeyCode) {
+        inspector.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    /// Save through the prompt to `path`.
+    fn save_to(inspector: &mut Inspector, path: &std::path::Path) {
+        inspector.save_path = Some(path.display().to_string());
+        press(inspector, KeyCode::Enter);
+    }
+
+    #[test]
+    fn test_for_value_binary_by_column_type() {
+        let inspector = Inspector::for_value("data", "varbinary", "0x4142");
+        assert!(matches!(inspector.content, InspectorContent::Hex(ref b) if b == b"AB"));
+        assert_eq!(inspector.title, "data (2 bytes)");
+        let inspector = Inspector::for_value("code", "nvarchar", "0x4142");
+        assert!(matches!(inspector.content, InspectorContent::Text(_)));
+        let inspector = Inspector::for_value("code", "", "0x4142");
+        assert!(matches!(inspector.content, InspectorContent::Text(_)));
+    }
+
+    #[test]
+    fn test_save_asks_before_overwriting() {
+        let path = std::env::temp_dir().join(format!("meow-inspector-{}.txt", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        let mut inspector = Inspector::for_value("note", "nvarchar", "first");
+        save_to(&mut inspector, &path);
+        assert!(inspector.overwrite_path.is_none());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first");
+
+        // Declining keeps the file
+        let mut inspector = Inspector::for_value("note", "nvarchar", "second");
+        save_to(&mut inspector, &path);
+        assert!(inspector.overwrite_path.is_some());
+        press(&mut inspector, KeyCode::Char('n'));
+        assert!(inspector.overwrite_path.is_none());
+        assert!(
+            inspector
+                .status
+                .as_deref()
+                .unwrap()
+                .starts_with("Not saved")
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first");
+
+        save_to(&mut inspector, &path);
+        press(&mut inspector, KeyCode::Char('y'));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+        assert!(
+            inspector
+                .status
+                .as_deref()
+                .unwrap()
+                .starts_with("Saved 6 bytes")
+        );
+        std::fs::remove_file(&path).ok();
+    }
+}
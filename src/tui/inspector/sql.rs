@@ -0,0 +1,111 @@
+//! Line-by-line SQL highlighting for object definitions.
+
+use crate::tui::editor;
+use ratatui::prelude::*;
+
+/// Highlight SQL text: keywords, string literals, numbers, and comments.
+/// Block comments may span lines.
+pub fn render(text: &str) -> Vec<Line<'static>> {
+    let keyword = Style::default()
+        .fg(Color::Blue)
+        .add_modifier(Modifier::BOLD);
+    let string = Style::default().fg(Color::Green);
+    let number = Style::default().fg(Color::Magenta);
+    let comment = Style::default().fg(Color::DarkGray);
+
+    let mut in_block_comment = false;
+    text.lines()
+        .map(|line| {
+            let chars: Vec<char> = line.chars().collect();
+            let mut spans: Vec<Span<'static>> = Vec::new();
+            let mut i = 0;
+            while i < chars.len() {
+                let start = i;
+                let c = chars[i];
+                let next = chars.get(i + 1).copied();
+                let style = if in_block_comment || (c == '/' && next == Some('*')) {
+                    i += if in_block_comment { 0 } else { 2 };
+                    in_block_comment = true;
+                    while i < chars.len() {
+                        if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                            i += 2;
+                            in_block_comment = false;
+                            break;
+                        }
+                        i += 1;
+                    }
+                    comment
+                } else if c == '-' && next == Some('-') {
+                    i = chars.len();
+                    comment
+                } else if c == '\'' || (matches!(c, 'N' | 'n') && next == Some('\'')) {
+                    i += if c == '\'' { 1 } else { 2 };
+                    while i < chars.len() {
+                        if chars[i] == '\'' {
+                            if chars.get(i + 1) == Some(&'\'') {
+                                i += 1; // doubled quote escape
+                            } else {
+                                i += 1;
+                                break;
+                            }
+                        }
+                        i += 1;
+                    }
+                    string
+                } else if c.is_alphabetic() || c == '_' || c == '@' || c == '#' {
+                    while i < chars.len()
+                        && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '@' | '#' | '$'))
+                    {
+                        i += 1;
+                    }
+                    let word: String = chars[start..i].iter().collect();
+                    if editor::is_sql_keyword(&word) {
+                        keyword
+                    } else {
+                        Style::default()
+                    }
+                } else if c.is_ascii_digit() {
+                    while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                        i += 1;
+                    }
+                    number
+                } else {
+                    i += 1;
+                    Style::default()
+                };
+                let text: String = chars[start..i].iter().collect();
+                spans.push(Span::styled(text, style));
+            }
+            Line::from(spans)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(line: &Line) -> Vec<String> {
+        line.spans.iter().map(|s| s.content.to_string()).collect()
+    }
+
+    #[test]
+    fn test_tokens() {
+        let lines = render("SELECT N'it''s', 42 -- done");
+        assert_eq!(
+            spans(&lines[0]),
+            vec!["SELECT", " ", "N'it''s'", ",", " ", "42", " ", "-- done"]
+        );
+        assert_eq!(lines[0].spans[0].style.fg, Some(Color::Blue));
+        assert_eq!(lines[0].spans[2].style.fg, Some(Color::Green));
+        assert_eq!(lines[0].spans[7].style.fg, Some(Color::DarkGray));
+    }
+
+    #[test]
+    fn test_block_comment_spans_lines() {
+        let lines = render("/* a\nb */ SELECT");
+        assert_eq!(spans(&lines[1]), vec!["b */", " ", "SELECT"]);
+        assert_eq!(lines[1].spans[0].style.fg, Some(Color::DarkGray));
+        assert_eq!(lines[1].spans[2].style.fg, Some(Color::Blue));
+    }
+}
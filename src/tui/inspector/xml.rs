@@ -0,0 +1,269 @@
+//! Lightweight XML tokenizer and indenting pretty-printer.
+
+use ratatui::prelude::*;
+
+/// A lexical XML token. Attribute text is kept verbatim.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// `<name attrs>` or `<name attrs/>`.
+    Open {
+        name: String,
+        attrs: Vec<(String, String)>,
+        self_closing: bool,
+    },
+    /// `</name>`.
+    Close(String),
+    /// Character data between tags.
+    Text(String),
+    /// `<!-- ... -->`.
+    Comment(String),
+    /// `<![CDATA[ ... ]]>`.
+    CData(String),
+    /// `<?...?>` or `<!DOCTYPE ...>`.
+    Decl(String),
+}
+
+/// Returns true if the value looks like an XML document or fragment.
+pub fn looks_like_xml(value: &str) -> bool {
+    let trimmed = value.trim();
+    trimmed.starts_with('<') && trimmed.ends_with('>') && tokenize(trimmed).is_ok()
+}
+
+/// Pretty-print XML into indented, highlighted lines.
+pub fn render(value: &str) -> Result<Vec<Line<'static>>, String> {
+    let tokens = tokenize(value.trim())?;
+    let mut out = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < tokens.len() {
+        let indent = Span::raw("  ".repeat(depth));
+        match &tokens[i] {
+            Token::Open {
+                name,
+                attrs,
+                self_closing,
+            } => {
+                let mut spans = vec![indent];
+                spans.extend(open_tag_spans(name, attrs, *self_closing));
+                // Collapse `<a>text</a>` onto one line
+                if !self_closing
+                    && let (Some(Token::Text(text)), Some(Token::Close(close))) =
+                        (tokens.get(i + 1), tokens.get(i + 2))
+                    && close == name
+                {
+                    spans.push(Span::raw(text.trim().to_string()));
+                    spans.extend(close_tag_spans(name));
+                    out.push(Line::from(spans));
+                    i += 3;
+                    continue;
+                }
+                out.push(Line::from(spans));
+                if !self_closing {
+                    depth += 1;
+                }
+            }
+            Token::Close(name) => {
+                depth = depth.saturating_sub(1);
+                let mut spans = vec![Span::raw("  ".repeat(depth))];
+                spans.extend(close_tag_spans(name));
+                out.push(Line::from(spans));
+            }
+            Token::Text(text) => {
+                for line in text.trim().lines() {
+                    out.push(Line::from(vec![
+                        Span::raw("  ".repeat(depth)),
+                        Span::raw(line.trim().to_string()),
+                    ]));
+                }
+            }
+            Token::Comment(c) => out.push(Line::from(vec![
+                indent,
+                Span::styled(
+                    format!("<!--{}-->", c),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])),
+            Token::CData(c) => out.push(Line::from(vec![
+                indent,
+                Span::styled("<![CDATA[", Style::default().fg(Color::DarkGray)),
+                Span::raw(c.clone()),
+                Span::styled("]]>", Style::default().fg(Color::DarkGray)),
+            ])),
+            Token::Decl(d) => out.push(Line::from(vec![
+                indent,
+                Span::styled(d.clone(), Style::default().fg(Color::Magenta)),
+            ])),
+        }
+        i += 1;
+    }
+    Ok(out)
+}
+
+fn open_tag_spans(
+    name: &str,
+    attrs: &[(String, String)],
+    self_closing: bool,
+) -> Vec<Span<'static>> {
+    let punct = Style::default().fg(Color::DarkGray);
+    let mut spans = vec![
+        Span::styled("<", punct),
+        Span::styled(name.to_string(), Style::default().fg(Color::Cyan)),
+    ];
+    for (attr, value) in attrs {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            attr.clone(),
+            Style::default().fg(Color::Yellow),
+        ));
+        spans.push(Span::styled("=", punct));
+        spans.push(Span::styled(
+            value.clone(),
+            Style::default().fg(Color::Green),
+        ));
+    }
+    spans.push(Span::styled(if self_closing { "/>" } else { ">" }, punct));
+    spans
+}
+
+fn close_tag_spans(name: &str) -> Vec<Span<'static>> {
+    let punct = Style::default().fg(Color::DarkGray);
+    vec![
+        Span::styled("</", punct),
+        Span::styled(name.to_string(), Style::default().fg(Color::Cyan)),
+        Span::styled(">", punct),
+    ]
+}
+
+/// Find `pat` in `s` starting at `from`, returning its absolute index.
+fn find_from(s: &str, from: usize, pat: &str) -> Result<usize, String> {
+    s[from..]
+        .find(pat)
+        .map(|i| from + i)
+        .ok_or_else(|| format!("unterminated markup at byte {}", from))
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < input.len() {
+        let rest = &input[pos..];
+        if !rest.starts_with('<') {
+            let end = rest.find('<').map(|i| pos + i).unwrap_or(input.len());
+            let text = &input[pos..end];
+            if !text.trim().is_empty() {
+                tokens.push(Token::Text(text.to_string()));
+            }
+            pos = end;
+        } else if rest.starts_with("<!--") {
+            let end = find_from(input, pos + 4, "-->")?;
+            tokens.push(Token::Comment(input[pos + 4..end].to_string()));
+            pos = end + 3;
+        } else if rest.starts_with("<![CDATA[") {
+            let end = find_from(input, pos + 9, "]]>")?;
+            tokens.push(Token::CData(input[pos + 9..end].to_string()));
+            pos = end + 3;
+        } else if rest.starts_with("<?") {
+            let end = find_from(input, pos + 2, "?>")?;
+            tokens.push(Token::Decl(input[pos..end + 2].to_string()));
+            pos = end + 2;
+        } else if rest.starts_with("<!") {
+            let end = find_from(input, pos + 2, ">")?;
+            tokens.push(Token::Decl(input[pos..end + 1].to_string()));
+            pos = end + 1;
+        } else if rest.starts_with("</") {
+            let end = find_from(input, pos + 2, ">")?;
+            tokens.push(Token::Close(input[pos + 2..end].trim().to_string()));
+            pos = end + 1;
+        } else {
+            let end = tag_end(input, pos)?;
+            tokens.push(parse_open_tag(&input[pos + 1..end])?);
+            pos = end + 1;
+        }
+    }
+    Ok(tokens)
+}
+
+/// Index of the `>` closing the tag at `start`, skipping quoted attribute values.
+fn tag_end(input: &str, start: usize) -> Result<usize, String> {
+    let mut quote: Option<char> = None;
+    for (i, ch) in input[start..].char_indices() {
+        match (quote, ch) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '>') => return Ok(start + i),
+            _ => {}
+        }
+    }
+    Err(format!("unterminated tag at byte {}", start))
+}
+
+/// Parse the inside of an opening tag (between `<` and `>`).
+fn parse_open_tag(inner: &str) -> Result<Token, String> {
+    let (inner, self_closing) = match inner.strip_suffix('/') {
+        Some(stripped) => (stripped, true),
+        None => (inner, false),
+    };
+    let name_end = inner
+        .find(|c: char| c.is_whitespace())
+        .unwrap_or(inner.len());
+    let name = inner[..name_end].to_string();
+    if name.is_empty() {
+        return Err("empty tag name".to_string());
+    }
+
+    let mut attrs = Vec::new();
+    let mut rest = inner[name_end..].trim_start();
+    while !rest.is_empty() {
+        let eq = rest
+            .find('=')
+            .ok_or_else(|| format!("attribute without value in <{}>", name))?;
+        let attr = rest[..eq].trim().to_string();
+        let after = rest[eq + 1..].trim_start();
+        let q = after
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+            .ok_or_else(|| format!("unquoted attribute in <{}>", name))?;
+        let close = after[1..]
+            .find(q)
+            .ok_or_else(|| format!("unterminated attribute in <{}>", name))?;
+        attrs.push((attr, after[..close + 2].to_string()));
+        rest = after[close + 2..].trim_start();
+    }
+    Ok(Token::Open {
+        name,
+        attrs,
+        self_closing,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_render_indents_nested_elements() {
+        let lines = render(r#"<a x="1"><b>hi</b><c/></a>"#).unwrap();
+        assert_eq!(
+            plain(&lines),
+            vec![r#"<a x="1">"#, "  <b>hi</b>", "  <c/>", "</a>"]
+        );
+    }
+
+    #[test]
+    fn test_quoted_gt_in_attribute() {
+        let lines = render(r#"<a x="1>2"/>"#).unwrap();
+        assert_eq!(plain(&lines), vec![r#"<a x="1>2"/>"#]);
+    }
+
+    #[test]
+    fn test_looks_like_xml() {
+        assert!(looks_like_xml("<root><!-- c --></root>"));
+        assert!(!looks_like_xml("<unterminated"));
+        assert!(!looks_like_xml("plain text"));
+    }
+}
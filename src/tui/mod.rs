@@ -1,16 +1,43 @@
 //! TUI setup, teardown, and main event loop.
 
 pub mod autocomplete;
+pub mod brackets;
+pub mod chart;
+pub mod compare;
 pub mod editor;
+pub mod emacs;
+pub mod fuzzy;
+pub mod history_search;
+pub mod inspector;
+pub mod picker;
+pub mod plan;
+pub mod prompt;
 pub mod results;
 pub mod sidebar;
+pub mod signature;
 pub mod statusbar;
+pub mod theme;
 pub mod ui;
+pub mod vim;
 
 use crate::Args;
-use crate::app::{App, FocusPane};
-use crate::commands;
+use crate::app::{
+    App, DiscardAction, FocusPane, KEPT_RESULTS, SIDEBAR_MAX_WIDTH, SIDEBAR_MIN_WIDTH, Suspend,
+};
+use crate::blocking;
+use crate::bookmarks;
+use crate::cli;
+use crate::commands::{self, CompareSource};
+use crate::config;
 use crate::db;
+use crate::display::DateStyle;
+use crate::history;
+use crate::params;
+use crate::scripts;
+use crate::snippets;
+use crate::state;
+use crate::timezone::Zone;
+use crate::variables;
 use crossterm::{
     event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
@@ -21,16 +48,73 @@ use std::io;
 
 /// Run the TUI application.
 pub async fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
-    let (host, port) = args.parse_server();
-    let user = args.user.as_deref().unwrap_or("sa");
-    let password = args.password.as_deref().unwrap_or("");
+    let login = args.login();
+
+    let saved = state::State::load();
 
-    // Connect to SQL Server
-    let mut client =
-        db::connect(&host, port, user, password, &args.database, args.trust_cert).await?;
+    // Connect to SQL Server, in the last session's database unless -d was
+    // given; fall back to master if that database is gone
+    let restored = match (&args.database, saved.database.as_deref()) {
+        (None, Some(last)) => db::connect(&login, last)
+            .await
+            .ok()
+            .map(|client| (client, last)),
+        _ => None,
+    };
+    let (mut client, database) = match restored {
+        Some(connected) => connected,
+        None => {
+            let database = args.database();
+            let client = db::connect(&login, database).await?;
+            (client, database)
+        }
+    };
 
     // Initialize app state
-    let mut app = App::new(&host, port, &args.database, user);
+    let mut app = App::new(&login.host, login.port, database, &login.user);
+    app.read_only_intent = login.read_only;
+    app.history = history::load();
+    app.autocomplete.set_history(&app.history);
+    app.bookmarks = bookmarks::load();
+    let settings = config::Settings::load();
+    app.set_keymap(settings.keymap);
+    app.sidebar_stats = settings.sidebar_stats;
+    app.ascii_icons = settings.ascii_icons;
+    if let Some(theme) = settings.theme.as_deref().and_then(theme::Theme::named) {
+        app.set_theme(theme);
+    }
+    if let Some(style) = settings.date_style.as_deref().and_then(DateStyle::parse) {
+        app.date_style = style;
+    }
+    if let Some(width) = settings.sidebar_width {
+        app.sidebar_width = width.clamp(SIDEBAR_MIN_WIDTH, SIDEBAR_MAX_WIDTH);
+    }
+    app.restore_state(&saved);
+    app.output_format = args.format().to_string();
+    if let Some(secs) = settings.health_interval {
+        app.health = Some(crate::app::HealthProbe::new(
+            std::time::Duration::from_secs(secs),
+        ));
+    }
+    match settings
+        .keepalive_interval
+        .unwrap_or(crate::app::KEEPALIVE_INTERVAL)
+    {
+        0 => {}
+        secs => {
+            app.keepalive = Some(crate::app::Keepalive::new(std::time::Duration::from_secs(
+                secs,
+            )))
+        }
+    }
+
+    if !args.no_meowrc
+        && let Some(path) = config::rc_file()
+    {
+        run_rc(&mut app, &mut client, &path).await;
+    }
+
+    app.session = db::query::fetch_session_info(&mut client).await.ok();
 
     // Load object tree
     app.load_objects(&mut client).await;
@@ -43,32 +127,197 @@ pub async fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
     let mut terminal = Terminal::new(backend)?;
 
     // Main event loop
-    let result = run_loop(&mut terminal, &mut app, &mut client).await;
+    let mut connections = Connections {
+        shared_database: app.current_database.clone(),
+        shared: client,
+        shared_session: None,
+        own: None,
+    };
+    let result = run_loop(&mut terminal, &mut app, &mut connections, &login).await;
 
     // Restore terminal
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
 
+    if let Err(e) = app.saved_state().save() {
+        eprintln!("Could not save display state: {}", e);
+    }
     result
 }
 
+/// Run the startup file's slash commands and SQL, as if typed in the editor.
+/// A failure is shown in the results pane and the rest still runs.
+async fn run_rc(app: &mut App, client: &mut db::ConnectionHandle, path: &std::path::Path) {
+    let text = match scripts::read(path) {
+        Ok(text) => text,
+        Err(e) => {
+            app.set_status(format!("{}: {}", path.display(), e));
+            return;
+        }
+    };
+    let mut failed = None;
+    for command in scripts::rc_commands(&text) {
+        // The terminal isn't set up yet, so there is nothing to redraw
+        let error = match execute_text(app, client, &command, &mut |_| {}).await {
+            Ok(_) => app.result.error.take(),
+            Err(e) => Some(e.to_string()),
+        };
+        if let Some(error) = error {
+            failed.get_or_insert(format!("{}: {}: {}", path.display(), command, error));
+        }
+    }
+    // Keep the first failure on screen rather than what ran after it
+    if let Some(error) = failed {
+        app.set_result(crate::app::QueryResult {
+            error: Some(error),
+            ..Default::default()
+        });
+    }
+}
+
+/// The main connection, shared by workspaces without their own, and the
+/// active workspace's own connection if it has one.
+struct Connections {
+    shared: db::ConnectionHandle,
+    own: Option<db::ConnectionHandle>,
+    /// Database the shared connection is in, as left by the last workspace
+    /// that used it.
+    shared_database: String,
+    /// Details of the shared connection while a workspace with its own
+    /// connection is active.
+    shared_session: Option<db::query::SessionInfo>,
+}
+
+impl Connections {
+    /// The connection queries in the active workspace run on.
+    fn active(&mut self) -> &mut db::ConnectionHandle {
+        self.own.as_mut().unwrap_or(&mut self.shared)
+    }
+}
+
+/// Check the active connection after it has sat idle, and reconnect to the
+/// current database if it has died. Open transactions are gone with it.
+async fn check_connection(app: &mut App, connections: &mut Connections, login: &db::Login) {
+    if let Some(ref mut keepalive) = app.keepalive {
+        keepalive.touch();
+    }
+    if db::query::ping(connections.active()).await.is_ok() {
+        return;
+    }
+    match db::connect(login, &app.current_database).await {
+        Ok(mut client) => {
+            let lost_transactions = app.session.as_ref().is_some_and(|s| s.transactions > 0);
+            apply_session_options(app, &mut client).await;
+            app.session = db::query::fetch_session_info(&mut client).await.ok();
+            *connections.active() = client;
+            if connections.own.is_none() {
+                connections.shared_database = app.current_database.clone();
+            }
+            app.notice = Some(format!(
+                "Connection was lost; reconnected to {}{}",
+                app.current_database,
+                if lost_transactions {
+                    " (open transactions were rolled back)"
+                } else {
+                    ""
+                }
+            ));
+        }
+        Err(e) => app.notice = Some(format!("Connection was lost; reconnecting failed: {}", e)),
+    }
+}
+
+/// Apply the `\set option` options to a new connection. One that fails is
+/// reported and the rest still apply.
+async fn apply_session_options(app: &mut App, client: &mut db::ConnectionHandle) {
+    for option in &app.session_options {
+        if let Err(e) = db::query::execute_query(client, &option.sql()).await {
+            app.notice = Some(format!("Could not apply {}: {}", option.sql(), e));
+        }
+    }
+}
+
 /// The main TUI event loop.
 async fn run_loop(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
-    client: &mut db::ConnectionHandle,
+    connections: &mut Connections,
+    login: &db::Login,
 ) -> Result<(), Box<dyn std::error::Error>> {
     loop {
         // Draw UI
         terminal.draw(|frame| ui::draw(frame, app))?;
 
+        if let Some(request) = app.workspace_request.take() {
+            handle_workspace(app, connections, login, request).await;
+            if let Some(script) = app.generated_script.take() {
+                app.load_history_entry(&script);
+            }
+            continue;
+        }
+        if app.keepalive.as_ref().is_some_and(|k| k.is_due()) {
+            check_connection(app, connections, login).await;
+            continue;
+        }
+        let client = connections.active();
+
+        // Sidebar expansion: the placeholder is on screen, now fetch, one
+        // node per pass so keys are handled between loads
+        if !app.pending_loads.is_empty() {
+            app.load_pending_node(client).await;
+        }
+        // Table stats load one database per pass, between key presses
+        if let Some(database) = app.database_needing_stats() {
+            app.load_table_stats(client, &database).await;
+        }
+        if app.watch.as_ref().is_some_and(|w| w.is_due()) {
+            run_watch(app, client).await;
+            continue;
+        }
+        if app.health.as_ref().is_some_and(|h| h.is_due()) {
+            let snapshot = db::query::fetch_health(client).await;
+            if let Some(ref mut health) = app.health {
+                health.last_run = Some(std::time::Instant::now());
+                match snapshot {
+                    Ok(snapshot) => health.snapshot = Some(snapshot),
+                    Err(_) => health.failed = true,
+                }
+            }
+        }
+        // Watches keep running in the background
+        if let Some(index) = app.background_watch_due() {
+            run_background_watch(app, connections, index).await;
+            continue;
+        }
+
         // Poll for events with a timeout so we can do async work
         if event::poll(std::time::Duration::from_millis(100))?
             && let Event::Key(key) = event::read()?
-            && handle_key(key, app, client).await?
+            && handle_key(key, app, client, &mut |app| {
+                terminal.draw(|frame| ui::draw(frame, app)).ok();
+            })
+            .await?
         {
-            break;
+            // Ask about unsaved changes one workspace at a time
+            let Some(index) = app.modified_workspace() else {
+                break;
+            };
+            switch_workspace(app, connections, index).await;
+            app.confirm_discard = Some(DiscardAction::Quit);
+        }
+
+        match app.suspend.take() {
+            Some(Suspend::Edit(text)) => match edit_externally(terminal, &text) {
+                Ok(edited) => app.load_history_entry(&edited),
+                Err(e) => app.set_status(format!("External editor failed: {}", e)),
+            },
+            Some(Suspend::Shell(command)) => {
+                if let Err(e) = run_shell(terminal, &command) {
+                    app.set_status(format!("Shell command failed: {}", e));
+                }
+            }
+            None => {}
         }
 
         if app.should_quit {
@@ -78,14 +327,486 @@ async fn run_loop(
     Ok(())
 }
 
-/// Handle a key event. Returns true if the app should exit.
+/// Open, switch, or close a workspace.
+async fn handle_workspace(
+    app: &mut App,
+    connections: &mut Connections,
+    login: &db::Login,
+    request: commands::WorkspaceCommand,
+) {
+    match request {
+        commands::WorkspaceCommand::List => {
+            app.set_result(crate::app::QueryResult::single(
+                ["#", "Name", "Database", "Connection", "Watch"]
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect(),
+                app.workspace_rows(),
+                0,
+            ));
+        }
+        commands::WorkspaceCommand::New { database: None } => {
+            let database = app.current_database.clone();
+            let index = app.add_workspace(&database, None);
+            switch_workspace(app, connections, index).await;
+        }
+        commands::WorkspaceCommand::New {
+            database: Some(database),
+        } => {
+            let mut client = match db::connect(login, &database).await {
+                Ok(client) => client,
+                Err(e) => {
+                    app.set_status(format!("Could not connect to {}: {}", database, e));
+                    return;
+                }
+            };
+            apply_session_options(app, &mut client).await;
+            let session = db::query::fetch_session_info(&mut client).await.ok();
+            let index = app.add_workspace(&database, Some((client, session)));
+            switch_workspace(app, connections, index).await;
+        }
+        commands::WorkspaceCommand::Switch(n) => {
+            if n > app.workspaces.len() {
+                app.set_status(format!("No workspace {}", n));
+            } else {
+                switch_workspace(app, connections, n - 1).await;
+            }
+        }
+        commands::WorkspaceCommand::Close => {
+            if app.workspaces.len() < 2 {
+                app.set_status("This is the only workspace");
+            } else if app.is_modified() {
+                app.set_status("Save the file (Ctrl+S) or clear the editor before closing");
+            } else {
+                // Dropping its own connection closes it
+                if connections.own.take().is_none() {
+                    connections.shared_database = app.current_database.clone();
+                    connections.shared_session = app.session.take();
+                }
+                app.close_workspace();
+                activate_connection(app, connections).await;
+            }
+        }
+    }
+}
+
+/// Show workspace `index`, parking the current workspace's connection and
+/// picking up the new one's.
+async fn switch_workspace(app: &mut App, connections: &mut Connections, index: usize) {
+    if index == app.active_workspace {
+        return;
+    }
+    let session = app.session.take();
+    match connections.own.take() {
+        Some(own) => {
+            let parked = &mut app.workspaces[app.active_workspace];
+            parked.connection = Some(own);
+            parked.session = session;
+        }
+        None => {
+            connections.shared_database = app.current_database.clone();
+            connections.shared_session = session;
+        }
+    }
+    app.switch_workspace(index);
+    activate_connection(app, connections).await;
+}
+
+/// Take the active workspace's own connection, or point the shared one at
+/// its database if another workspace moved it elsewhere.
+async fn activate_connection(app: &mut App, connections: &mut Connections) {
+    let active = &mut app.workspaces[app.active_workspace];
+    connections.own = active.connection.take();
+    app.session = match connections.own {
+        Some(_) => active.session.take(),
+        None => connections.shared_session.take(),
+    };
+    if connections.own.is_some() || connections.shared_database == app.current_database {
+        return;
+    }
+    let sql = format!("USE {}", commands::quote_ident(&app.current_database));
+    match db::query::execute_query(&mut connections.shared, &sql).await {
+        Ok(_) => connections.shared_database = app.current_database.clone(),
+        Err(e) => app.set_status(format!(
+            "Could not switch to {}: {}",
+            app.current_database, e
+        )),
+    }
+}
+
+/// Leave the alternate screen and raw mode, run `f`, then restore the TUI.
+/// The terminal is restored even if `f` fails.
+fn suspended<T>(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    f: impl FnOnce() -> io::Result<T>,
+) -> io::Result<T> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    let result = f();
+
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    terminal.clear()?;
+    result
+}
+
+/// Suspend the TUI, edit `text` in `$VISUAL`/`$EDITOR` through a temp file,
+/// and return the edited text.
+fn edit_externally(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    text: &str,
+) -> io::Result<String> {
+    let path = scripts::write_temp(text)?;
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+    // Allow arguments, e.g. EDITOR="code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+
+    let status = suspended(terminal, || {
+        std::process::Command::new(program)
+            .args(parts)
+            .arg(&path)
+            .status()
+    });
+
+    let edited = scripts::read(&path);
+    std::fs::remove_file(&path).ok();
+    let status = status?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {}",
+            program, status
+        )));
+    }
+    let edited = edited?;
+    Ok(edited.strip_suffix('\n').unwrap_or(&edited).to_string())
+}
+
+/// Suspend the TUI and run `command` in the user's shell (an interactive
+/// shell if empty), then wait for Enter so its output can be read.
+fn run_shell(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    command: &str,
+) -> io::Result<()> {
+    suspended(terminal, || {
+        let mut cmd = if cfg!(windows) {
+            let mut cmd = std::process::Command::new("cmd");
+            if !command.is_empty() {
+                cmd.args(["/C", command]);
+            }
+            cmd
+        } else {
+            let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+            let mut cmd = std::process::Command::new(shell);
+            if !command.is_empty() {
+                cmd.args(["-c", command]);
+            }
+            cmd
+        };
+        let status = cmd.status()?;
+        if !command.is_empty() {
+            if !status.success() {
+                println!("[{}]", status);
+            }
+            print!("Press Enter to return to meow...");
+            io::Write::flush(&mut io::stdout())?;
+            io::stdin().read_line(&mut String::new())?;
+        }
+        Ok(())
+    })
+}
+
+/// Handle a key event. Returns true if the app should exit. `redraw` draws
+/// the screen while a query is still fetching rows.
 async fn handle_key(
     key: KeyEvent,
     app: &mut App,
     client: &mut db::ConnectionHandle,
+    redraw: &mut dyn FnMut(&App),
 ) -> Result<bool, Box<dyn std::error::Error>> {
-    // Global keys
+    app.notice = None;
+    // Workspace keys come first so they don't stop a `\watch`
     match (key.modifiers, key.code) {
+        // Ctrl+T — new workspace on the shared connection
+        (KeyModifiers::CONTROL, KeyCode::Char('t')) => {
+            app.workspace_request = Some(commands::WorkspaceCommand::New { database: None });
+            return Ok(false);
+        }
+        // Alt+1..9 — switch workspace
+        (KeyModifiers::ALT, KeyCode::Char(c @ '1'..='9')) => {
+            let n = c.to_digit(10).unwrap_or(1) as usize;
+            app.workspace_request = Some(commands::WorkspaceCommand::Switch(n));
+            return Ok(false);
+        }
+        _ => {}
+    }
+
+    // Any key stops `\watch`, keeping the last result on screen
+    if app.watch.take().is_some() {
+        return Ok(false);
+    }
+
+    // The inspector overlay captures all keys while open
+    if let Some(ref mut inspector) = app.inspector {
+        match inspector.handle_key(key) {
+            inspector::InspectorOutcome::Continue => {}
+            inspector::InspectorOutcome::Close => app.inspector = None,
+            inspector::InspectorOutcome::ToEditor => {
+                let text = inspector.raw.clone();
+                app.inspector = None;
+                app.editor.insert_str(&text);
+                app.focus = FocusPane::Editor;
+            }
+        }
+        return Ok(false);
+    }
+
+    if let Some(ref mut chart) = app.chart {
+        if chart.handle_key(key) {
+            app.chart = None;
+        }
+        return Ok(false);
+    }
+
+    if let Some(ref mut view) = app.compare {
+        if view.handle_key(key) {
+            app.compare = None;
+        }
+        return Ok(false);
+    }
+
+    if let Some(ref mut view) = app.plan {
+        if view.handle_key(key) {
+            app.plan = None;
+        }
+        return Ok(false);
+    }
+
+    if let Some(ref mut search) = app.history_search {
+        match search.handle_key(key, &app.history) {
+            history_search::SearchOutcome::Continue => {}
+            history_search::SearchOutcome::Cancel => app.history_search = None,
+            history_search::SearchOutcome::Accept(text) => {
+                app.history_search = None;
+                app.load_history_entry(&text);
+            }
+            history_search::SearchOutcome::ToggleStar(text) => {
+                app.toggle_bookmark(&text);
+            }
+        }
+        return Ok(false);
+    }
+
+    if let Some(action) = app.confirm_discard.clone() {
+        match key.code {
+            KeyCode::Char('y' | 'Y') => {
+                app.confirm_discard = None;
+                if let Err(e) = app.save_file(None) {
+                    app.set_status(format!("Could not save: {}", e));
+                    return Ok(false);
+                }
+                return Ok(discard_and(app, action));
+            }
+            KeyCode::Char('n' | 'N') => {
+                app.confirm_discard = None;
+                return Ok(discard_and(app, action));
+            }
+            KeyCode::Esc => app.confirm_discard = None,
+            _ => {}
+        }
+        return Ok(false);
+    }
+
+    if let Some(ref mut prompt) = app.prompt {
+        // `gg`: a `g` in the empty go-to prompt jumps to the first row
+        if prompt.kind == prompt::PromptKind::GoToRow
+            && prompt.input.is_empty()
+            && key.code == KeyCode::Char('g')
+        {
+            app.prompt = None;
+            app.go_to_row(0);
+            return Ok(false);
+        }
+        match prompt.handle_key(key) {
+            prompt::PromptOutcome::Continue => {}
+            prompt::PromptOutcome::Cancel => {
+                // An empty search clears the highlights
+                if prompt.kind == prompt::PromptKind::ResultSearch && prompt.input.trim().is_empty()
+                {
+                    app.result_search = None;
+                }
+                app.prompt = None;
+            }
+            prompt::PromptOutcome::Submit(input) => {
+                let kind = prompt.kind.clone();
+                app.prompt = None;
+                match kind {
+                    prompt::PromptKind::SaveAs => save_editor(app, Some(input.as_ref())),
+                    prompt::PromptKind::ResultSearch => app.search_results(&input),
+                    prompt::PromptKind::GoToRow => match input.parse::<usize>() {
+                        Ok(row) => app.go_to_row(row.saturating_sub(1)),
+                        Err(_) => app.notice = Some(format!("Not a row number: {}", input)),
+                    },
+                    prompt::PromptKind::InsertTable => {
+                        // A table that doesn't exist yet has no such columns
+                        let columns = db::query::fetch_insert_columns(client, &input)
+                            .await
+                            .unwrap_or_default();
+                        if let Some(statements) = app.insert_statements(&input, &columns) {
+                            app.editor.insert_str(format!("{}\n", statements));
+                            app.marked_rows.clear();
+                            app.focus = FocusPane::Editor;
+                        }
+                    }
+                    prompt::PromptKind::Variable(name) => {
+                        app.set_status(format!("{} = {}", name, input));
+                        app.variables.insert(name, input);
+                        apply_timezone(app);
+                    }
+                    prompt::PromptKind::CellValue(edit) => {
+                        let update = app.cell_update(&edit, crate::app::parse_cell_text(&input));
+                        app.prompt = Some(prompt::Prompt::new(
+                            prompt::PromptKind::ConfirmUpdate(edit),
+                            "Run this UPDATE? (Enter: run, Esc: cancel)",
+                            &update,
+                        ));
+                    }
+                    prompt::PromptKind::ConfirmUpdate(edit) => {
+                        apply_cell_edit(app, client, &edit, &input).await
+                    }
+                    prompt::PromptKind::Parameter(mut form) => {
+                        let name = form.names[form.values.len()].clone();
+                        match params::parse_answer(&name, &input) {
+                            Ok(param) => {
+                                app.param_answers.insert(name.to_lowercase(), input);
+                                form.values.push(param);
+                                if form.values.len() < form.names.len() {
+                                    ask_parameter(app, form);
+                                } else {
+                                    let exec = params::sp_executesql(&form.sql, &form.values);
+                                    run_query(app, client, &exec, form.sql, redraw).await;
+                                }
+                            }
+                            Err(e) => {
+                                app.notice = Some(e);
+                                ask_parameter(app, form);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        return Ok(false);
+    }
+
+    if let Some(ref mut picker) = app.picker {
+        match picker.handle_key(key) {
+            picker::PickerOutcome::Continue => {}
+            picker::PickerOutcome::Cancel => app.picker = None,
+            picker::PickerOutcome::Accept(item) => {
+                let kind = picker.kind;
+                app.picker = None;
+                match kind {
+                    picker::PickerKind::Snippets => match snippets::load(&item.label) {
+                        Ok(body) => app.load_history_entry(&body),
+                        Err(e) => app.set_status(format!("Could not load '{}': {}", item.label, e)),
+                    },
+                    picker::PickerKind::QuickAccess => app.load_history_entry(&item.preview),
+                    picker::PickerKind::OpenFile => {
+                        let path = std::path::PathBuf::from(&item.label);
+                        if app.is_modified() {
+                            app.confirm_discard = Some(DiscardAction::Open(path));
+                        } else {
+                            open_in_editor(app, &path);
+                        }
+                    }
+                }
+            }
+            picker::PickerOutcome::Delete(item) => match picker.kind {
+                picker::PickerKind::Snippets => {
+                    snippets::delete(&item.label).ok();
+                    picker.set_items(snippet_items());
+                }
+                picker::PickerKind::OpenFile => {}
+                // Delete unstars; plain history entries stay put
+                picker::PickerKind::QuickAccess => {
+                    if app.bookmarks.contains(&item.preview) {
+                        app.toggle_bookmark(&item.preview);
+                        let items = quick_access_items(app);
+                        if let Some(ref mut picker) = app.picker {
+                            picker.set_items(items);
+                        }
+                    }
+                }
+            },
+        }
+        return Ok(false);
+    }
+
+    // Global keys. In the editor, the Emacs keymap's Ctrl+D and Ctrl+L
+    // (delete, recenter) take precedence.
+    let emacs_editor = app.keymap == config::Keymap::Emacs && app.focus == FocusPane::Editor;
+    match (key.modifiers, key.code) {
+        // Ctrl+R — reverse history search
+        (KeyModifiers::CONTROL, KeyCode::Char('r')) => {
+            app.autocomplete.dismiss();
+            app.history_search = Some(history_search::HistorySearch::new(&app.history));
+            return Ok(false);
+        }
+        // F2 — quick access to starred and recent queries
+        (_, KeyCode::F(2)) => {
+            app.autocomplete.dismiss();
+            app.picker = Some(picker::Picker::new(
+                picker::PickerKind::QuickAccess,
+                "Quick access",
+                quick_access_items(app),
+            ));
+            return Ok(false);
+        }
+        // Ctrl+O — open a .sql file
+        (KeyModifiers::CONTROL, KeyCode::Char('o')) => {
+            app.autocomplete.dismiss();
+            let items = scripts::find_sql_files(std::path::Path::new("."))
+                .into_iter()
+                .map(|path| {
+                    let preview = scripts::read(&path).unwrap_or_default();
+                    let label = path
+                        .strip_prefix(".")
+                        .unwrap_or(&path)
+                        .display()
+                        .to_string();
+                    picker::PickerItem { label, preview }
+                })
+                .collect();
+            app.picker = Some(
+                picker::Picker::new(picker::PickerKind::OpenFile, "Open .sql file", items)
+                    .with_free_text(),
+            );
+            return Ok(false);
+        }
+        // Ctrl+S — save the editor (asks for a path the first time)
+        (KeyModifiers::CONTROL, KeyCode::Char('s')) => {
+            if app.file.is_some() {
+                save_editor(app, None);
+            } else {
+                app.prompt = Some(prompt::Prompt::new(
+                    prompt::PromptKind::SaveAs,
+                    "Save as",
+                    "query.sql",
+                ));
+            }
+            return Ok(false);
+        }
+        // Alt+E — edit the buffer in $EDITOR
+        (KeyModifiers::ALT, KeyCode::Char('e')) => {
+            app.suspend = Some(Suspend::Edit(app.get_editor_text()));
+            return Ok(false);
+        }
         // Ctrl+Q — quit
         (KeyModifiers::CONTROL, KeyCode::Char('q')) => return Ok(true),
         // F1 — toggle help
@@ -99,12 +820,12 @@ async fn handle_key(
             return Ok(false);
         }
         // Ctrl+D — toggle sidebar
-        (KeyModifiers::CONTROL, KeyCode::Char('d')) => {
+        (KeyModifiers::CONTROL, KeyCode::Char('d')) if !emacs_editor => {
             app.toggle_sidebar();
             return Ok(false);
         }
         // Ctrl+L — clear editor
-        (KeyModifiers::CONTROL, KeyCode::Char('l')) => {
+        (KeyModifiers::CONTROL, KeyCode::Char('l')) if !emacs_editor => {
             app.clear_editor();
             return Ok(false);
         }
@@ -113,81 +834,7 @@ async fn handle_key(
             let sql = app.get_editor_text();
             if !sql.trim().is_empty() {
                 app.push_history();
-                // Check for slash commands
-                if let Some(cmd) = commands::parse(&sql) {
-                    let action = commands::to_action(
-                        &cmd,
-                        &app.connection_info,
-                        &app.current_database,
-                        &app.user,
-                    );
-                    match action {
-                        commands::CommandAction::ExecuteSql(query) => {
-                            app.query_running = true;
-                            match db::query::execute_query(client, &query).await {
-                                Ok(result) => {
-                                    // If it was a USE command, update current database
-                                    if let commands::SlashCommand::UseDatabase(ref db_name) = cmd {
-                                        app.current_database = db_name.clone();
-                                    }
-                                    app.result = result;
-                                    app.result_scroll = 0;
-                                    app.result_col_scroll = 0;
-                                    app.current_result_set = 0;
-                                }
-                                Err(e) => {
-                                    app.result = crate::app::QueryResult {
-                                        error: Some(e.to_string()),
-                                        ..Default::default()
-                                    };
-                                }
-                            }
-                            app.query_running = false;
-                        }
-                        commands::CommandAction::DisplayMessage { columns, rows } => {
-                            app.result = crate::app::QueryResult::single(columns, rows, 0);
-                            app.result_scroll = 0;
-                            app.result_col_scroll = 0;
-                            app.current_result_set = 0;
-                        }
-                        commands::CommandAction::ToggleExpanded => {
-                            app.expanded_mode = !app.expanded_mode;
-                            let state = if app.expanded_mode { "ON" } else { "OFF" };
-                            app.result = crate::app::QueryResult::single(
-                                vec!["Status".to_string()],
-                                vec![vec![format!("Expanded display is {}", state)]],
-                                0,
-                            );
-                        }
-                        commands::CommandAction::ToggleTiming => {
-                            app.show_timing = !app.show_timing;
-                            let state = if app.show_timing { "ON" } else { "OFF" };
-                            app.result = crate::app::QueryResult::single(
-                                vec!["Status".to_string()],
-                                vec![vec![format!("Timing is {}", state)]],
-                                0,
-                            );
-                        }
-                        commands::CommandAction::Quit => return Ok(true),
-                    }
-                } else {
-                    app.query_running = true;
-                    match db::query::execute_query(client, &sql).await {
-                        Ok(result) => {
-                            app.result = result;
-                            app.result_scroll = 0;
-                            app.result_col_scroll = 0;
-                            app.current_result_set = 0;
-                        }
-                        Err(e) => {
-                            app.result = crate::app::QueryResult {
-                                error: Some(e.to_string()),
-                                ..Default::default()
-                            };
-                        }
-                    }
-                    app.query_running = false;
-                }
+                return execute_text(app, client, &sql, redraw).await;
             }
             return Ok(false);
         }
@@ -195,8 +842,23 @@ async fn handle_key(
     }
 
     // Pane-specific keys
+    let shift = key.modifiers.contains(KeyModifiers::SHIFT);
     match app.focus {
         FocusPane::Editor => {
+            // Alt+Up/Down move lines, Alt+Shift+Down duplicates them
+            if key.modifiers.contains(KeyModifiers::ALT)
+                && matches!(key.code, KeyCode::Up | KeyCode::Down)
+            {
+                app.autocomplete.dismiss();
+                if key.modifiers.contains(KeyModifiers::SHIFT) {
+                    if key.code == KeyCode::Down {
+                        editor::duplicate_lines(&mut app.editor);
+                    }
+                } else {
+                    editor::move_lines(&mut app.editor, key.code == KeyCode::Up);
+                }
+                return Ok(false);
+            }
             // If autocomplete is active, intercept navigation keys
             if app.autocomplete.active {
                 match key.code {
@@ -214,20 +876,18 @@ async fn handle_key(
                     }
                     KeyCode::Tab | KeyCode::Enter => {
                         // Accept selected suggestion
-                        if let Some(keyword) = app.autocomplete.selected_keyword() {
-                            let prefix_len = app.autocomplete.prefix.len();
-                            // Delete the prefix characters by sending backspaces
-                            for _ in 0..prefix_len {
+                        if let Some(suggestion) = app.autocomplete.accept() {
+                            // Delete the typed word (or clause) by sending backspaces
+                            for _ in 0..suggestion.replace {
                                 app.editor
                                     .input(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE));
                             }
-                            // Insert the keyword character by character
-                            for ch in keyword.chars() {
+                            // Insert the suggestion character by character
+                            for ch in suggestion.text.chars() {
                                 app.editor
                                     .input(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE));
                             }
                         }
-                        app.autocomplete.dismiss();
                         return Ok(false);
                     }
                     _ => {
@@ -235,18 +895,113 @@ async fn handle_key(
                     }
                 }
             }
+            if let Some(ref mut vim) = app.vim {
+                match vim.handle_key(key, &mut app.editor) {
+                    vim::VimOutcome::Insert => {}
+                    vim::VimOutcome::Consumed => {
+                        app.autocomplete.dismiss();
+                        return Ok(false);
+                    }
+                    vim::VimOutcome::Command(cmd) => {
+                        app.autocomplete.dismiss();
+                        return run_vim_command(app, client, &cmd, redraw).await;
+                    }
+                }
+            }
             // Let tui-textarea handle input
-            app.editor.input(key);
+            if !(app.keymap == config::Keymap::Emacs && emacs::handle_key(key, &mut app.editor)) {
+                app.editor.input(key);
+            }
             // Update autocomplete after keystroke
-            let cursor = app.editor.cursor();
-            let lines: Vec<String> = app.editor.lines().iter().map(|s| s.to_string()).collect();
-            app.autocomplete.update(&lines, cursor.0, cursor.1);
+            app.update_completions(client).await;
+            app.update_signature(client).await;
         }
+        FocusPane::Results if app.expanded_mode => match key.code {
+            KeyCode::Up => app.scroll_results_up(),
+            KeyCode::Down => {
+                let (_, total_lines) = results::expanded_record_starts(app);
+                app.scroll_expanded_down(total_lines);
+            }
+            KeyCode::Char('n') | KeyCode::PageDown => {
+                let (starts, _) = results::expanded_record_starts(app);
+                app.next_record(&starts);
+            }
+            KeyCode::Char('p') | KeyCode::PageUp => {
+                let (starts, _) = results::expanded_record_starts(app);
+                app.prev_record(&starts);
+            }
+            KeyCode::Char('[') => app.prev_result_set(),
+            KeyCode::Char(']') => app.next_result_set(),
+            _ => {}
+        },
         FocusPane::Results => match key.code {
+            KeyCode::Left if shift => app.page_results_left(),
+            KeyCode::Right if shift => app.page_results_right(),
+            KeyCode::Home if shift => app.results_column_edge(false),
+            KeyCode::End if shift => app.results_column_edge(true),
             KeyCode::Up => app.scroll_results_up(),
             KeyCode::Down => app.scroll_results_down(),
             KeyCode::Left => app.scroll_results_left(),
             KeyCode::Right => app.scroll_results_right(),
+            KeyCode::PageDown => app.page_results_down(),
+            KeyCode::PageUp => app.page_results_up(),
+            KeyCode::Home => app.go_to_row(0),
+            KeyCode::End => app.go_to_row(usize::MAX),
+            KeyCode::Enter => app.inspect_focused_cell(),
+            KeyCode::Char('a') => app.show_column_stats = !app.show_column_stats,
+            KeyCode::Char('e') => app.focused_cell_to_editor(),
+            KeyCode::Char(' ') => app.toggle_marked_row(),
+            KeyCode::Char('/') => {
+                app.prompt = Some(prompt::Prompt::new(
+                    prompt::PromptKind::ResultSearch,
+                    "Search results (empty clears)",
+                    "",
+                ))
+            }
+            KeyCode::Char('n') => app.next_result_match(true),
+            KeyCode::Char('N') => app.next_result_match(false),
+            KeyCode::Char('<') => app.move_focused_column(false),
+            KeyCode::Char('>') => app.move_focused_column(true),
+            KeyCode::Char(':') | KeyCode::Char('g') => {
+                app.prompt = Some(prompt::Prompt::new(
+                    prompt::PromptKind::GoToRow,
+                    "Go to row (g: first)",
+                    "",
+                ))
+            }
+            KeyCode::Char('G') => app.go_to_row(usize::MAX),
+            KeyCode::Char('u') => start_cell_edit(app, client).await,
+            KeyCode::Char('i') if app.focused_cell().is_some() => {
+                let table = app
+                    .result
+                    .sql
+                    .as_deref()
+                    .and_then(crate::dml::source_table)
+                    .unwrap_or_default();
+                app.prompt = Some(prompt::Prompt::new(
+                    prompt::PromptKind::InsertTable,
+                    "INSERT into table",
+                    &table,
+                ));
+            }
+            KeyCode::Char('s') if app.blocking_view => {
+                // The session column, less its tree prefix
+                let spid = app.focused_cell().and_then(|(row, _)| {
+                    let rows = app.result.rows_for(app.current_result_set);
+                    let session = rows.get(row)?.first()?;
+                    session.rsplit(' ').next().map(str::to_string)
+                });
+                if let Some(spid) = spid {
+                    app.load_history_entry(&format!("\\session {}", spid));
+                    app.focus = FocusPane::Editor;
+                }
+            }
+            KeyCode::Char('k') => {
+                if let Some(spid) = app.blocking_head.clone() {
+                    app.load_history_entry(&format!("\\kill {}", spid));
+                    app.focus = FocusPane::Editor;
+                }
+            }
             KeyCode::Char('[') => app.prev_result_set(),
             KeyCode::Char(']') => app.next_result_set(),
             _ => {}
@@ -254,10 +1009,958 @@ async fn handle_key(
         FocusPane::Sidebar => match key.code {
             KeyCode::Up => app.scroll_sidebar_up(),
             KeyCode::Down => app.scroll_sidebar_down(),
-            KeyCode::Enter => app.toggle_sidebar_node(),
+            KeyCode::Enter => app.sidebar_enter(),
+            KeyCode::Char('i') => app.insert_sidebar_name(),
+            KeyCode::Char('s') => app.toggle_sidebar_stats(),
+            KeyCode::Char('<') => app.resize_sidebar(-2),
+            KeyCode::Char('>') => app.resize_sidebar(2),
+            KeyCode::Char('v') => show_definition(app, client).await,
+            KeyCode::Char('d') => {
+                if let Some(sql) = app.describe_sidebar_node() {
+                    execute_text(app, client, &sql, redraw).await?;
+                }
+            }
             _ => {}
         },
     }
 
     Ok(false)
 }
+
+/// Run editor text: a slash command or SQL. Returns `true` if the app should
+/// quit. While SQL fetches rows, the count so far is shown through `redraw`.
+async fn execute_text(
+    app: &mut App,
+    client: &mut db::ConnectionHandle,
+    sql: &str,
+    redraw: &mut dyn FnMut(&App),
+) -> Result<bool, Box<dyn std::error::Error>> {
+    app.blocking_view = false;
+    app.blocking_head = None;
+    if let Some(ref mut keepalive) = app.keepalive {
+        keepalive.touch();
+    }
+    // Check for slash commands
+    if let Some(cmd) = commands::parse(sql) {
+        let action =
+            commands::to_action(&cmd, &app.connection_info, &app.current_database, &app.user);
+        match action {
+            commands::CommandAction::ExecuteSql(query) => {
+                app.query_running = true;
+                match db::query::execute_query(client, &query).await {
+                    Ok(result) => {
+                        // If it was a USE command, update current database
+                        if let commands::SlashCommand::UseDatabase(ref db_name) = cmd {
+                            app.current_database = db_name.clone();
+                        }
+                        show_result(app, result);
+                    }
+                    Err(e) => show_error(app, &*e, &query),
+                }
+                app.query_running = false;
+            }
+            commands::CommandAction::ExecuteQuery { sql, params } => {
+                app.query_running = true;
+                match db::query::execute_query_params(client, &sql, &params).await {
+                    Ok(result) => show_result(app, result),
+                    Err(e) => show_error(app, &*e, &sql),
+                }
+                app.query_running = false;
+            }
+            commands::CommandAction::DisplayMessage { columns, mut rows } => {
+                if cmd == commands::SlashCommand::ConnInfo {
+                    rows.extend(app.connection_details());
+                }
+                app.set_result(crate::app::QueryResult::single(columns, rows, 0));
+            }
+            commands::CommandAction::Blocking => show_blocking(app, client).await,
+            commands::CommandAction::ToggleExpanded => {
+                app.expanded_mode = !app.expanded_mode;
+                let state = if app.expanded_mode { "ON" } else { "OFF" };
+                app.set_result(crate::app::QueryResult::single(
+                    vec!["Status".to_string()],
+                    vec![vec![format!("Expanded display is {}", state)]],
+                    0,
+                ));
+            }
+            commands::CommandAction::ToggleTiming => {
+                app.show_timing = !app.show_timing;
+                let state = if app.show_timing { "ON" } else { "OFF" };
+                app.set_result(crate::app::QueryResult::single(
+                    vec!["Status".to_string()],
+                    vec![vec![format!("Timing is {}", state)]],
+                    0,
+                ));
+            }
+            commands::CommandAction::NullDisplay(Some(text)) => {
+                app.set_status(format!("Null display is \"{}\"", text));
+                app.null_display = text;
+            }
+            commands::CommandAction::NullDisplay(None) => {
+                app.set_status(format!("Null display is \"{}\"", app.null_display));
+            }
+            commands::CommandAction::DateStyle(Some(name)) => match DateStyle::parse(&name) {
+                Some(style) => {
+                    app.date_style = style;
+                    app.notice = Some(format!("Date style is {}", style.name()));
+                }
+                None => {
+                    app.notice = Some(format!(
+                        "Unknown date style {} (raw, iso, locale, or epoch)",
+                        name
+                    ))
+                }
+            },
+            commands::CommandAction::DateStyle(None) => {
+                app.notice = Some(format!("Date style is {}", app.date_style.name()));
+            }
+            commands::CommandAction::Thousands(on) => {
+                let format = &mut app.number_format;
+                format.thousands = on.unwrap_or(!format.thousands);
+                app.notice = Some(format!(
+                    "Digit grouping is {}",
+                    if format.thousands { "on" } else { "off" }
+                ));
+            }
+            commands::CommandAction::RowNumbers(on) => {
+                app.show_row_numbers = on.unwrap_or(!app.show_row_numbers);
+                app.notice = Some(format!(
+                    "Row numbers are {}",
+                    if app.show_row_numbers { "on" } else { "off" }
+                ));
+            }
+            commands::CommandAction::Decimals(places) => {
+                app.number_format.decimals = places;
+                app.notice = Some(match places {
+                    Some(n) => format!("Numbers show {} decimal places", n),
+                    None => "Numbers show as returned".to_string(),
+                });
+            }
+            commands::CommandAction::Currency(symbol) => {
+                app.notice = Some(match symbol {
+                    Some(ref symbol) => format!("Money shows as {}1234.5000", symbol),
+                    None => "Money shows without a currency symbol".to_string(),
+                });
+                app.currency = symbol;
+            }
+            commands::CommandAction::PinColumns(n) => {
+                // Keep the current result so the pin takes effect on it
+                app.set_pinned_columns(n);
+            }
+            commands::CommandAction::ShowChart { label, value } => {
+                let rs = app.result.result_sets.get(app.current_result_set);
+                app.chart = Some(chart::ChartView::from_result(rs, &label, &value));
+            }
+            commands::CommandAction::Compare(sets) => match compare_view(app, sets) {
+                Ok(view) => app.compare = Some(view),
+                Err(e) => app.set_status(e),
+            },
+            commands::CommandAction::Explain(sql) => {
+                let Some(sql) = sql.or_else(|| app.last_sql_query().map(str::to_string)) else {
+                    app.set_status("No query to explain yet");
+                    return Ok(false);
+                };
+                let sql = variables::interpolate(&sql, &app.variables);
+                match db::query::fetch_plan(client, &sql).await {
+                    Ok(result) => app.plan = Some(plan::PlanView::from_result(&result)),
+                    Err(e) => show_error(app, &*e, &sql),
+                }
+            }
+            commands::CommandAction::Snippet(snip) => handle_snippet(app, snip),
+            commands::CommandAction::Workspace(request) => app.workspace_request = Some(request),
+            commands::CommandAction::RunScript(path) => {
+                run_script(app, client, &path).await;
+                refresh_transactions(app, client).await;
+            }
+            commands::CommandAction::Shell(command) => {
+                app.suspend = Some(Suspend::Shell(command));
+            }
+            commands::CommandAction::Tee(Some(path)) => {
+                app.set_status(format!("Copying results to {}", path));
+                app.tee = Some(std::path::PathBuf::from(path));
+            }
+            commands::CommandAction::Tee(None) => {
+                app.tee = None;
+                app.set_status("Stopped copying results");
+            }
+            commands::CommandAction::EditExternal => {
+                app.suspend = Some(Suspend::Edit(
+                    app.last_sql_query().unwrap_or_default().to_string(),
+                ));
+            }
+            commands::CommandAction::Definition { name, edit } => {
+                let database = app.current_database.clone();
+                let Some(object) = commands::ObjectName::parse(&name) else {
+                    app.set_status(format!("Not an object name: {}", name));
+                    return Ok(false);
+                };
+                match db::query::fetch_definition(client, &database, &object).await {
+                    Ok(Some(definition)) if edit => {
+                        app.load_history_entry(&commands::create_or_alter(&definition));
+                    }
+                    Ok(Some(definition)) => {
+                        app.inspector =
+                            Some(inspector::Inspector::for_definition(&name, &definition));
+                    }
+                    Ok(None) => app.set_status(format!("No SQL definition available for {}", name)),
+                    Err(e) => {
+                        app.set_status(format!("Could not load definition of {}: {}", name, e))
+                    }
+                }
+            }
+            commands::CommandAction::ScriptDatabase(dir) => {
+                let database = app.current_database.clone();
+                match db::script::script_schema(client, &database).await {
+                    Ok(objects) => match dir {
+                        Some(dir) => {
+                            let dir = std::path::PathBuf::from(dir);
+                            match crate::cli::script_schema::write_files(&objects, &dir) {
+                                Ok(n) => app.set_status(format!(
+                                    "Scripted {} objects to {}",
+                                    n,
+                                    dir.display()
+                                )),
+                                Err(e) => app.set_status(format!("Could not write scripts: {}", e)),
+                            }
+                        }
+                        None => {
+                            let script: Vec<&str> =
+                                objects.iter().map(|o| o.ddl.as_str()).collect();
+                            app.open_generated(script.join("\n"));
+                        }
+                    },
+                    Err(e) => app.set_status(format!("Could not script {}: {}", database, e)),
+                }
+            }
+            commands::CommandAction::ScriptTable(table) => {
+                let database = app.current_database.clone();
+                match db::script::script_table(client, &database, &table).await {
+                    Ok(Some(script)) => app.open_generated(script),
+                    Ok(None) => app.set_status(format!("Table {} not found", table.local())),
+                    Err(e) => app.set_status(format!("Could not script {}: {}", table.local(), e)),
+                }
+            }
+            commands::CommandAction::ErrVerbose => show_last_error(app),
+            commands::CommandAction::GSet(prefix) => app.gset(&prefix),
+            commands::CommandAction::SetVariable { name, value } => {
+                app.set_status(format!("{} = {}", name, value));
+                app.variables.insert(name, value);
+                apply_timezone(app);
+            }
+            commands::CommandAction::Prompt { text, name } => {
+                let title = text.unwrap_or_else(|| format!("Value for {}", name));
+                let initial = app.variables.get(&name).cloned().unwrap_or_default();
+                app.prompt = Some(prompt::Prompt::new(
+                    prompt::PromptKind::Variable(name),
+                    &title,
+                    &initial,
+                ));
+            }
+            commands::CommandAction::UnsetVariable(name) => {
+                if app.variables.remove(&name).is_some() {
+                    app.set_status(format!("Unset {}", name));
+                } else {
+                    app.set_status(format!("{} is not set", name));
+                }
+                apply_timezone(app);
+            }
+            commands::CommandAction::SetOption(Ok(option)) => {
+                let sql = option.sql();
+                match db::query::execute_query(client, &sql).await {
+                    Ok(_) => {
+                        app.notice = Some(format!("{} is {}", option.name, option.value));
+                        app.session_options.retain(|o| o.name != option.name);
+                        app.session_options.push(option);
+                    }
+                    Err(e) => show_error(app, &*e, &sql),
+                }
+            }
+            commands::CommandAction::SetOption(Err(message)) => app.notice = Some(message),
+            commands::CommandAction::ListOptions => {
+                if app.session_options.is_empty() {
+                    app.notice = Some("No session options set".to_string());
+                } else {
+                    app.set_result(crate::app::QueryResult::single(
+                        vec!["Option".to_string(), "Value".to_string()],
+                        app.session_options
+                            .iter()
+                            .map(|o| vec![o.name.clone(), o.value.clone()])
+                            .collect(),
+                        0,
+                    ));
+                }
+            }
+            commands::CommandAction::ListVariables => {
+                app.set_result(crate::app::QueryResult::single(
+                    vec!["Name".to_string(), "Value".to_string()],
+                    variables::rows(&app.variables),
+                    0,
+                ));
+            }
+            commands::CommandAction::Watch(secs) => match app.last_sql_query() {
+                Some(query) => {
+                    app.watch = Some(crate::app::Watch {
+                        query: query.to_string(),
+                        interval: std::time::Duration::from_secs(secs),
+                        iterations: 0,
+                        last_run: None,
+                    });
+                }
+                None => app.set_status("No query to watch yet"),
+            },
+            commands::CommandAction::Quit => return Ok(true),
+        }
+    } else {
+        let sql = variables::interpolate(sql, &app.variables);
+        // Text with `GO` lines (e.g. from `\script`) runs batch by batch
+        if sql
+            .lines()
+            .any(|line| scripts::go_separator(line.trim()).is_some())
+        {
+            match scripts::prepare(&sql, &app.variables, false) {
+                Ok(batches) => run_batches(app, client, &batches).await,
+                Err(e) => app.set_status(e),
+            }
+            return Ok(false);
+        }
+        let names = params::undeclared(&sql);
+        if names.is_empty() {
+            run_query(app, client, &sql, sql.clone(), redraw).await;
+        } else {
+            ask_parameter(
+                app,
+                params::ParamForm {
+                    sql,
+                    names,
+                    values: Vec::new(),
+                },
+            );
+        }
+    }
+    Ok(false)
+}
+
+/// Run `exec` and show its result as the result of the typed query `sql`
+/// (the same text unless it was wrapped in `sp_executesql`).
+async fn run_query(
+    app: &mut App,
+    client: &mut db::ConnectionHandle,
+    exec: &str,
+    sql: String,
+    redraw: &mut dyn FnMut(&App),
+) {
+    app.query_running = true;
+    let result = db::query::execute_query_with_progress(client, exec, |rows| {
+        app.fetched_rows = Some(rows);
+        redraw(app);
+    })
+    .await;
+    app.fetched_rows = None;
+    match result {
+        Ok(mut result) => {
+            result.sql = Some(sql);
+            app.keep_result();
+            show_result(app, result);
+        }
+        Err(e) => show_error(app, &*e, &sql),
+    }
+    app.query_running = false;
+    refresh_transactions(app, client).await;
+}
+
+/// Ask for the next parameter of `form`, offering the last answer given
+/// for that name.
+fn ask_parameter(app: &mut App, form: params::ParamForm) {
+    let index = form.values.len();
+    let name = &form.names[index];
+    let title = format!(
+        "{} ({} of {}): type = value (Esc: cancel)",
+        name,
+        index + 1,
+        form.names.len()
+    );
+    let initial = app
+        .param_answers
+        .get(&name.to_lowercase())
+        .cloned()
+        .unwrap_or_else(|| "nvarchar(4000) = ".to_string());
+    app.prompt = Some(prompt::Prompt::new(
+        prompt::PromptKind::Parameter(form),
+        &title,
+        &initial,
+    ));
+}
+
+/// The `\compare` view of `sources`, else the current result's first two
+/// result sets, else the previous query's result next to the current one.
+fn compare_view(
+    app: &App,
+    sources: Option<(CompareSource, CompareSource)>,
+) -> Result<compare::CompareView, String> {
+    let side = |source: CompareSource| {
+        let (title, rs) = match source {
+            CompareSource::ResultSet(n) => (
+                format!("Result set {}", n),
+                app.result
+                    .result_sets
+                    .get(n - 1)
+                    .ok_or_else(|| format!("No result set {}", n))?,
+            ),
+            CompareSource::Previous(n) => (
+                match n {
+                    1 => "Previous query".to_string(),
+                    n => format!("{} queries back", n),
+                },
+                app.previous_results
+                    .get(n - 1)
+                    .and_then(|r| r.result_sets.first())
+                    .ok_or_else(|| {
+                        format!("No result from {} queries back (keeps {})", n, KEPT_RESULTS)
+                    })?,
+            ),
+            CompareSource::Current => (
+                "Current query".to_string(),
+                app.result
+                    .result_sets
+                    .get(app.current_result_set)
+                    .ok_or("No current result to compare")?,
+            ),
+        };
+        Ok::<_, String>(compare::CompareSide::new(title, rs))
+    };
+    let (left, right) = match sources {
+        Some(sources) => sources,
+        None if app.result.result_sets.len() >= 2 => {
+            (CompareSource::ResultSet(1), CompareSource::ResultSet(2))
+        }
+        None if app.previous_results.is_empty() => {
+            return Err(
+                "Nothing to compare: run two queries, or a batch with two result sets".into(),
+            );
+        }
+        None => (CompareSource::Previous(1), CompareSource::Current),
+    };
+    Ok(compare::CompareView::new(side(left)?, side(right)?))
+}
+
+/// Pick up the `timezone` and `timezone_datetime2` variables. An unknown
+/// zone is reported and unset.
+fn apply_timezone(app: &mut App) {
+    app.timezone_datetime2 = app
+        .variables
+        .get("timezone_datetime2")
+        .and_then(|v| variables::truthy(v))
+        .unwrap_or(false);
+    app.timezone = match app.variables.get("timezone").map(|spec| Zone::parse(spec)) {
+        Some(Ok(zone)) => Some(zone),
+        Some(Err(e)) => {
+            app.variables.remove("timezone");
+            app.set_status(e);
+            None
+        }
+        None => None,
+    };
+}
+
+/// Start editing the focused results cell. The result must come from a
+/// single-table SELECT whose primary key columns are all in it.
+async fn start_cell_edit(app: &mut App, client: &mut db::ConnectionHandle) {
+    let Some((row, col)) = app.focused_cell() else {
+        return;
+    };
+    let Some(table) = app.result.sql.as_deref().and_then(crate::dml::source_table) else {
+        app.notice = Some("Only results of a single-table SELECT can be edited".to_string());
+        return;
+    };
+    let key = match db::query::fetch_primary_key(client, &table).await {
+        Ok(key) if key.is_empty() => {
+            app.notice = Some(format!("{} has no primary key to find the row by", table));
+            return;
+        }
+        Ok(key) => key,
+        Err(e) => {
+            app.notice = Some(format!(
+                "Could not read the primary key of {}: {}",
+                table, e
+            ));
+            return;
+        }
+    };
+    let sql = app.result.sql.clone().unwrap_or_default();
+    let sources = match db::query::fetch_source_columns(client, &sql).await {
+        Ok(sources) => sources,
+        Err(e) => {
+            app.notice = Some(format!("Could not describe the result: {}", e));
+            return;
+        }
+    };
+    match app.cell_edit(&table, &key, &sources) {
+        Ok(edit) => {
+            let rs_idx = app.current_result_set;
+            let column = app.result.columns_for(rs_idx)[col].clone();
+            let value = crate::app::cell_text(app.result.value(rs_idx, row, col));
+            app.prompt = Some(prompt::Prompt::new(
+                prompt::PromptKind::CellValue(edit),
+                &format!(
+                    "New value for {} (NULL for null, 'NULL' for the text)",
+                    column
+                ),
+                &value,
+            ));
+        }
+        Err(e) => app.notice = Some(e),
+    }
+}
+
+/// Run a confirmed cell UPDATE, then re-read the cell so the grid shows
+/// what the server stored.
+async fn apply_cell_edit(
+    app: &mut App,
+    client: &mut db::ConnectionHandle,
+    edit: &crate::app::CellEdit,
+    update: &str,
+) {
+    let batch = format!(
+        "{}\nSELECT @@ROWCOUNT AS affected;\n{}",
+        update,
+        app.cell_refresh(edit)
+    );
+    app.query_running = true;
+    match db::query::execute_query(client, &batch).await {
+        Ok(result) => {
+            let sets = result.result_sets.len();
+            let affected = result
+                .value(sets.saturating_sub(2), 0, 0)
+                .unwrap_or_default();
+            let refreshed = result
+                .result_sets
+                .last()
+                .filter(|rs| !rs.rows.is_empty())
+                .map(|rs| rs.value(0, 0));
+            if let Some(value) = refreshed
+                && let Some(rs) = app.result.result_sets.get_mut(edit.result_set)
+                && let Some(row) = rs.rows.get_mut(edit.row)
+            {
+                row[edit.col] = value.unwrap_or("NULL").to_string();
+                if value.is_some() {
+                    rs.nulls.remove(&(edit.row, edit.col));
+                } else {
+                    rs.nulls.insert((edit.row, edit.col));
+                }
+            }
+            app.notice = Some(format!("Updated {} row(s)", affected));
+        }
+        Err(e) => app.notice = Some(format!("UPDATE failed: {}", e)),
+    }
+    app.query_running = false;
+    refresh_transactions(app, client).await;
+}
+
+/// Show blocking chains as a tree and remember the worst head blocker.
+async fn show_blocking(app: &mut App, client: &mut db::ConnectionHandle) {
+    let result = match db::query::execute_query(client, blocking::QUERY).await {
+        Ok(result) => result,
+        Err(e) => return show_error(app, &*e, blocking::QUERY),
+    };
+    let rows = result.rows_for(0);
+    if rows.is_empty() {
+        app.set_status("No blocked sessions");
+        return;
+    }
+    let (tree, head) = blocking::tree(rows);
+    let columns = result.columns_for(0).to_vec();
+    show_result(
+        app,
+        crate::app::QueryResult::single(columns, tree, result.elapsed_ms),
+    );
+    app.blocking_view = true;
+    app.blocking_head = head;
+}
+
+/// Update the status bar's open transaction count after running SQL.
+async fn refresh_transactions(app: &mut App, client: &mut db::ConnectionHandle) {
+    if let Some(ref mut session) = app.session
+        && let Ok(count) = db::query::fetch_transaction_count(client).await
+    {
+        session.transactions = count;
+    }
+}
+
+/// Run a Vim `:` command: a line number jumps there, `:w [path]` saves, and
+/// anything else is run as the slash command of the same name (`:q`,
+/// `:d users`, `:x`).
+async fn run_vim_command(
+    app: &mut App,
+    client: &mut db::ConnectionHandle,
+    cmd: &str,
+    redraw: &mut dyn FnMut(&App),
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if let Ok(line) = cmd.parse::<usize>() {
+        let row = line.saturating_sub(1).min(app.editor.lines().len() - 1);
+        app.editor
+            .move_cursor(tui_textarea::CursorMove::Jump(row as u16, 0));
+        return Ok(false);
+    }
+    if cmd == "w" || cmd.starts_with("w ") {
+        match cmd[1..].trim() {
+            "" if app.file.is_none() => {
+                app.prompt = Some(prompt::Prompt::new(
+                    prompt::PromptKind::SaveAs,
+                    "Save as",
+                    "query.sql",
+                ))
+            }
+            "" => save_editor(app, None),
+            path => save_editor(app, Some(std::path::Path::new(path))),
+        }
+        return Ok(false);
+    }
+    let slash = format!("\\{}", cmd);
+    if commands::parse(&slash).is_none() {
+        app.set_status(format!("Not an editor command: {}", cmd));
+        return Ok(false);
+    }
+    execute_text(app, client, &slash, redraw).await
+}
+
+/// Show the selected sidebar view's, procedure's, function's, or trigger's
+/// definition in the inspector.
+async fn show_definition(app: &mut App, client: &mut db::ConnectionHandle) {
+    let Some((database, object)) = app.sidebar_object() else {
+        return;
+    };
+    let name = object.local();
+    match db::query::fetch_definition(client, &database, &object).await {
+        Ok(Some(definition)) => {
+            app.inspector = Some(inspector::Inspector::for_definition(&name, &definition));
+        }
+        Ok(None) => app.set_status(format!("No SQL definition available for {}", name)),
+        Err(e) => app.set_status(format!("Could not load definition of {}: {}", name, e)),
+    }
+}
+
+/// Run the `\watch` query once and show its result. Errors are shown but
+/// don't stop the watch.
+async fn run_watch(app: &mut App, client: &mut db::ConnectionHandle) {
+    let Some(query) = app.watch.as_ref().map(|w| w.query.clone()) else {
+        return;
+    };
+    let query = variables::interpolate(&query, &app.variables);
+    app.query_running = true;
+    match db::query::execute_query(client, &query).await {
+        Ok(result) => show_result(app, result),
+        Err(e) => show_error(app, &*e, &query),
+    }
+    app.query_running = false;
+    if let Some(ref mut watch) = app.watch {
+        watch.finished();
+    }
+}
+
+/// Run the `\watch` query of inactive workspace `index` on its connection
+/// and keep the result there, leaving the workspace on screen as it is.
+async fn run_background_watch(app: &mut App, connections: &mut Connections, index: usize) {
+    let workspace = &mut app.workspaces[index];
+    let Some(query) = workspace.watch_query() else {
+        return;
+    };
+    let query = variables::interpolate(query, &app.variables);
+    let result = match workspace.connection {
+        Some(ref mut own) => db::query::execute_query(own, &query).await,
+        None => {
+            // The shared connection is where the active workspace left it
+            let database = workspace.database().to_string();
+            let back = match connections.own {
+                Some(_) => connections.shared_database.clone(),
+                None => app.current_database.clone(),
+            };
+            let use_db = |db: &str| format!("USE {}", commands::quote_ident(db));
+            let shared = &mut connections.shared;
+            let moved = database != back;
+            let mut result = Ok(crate::app::QueryResult::default());
+            if moved {
+                result = db::query::execute_query(shared, &use_db(&database)).await;
+            }
+            if result.is_ok() {
+                result = db::query::execute_query(shared, &query).await;
+            }
+            if moved && let Err(e) = db::query::execute_query(shared, &use_db(&back)).await {
+                app.notice = Some(format!("Could not switch back to {}: {}", back, e));
+            }
+            result
+        }
+    };
+    let (result, error) = match result {
+        Ok(result) => {
+            tee_result(app, &result);
+            (result, None)
+        }
+        Err(e) => (
+            crate::app::QueryResult {
+                error: Some(e.to_string()),
+                ..Default::default()
+            },
+            Some(db::query::query_error(&*e, &query)),
+        ),
+    };
+    app.workspaces[index].watch_finished(result, error);
+}
+
+/// Show a failed query's error in the results pane and keep its details
+/// for `\errverbose`.
+fn show_error(app: &mut App, e: &(dyn std::error::Error + 'static), sql: &str) {
+    app.set_result(crate::app::QueryResult {
+        error: Some(e.to_string()),
+        ..Default::default()
+    });
+    app.last_error = Some(db::query::query_error(e, sql));
+}
+
+/// Show the last error in full and move the editor cursor to the line it
+/// points at. If the editor only holds the `\errverbose` command, the failed
+/// batch is loaded back first.
+fn show_last_error(app: &mut App) {
+    let Some(error) = app.last_error.clone() else {
+        app.set_status("No error in this session");
+        return;
+    };
+    app.set_result(crate::app::QueryResult::single(
+        vec!["Property".to_string(), "Value".to_string()],
+        error.details(),
+        0,
+    ));
+    let Some((line, _)) = error.batch_line() else {
+        return;
+    };
+    if commands::parse(&app.get_editor_text()).is_some() {
+        app.set_editor_text(&error.sql);
+    }
+    if app.get_editor_text() == error.sql {
+        app.editor
+            .move_cursor(tui_textarea::CursorMove::Jump((line - 1) as u16, 0));
+    }
+}
+
+/// Display a query result, also appending it to the `\o` file if one is set.
+fn show_result(app: &mut App, result: crate::app::QueryResult) {
+    if tee_result(app, &result) {
+        app.set_result(result);
+    }
+}
+
+/// Append a result to the `\o` file, if one is set. Returns `false` if
+/// that failed, which stops the copying.
+fn tee_result(app: &mut App, result: &crate::app::QueryResult) -> bool {
+    if let Some(path) = app.tee.clone()
+        && let Err(e) = cli::append_result(
+            &path,
+            &app.in_display_order(result),
+            &app.output_format,
+            &cli::OutputOptions {
+                date_style: app.date_style,
+                ..Default::default()
+            },
+        )
+    {
+        app.tee = None;
+        app.set_status(format!(
+            "Could not write to {}: {} (stopped copying results)",
+            path.display(),
+            e
+        ));
+        return false;
+    }
+    true
+}
+
+/// Run a script file batch by batch (`\i`), as [`run_batches`] does.
+async fn run_script(app: &mut App, client: &mut db::ConnectionHandle, path: &str) {
+    let batches = match scripts::read(std::path::Path::new(path)) {
+        Ok(text) => scripts::prepare(&text, &app.variables, true),
+        Err(e) => Err(e.to_string()),
+    };
+    match batches {
+        Ok(batches) => run_batches(app, client, &batches).await,
+        Err(e) => app.set_status(format!("{}: {}", path, e)),
+    }
+}
+
+/// Run prepared batches in order. Shows a per-batch summary as the first
+/// result set, followed by every batch's results. Stops at the first
+/// failing batch.
+async fn run_batches(app: &mut App, client: &mut db::ConnectionHandle, batches: &[scripts::Batch]) {
+    app.query_running = true;
+    let mut summary = Vec::new();
+    let mut result_sets = Vec::new();
+    let mut elapsed_ms = 0;
+    let mut failed = false;
+    let mut conditions = scripts::Conditions::default();
+    for (i, batch) in batches.iter().enumerate() {
+        let status = if failed {
+            "skipped".to_string()
+        } else if let Some(ref branch) = batch.branch {
+            match db::query::eval_branch(client, &conditions, branch).await {
+                Ok(value) => {
+                    let evaluated = conditions.condition(branch).is_some();
+                    // Nesting was checked when the script was prepared
+                    conditions.apply(branch, value).ok();
+                    if evaluated {
+                        format!("condition is {}", value)
+                    } else {
+                        String::new()
+                    }
+                }
+                Err(e) => {
+                    failed = true;
+                    format!("error: {}", e)
+                }
+            }
+        } else if !conditions.active() {
+            "skipped (\\if)".to_string()
+        } else {
+            let mut sets = 0;
+            let mut error = None;
+            for _ in 0..batch.repeat {
+                match db::query::execute_query(client, &batch.sql).await {
+                    Ok(result) => {
+                        elapsed_ms += result.elapsed_ms;
+                        sets += result.result_sets.len();
+                        result_sets.extend(result.result_sets);
+                    }
+                    Err(e) => {
+                        error = Some(e.to_string());
+                        app.last_error = Some(db::query::query_error(&*e, &batch.sql));
+                        break;
+                    }
+                }
+            }
+            match error {
+                Some(e) => {
+                    failed = true;
+                    format!("error: {}", e)
+                }
+                None => format!("ok, {} result set(s)", sets),
+            }
+        };
+        summary.push(vec![(i + 1).to_string(), batch.line.to_string(), status]);
+    }
+    app.query_running = false;
+
+    let mut result = crate::app::QueryResult::single(
+        vec![
+            "Batch".to_string(),
+            "Line".to_string(),
+            "Status".to_string(),
+        ],
+        summary,
+        elapsed_ms,
+    );
+    result.result_sets.extend(result_sets);
+    show_result(app, result);
+}
+
+/// Carry out an action after the unsaved-changes dialog. Returns `true` to
+/// quit.
+fn discard_and(app: &mut App, action: DiscardAction) -> bool {
+    match action {
+        DiscardAction::Quit => {
+            // Dropping the file means quitting no longer sees unsaved changes
+            app.file = None;
+            true
+        }
+        DiscardAction::Open(path) => {
+            open_in_editor(app, &path);
+            false
+        }
+    }
+}
+
+/// Load a script into the editor, reporting failures in the results pane.
+fn open_in_editor(app: &mut App, path: &std::path::Path) {
+    if let Err(e) = app.open_file(path) {
+        app.set_status(format!("Could not open {}: {}", path.display(), e));
+    }
+}
+
+/// Save the editor to `path` (or the open file) and report the outcome.
+fn save_editor(app: &mut App, path: Option<&std::path::Path>) {
+    match app.save_file(path) {
+        Ok(path) => app.set_status(format!("Saved {}", path.display())),
+        Err(e) => app.set_status(format!("Could not save: {}", e)),
+    }
+}
+
+/// Run a `\snip` subcommand.
+fn handle_snippet(app: &mut App, cmd: commands::SnippetCommand) {
+    match cmd {
+        commands::SnippetCommand::List => {
+            app.picker = Some(picker::Picker::new(
+                picker::PickerKind::Snippets,
+                "Snippets",
+                snippet_items(),
+            ));
+        }
+        commands::SnippetCommand::Save { name, body } => {
+            let body = body.or_else(|| app.last_sql_query().map(|q| q.to_string()));
+            match body {
+                Some(body) => match snippets::save(&name, &body) {
+                    Ok(path) => {
+                        app.set_status(format!("Saved snippet '{}' to {}", name, path.display()))
+                    }
+                    Err(e) => app.set_status(format!("Could not save '{}': {}", name, e)),
+                },
+                None => app.set_status("Nothing to save: run a query first"),
+            }
+        }
+        commands::SnippetCommand::Load(name) => match snippets::load(&name) {
+            Ok(body) => app.load_history_entry(&body),
+            Err(e) => app.set_status(format!("Could not load '{}': {}", name, e)),
+        },
+        commands::SnippetCommand::Delete(name) => match snippets::delete(&name) {
+            Ok(()) => app.set_status(format!("Deleted snippet '{}'", name)),
+            Err(e) => app.set_status(format!("Could not delete '{}': {}", name, e)),
+        },
+    }
+}
+
+/// Saved snippets as picker items.
+fn snippet_items() -> Vec<picker::PickerItem> {
+    snippets::list()
+        .into_iter()
+        .map(|(name, body)| picker::PickerItem {
+            label: name,
+            preview: body,
+        })
+        .collect()
+}
+
+/// How many recent history entries the quick-access menu lists below the
+/// starred ones.
+const QUICK_ACCESS_RECENT: usize = 50;
+
+/// Starred queries first, then recent distinct history, as picker items.
+fn quick_access_items(app: &App) -> Vec<picker::PickerItem> {
+    let flat = |q: &str| q.trim().replace('\n', " ⏎ ");
+    let mut items: Vec<picker::PickerItem> = app
+        .bookmarks
+        .iter()
+        .map(|q| picker::PickerItem {
+            label: format!("★ {}", flat(q)),
+            preview: q.clone(),
+        })
+        .collect();
+    let mut seen: std::collections::HashSet<&str> =
+        app.bookmarks.iter().map(|b| b.as_str()).collect();
+    items.extend(
+        app.history
+            .iter()
+            .rev()
+            .filter(|q| seen.insert(q.as_str()))
+            .take(QUICK_ACCESS_RECENT)
+            .map(|q| picker::PickerItem {
+                label: format!("  {}", flat(q)),
+                preview: q.clone(),
+            }),
+    );
+    items
+}
@@ -0,0 +1,201 @@
+//! Generic filterable list overlay used for snippets and other pickers.
+
+use super::fuzzy;
+use super::theme::Theme;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+
+/// What a picker is choosing, so the caller knows how to act on the result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickerKind {
+    /// Saved snippets (`\snip`).
+    Snippets,
+    /// Starred queries followed by recent history (F2).
+    QuickAccess,
+    /// `.sql` files to open in the editor (Ctrl+O).
+    OpenFile,
+}
+
+/// One selectable entry.
+#[derive(Debug, Clone)]
+pub struct PickerItem {
+    /// Text shown in the list and matched by the filter.
+    pub label: String,
+    /// Preview shown for the selected item.
+    pub preview: String,
+}
+
+/// Outcome of a key press in the picker.
+pub enum PickerOutcome {
+    /// Keep the picker open.
+    Continue,
+    /// Close without choosing.
+    Cancel,
+    /// This item was chosen.
+    Accept(PickerItem),
+    /// Delete was requested for this item.
+    Delete(PickerItem),
+}
+
+/// A filterable list overlay with a preview pane.
+pub struct Picker {
+    /// What is being picked.
+    pub kind: PickerKind,
+    /// Overlay title.
+    pub title: String,
+    /// All items, in display order.
+    pub items: Vec<PickerItem>,
+    /// Filter text typed so far.
+    pub filter: String,
+    /// Indexes into `items` matching the filter.
+    pub visible: Vec<usize>,
+    /// Selected position within `visible`.
+    pub selected: usize,
+    /// Accept the filter text itself when nothing matches it.
+    pub free_text: bool,
+}
+
+impl Picker {
+    /// Create a picker over `items`.
+    pub fn new(kind: PickerKind, title: &str, items: Vec<PickerItem>) -> Self {
+        let mut picker = Self {
+            kind,
+            title: title.to_string(),
+            items,
+            filter: String::new(),
+            visible: Vec::new(),
+            selected: 0,
+            free_text: false,
+        };
+        picker.refresh();
+        picker
+    }
+
+    /// Let Enter accept the typed filter when no item matches it, e.g. a path
+    /// that isn't in the list. The accepted item has an empty preview.
+    pub fn with_free_text(mut self) -> Self {
+        self.free_text = true;
+        self
+    }
+
+    /// Replace the items (e.g. after a delete), keeping the filter.
+    pub fn set_items(&mut self, items: Vec<PickerItem>) {
+        self.items = items;
+        self.refresh();
+    }
+
+    fn refresh(&mut self) {
+        self.visible = (0..self.items.len())
+            .filter(|&i| fuzzy::fuzzy_match(&self.filter, &self.items[i].label).is_some())
+            .collect();
+        self.selected = self.selected.min(self.visible.len().saturating_sub(1));
+    }
+
+    fn selected_item(&self) -> Option<PickerItem> {
+        self.visible
+            .get(self.selected)
+            .map(|&i| self.items[i].clone())
+    }
+
+    /// Handle a key press.
+    pub fn handle_key(&mut self, key: KeyEvent) -> PickerOutcome {
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc) => return PickerOutcome::Cancel,
+            (_, KeyCode::Enter) => {
+                if let Some(item) = self.selected_item() {
+                    return PickerOutcome::Accept(item);
+                }
+                if self.free_text && !self.filter.trim().is_empty() {
+                    return PickerOutcome::Accept(PickerItem {
+                        label: self.filter.trim().to_string(),
+                        preview: String::new(),
+                    });
+                }
+                return PickerOutcome::Cancel;
+            }
+            (_, KeyCode::Delete) => {
+                if let Some(item) = self.selected_item() {
+                    return PickerOutcome::Delete(item);
+                }
+            }
+            (_, KeyCode::Up) => self.selected = self.selected.saturating_sub(1),
+            (_, KeyCode::Down) => {
+                self.selected = (self.selected + 1).min(self.visible.len().saturating_sub(1));
+            }
+            (_, KeyCode::Backspace) => {
+                self.filter.pop();
+                self.refresh();
+            }
+            (KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char(c)) => {
+                self.filter.push(c);
+                self.selected = 0;
+                self.refresh();
+            }
+            _ => {}
+        }
+        PickerOutcome::Continue
+    }
+}
+
+/// Draw the picker overlay into the given area: list on the left, preview on the right.
+pub fn draw(frame: &mut Frame, picker: &Picker, area: Rect, theme: &Theme) {
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ({}) ", picker.title, picker.visible.len()))
+        .title_bottom(" Type to filter │ ↑/↓ Select │ Enter: Open │ Del: Delete │ Esc: Close ")
+        .border_style(Style::default().fg(theme.highlight))
+        .style(Style::default().bg(theme.background));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(inner);
+
+    let height = chunks[0].height.saturating_sub(2) as usize;
+    let first = picker.selected.saturating_sub(height.saturating_sub(1));
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("filter: ", Style::default().fg(theme.muted)),
+            Span::raw(format!("{}█", picker.filter)),
+        ]),
+        Line::from(""),
+    ];
+    if picker.visible.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  (nothing here yet)",
+            Style::default().fg(theme.muted),
+        )));
+    }
+    for (pos, &i) in picker.visible.iter().enumerate().skip(first).take(height) {
+        let style = if pos == picker.selected {
+            Style::default().fg(theme.on_accent).bg(theme.accent)
+        } else {
+            Style::default().fg(theme.text)
+        };
+        lines.push(Line::from(Span::styled(
+            picker.items[i].label.clone(),
+            style,
+        )));
+    }
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let preview = picker
+        .visible
+        .get(picker.selected)
+        .map(|&i| picker.items[i].preview.as_str())
+        .unwrap_or("");
+    let preview = Paragraph::new(preview)
+        .block(
+            Block::default()
+                .borders(Borders::LEFT)
+                .border_style(Style::default().fg(theme.muted)),
+        )
+        .style(Style::default().fg(theme.subtle))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(preview, chunks[1]);
+}
@@ -0,0 +1,311 @@
+//! Estimated execution plan overlay (`\explain`): the `SET SHOWPLAN_ALL`
+//! rows as a collapsible operator tree with estimated rows and costs.
+
+use super::theme::Theme;
+use crate::app::{QueryResult, ResultSet};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+
+/// A statement or operator of the plan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanNode {
+    /// Nesting level: 0 for statements, 1 for their top operator, ...
+    pub depth: usize,
+    /// The physical operator (with the logical one when it differs), or
+    /// the statement text.
+    pub label: String,
+    /// `Argument`: the objects, predicates, and seek keys used.
+    pub argument: Option<String>,
+    /// `EstimateRows`, as returned.
+    pub rows: Option<String>,
+    /// `TotalSubtreeCost`, as returned.
+    pub cost: Option<String>,
+}
+
+/// The plan tree with a selection, navigable like the sidebar.
+pub struct PlanView {
+    pub nodes: Vec<PlanNode>,
+    /// Indexes of nodes whose children are hidden.
+    pub collapsed: HashSet<usize>,
+    /// Selected position among the visible nodes.
+    pub selected: usize,
+    /// First visible line.
+    pub scroll: usize,
+    /// Height of the tree area from the last draw.
+    pub viewport_height: Cell<u16>,
+}
+
+impl PlanView {
+    /// Build the tree from the `SHOWPLAN_ALL` result sets, one per statement.
+    pub fn from_result(result: &QueryResult) -> Self {
+        Self {
+            nodes: result.result_sets.iter().flat_map(plan_nodes).collect(),
+            collapsed: HashSet::new(),
+            selected: 0,
+            scroll: 0,
+            viewport_height: Cell::new(0),
+        }
+    }
+
+    /// Indexes of the nodes not hidden under a collapsed node.
+    pub fn visible(&self) -> Vec<usize> {
+        let mut visible = Vec::new();
+        let mut hidden_below: Option<usize> = None;
+        for (i, node) in self.nodes.iter().enumerate() {
+            match hidden_below {
+                Some(depth) if node.depth > depth => continue,
+                _ => hidden_below = None,
+            }
+            visible.push(i);
+            if self.collapsed.contains(&i) {
+                hidden_below = Some(node.depth);
+            }
+        }
+        visible
+    }
+
+    fn has_children(&self, i: usize) -> bool {
+        self.nodes
+            .get(i + 1)
+            .is_some_and(|next| next.depth > self.nodes[i].depth)
+    }
+
+    /// Handle a key press. Returns true when the plan should close.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let visible = self.visible();
+        let page = (self.viewport_height.get() as usize).max(1);
+        let last = visible.len().saturating_sub(1);
+        let current = visible.get(self.selected).copied();
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return true,
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(last),
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(page),
+            KeyCode::PageDown => self.selected = (self.selected + page).min(last),
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = last,
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(i) = current.filter(|&i| self.has_children(i))
+                    && !self.collapsed.remove(&i)
+                {
+                    self.collapsed.insert(i);
+                }
+            }
+            KeyCode::Right => {
+                if let Some(i) = current {
+                    self.collapsed.remove(&i);
+                }
+            }
+            KeyCode::Left => {
+                if let Some(i) = current.filter(|&i| self.has_children(i)) {
+                    self.collapsed.insert(i);
+                }
+            }
+            _ => {}
+        }
+        // Keep the selection in view
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + page {
+            self.scroll = self.selected + 1 - page;
+        }
+        false
+    }
+}
+
+/// The nodes of one statement's `SHOWPLAN_ALL` rows, in their (pre-order)
+/// row order. Operators nest under their `Parent`; the statement row is
+/// the root.
+fn plan_nodes(rs: &ResultSet) -> Vec<PlanNode> {
+    let column = |name: &str| rs.columns.iter().position(|c| c == name);
+    let (Some(stmt_text), Some(node_id), Some(parent), Some(physical)) = (
+        column("StmtText"),
+        column("NodeId"),
+        column("Parent"),
+        column("PhysicalOp"),
+    ) else {
+        return Vec::new();
+    };
+    let (logical, argument, rows, cost) = (
+        column("LogicalOp"),
+        column("Argument"),
+        column("EstimateRows"),
+        column("TotalSubtreeCost"),
+    );
+
+    let mut depths: HashMap<&str, usize> = HashMap::new();
+    let mut nodes = Vec::new();
+    for r in 0..rs.rows.len() {
+        let value = |col: Option<usize>| col.and_then(|c| rs.value(r, c));
+        let (label, depth) = match value(Some(physical)) {
+            // The statement row has no operator
+            None => {
+                let text = value(Some(stmt_text)).unwrap_or("");
+                (text.split_whitespace().collect::<Vec<_>>().join(" "), 0)
+            }
+            Some(op) => {
+                let depth = value(Some(parent))
+                    .and_then(|p| depths.get(p))
+                    .map_or(1, |d| d + 1);
+                if let Some(id) = value(Some(node_id)) {
+                    depths.insert(id, depth);
+                }
+                let label = match value(logical) {
+                    Some(logical) if logical != op => format!("{} ({})", op, logical),
+                    _ => op.to_string(),
+                };
+                (label, depth)
+            }
+        };
+        nodes.push(PlanNode {
+            depth,
+            label,
+            argument: value(argument).map(str::to_string),
+            rows: value(rows).map(str::to_string),
+            cost: value(cost).map(str::to_string),
+        });
+    }
+    nodes
+}
+
+/// Draw the plan overlay into the given area: the tree, then the selected
+/// node's argument.
+pub fn draw(frame: &mut Frame, view: &PlanView, area: Rect, theme: &Theme) {
+    frame.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Estimated plan ")
+        .title_bottom(" ↑/↓ Navigate │ Enter: Expand/collapse │ Esc: Close ")
+        .border_style(Style::default().fg(theme.accent))
+        .style(Style::default().bg(theme.background));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if view.nodes.is_empty() {
+        frame.render_widget(
+            Paragraph::new("No plan rows returned.").style(Style::default().fg(theme.muted)),
+            inner,
+        );
+        return;
+    }
+
+    let [tree_area, detail_area] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(4)]).areas(inner);
+    view.viewport_height.set(tree_area.height);
+
+    let visible = view.visible();
+    let lines: Vec<Line> = visible
+        .iter()
+        .enumerate()
+        .skip(view.scroll)
+        .take(tree_area.height as usize)
+        .map(|(pos, &i)| {
+            let node = &view.nodes[i];
+            let marker = match (view.has_children(i), view.collapsed.contains(&i)) {
+                (false, _) => "  ",
+                (true, true) => "▸ ",
+                (true, false) => "▾ ",
+            };
+            let label_style = if node.depth == 0 {
+                Style::default().fg(theme.accent).bold()
+            } else {
+                Style::default().fg(theme.text)
+            };
+            let mut spans = vec![
+                Span::raw(format!("{}{}", "  ".repeat(node.depth), marker)),
+                Span::styled(node.label.clone(), label_style),
+            ];
+            if let Some(ref rows) = node.rows {
+                spans.push(Span::styled(
+                    format!("  rows {}", rows),
+                    Style::default().fg(theme.muted),
+                ));
+            }
+            if let Some(ref cost) = node.cost {
+                spans.push(Span::styled(
+                    format!("  cost {}", cost),
+                    Style::default().fg(theme.highlight),
+                ));
+            }
+            let line = Line::from(spans);
+            if pos == view.selected {
+                line.style(Style::default().bg(theme.selection))
+            } else {
+                line
+            }
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), tree_area);
+
+    let argument = visible
+        .get(view.selected)
+        .and_then(|&i| view.nodes[i].argument.as_deref())
+        .unwrap_or("");
+    let detail = Paragraph::new(argument)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_style(Style::default().fg(theme.muted)),
+        )
+        .style(Style::default().fg(theme.muted))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(detail, detail_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan_rows(rows: &[[&str; 5]]) -> ResultSet {
+        let mut rs = ResultSet {
+            columns: ["StmtText", "NodeId", "Parent", "PhysicalOp", "LogicalOp"]
+                .map(String::from)
+                .to_vec(),
+            ..Default::default()
+        };
+        for (r, row) in rows.iter().enumerate() {
+            rs.rows.push(row.map(String::from).to_vec());
+            for (c, value) in row.iter().enumerate() {
+                if *value == "NULL" {
+                    rs.nulls.insert((r, c));
+                }
+            }
+        }
+        rs
+    }
+
+    #[test]
+    fn test_plan_nodes() {
+        let rs = plan_rows(&[
+            ["SELECT *\n  FROM t", "1", "0", "NULL", "NULL"],
+            ["  |--Nested Loops", "2", "1", "Nested Loops", "Inner Join"],
+            ["       |--Index Seek", "3", "2", "Index Seek", "Index Seek"],
+            ["       |--Key Lookup", "4", "2", "Key Lookup", "Key Lookup"],
+        ]);
+        let nodes = plan_nodes(&rs);
+        let shape: Vec<(usize, &str)> = nodes.iter().map(|n| (n.depth, n.label.as_str())).collect();
+        assert_eq!(
+            shape,
+            vec![
+                (0, "SELECT * FROM t"),
+                (1, "Nested Loops (Inner Join)"),
+                (2, "Index Seek"),
+                (2, "Key Lookup"),
+            ]
+        );
+
+        let mut view = PlanView {
+            nodes,
+            collapsed: HashSet::from([1]),
+            selected: 0,
+            scroll: 0,
+            viewport_height: Cell::new(0),
+        };
+        assert_eq!(view.visible(), vec![0, 1]);
+        view.collapsed.clear();
+        assert_eq!(view.visible(), vec![0, 1, 2, 3]);
+    }
+}
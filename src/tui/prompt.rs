@@ -0,0 +1,96 @@
+//! Single-line text prompt overlay (e.g. "Save as").
+
+use super::theme::Theme;
+use crate::app::CellEdit;
+use crate::params::ParamForm;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+/// What the prompt is asking for, so the caller knows how to use the answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptKind {
+    /// Path to save the editor buffer to.
+    SaveAs,
+    /// Value for this client variable (`\prompt`).
+    Variable(String),
+    /// Table to generate INSERT statements for from the marked result rows.
+    InsertTable,
+    /// New value for a results cell.
+    CellValue(CellEdit),
+    /// The UPDATE for a cell edit, to confirm (or adjust) before it runs.
+    ConfirmUpdate(CellEdit),
+    /// Text to search the results grid for.
+    ResultSearch,
+    /// Row number (from 1) to scroll the results to.
+    GoToRow,
+    /// `type = value` for the next parameter of a query.
+    Parameter(ParamForm),
+}
+
+/// Outcome of a key press in the prompt.
+pub enum PromptOutcome {
+    /// Keep the prompt open.
+    Continue,
+    /// Close without an answer.
+    Cancel,
+    /// The entered text.
+    Submit(String),
+}
+
+/// A single-line input overlay.
+pub struct Prompt {
+    /// What is being asked.
+    pub kind: PromptKind,
+    /// Overlay title.
+    pub title: String,
+    /// Text entered so far.
+    pub input: String,
+}
+
+impl Prompt {
+    /// Create a prompt pre-filled with `initial`.
+    pub fn new(kind: PromptKind, title: &str, initial: &str) -> Self {
+        Self {
+            kind,
+            title: title.to_string(),
+            input: initial.to_string(),
+        }
+    }
+
+    /// Handle a key press.
+    pub fn handle_key(&mut self, key: KeyEvent) -> PromptOutcome {
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc) => return PromptOutcome::Cancel,
+            (_, KeyCode::Enter) => {
+                let input = self.input.trim();
+                if input.is_empty() {
+                    return PromptOutcome::Cancel;
+                }
+                return PromptOutcome::Submit(input.to_string());
+            }
+            (_, KeyCode::Backspace) => {
+                self.input.pop();
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('u')) => self.input.clear(),
+            (KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char(c)) => self.input.push(c),
+            _ => {}
+        }
+        PromptOutcome::Continue
+    }
+}
+
+/// Draw the prompt into the given area.
+pub fn draw(frame: &mut Frame, prompt: &Prompt, area: Rect, theme: &Theme) {
+    frame.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", prompt.title))
+        .title_bottom(" Enter: OK │ Ctrl+U: Clear │ Esc: Cancel ")
+        .border_style(Style::default().fg(theme.highlight))
+        .style(Style::default().bg(theme.background));
+    let text = Paragraph::new(format!("{}█", prompt.input))
+        .style(Style::default().fg(theme.text))
+        .block(block);
+    frame.render_widget(text, area);
+}
@@ -1,8 +1,15 @@
 //! Results table pane with vertical and horizontal scrolling.
 
-use crate::app::{App, FocusPane};
+use super::theme::Theme;
+use crate::app::{self, App, ColumnStats, FocusPane};
+use crate::display;
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table};
+use std::borrow::Cow;
+use unicode_width::UnicodeWidthChar;
+
+/// Bytes of a binary value shown inline in the grid before truncating.
+const BINARY_PREVIEW_BYTES: usize = 16;
 
 /// Draw the results pane.
 pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
@@ -16,68 +23,193 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
 
 /// Draw results in expanded (vertical record) mode.
 fn draw_expanded(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let focused = app.focus == FocusPane::Results;
     let border_style = if focused {
-        Style::default().fg(Color::Cyan)
+        Style::default().fg(theme.accent)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme.muted)
     };
 
     let rs_idx = app.current_result_set;
     let columns = app.result.columns_for(rs_idx);
     let rows = app.result.rows_for(rs_idx);
+
+    let block = Block::default().borders(Borders::ALL);
+    let inner = block.inner(area);
+    app.results_viewport.set((inner.width, inner.height));
+
+    let (lines, record_starts) = expanded_layout(app, inner.width as usize);
+    let record = app::record_at_line(&record_starts, app.result_scroll);
+
     let set_indicator = result_set_indicator(app);
     let title = format!(
-        " Results (expanded){} — {} rows  {}ms ",
+        " Results (expanded){} — record {}/{}  {}ms ",
         set_indicator,
+        (record + 1).min(rows.len()),
         rows.len(),
         app.result.elapsed_ms
     );
+    let block = block.title(title).border_style(border_style);
 
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .title(title)
-        .border_style(border_style);
+    // Slice rather than Paragraph::scroll, whose u16 offset overflows on large results
+    let visible: Vec<Line> = lines
+        .into_iter()
+        .skip(app.result_scroll)
+        .take(inner.height as usize)
+        .collect();
+    let paragraph = Paragraph::new(Text::from(visible)).block(block);
+    frame.render_widget(paragraph, area);
+}
+
+/// Line index at which each expanded-mode record starts, plus the total
+/// line count, for the pane size recorded at the last draw.
+pub fn expanded_record_starts(app: &App) -> (Vec<usize>, usize) {
+    let rs_idx = app.current_result_set;
+    let width = app.results_viewport.get().0 as usize;
+    let (lines, starts) = expanded_layout(app, width);
+    (starts, lines.len())
+}
 
-    // Build expanded text lines
+/// Lay out records vertically, wrapping long values to the pane width.
+/// Returns the display lines and the line index where each record starts.
+fn expanded_layout(app: &App, width: usize) -> (Vec<Line<'static>>, Vec<usize>) {
+    let theme = &app.theme;
+    let rs_idx = app.current_result_set;
+    let columns = app.result.columns_for(rs_idx);
+    let rows = app.result.rows_for(rs_idx);
+    let types = app.result.column_types_for(rs_idx);
     let max_col_width = columns.iter().map(|c| c.len()).max().unwrap_or(0);
-    let mut lines: Vec<ratatui::text::Line> = Vec::new();
+    let value_width = width.saturating_sub(max_col_width + 3).max(10);
+
+    let mut lines: Vec<Line> = Vec::new();
+    let mut starts = Vec::with_capacity(rows.len());
     for (i, row) in rows.iter().enumerate() {
+        starts.push(lines.len());
         let sep = format!("-[ RECORD {} ]{}", i + 1, "-".repeat(20));
-        lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+        lines.push(Line::from(Span::styled(
             sep,
-            Style::default().fg(Color::Cyan),
+            Style::default().fg(theme.accent),
         )));
-        for (j, col) in columns.iter().enumerate() {
+        for j in (0..columns.len()).map(|p| app.column_at(p)) {
+            let col = &columns[j];
+            if app.result.is_null(rs_idx, i, j) {
+                lines.push(Line::from(vec![
+                    Span::raw(format!("{:>width$} | ", col, width = max_col_width)),
+                    Span::styled(app.null_display.clone(), null_style(theme)),
+                ]));
+                continue;
+            }
             let val = row.get(j).map(|s| s.as_str()).unwrap_or("");
-            lines.push(ratatui::text::Line::from(format!(
-                "{:>width$} | {}",
-                col,
-                val,
-                width = max_col_width
-            )));
+            let ty = types.get(j).map(String::as_str).unwrap_or("");
+            let val = if display::is_date_type(ty) {
+                date_text(val, ty, app)
+            } else {
+                number_text(val, ty, app)
+            };
+            for (k, chunk) in wrap_value(&val, value_width).into_iter().enumerate() {
+                let label = if k == 0 { col.as_str() } else { "" };
+                lines.push(Line::from(format!(
+                    "{:>width$} | {}",
+                    label,
+                    chunk,
+                    width = max_col_width
+                )));
+            }
         }
     }
+    (lines, starts)
+}
 
-    let text = ratatui::text::Text::from(lines);
-    let paragraph = Paragraph::new(text)
-        .block(block)
-        .scroll((app.result_scroll as u16, 0));
-    frame.render_widget(paragraph, area);
+/// Split a value into chunks no wider than `width` display columns,
+/// breaking on embedded newlines as well.
+fn wrap_value(val: &str, width: usize) -> Vec<String> {
+    let mut out = Vec::new();
+    for segment in val.split('\n') {
+        let mut chunk = String::new();
+        let mut chunk_width = 0;
+        for ch in segment.chars() {
+            let w = UnicodeWidthChar::width(ch).unwrap_or(0);
+            if chunk_width + w > width && !chunk.is_empty() {
+                out.push(std::mem::take(&mut chunk));
+                chunk_width = 0;
+            }
+            chunk.push(ch);
+            chunk_width += w;
+        }
+        out.push(chunk);
+    }
+    out
 }
 
 /// Draw the results as a normal table.
 fn draw_table(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let focused = app.focus == FocusPane::Results;
     let border_style = if focused {
-        Style::default().fg(Color::Cyan)
+        Style::default().fg(theme.accent)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme.muted)
     };
 
     let rs_idx = app.current_result_set;
     let columns = app.result.columns_for(rs_idx);
     let rows = app.result.rows_for(rs_idx);
+    let headers = app.result.header_labels_for(rs_idx);
+    let types = app.result.column_types_for(rs_idx);
+    let type_of = |i: usize| types.get(i).map(|s| s.as_str()).unwrap_or("");
+
+    // Columns in display order, and widths for ALL of them (needed for
+    // slicing), by display position
+    let order: Vec<usize> = (0..columns.len()).map(|p| app.column_at(p)).collect();
+    let all_widths: Vec<u16> = order
+        .iter()
+        .map(|&i| {
+            let col = &headers[i];
+            let max_data = (0..rows.len())
+                .map(|r| {
+                    rows[r]
+                        .get(i)
+                        .map(|_| {
+                            let value = app.result.value(rs_idx, r, i);
+                            display_value(value, type_of(i), app).len()
+                        })
+                        .unwrap_or(0)
+                })
+                .max()
+                .unwrap_or(0);
+            col.len().max(max_data).min(50) as u16 + 2
+        })
+        .collect();
+
+    // Pinned columns are always drawn; the scrolled slice starts after them
+    let pinned = app.pinned_columns.min(columns.len());
+    let col_offset = app.result_col_scroll.max(pinned);
+    let pinned_width: u16 = all_widths[..pinned].iter().sum();
+    // Row-number gutter, wide enough for the last row's number
+    let gutter_width = if app.show_row_numbers {
+        rows.len().max(1).to_string().len() as u16 + 1
+    } else {
+        0
+    };
+
+    // Figure out how many columns fit in the available width (minus borders)
+    let available_width = area
+        .width
+        .saturating_sub(2)
+        .saturating_sub(pinned_width + gutter_width);
+    let mut total_w = 0u16;
+    let mut visible_end = col_offset;
+    for (i, &w) in all_widths.iter().enumerate().skip(col_offset) {
+        let next = total_w + w;
+        if next > available_width && visible_end > col_offset {
+            break;
+        }
+        total_w = next;
+        visible_end = i + 1;
+    }
+    app.results_visible_columns
+        .set(visible_end.saturating_sub(col_offset).max(1));
 
     // Title with row count, timing, and scroll hint
     let title = if let Some(ref err) = app.result.error {
@@ -87,13 +219,17 @@ fn draw_table(frame: &mut Frame, app: &App, area: Rect) {
     } else {
         let set_indicator = result_set_indicator(app);
         let col_info = if columns.len() > 1 {
+            let pin_info = if pinned > 0 {
+                format!(", {} pinned", pinned)
+            } else {
+                String::new()
+            };
             format!(
-                " (cols {}-{}/{})",
-                app.result_col_scroll + 1,
-                columns
-                    .len()
-                    .min(app.result_col_scroll + visible_col_count(app, area)),
-                columns.len()
+                " (cols {}-{}/{}{})",
+                col_offset + 1,
+                visible_end.max(col_offset + 1).min(columns.len()),
+                columns.len(),
+                pin_info
             )
         } else {
             String::new()
@@ -107,10 +243,21 @@ fn draw_table(frame: &mut Frame, app: &App, area: Rect) {
         )
     };
 
-    let block = Block::default()
+    let mut block = Block::default()
         .borders(Borders::ALL)
         .title(title)
         .border_style(border_style);
+    if app.show_column_stats
+        && let Some((_, col)) = app.focused_cell()
+    {
+        block = block.title_bottom(stats_footer(
+            &columns[col],
+            &ColumnStats::compute((0..rows.len()).map(|r| app.result.value(rs_idx, r, col))),
+            theme,
+        ));
+    }
+    let inner = block.inner(area);
+    app.results_viewport.set((inner.width, inner.height));
 
     if columns.is_empty() {
         let msg = if let Some(ref err) = app.result.error {
@@ -122,75 +269,180 @@ fn draw_table(frame: &mut Frame, app: &App, area: Rect) {
         };
         let paragraph = Paragraph::new(msg)
             .block(block)
-            .style(Style::default().fg(Color::DarkGray));
+            .style(Style::default().fg(theme.muted));
         frame.render_widget(paragraph, area);
         return;
     }
 
-    let col_offset = app.result_col_scroll;
-
-    // Compute column widths for ALL columns (needed for slicing)
-    let all_widths: Vec<u16> = columns
-        .iter()
-        .enumerate()
-        .map(|(i, col)| {
-            let max_data = rows
+    // Pinned columns followed by the scrolled slice, as display positions
+    let visible_positions: Vec<usize> = (0..pinned).chain(col_offset..visible_end).collect();
+    let visible_cols: Vec<usize> = visible_positions.iter().map(|&p| order[p]).collect();
+    let gutter = (gutter_width > 0).then_some(Constraint::Length(gutter_width));
+    let widths: Vec<Constraint> = gutter
+        .into_iter()
+        .chain(
+            visible_positions
                 .iter()
-                .map(|r| r.get(i).map(|s| s.len()).unwrap_or(0))
-                .max()
-                .unwrap_or(0);
-            col.len().max(max_data).min(50) as u16 + 2
-        })
-        .collect();
-
-    // Figure out how many columns fit in the available width (minus borders)
-    let available_width = area.width.saturating_sub(2); // borders
-    let mut total_w = 0u16;
-    let mut visible_end = col_offset;
-    for (i, &w) in all_widths.iter().enumerate().skip(col_offset) {
-        let next = total_w + w;
-        if next > available_width && visible_end > col_offset {
-            break;
-        }
-        total_w = next;
-        visible_end = i + 1;
-    }
-
-    // Slice columns
-    let visible_cols = col_offset..visible_end;
-    let widths: Vec<Constraint> = visible_cols
-        .clone()
-        .map(|i| Constraint::Length(all_widths[i]))
+                .map(|&p| Constraint::Length(all_widths[p])),
+        )
         .collect();
+    let gutter_style = Style::default().fg(theme.muted);
+    let gutter_cell = |text: String| {
+        Cell::from(format!(
+            "{:>width$}",
+            text,
+            width = gutter_width as usize - 1
+        ))
+        .style(gutter_style)
+    };
 
     // Build header (visible columns only)
-    let header_cells: Vec<Cell> = visible_cols
-        .clone()
-        .map(|i| Cell::from(columns[i].as_str()).style(Style::default().fg(Color::Cyan).bold()))
+    let header_cells: Vec<Cell> = gutter
+        .map(|_| gutter_cell("#".to_string()))
+        .into_iter()
+        .chain(visible_positions.iter().map(|&p| {
+            let i = order[p];
+            let color = if p < pinned {
+                theme.highlight
+            } else {
+                theme.accent
+            };
+            Cell::from(headers[i].as_str()).style(Style::default().fg(color).bold())
+        }))
         .collect();
     let header = Row::new(header_cells).height(1);
 
     // Build rows with vertical scroll, horizontal slice
+    let focused_cell = if focused { app.focused_cell() } else { None };
     let visible_rows: Vec<Row> = rows
         .iter()
+        .enumerate()
         .skip(app.result_scroll)
-        .map(|row_data| {
-            let cells: Vec<Cell> = visible_cols
-                .clone()
-                .map(|i| Cell::from(row_data.get(i).map(|s| s.as_str()).unwrap_or("")))
+        .take(inner.height as usize)
+        .map(|(r, row_data)| {
+            let number = gutter.map(|_| gutter_cell((r + 1).to_string()));
+            let cells: Vec<Cell> = number
+                .into_iter()
+                .chain(visible_cols.iter().map(|&i| {
+                    let value = match row_data.get(i) {
+                        Some(_) => app.result.value(rs_idx, r, i),
+                        None => Some(""),
+                    };
+                    let mut cell = Cell::from(display_value(value, type_of(i), app));
+                    if value.is_none() {
+                        cell = cell.style(null_style(theme));
+                    } else if app.cell_matches(r, i) {
+                        cell =
+                            cell.style(Style::default().fg(theme.background).bg(theme.highlight));
+                    }
+                    if focused_cell == Some((r, i)) {
+                        cell.style(Style::default().bg(theme.selection))
+                    } else {
+                        cell
+                    }
+                }))
                 .collect();
-            Row::new(cells)
+            if app.marked_rows.contains(&r) {
+                Row::new(cells).style(Style::default().fg(theme.highlight))
+            } else {
+                Row::new(cells)
+            }
         })
         .collect();
 
     let table = Table::new(visible_rows, &widths)
         .header(header)
         .block(block)
-        .row_highlight_style(Style::default().bg(Color::Rgb(49, 50, 68)));
+        .row_highlight_style(Style::default().bg(theme.selection));
 
     frame.render_widget(table, area);
 }
 
+/// Grid text for a cell: the `\pset null` text for NULL values (`None`),
+/// dates in the `\pset datestyle`, numbers in the `\pset thousands`,
+/// `\pset decimals`, and `\pset currency` format, and long binary values truncated to a short
+/// preview. The full value stays in the result set for the inspector and
+/// exports.
+fn display_value<'a>(value: Option<&'a str>, column_type: &str, app: &'a App) -> Cow<'a, str> {
+    let Some(value) = value else {
+        return Cow::Borrowed(&app.null_display);
+    };
+    if display::is_date_type(column_type) {
+        return date_text(value, column_type, app);
+    }
+    if let Cow::Owned(number) = number_text(value, column_type, app) {
+        return Cow::Owned(number);
+    }
+    let preview_len = 2 + BINARY_PREVIEW_BYTES * 2;
+    if matches!(column_type, "binary" | "varbinary" | "image")
+        && value.starts_with("0x")
+        && value.len() > preview_len
+    {
+        Cow::Owned(format!(
+            "{}… ({} bytes)",
+            &value[..preview_len],
+            (value.len() - 2) / 2
+        ))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// A number in the `\pset thousands` and `\pset decimals` format, money
+/// after the `\pset currency` symbol.
+fn number_text<'a>(value: &'a str, column_type: &str, app: &App) -> Cow<'a, str> {
+    let number = display::format_number(value, column_type, app.number_format);
+    match app.currency {
+        Some(ref symbol) => display::with_currency(number, column_type, symbol),
+        None => number,
+    }
+}
+
+/// A date or time value in the `\set timezone` zone, if it applies, and
+/// the `\pset datestyle`.
+fn date_text<'a>(value: &'a str, column_type: &str, app: &App) -> Cow<'a, str> {
+    let converted = app
+        .timezone
+        .as_ref()
+        .and_then(|zone| display::to_zone(value, column_type, zone, app.timezone_datetime2));
+    match converted {
+        Some(converted) => Cow::Owned(
+            display::format_date(&converted, "datetimeoffset", app.date_style).into_owned(),
+        ),
+        None => display::format_date(value, column_type, app.date_style),
+    }
+}
+
+/// NULL cells are dimmed and italic so they stand apart from text that
+/// reads `NULL`.
+fn null_style(theme: &Theme) -> Style {
+    Style::default().fg(theme.muted).italic()
+}
+
+/// Footer line summarising a column's aggregates.
+fn stats_footer(column: &str, stats: &ColumnStats, theme: &Theme) -> Line<'static> {
+    let mut parts = vec![format!("count {}", stats.count)];
+    if let Some(sum) = stats.sum {
+        parts.push(format!("sum {}", app::format_number(sum)));
+    }
+    if let Some(avg) = stats.avg {
+        parts.push(format!("avg {}", app::format_number(avg)));
+    }
+    if let Some(ref min) = stats.min {
+        parts.push(format!("min {}", min));
+    }
+    if let Some(ref max) = stats.max {
+        parts.push(format!("max {}", max));
+    }
+    Line::from(vec![
+        Span::styled(
+            format!(" Σ {}: ", column),
+            Style::default().fg(theme.highlight),
+        ),
+        Span::raw(format!("{} ", parts.join(" │ "))),
+    ])
+}
+
 /// Build a result set indicator string like " — Set 1/3" when there are multiple sets.
 fn result_set_indicator(app: &App) -> String {
     if app.result.result_sets.len() > 1 {
@@ -204,25 +456,18 @@ fn result_set_indicator(app: &App) -> String {
     }
 }
 
-/// Estimate how many columns are visible from the current scroll offset.
-fn visible_col_count(app: &App, area: Rect) -> usize {
-    let columns = app.result.columns_for(app.current_result_set);
-    let rows = app.result.rows_for(app.current_result_set);
-    let available = area.width.saturating_sub(2) as usize;
-    let mut total = 0;
-    let mut count = 0;
-    for (i, col) in columns.iter().enumerate().skip(app.result_col_scroll) {
-        let max_data = rows
-            .iter()
-            .map(|r| r.get(i).map(|s| s.len()).unwrap_or(0))
-            .max()
-            .unwrap_or(0);
-        let w = col.len().max(max_data).min(50) + 2;
-        total += w;
-        if total > available && count > 0 {
-            break;
-        }
-        count += 1;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_value() {
+        assert_eq!(wrap_value("abcdefg", 3), ["abc", "def", "g"]);
+        assert_eq!(wrap_value("ab\n\ncd", 10), ["ab", "", "cd"]);
+        assert_eq!(wrap_value("", 5), [""]);
+        // Wide characters take two columns
+        assert_eq!(wrap_value("日本語", 4), ["日本", "語"]);
+        // A character wider than the width still gets a line
+        assert_eq!(wrap_value("日", 1), ["日"]);
     }
-    count.max(1)
 }
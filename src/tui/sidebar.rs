@@ -1,16 +1,18 @@
 //! Object browser sidebar pane.
 
-use crate::app::{self, App, FocusPane};
+use super::theme::Theme;
+use crate::app::{self, App, FocusPane, NodeKind, ObjectNode};
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Paragraph};
 
 /// Draw the sidebar object browser.
 pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let focused = app.focus == FocusPane::Sidebar;
     let border_style = if focused {
-        Style::default().fg(Color::Cyan)
+        Style::default().fg(theme.accent)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(theme.muted)
     };
 
     let block = Block::default()
@@ -22,7 +24,7 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
     if flat.is_empty() {
         let msg = Paragraph::new("  Loading...")
             .block(block)
-            .style(Style::default().fg(Color::DarkGray));
+            .style(Style::default().fg(theme.muted));
         frame.render_widget(msg, area);
         return;
     }
@@ -30,29 +32,103 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
+    let width = inner.width as usize;
     let lines: Vec<Line> = flat
         .iter()
         .enumerate()
-        .map(|(i, (depth, name, expanded, has_children))| {
-            let indent = "  ".repeat(*depth as usize);
-            let icon = if *has_children {
-                if *expanded { "▾ " } else { "▸ " }
-            } else {
-                "  "
-            };
+        .map(|(i, node)| {
             let style = if i == app.sidebar_scroll && focused {
-                Style::default().fg(Color::Cyan).bg(Color::Rgb(49, 50, 68))
+                Style::default().fg(theme.accent).bg(theme.selection)
             } else {
-                match depth {
-                    0 => Style::default().fg(Color::Yellow),
-                    1 => Style::default().fg(Color::Green),
-                    _ => Style::default().fg(Color::White),
-                }
+                Style::default().fg(kind_color(node.kind, theme))
             };
-            Line::from(Span::styled(format!("{}{}{}", indent, icon, name), style))
+            let label = label(node, app.ascii_icons);
+            let label_width = label.chars().count();
+            if label_width > width {
+                return Line::from(Span::styled(truncate(&label, width), style));
+            }
+            let mut spans = vec![Span::styled(label, style)];
+            if let Some(ref info) = node.info {
+                let room = width - label_width;
+                if room > 1 {
+                    spans.push(Span::styled(
+                        truncate(&format!(" {}", info), room),
+                        Style::default().fg(theme.muted),
+                    ));
+                }
+            }
+            Line::from(spans)
         })
         .collect();
 
     let paragraph = Paragraph::new(lines).scroll((0, 0));
     frame.render_widget(paragraph, inner);
 }
+
+/// Indentation, expand marker, type glyph, and name of a sidebar node.
+fn label(node: &ObjectNode, ascii: bool) -> String {
+    let indent = "  ".repeat(node.depth as usize);
+    let has_children = !node.children.is_empty() || !node.loaded;
+    let marker = match (has_children, node.expanded, ascii) {
+        (false, _, _) => ' ',
+        (true, true, false) => '▾',
+        (true, false, false) => '▸',
+        (true, true, true) => '-',
+        (true, false, true) => '+',
+    };
+    match glyph(node.kind, ascii) {
+        Some(glyph) => format!("{}{}{} {}", indent, marker, glyph, node.name),
+        None => format!("{}{} {}", indent, marker, node.name),
+    }
+}
+
+/// Glyph marking a node's type; folders and details have none.
+fn glyph(kind: NodeKind, ascii: bool) -> Option<char> {
+    let (unicode, plain) = match kind {
+        NodeKind::Database => ('◆', 'D'),
+        NodeKind::Schema => ('◇', 'S'),
+        NodeKind::Table => ('▦', 'T'),
+        NodeKind::View => ('◫', 'V'),
+        NodeKind::Procedure => ('λ', 'P'),
+        NodeKind::Function => ('ƒ', 'F'),
+        NodeKind::Trigger => ('↯', 'R'),
+        NodeKind::Folder | NodeKind::Detail => return None,
+    };
+    Some(if ascii { plain } else { unicode })
+}
+
+/// Text color for each node type.
+fn kind_color(kind: NodeKind, theme: &Theme) -> Color {
+    match kind {
+        NodeKind::Database => Color::Yellow,
+        NodeKind::Schema => Color::Green,
+        NodeKind::Table => theme.text,
+        NodeKind::View => Color::LightBlue,
+        NodeKind::Procedure => Color::LightMagenta,
+        NodeKind::Function => Color::LightCyan,
+        NodeKind::Trigger => Color::LightRed,
+        NodeKind::Folder => Color::Magenta,
+        NodeKind::Detail => theme.subtle,
+    }
+}
+
+/// Cut `text` to `width` chars, ending in an ellipsis if anything was cut.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    format!("{}…", cut)
+}
+
+/// Full name of the selected node when the sidebar is too narrow to show
+/// it, for the status bar.
+pub fn truncated_selection(app: &App) -> Option<String> {
+    if !app.sidebar_visible || app.focus != FocusPane::Sidebar {
+        return None;
+    }
+    let flat = app::flatten_tree(&app.objects);
+    let node = flat.get(app.sidebar_scroll)?;
+    let inner_width = app.sidebar_width.saturating_sub(2) as usize;
+    (label(node, app.ascii_icons).chars().count() > inner_width).then(|| node.name.clone())
+}
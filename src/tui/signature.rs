@@ -0,0 +1,180 @@
+//! Parameter hints for stored procedures: after `EXEC dbo.SomeProc `, show
+//! the procedure's parameters with the one being typed highlighted.
+
+use super::theme::Theme;
+use crate::app::ProcParam;
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+/// A parameter hint being shown in the editor.
+#[derive(Debug, Clone)]
+pub struct SignatureHint {
+    /// Procedure name as typed.
+    pub proc: String,
+    /// Its parameters, in declaration order.
+    pub params: Vec<ProcParam>,
+    /// Index of the argument under the cursor.
+    pub arg: usize,
+}
+
+/// If the cursor is in the argument list of an `EXEC`/`EXECUTE` call, return
+/// the procedure name and the index of the argument being typed.
+///
+/// `before` is the editor text up to the cursor. The name must be followed
+/// by whitespace, so the hint appears once the name is complete.
+pub fn exec_context(before: &str) -> Option<(String, usize)> {
+    let upper = before.to_ascii_uppercase();
+    let start = find_last_exec(&upper)?;
+    let rest = &before[start..];
+
+    // Optional `@rc =` return-value capture
+    let mut rest = rest.trim_start();
+    if rest.starts_with('@')
+        && let Some(eq) = rest.find('=')
+    {
+        rest = rest[eq + 1..].trim_start();
+    }
+
+    let name_len = rest
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '.' | '[' | ']' | '#')))
+        .unwrap_or(rest.len());
+    let (name, args) = rest.split_at(name_len);
+    if name.is_empty() || !args.starts_with(char::is_whitespace) {
+        return None;
+    }
+    count_args(args).map(|arg| (name.to_string(), arg))
+}
+
+/// Byte offset just past the last `EXEC`/`EXECUTE` keyword.
+fn find_last_exec(upper: &str) -> Option<usize> {
+    let bytes = upper.as_bytes();
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_' || b == b'@';
+    ["EXECUTE", "EXEC"]
+        .iter()
+        .filter_map(|kw| {
+            upper.rmatch_indices(kw).find_map(|(i, _)| {
+                let end = i + kw.len();
+                let bounded = (i == 0 || !is_word(bytes[i - 1]))
+                    && end < bytes.len()
+                    && bytes[end].is_ascii_whitespace();
+                bounded.then_some(end)
+            })
+        })
+        .max()
+}
+
+/// Count commas outside string literals; `None` if the statement has ended,
+/// either at `;` or at a line break that doesn't follow a comma.
+fn count_args(args: &str) -> Option<usize> {
+    let mut count = 0;
+    let mut in_str = false;
+    let mut last = None;
+    for c in args.chars() {
+        match c {
+            '\'' => in_str = !in_str,
+            ',' if !in_str => count += 1,
+            ';' if !in_str => return None,
+            '\n' if !in_str && last.is_some_and(|l| l != ',') => return None,
+            _ => {}
+        }
+        if !c.is_whitespace() {
+            last = Some(c);
+        }
+    }
+    Some(count)
+}
+
+/// Draw the hint as a one-line box at `anchor` (the cursor's screen cell),
+/// above the cursor when there is room.
+pub fn draw(
+    frame: &mut Frame,
+    hint: &SignatureHint,
+    area: Rect,
+    anchor: (u16, u16),
+    theme: &Theme,
+) {
+    let mut spans = vec![Span::styled(
+        format!("{} ", hint.proc),
+        Style::default().fg(theme.accent),
+    )];
+    for (i, p) in hint.params.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(", "));
+        }
+        let mut text = format!("{} {}", p.name, p.type_name);
+        if let Some(ref default) = p.default {
+            text.push_str(&format!(" = {}", default));
+        }
+        if p.output {
+            text.push_str(" OUTPUT");
+        }
+        let style = if i == hint.arg {
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.text)
+        };
+        spans.push(Span::styled(text, style));
+    }
+    if hint.params.is_empty() {
+        spans.push(Span::styled(
+            "(no parameters)",
+            Style::default().fg(theme.muted),
+        ));
+    }
+
+    let line = Line::from(spans);
+    let width = (line.width() as u16 + 2).min(area.width);
+    let x = anchor.0.min(area.width.saturating_sub(width));
+    let y = if anchor.1 >= 3 {
+        anchor.1 - 3
+    } else {
+        (anchor.1 + 1).min(area.height.saturating_sub(3))
+    };
+    let popup = Rect::new(x, y, width, 3.min(area.height));
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(line).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.highlight))
+                .style(Style::default().bg(theme.popup)),
+        ),
+        popup,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exec_context() {
+        assert_eq!(
+            exec_context("EXEC dbo.GetOrders "),
+            Some(("dbo.GetOrders".into(), 0))
+        );
+        assert_eq!(
+            exec_context("exec @rc = [dbo].[Save] 1, 'a,b', "),
+            Some(("[dbo].[Save]".into(), 2))
+        );
+        assert_eq!(
+            exec_context("SELECT 1; EXECUTE sp_who 'x'"),
+            Some(("sp_who".into(), 0))
+        );
+        assert_eq!(
+            exec_context("EXEC p\n  @a = 1,\n  @b"),
+            Some(("p".into(), 1))
+        );
+    }
+
+    #[test]
+    fn test_exec_context_none() {
+        assert_eq!(exec_context("EXEC dbo.GetOrd"), None);
+        assert_eq!(exec_context("EXEC p 1;\nSELECT "), None);
+        assert_eq!(exec_context("EXEC p 1\nSELECT "), None);
+        assert_eq!(exec_context("SELECT * FROM Executions "), None);
+    }
+}
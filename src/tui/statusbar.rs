@@ -1,13 +1,60 @@
-//! Status bar showing connection info, timing, and row count.
+//! Status bar showing connection and session info, timing, and row count.
 
+use super::sidebar;
 use crate::app::App;
 use ratatui::prelude::*;
 use ratatui::widgets::Paragraph;
 
 /// Draw the status bar.
 pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
-    let left = format!(" {} | {} ", app.connection_info, app.current_database);
-    let right = if app.query_running {
+    let theme = &app.theme;
+    let mut left = format!(" {} | {} ", app.connection_info, app.current_database);
+    if let Some(ref session) = app.session {
+        left.push_str(&format!(
+            "| SQL {} {} | SPID {} ",
+            session.version, session.edition, session.spid
+        ));
+        match session.encrypted {
+            Some(true) => left.push_str("| encrypted "),
+            Some(false) => left.push_str("| unencrypted "),
+            None => {}
+        }
+        if session.transactions > 0 {
+            left.push_str(&format!("| open transactions: {} ", session.transactions));
+        }
+    }
+    if let Some(ref tee) = app.tee {
+        left.push_str(&format!("| \\o {} ", tee.display()));
+    }
+    if let Some(ref watch) = app.watch {
+        left.push_str(&format!(
+            "| \\watch {}s: run {}",
+            watch.interval.as_secs(),
+            watch.iterations
+        ));
+        if let Some(last) = watch.last_run {
+            left.push_str(&format!(", {}s ago", last.elapsed().as_secs()));
+        }
+        left.push_str(" (any key stops) ");
+    }
+    if let Some(ref health) = app.health {
+        match health.snapshot {
+            _ if health.failed => left.push_str("| health: needs VIEW SERVER STATE "),
+            Some(h) => left.push_str(&format!(
+                "| {} active, {} blocked, tempdb {} MB ",
+                h.active_sessions, h.blocked_sessions, h.tempdb_used_mb
+            )),
+            None => {}
+        }
+    }
+    if let Some(name) = sidebar::truncated_selection(app) {
+        left.push_str(&format!("| {} ", name));
+    }
+    let right = if let Some(ref notice) = app.notice {
+        format!(" {} ", notice)
+    } else if let Some(rows) = app.fetched_rows {
+        format!(" ⏳ Fetched {} rows... ", rows)
+    } else if app.query_running {
         " ⏳ Running... ".to_string()
     } else if !app.result.columns_for(app.current_result_set).is_empty() {
         let set_info = if app.result.result_sets.len() > 1 {
@@ -35,6 +82,6 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
     let status = format!("{}{}{}", left, " ".repeat(padding), right);
 
     let paragraph =
-        Paragraph::new(status).style(Style::default().fg(Color::White).bg(Color::Rgb(49, 50, 68)));
+        Paragraph::new(status).style(Style::default().fg(theme.text).bg(theme.selection));
     frame.render_widget(paragraph, area);
 }
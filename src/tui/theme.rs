@@ -0,0 +1,123 @@
+//! Color themes for the UI chrome: backgrounds, borders, selections, and
+//! overlays. Chosen with `theme = <name>` in the config file.
+
+use ratatui::style::Color;
+
+/// Colors used by the draw functions, by role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Background of the title bar, key bar, panes, and overlays.
+    pub background: Color,
+    /// Normal text.
+    pub text: Color,
+    /// Secondary text: hints, unfocused borders, line numbers.
+    pub muted: Color,
+    /// Text between `text` and `muted`, e.g. previews.
+    pub subtle: Color,
+    /// Focused borders, column headers, and the selected list item.
+    pub accent: Color,
+    /// Text drawn on an `accent` or `highlight` background.
+    pub on_accent: Color,
+    /// Overlay borders, pinned columns, and search matches.
+    pub highlight: Color,
+    /// Errors and destructive prompts.
+    pub error: Color,
+    /// Background of the selected row or cell and the status bar.
+    pub selection: Color,
+    /// Background of small popups (autocomplete, parameter hints).
+    pub popup: Color,
+}
+
+/// Names accepted by [`Theme::named`].
+pub const THEME_NAMES: &[&str] = &["default", "gruvbox", "solarized", "dracula", "plain"];
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background: Color::Rgb(30, 30, 46),
+            text: Color::White,
+            muted: Color::DarkGray,
+            subtle: Color::Gray,
+            accent: Color::Cyan,
+            on_accent: Color::Black,
+            highlight: Color::Yellow,
+            error: Color::Red,
+            selection: Color::Rgb(49, 50, 68),
+            popup: Color::Rgb(40, 40, 60),
+        }
+    }
+}
+
+impl Theme {
+    /// A built-in theme by name (case-insensitive).
+    pub fn named(name: &str) -> Option<Self> {
+        let theme = match name.to_ascii_lowercase().as_str() {
+            "default" => Self::default(),
+            "gruvbox" => Self {
+                background: Color::Rgb(40, 40, 40),
+                text: Color::Rgb(235, 219, 178),
+                muted: Color::Rgb(146, 131, 116),
+                subtle: Color::Rgb(168, 153, 132),
+                accent: Color::Rgb(131, 165, 152),
+                on_accent: Color::Rgb(40, 40, 40),
+                highlight: Color::Rgb(250, 189, 47),
+                error: Color::Rgb(251, 73, 52),
+                selection: Color::Rgb(60, 56, 54),
+                popup: Color::Rgb(50, 48, 47),
+            },
+            "solarized" => Self {
+                background: Color::Rgb(0, 43, 54),
+                text: Color::Rgb(147, 161, 161),
+                muted: Color::Rgb(88, 110, 117),
+                subtle: Color::Rgb(131, 148, 150),
+                accent: Color::Rgb(42, 161, 152),
+                on_accent: Color::Rgb(0, 43, 54),
+                highlight: Color::Rgb(181, 137, 0),
+                error: Color::Rgb(220, 50, 47),
+                selection: Color::Rgb(7, 54, 66),
+                popup: Color::Rgb(7, 54, 66),
+            },
+            "dracula" => Self {
+                background: Color::Rgb(40, 42, 54),
+                text: Color::Rgb(248, 248, 242),
+                muted: Color::Rgb(98, 114, 164),
+                subtle: Color::Rgb(191, 191, 191),
+                accent: Color::Rgb(139, 233, 253),
+                on_accent: Color::Rgb(40, 42, 54),
+                highlight: Color::Rgb(241, 250, 140),
+                error: Color::Rgb(255, 85, 85),
+                selection: Color::Rgb(68, 71, 90),
+                popup: Color::Rgb(52, 55, 70),
+            },
+            // The terminal's own 16 colors, for terminals without true color
+            "plain" => Self {
+                background: Color::Reset,
+                text: Color::Reset,
+                muted: Color::DarkGray,
+                subtle: Color::Gray,
+                accent: Color::Cyan,
+                on_accent: Color::Black,
+                highlight: Color::Yellow,
+                error: Color::Red,
+                selection: Color::DarkGray,
+                popup: Color::Reset,
+            },
+            _ => return None,
+        };
+        Some(theme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named() {
+        for name in THEME_NAMES {
+            assert!(Theme::named(name).is_some(), "{}", name);
+        }
+        assert_eq!(Theme::named("Default"), Some(Theme::default()));
+        assert_eq!(Theme::named("nope"), None);
+    }
+}
@@ -1,13 +1,18 @@
 //! Main UI layout and rendering.
 
-use crate::app::App;
+use crate::app::{App, FocusPane};
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
 
-use super::{autocomplete, editor, results, sidebar, statusbar};
+use super::theme::Theme;
+use super::{
+    autocomplete, chart, compare, editor, history_search, inspector, picker, plan, prompt, results,
+    sidebar, signature, statusbar,
+};
 
 /// Draw the entire TUI.
 pub fn draw(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
     let size = frame.area();
 
     // Main layout: title bar, content, status bar, keybindings
@@ -21,12 +26,24 @@ pub fn draw(frame: &mut Frame, app: &App) {
         ])
         .split(size);
 
-    // Title bar
-    let title = Paragraph::new(format!(
+    // Title bar, with workspace tabs once there is more than one
+    let mut title = vec![Span::raw(format!(
         " 🐱 meow — connected to {} ({})",
         app.connection_info, app.current_database
-    ))
-    .style(Style::default().fg(Color::White).bg(Color::Rgb(30, 30, 46)));
+    ))];
+    if app.workspaces.len() > 1 {
+        title.push(Span::raw("  "));
+        for (i, ws) in app.workspaces.iter().enumerate() {
+            let style = if i == app.active_workspace {
+                Style::default().fg(theme.on_accent).bg(theme.accent)
+            } else {
+                Style::default().fg(theme.muted)
+            };
+            title.push(Span::styled(format!(" {} {} ", i + 1, ws.name), style));
+        }
+    }
+    let title = Paragraph::new(Line::from(title))
+        .style(Style::default().fg(theme.text).bg(theme.background));
     frame.render_widget(title, chunks[0]);
 
     // Content area: sidebar | (editor / results)
@@ -34,8 +51,8 @@ pub fn draw(frame: &mut Frame, app: &App) {
         let content_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
-                Constraint::Length(22), // sidebar
-                Constraint::Min(30),    // editor + results
+                Constraint::Length(app.sidebar_width), // sidebar
+                Constraint::Min(30),                   // editor + results
             ])
             .split(chunks[1]);
 
@@ -49,21 +66,74 @@ pub fn draw(frame: &mut Frame, app: &App) {
     statusbar::draw(frame, app, chunks[2]);
 
     // Key bindings bar
-    let keys_text = if app.result.result_sets.len() > 1 {
+    let keys_text = if app.blocking_view {
+        " Ctrl+Enter: Run │ Tab: Switch Pane │ k: \\kill Head Blocker │ s: \\session (Results) │ Ctrl+Q: Quit │ F1: Help"
+    } else if app.result.result_sets.len() > 1 {
         " Ctrl+Enter: Run │ Tab: Switch Pane │ [/]: Prev/Next Set │ Ctrl+D: Sidebar │ Ctrl+Q: Quit │ F1: Help"
     } else {
         " Ctrl+Enter: Run │ Tab: Switch Pane │ Ctrl+D: Sidebar │ Ctrl+Q: Quit │ F1: Help"
     };
-    let keys = Paragraph::new(keys_text).style(
-        Style::default()
-            .fg(Color::DarkGray)
-            .bg(Color::Rgb(30, 30, 46)),
-    );
+    let keys =
+        Paragraph::new(keys_text).style(Style::default().fg(theme.muted).bg(theme.background));
     frame.render_widget(keys, chunks[3]);
 
     // Help overlay
     if app.show_help {
-        draw_help_overlay(frame, size);
+        draw_help_overlay(frame, size, theme);
+    }
+
+    // Value inspector overlay
+    if let Some(ref insp) = app.inspector {
+        inspector::draw(frame, insp, centered_rect(80, 80, size), theme);
+    }
+
+    // Bar chart overlay
+    if let Some(ref view) = app.chart {
+        chart::draw(frame, view, centered_rect(80, 80, size), theme);
+    }
+
+    // Result comparison overlay
+    if let Some(ref view) = app.compare {
+        compare::draw(frame, view, centered_rect(95, 90, size), theme);
+    }
+
+    // Estimated plan overlay
+    if let Some(ref view) = app.plan {
+        plan::draw(frame, view, centered_rect(90, 85, size), theme);
+    }
+
+    // Reverse history search overlay
+    if let Some(ref search) = app.history_search {
+        history_search::draw(
+            frame,
+            search,
+            &app.history,
+            &app.bookmarks,
+            centered_rect(70, 60, size),
+            theme,
+        );
+    }
+
+    // List picker overlay (snippets, ...)
+    if let Some(ref p) = app.picker {
+        picker::draw(frame, p, centered_rect(80, 70, size), theme);
+    }
+
+    // Single-line prompt overlay (save as, ...)
+    if let Some(ref p) = app.prompt {
+        prompt::draw(frame, p, centered_box(60, 3, size), theme);
+    }
+
+    // Unsaved-changes dialog
+    if app.confirm_discard.is_some() {
+        draw_confirm_discard(frame, app, centered_box(60, 5, size));
+    }
+
+    // Parameter hint for the EXEC call being typed
+    if let Some(ref hint) = app.signature
+        && app.focus == FocusPane::Editor
+    {
+        signature::draw(frame, hint, size, cursor_anchor(app), theme);
     }
 
     // Autocomplete popup overlay
@@ -87,7 +157,7 @@ fn draw_editor_results(frame: &mut Frame, app: &App, area: Rect) {
 }
 
 /// Draw the help overlay.
-fn draw_help_overlay(frame: &mut Frame, area: Rect) {
+fn draw_help_overlay(frame: &mut Frame, area: Rect, theme: &Theme) {
     let help_area = centered_rect(60, 70, area);
     frame.render_widget(Clear, help_area);
 
@@ -98,16 +168,42 @@ fn draw_help_overlay(frame: &mut Frame, area: Rect) {
         "  Tab                Cycle focus (Editor → Results → Sidebar)",
         "  Ctrl+D             Toggle sidebar",
         "  Ctrl+L             Clear editor",
+        "  Ctrl+O / Ctrl+S    Open / save a .sql file",
+        "  Alt+E              Edit the query in $EDITOR",
+        "  Alt+↑/↓            Move line(s) up/down",
+        "  Alt+Shift+↓        Duplicate line(s)",
+        "  Ctrl+R             Search query history (Ctrl+S stars)",
+        "  F2                 Quick access: starred and recent queries",
+        "  Ctrl+T / Alt+1..9  New workspace / switch workspace",
         "  Ctrl+Q             Quit",
         "  F1                 Toggle this help",
         "",
         "  Results pane:",
         "    ↑/↓              Scroll results",
+        "    ←/→              Scroll columns",
+        "    Shift+←/→        Scroll a screenful of columns",
+        "    Shift+Home/End   First / last column",
+        "    < / >            Move focused column left / right",
+        "    PgUp/PgDn        Scroll a page",
+        "    Home/End         First / last row",
+        "    Enter            Inspect focused cell (JSON/XML/hex, s: save)",
+        "    a                Toggle count/sum/avg/min/max footer",
+        "    e                Insert focused cell into the editor",
+        "    Space / i        Mark row / INSERT statements for marked rows",
+        "    u                Edit cell (UPDATE by primary key, confirmed first)",
+        "    / then n/N       Search cells / next / previous match",
+        "    : or g / gg / G  Go to row / first row / last row",
         "    [ / ]            Previous / next result set",
+        "    n/p, PgDn/PgUp   Next / previous record (expanded mode)",
         "",
         "  Sidebar:",
         "    ↑/↓              Navigate",
-        "    Enter            Expand/collapse",
+        "    Enter            Expand/collapse (twice: insert name)",
+        "    i                Insert quoted name at the editor cursor",
+        "    s                Show/hide table row counts and sizes",
+        "    < / >            Narrow/widen the sidebar",
+        "    v                View definition of a view, procedure, ...",
+        "    d                Describe table, view, procedure, ... (sp_help)",
         "",
         "  Press F1 to close",
     ];
@@ -117,9 +213,9 @@ fn draw_help_overlay(frame: &mut Frame, area: Rect) {
             Block::default()
                 .borders(Borders::ALL)
                 .title(" Help ")
-                .border_style(Style::default().fg(Color::Cyan)),
+                .border_style(Style::default().fg(theme.accent)),
         )
-        .style(Style::default().fg(Color::White).bg(Color::Rgb(30, 30, 46)))
+        .style(Style::default().fg(theme.text).bg(theme.background))
         .wrap(Wrap { trim: false });
 
     frame.render_widget(paragraph, help_area);
@@ -146,33 +242,86 @@ fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
-/// Draw the autocomplete popup near the cursor.
-fn draw_autocomplete(frame: &mut Frame, app: &App, area: Rect) {
-    let max_items = 8usize;
-    let suggestions = &app.autocomplete.suggestions;
-    let count = suggestions.len().min(max_items);
-    if count == 0 {
-        return;
-    }
+/// A rect `percent_x` wide and `height` rows tall, centered in `area`.
+fn centered_box(percent_x: u16, height: u16, area: Rect) -> Rect {
+    let width = area.width * percent_x / 100;
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Draw the "save changes?" dialog shown before discarding an edited file.
+fn draw_confirm_discard(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let name = app
+        .file
+        .as_ref()
+        .map(|f| f.path.display().to_string())
+        .unwrap_or_default();
+    frame.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Unsaved changes ")
+        .border_style(Style::default().fg(theme.error))
+        .style(Style::default().bg(theme.background));
+    let text = vec![
+        Line::from(format!("{} has unsaved changes. Save them?", name)),
+        Line::from(""),
+        Line::from(Span::styled(
+            "y: Save │ n: Discard │ Esc: Cancel",
+            Style::default().fg(theme.muted),
+        )),
+    ];
+    frame.render_widget(Paragraph::new(text).block(block), area);
+}
 
+/// Approximate screen position of the editor cursor, for popups.
+fn cursor_anchor(app: &App) -> (u16, u16) {
     // Figure out cursor position in the terminal.
     // The editor is inside content area. We approximate:
     // row 0 = title bar, then content starts at row 1.
-    // If sidebar visible, editor starts at x=22+1 (border), else x=1.
+    // If sidebar visible, editor starts after it plus 1 (border), else x=1.
     // Editor area starts at row 1 (title) + 1 (border).
     let cursor = app.editor.cursor();
-    let editor_x_offset: u16 = if app.sidebar_visible { 23 } else { 1 };
+    let editor_x_offset: u16 = if app.sidebar_visible {
+        app.sidebar_width + 1
+    } else {
+        1
+    };
     // Line numbers take ~4 chars, plus 1 border
     let line_num_width: u16 = 5;
     let cursor_x = editor_x_offset + line_num_width + cursor.1 as u16;
     // Title bar (1) + editor border (1) + cursor row - scroll offset
     let cursor_y = 2 + cursor.0 as u16;
+    (cursor_x, cursor_y)
+}
+
+/// Draw the autocomplete popup near the cursor.
+fn draw_autocomplete(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let max_items = 8usize;
+    let suggestions = &app.autocomplete.suggestions;
+    let count = suggestions.len().min(max_items);
+    if count == 0 {
+        return;
+    }
+
+    let (cursor_x, cursor_y) = cursor_anchor(app);
 
     // Position popup below cursor
     let popup_y = (cursor_y + 1).min(area.height.saturating_sub(count as u16 + 2));
     let popup_x = cursor_x.min(area.width.saturating_sub(22));
 
-    let width = 20u16;
+    let longest = suggestions
+        .iter()
+        .map(|s| s.text.chars().count())
+        .max()
+        .unwrap_or(0);
+    let width = (longest as u16 + 2).clamp(20, 60);
     let height = count as u16 + 2; // +2 for borders
 
     let popup_area = Rect::new(
@@ -184,15 +333,19 @@ fn draw_autocomplete(frame: &mut Frame, app: &App, area: Rect) {
 
     frame.render_widget(Clear, popup_area);
 
+    // Scroll so the selection stays visible
+    let first = app.autocomplete.selected.saturating_sub(max_items - 1);
     let items: Vec<Line> = suggestions
         .iter()
-        .take(max_items)
         .enumerate()
-        .map(|(i, kw)| {
+        .skip(first)
+        .take(max_items)
+        .map(|(i, s)| {
             if i == app.autocomplete.selected {
-                Line::from(*kw).style(Style::default().fg(Color::Black).bg(Color::Cyan))
+                Line::from(s.text.as_str())
+                    .style(Style::default().fg(theme.on_accent).bg(theme.accent))
             } else {
-                Line::from(*kw).style(Style::default().fg(Color::White))
+                Line::from(s.text.as_str()).style(Style::default().fg(theme.text))
             }
         })
         .collect();
@@ -200,8 +353,8 @@ fn draw_autocomplete(frame: &mut Frame, app: &App, area: Rect) {
     let popup = Paragraph::new(items).block(
         Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Yellow))
-            .style(Style::default().bg(Color::Rgb(40, 40, 60))),
+            .border_style(Style::default().fg(theme.highlight))
+            .style(Style::default().bg(theme.popup)),
     );
 
     frame.render_widget(popup, popup_area);
@@ -0,0 +1,580 @@
+//! Modal Vim emulation for the SQL editor.
+//!
+//! Translates key presses into tui-textarea edits. Insert mode hands keys back
+//! to the normal editor path (so autocomplete keeps working); the other modes
+//! consume them. `:` commands are returned to the caller, which runs them as
+//! slash commands (`:d users` is `\d users`).
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui_textarea::{CursorMove, Scrolling, TextArea};
+
+/// Current Vim mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimMode {
+    Normal,
+    Insert,
+    /// Characterwise visual selection (`v`).
+    Visual,
+    /// Linewise visual selection (`V`), anchored at this row.
+    VisualLine(usize),
+    /// Typing a `:` command.
+    Command,
+}
+
+/// What the editor should do with a key after Vim has seen it.
+#[derive(Debug, PartialEq, Eq)]
+pub enum VimOutcome {
+    /// The key was handled.
+    Consumed,
+    /// Insert mode: pass the key to the editor as usual.
+    Insert,
+    /// A `:` command line was entered (without the colon).
+    Command(String),
+}
+
+/// State of the Vim emulation layer.
+#[derive(Debug)]
+pub struct Vim {
+    pub mode: VimMode,
+    /// Operator waiting for a motion (`d`, `y` or `c`).
+    operator: Option<char>,
+    /// Saw `g`, waiting for the second key of `gg`.
+    pending_g: bool,
+    /// Repeat count typed so far (0 if none).
+    count: usize,
+    /// Whether the yank register holds whole lines (`yy`, `dd`, `V`).
+    linewise: bool,
+    /// Text typed after `:`.
+    pub command: String,
+}
+
+impl Default for Vim {
+    fn default() -> Self {
+        Self {
+            mode: VimMode::Normal,
+            operator: None,
+            pending_g: false,
+            count: 0,
+            linewise: false,
+            command: String::new(),
+        }
+    }
+}
+
+impl Vim {
+    /// Short mode name for the editor title.
+    pub fn mode_label(&self) -> &'static str {
+        match self.mode {
+            VimMode::Normal if self.operator.is_some() => "OPERATOR",
+            VimMode::Normal => "NORMAL",
+            VimMode::Insert => "INSERT",
+            VimMode::Visual => "VISUAL",
+            VimMode::VisualLine(_) => "VISUAL LINE",
+            VimMode::Command => "COMMAND",
+        }
+    }
+
+    /// Handle a key press against the editor.
+    pub fn handle_key(&mut self, key: KeyEvent, ta: &mut TextArea) -> VimOutcome {
+        match self.mode {
+            VimMode::Insert => {
+                if key.code == KeyCode::Esc {
+                    self.mode = VimMode::Normal;
+                    ta.move_cursor(CursorMove::Back);
+                    return VimOutcome::Consumed;
+                }
+                VimOutcome::Insert
+            }
+            VimMode::Command => self.command_key(key),
+            _ => {
+                self.normal_key(key, ta);
+                VimOutcome::Consumed
+            }
+        }
+    }
+
+    fn command_key(&mut self, key: KeyEvent) -> VimOutcome {
+        match key.code {
+            KeyCode::Esc => self.mode = VimMode::Normal,
+            KeyCode::Enter => {
+                self.mode = VimMode::Normal;
+                let cmd = std::mem::take(&mut self.command);
+                if !cmd.trim().is_empty() {
+                    return VimOutcome::Command(cmd.trim().to_string());
+                }
+            }
+            KeyCode::Backspace => {
+                if self.command.is_empty() {
+                    self.mode = VimMode::Normal;
+                } else {
+                    self.command.pop();
+                }
+            }
+            KeyCode::Char(c) => self.command.push(c),
+            _ => {}
+        }
+        VimOutcome::Consumed
+    }
+
+    fn reset_pending(&mut self) {
+        self.operator = None;
+        self.pending_g = false;
+        self.count = 0;
+    }
+
+    fn normal_key(&mut self, key: KeyEvent, ta: &mut TextArea) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('u') => ta.scroll(Scrolling::HalfPageUp),
+                KeyCode::Char('f') => ta.scroll(Scrolling::PageDown),
+                KeyCode::Char('b') => ta.scroll(Scrolling::PageUp),
+                KeyCode::Char('e') => ta.scroll((1, 0)),
+                KeyCode::Char('y') => ta.scroll((-1, 0)),
+                _ => {}
+            }
+            return;
+        }
+
+        let count = self.count.max(1);
+        let visual = matches!(self.mode, VimMode::Visual | VimMode::VisualLine(_));
+
+        // Count prefix (a leading 0 is the "line start" motion)
+        if let KeyCode::Char(c @ '0'..='9') = key.code
+            && (c != '0' || self.count > 0)
+        {
+            self.count = self.count * 10 + c.to_digit(10).unwrap() as usize;
+            return;
+        }
+
+        if key.code == KeyCode::Char('g') && !self.pending_g {
+            self.pending_g = true;
+            return;
+        }
+
+        // Motions, possibly completing a pending operator. Like Vim, `cw`
+        // changes to the end of the word.
+        let code = match (self.operator, key.code) {
+            (Some('c'), KeyCode::Char('w')) => KeyCode::Char('e'),
+            (_, code) => code,
+        };
+        if let Some(moved) = self.motion(code, count, ta) {
+            self.pending_g = false;
+            self.count = 0;
+            if !moved {
+                if self.operator.take().is_some() {
+                    ta.cancel_selection();
+                }
+                return;
+            }
+            if let VimMode::VisualLine(anchor) = self.mode {
+                select_lines(ta, anchor, ta.cursor().0);
+            }
+            if let Some(op) = self.operator.take() {
+                if code == KeyCode::Char('e') {
+                    // Inclusive motions take the character under the cursor
+                    ta.move_cursor(CursorMove::Forward);
+                }
+                self.apply_charwise(op, ta);
+            }
+            return;
+        }
+        self.pending_g = false;
+
+        match key.code {
+            KeyCode::Esc => {
+                ta.cancel_selection();
+                self.mode = VimMode::Normal;
+            }
+            KeyCode::Char(op @ ('d' | 'y' | 'c')) if self.mode == VimMode::Normal => {
+                if self.operator == Some(op) {
+                    // dd / yy / cc
+                    self.operator = None;
+                    ta.cancel_selection();
+                    let row = ta.cursor().0;
+                    self.apply_linewise(op, row, row + count - 1, ta);
+                    self.reset_pending();
+                } else {
+                    self.operator = Some(op);
+                    ta.start_selection();
+                }
+                return;
+            }
+            KeyCode::Char(op @ ('d' | 'x' | 'y' | 'c')) if visual => {
+                let op = if op == 'x' { 'd' } else { op };
+                if let VimMode::VisualLine(anchor) = self.mode {
+                    let row = ta.cursor().0;
+                    ta.cancel_selection();
+                    self.apply_linewise(op, anchor.min(row), anchor.max(row), ta);
+                } else {
+                    // Vim's selection includes the character under the cursor
+                    ta.move_cursor(CursorMove::Forward);
+                    self.apply_charwise(op, ta);
+                }
+            }
+            KeyCode::Char('v') if self.mode == VimMode::Normal => {
+                ta.start_selection();
+                self.mode = VimMode::Visual;
+            }
+            KeyCode::Char('V') if self.mode == VimMode::Normal => {
+                let row = ta.cursor().0;
+                select_lines(ta, row, row);
+                self.mode = VimMode::VisualLine(row);
+            }
+            KeyCode::Char('v' | 'V') if visual => {
+                ta.cancel_selection();
+                self.mode = VimMode::Normal;
+            }
+            KeyCode::Char('i') => self.insert(ta),
+            KeyCode::Char('a') => {
+                self.insert(ta);
+                ta.move_cursor(CursorMove::Forward);
+            }
+            KeyCode::Char('I') => {
+                self.insert(ta);
+                ta.move_cursor(CursorMove::Head);
+            }
+            KeyCode::Char('A') => {
+                self.insert(ta);
+                ta.move_cursor(CursorMove::End);
+            }
+            KeyCode::Char('o') => {
+                self.insert(ta);
+                ta.move_cursor(CursorMove::End);
+                ta.insert_newline();
+            }
+            KeyCode::Char('O') => {
+                self.insert(ta);
+                ta.move_cursor(CursorMove::Head);
+                ta.insert_newline();
+                ta.move_cursor(CursorMove::Up);
+            }
+            KeyCode::Char('x') => {
+                // One edit, so `u` undoes it whole; stops at the end of the line
+                let (row, col) = ta.cursor();
+                let rest = ta.lines()[row].chars().count().saturating_sub(col);
+                if ta.delete_str(count.min(rest)) {
+                    self.linewise = false;
+                }
+            }
+            KeyCode::Char('D') => {
+                ta.delete_line_by_end();
+            }
+            KeyCode::Char('C') => {
+                ta.delete_line_by_end();
+                self.insert(ta);
+            }
+            KeyCode::Char('p') => self.paste(ta, true),
+            KeyCode::Char('P') => self.paste(ta, false),
+            KeyCode::Char('u') => {
+                for _ in 0..count {
+                    ta.undo();
+                }
+            }
+            KeyCode::Char(':') => {
+                ta.cancel_selection();
+                self.mode = VimMode::Command;
+                self.command.clear();
+            }
+            _ => {}
+        }
+        self.reset_pending();
+    }
+
+    /// Apply a motion key `count` times. Returns `None` if the key is not a
+    /// motion, otherwise whether the cursor moved.
+    fn motion(&self, code: KeyCode, count: usize, ta: &mut TextArea) -> Option<bool> {
+        let before = ta.cursor();
+        let step = match code {
+            KeyCode::Char('h') | KeyCode::Left => CursorMove::Back,
+            KeyCode::Char('l') | KeyCode::Right => CursorMove::Forward,
+            KeyCode::Char('j') | KeyCode::Down => CursorMove::Down,
+            KeyCode::Char('k') | KeyCode::Up => CursorMove::Up,
+            KeyCode::Char('w') => CursorMove::WordForward,
+            KeyCode::Char('b') => CursorMove::WordBack,
+            KeyCode::Char('e') => CursorMove::WordEnd,
+            KeyCode::Char('}') => CursorMove::ParagraphForward,
+            KeyCode::Char('{') => CursorMove::ParagraphBack,
+            KeyCode::Char('0' | '^') | KeyCode::Home => {
+                ta.move_cursor(CursorMove::Head);
+                return Some(ta.cursor() != before || self.operator.is_none());
+            }
+            KeyCode::Char('$') | KeyCode::End => {
+                ta.move_cursor(CursorMove::End);
+                return Some(ta.cursor() != before || self.operator.is_none());
+            }
+            KeyCode::Char('G') => {
+                if self.count > 0 {
+                    jump_to_line(ta, self.count);
+                } else {
+                    ta.move_cursor(CursorMove::Bottom);
+                }
+                return Some(true);
+            }
+            KeyCode::Char('g') if self.pending_g => {
+                jump_to_line(ta, count);
+                return Some(true);
+            }
+            _ => return None,
+        };
+        for _ in 0..count {
+            ta.move_cursor(step);
+        }
+        Some(ta.cursor() != before || self.operator.is_none())
+    }
+
+    fn insert(&mut self, ta: &mut TextArea) {
+        ta.cancel_selection();
+        self.mode = VimMode::Insert;
+    }
+
+    /// Apply `op` to the current selection.
+    fn apply_charwise(&mut self, op: char, ta: &mut TextArea) {
+        self.linewise = false;
+        match op {
+            'y' => {
+                let start = ta.selection_range().map(|(start, _)| start);
+                ta.copy();
+                if let Some((row, col)) = start {
+                    ta.move_cursor(CursorMove::Jump(row as u16, col as u16));
+                }
+                self.mode = VimMode::Normal;
+            }
+            'c' => {
+                ta.cut();
+                self.mode = VimMode::Insert;
+            }
+            _ => {
+                ta.cut();
+                self.mode = VimMode::Normal;
+            }
+        }
+    }
+
+    /// Apply `op` to whole lines `first..=last`.
+    fn apply_linewise(&mut self, op: char, first: usize, last: usize, ta: &mut TextArea) {
+        let lines = ta.lines();
+        let last = last.min(lines.len() - 1);
+        let text = lines[first..=last].join("\n");
+        let chars = text.chars().count();
+        let total = lines.len();
+        let prev_len = first
+            .checked_sub(1)
+            .map(|r| lines[r].chars().count())
+            .unwrap_or(0);
+
+        match op {
+            'y' => {}
+            'c' => {
+                ta.move_cursor(CursorMove::Jump(first as u16, 0));
+                ta.delete_str(chars);
+            }
+            _ => {
+                if last + 1 < total {
+                    ta.move_cursor(CursorMove::Jump(first as u16, 0));
+                    ta.delete_str(chars + 1);
+                } else if first > 0 {
+                    // Deleting through the last line: take the preceding newline
+                    ta.move_cursor(CursorMove::Jump(first as u16 - 1, prev_len as u16));
+                    ta.delete_str(chars + 1);
+                    ta.move_cursor(CursorMove::Head);
+                } else {
+                    ta.move_cursor(CursorMove::Jump(0, 0));
+                    ta.delete_str(chars);
+                }
+            }
+        }
+        ta.set_yank_text(text);
+        self.linewise = true;
+        self.mode = if op == 'c' {
+            VimMode::Insert
+        } else {
+            VimMode::Normal
+        };
+        if op == 'y' {
+            ta.move_cursor(CursorMove::Jump(first as u16, 0));
+        }
+    }
+
+    /// `p` (after the cursor) and `P` (before it).
+    fn paste(&self, ta: &mut TextArea, after: bool) {
+        if self.linewise {
+            let text = ta.yank_text();
+            let row = ta.cursor().0;
+            if after {
+                ta.move_cursor(CursorMove::End);
+                ta.insert_newline();
+                ta.insert_str(&text);
+                ta.move_cursor(CursorMove::Jump(row as u16 + 1, 0));
+            } else {
+                ta.move_cursor(CursorMove::Head);
+                ta.insert_str(&text);
+                ta.insert_newline();
+                ta.move_cursor(CursorMove::Jump(row as u16, 0));
+            }
+        } else {
+            if after {
+                ta.move_cursor(CursorMove::Forward);
+            }
+            ta.paste();
+        }
+    }
+}
+
+/// Move to 1-based line `line`, clamped to the buffer.
+fn jump_to_line(ta: &mut TextArea, line: usize) {
+    let row = line.saturating_sub(1).min(ta.lines().len() - 1);
+    ta.move_cursor(CursorMove::Jump(row as u16, 0));
+}
+
+/// Select whole lines between `anchor` and `row`, leaving the cursor on `row`.
+fn select_lines(ta: &mut TextArea, anchor: usize, row: usize) {
+    ta.cancel_selection();
+    if row >= anchor {
+        ta.move_cursor(CursorMove::Jump(anchor as u16, 0));
+        ta.start_selection();
+        ta.move_cursor(CursorMove::Jump(row as u16, 0));
+        ta.move_cursor(CursorMove::End);
+    } else {
+        ta.move_cursor(CursorMove::Jump(anchor as u16, 0));
+        ta.move_cursor(CursorMove::End);
+        ta.start_selection();
+        ta.move_cursor(CursorMove::Jump(row as u16, 0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str) -> TextArea<'static> {
+        TextArea::from(text.lines().map(str::to_string))
+    }
+
+    /// Feed `keys` to Vim one character at a time, returning the last outcome.
+    fn press(vim: &mut Vim, ta: &mut TextArea, keys: &str) -> VimOutcome {
+        let mut outcome = VimOutcome::Consumed;
+        for c in keys.chars() {
+            let code = match c {
+                '\x1b' => KeyCode::Esc,
+                '\n' => KeyCode::Enter,
+                '\x08' => KeyCode::Backspace,
+                c => KeyCode::Char(c),
+            };
+            outcome = vim.handle_key(KeyEvent::new(code, KeyModifiers::NONE), ta);
+        }
+        outcome
+    }
+
+    #[test]
+    fn test_motions() {
+        let mut vim = Vim::default();
+        let mut ta = editor("select id, name\nfrom users\nwhere id = 1");
+        press(&mut vim, &mut ta, "w");
+        assert_eq!(ta.cursor(), (0, 7));
+        press(&mut vim, &mut ta, "2l");
+        assert_eq!(ta.cursor(), (0, 9));
+        press(&mut vim, &mut ta, "$");
+        assert_eq!(ta.cursor(), (0, 15));
+        press(&mut vim, &mut ta, "0");
+        assert_eq!(ta.cursor(), (0, 0));
+        press(&mut vim, &mut ta, "2j");
+        assert_eq!(ta.cursor().0, 2);
+        press(&mut vim, &mut ta, "gg");
+        assert_eq!(ta.cursor(), (0, 0));
+        press(&mut vim, &mut ta, "G");
+        assert_eq!(ta.cursor().0, 2);
+        press(&mut vim, &mut ta, "2G");
+        assert_eq!(ta.cursor(), (1, 0));
+        press(&mut vim, &mut ta, "e");
+        assert_eq!(ta.cursor(), (1, 3));
+        press(&mut vim, &mut ta, "b");
+        assert_eq!(ta.cursor(), (1, 0));
+        assert_eq!(vim.mode, VimMode::Normal);
+    }
+
+    #[test]
+    fn test_operators() {
+        let mut vim = Vim::default();
+        let mut ta = editor("select id, name\nfrom users\nwhere id = 1");
+        press(&mut vim, &mut ta, "dw");
+        assert_eq!(ta.lines()[0], "id, name");
+
+        press(&mut vim, &mut ta, "2x");
+        assert_eq!(ta.lines()[0], ", name");
+        press(&mut vim, &mut ta, "u");
+        assert_eq!(ta.lines()[0], "id, name");
+
+        // dd takes the whole line; p puts it back below the cursor
+        press(&mut vim, &mut ta, "dd");
+        assert_eq!(ta.lines(), ["from users", "where id = 1"]);
+        press(&mut vim, &mut ta, "p");
+        assert_eq!(ta.lines(), ["from users", "id, name", "where id = 1"]);
+        assert_eq!(ta.cursor(), (1, 0));
+
+        // yy then P copies the line above
+        press(&mut vim, &mut ta, "jyyP");
+        assert_eq!(ta.lines()[2..], ["where id = 1", "where id = 1"]);
+
+        // A count applies to dd
+        press(&mut vim, &mut ta, "gg2dd");
+        assert_eq!(ta.lines(), ["where id = 1", "where id = 1"]);
+
+        // cw changes to the end of the word and enters insert mode
+        press(&mut vim, &mut ta, "cw");
+        assert_eq!(ta.lines()[0], " id = 1");
+        assert_eq!(vim.mode, VimMode::Insert);
+        press(&mut vim, &mut ta, "\x1b");
+
+        press(&mut vim, &mut ta, "wD");
+        assert_eq!(ta.lines()[0], " ");
+    }
+
+    #[test]
+    fn test_mode_transitions() {
+        let mut vim = Vim::default();
+        let mut ta = editor("select 1\nselect 2");
+        assert_eq!(press(&mut vim, &mut ta, "A"), VimOutcome::Consumed);
+        assert_eq!(vim.mode, VimMode::Insert);
+        assert_eq!(ta.cursor(), (0, 8));
+        // Insert mode hands keys back to the editor
+        assert_eq!(press(&mut vim, &mut ta, "x"), VimOutcome::Insert);
+        press(&mut vim, &mut ta, "\x1b");
+        assert_eq!(vim.mode, VimMode::Normal);
+        assert_eq!(ta.cursor(), (0, 7));
+
+        press(&mut vim, &mut ta, "d");
+        assert_eq!(vim.mode_label(), "OPERATOR");
+        press(&mut vim, &mut ta, "\x1b");
+        assert_eq!(vim.mode_label(), "NORMAL");
+
+        press(&mut vim, &mut ta, "0vl");
+        assert_eq!(vim.mode, VimMode::Visual);
+        press(&mut vim, &mut ta, "d");
+        assert_eq!(vim.mode, VimMode::Normal);
+        assert_eq!(ta.lines()[0], "lect 1");
+
+        press(&mut vim, &mut ta, "Vj");
+        assert_eq!(vim.mode, VimMode::VisualLine(0));
+        press(&mut vim, &mut ta, "y");
+        assert_eq!(vim.mode, VimMode::Normal);
+        assert_eq!(ta.yank_text(), "lect 1\nselect 2");
+
+        press(&mut vim, &mut ta, "o");
+        assert_eq!(vim.mode, VimMode::Insert);
+        assert_eq!(ta.lines(), ["lect 1", "", "select 2"]);
+        press(&mut vim, &mut ta, "\x1b");
+
+        press(&mut vim, &mut ta, ":");
+        assert_eq!(vim.mode, VimMode::Command);
+        assert_eq!(
+            press(&mut vim, &mut ta, "d users\n"),
+            VimOutcome::Command("d users".to_string())
+        );
+        assert_eq!(vim.mode, VimMode::Normal);
+        // Backspace past the colon leaves command mode
+        press(&mut vim, &mut ta, ":x\x08");
+        assert_eq!(vim.mode, VimMode::Command);
+        press(&mut vim, &mut ta, "\x08");
+        assert_eq!(vim.mode, VimMode::Normal);
+    }
+}
@@ -0,0 +1,249 @@
+//! Client-side variables (`\set`, `\gset`) and their interpolation into
+//! queries.
+
+use crate::config;
+use std::collections::HashMap;
+
+/// Replace variable references in `sql`.
+///
+/// `:name`, `:'name'` (the value as a quoted string literal), and `$(name)`
+/// (matched case-insensitively, as sqlcmd does) are replaced only outside
+/// string literals, quoted identifiers, and comments, and only when the
+/// variable is set, so `::`, labels, and unknown names are left alone.
+/// Environment variables are not consulted; only scripts fall back to them
+/// (see [`interpolate_script`]).
+pub fn interpolate(sql: &str, vars: &HashMap<String, String>) -> String {
+    if vars.is_empty() {
+        return sql.to_string();
+    }
+    // Only scripts fail on unknown names
+    replace(sql, vars, false).unwrap_or_else(|_| sql.to_string())
+}
+
+/// [`interpolate`] for a script, sqlcmd style: a `$(name)` that isn't set
+/// is replaced by the environment variable of that name, and is an error
+/// if there is none.
+pub fn interpolate_script(sql: &str, vars: &HashMap<String, String>) -> Result<String, String> {
+    replace(sql, vars, true)
+}
+
+/// Replace the references in `sql`; with `env`, `$(name)` falls back to
+/// the environment and fails if unset there too.
+fn replace(sql: &str, vars: &HashMap<String, String>, env: bool) -> Result<String, String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut out = String::with_capacity(sql.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\'' | '"' | '[' => {
+                let close = if c == '[' { ']' } else { c };
+                let end = quoted_end(&chars, i + 1, close);
+                out.extend(&chars[i..end]);
+                i = end;
+            }
+            '-' if next == Some('-') => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == '\n')
+                    .map_or(chars.len(), |n| i + n);
+                out.extend(&chars[i..end]);
+                i = end;
+            }
+            '/' if next == Some('*') => {
+                let end = (i + 2..chars.len())
+                    .find(|&j| chars[j] == '*' && chars.get(j + 1) == Some(&'/'))
+                    .map_or(chars.len(), |j| j + 2);
+                out.extend(&chars[i..end]);
+                i = end;
+            }
+            '$' if next == Some('(') => match dollar_reference(&chars, i + 2, vars, env)? {
+                Some((value, end)) => {
+                    out.push_str(&value);
+                    i = end;
+                }
+                None => {
+                    out.push(c);
+                    i += 1;
+                }
+            },
+            ':' if next == Some(':') => {
+                out.push_str("::");
+                i += 2;
+            }
+            ':' => match reference(&chars, i + 1) {
+                Some((name, quoted, end)) if vars.contains_key(&name) => {
+                    let value = &vars[&name];
+                    if quoted {
+                        out.push_str(&format!("'{}'", value.replace('\'', "''")));
+                    } else {
+                        out.push_str(value);
+                    }
+                    i = end;
+                }
+                _ => {
+                    out.push(c);
+                    i += 1;
+                }
+            },
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Read a value as a boolean, psql style: anything [`config::parse_bool`]
+/// accepts, or an integer (non-zero is true).
+pub fn truthy(value: &str) -> Option<bool> {
+    let value = value.trim();
+    config::parse_bool(value).or_else(|| value.parse::<i64>().ok().map(|n| n != 0))
+}
+
+/// Variables sorted by name, as `[name, value]` rows for display.
+pub fn rows(vars: &HashMap<String, String>) -> Vec<Vec<String>> {
+    let mut rows: Vec<Vec<String>> = vars
+        .iter()
+        .map(|(name, value)| vec![name.clone(), value.clone()])
+        .collect();
+    rows.sort();
+    rows
+}
+
+/// Index just past the closing `close` of a quoted run starting at `start`;
+/// a doubled `close` is an escape.
+pub(crate) fn quoted_end(chars: &[char], start: usize, close: char) -> usize {
+    let mut i = start;
+    while i < chars.len() {
+        if chars[i] == close {
+            if chars.get(i + 1) == Some(&close) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+/// The value of a set variable named by `$(name)`, whose name starts at
+/// `start`, and the index just past the `)`. With `env`, an unset name is
+/// looked up in the environment, and an error if not there either.
+fn dollar_reference(
+    chars: &[char],
+    start: usize,
+    vars: &HashMap<String, String>,
+    env: bool,
+) -> Result<Option<(String, usize)>, String> {
+    let Some(len) = chars[start.min(chars.len())..]
+        .iter()
+        .position(|&c| c == ')')
+    else {
+        return Ok(None);
+    };
+    let name: String = chars[start..start + len].iter().collect();
+    let end = start + len + 1;
+    if let Some((_, value)) = vars.iter().find(|(k, _)| k.eq_ignore_ascii_case(&name)) {
+        return Ok(Some((value.clone(), end)));
+    }
+    if !env {
+        return Ok(None);
+    }
+    match std::env::var(&name) {
+        Ok(value) => Ok(Some((value, end))),
+        Err(_) => Err(format!("undefined variable $({})", name)),
+    }
+}
+
+/// A variable name after `:` at `start`, as `name` or `'name'`. Returns the
+/// name, whether it was quoted, and the index just past the reference.
+fn reference(chars: &[char], start: usize) -> Option<(String, bool, usize)> {
+    let quoted = chars.get(start) == Some(&'\'');
+    let begin = if quoted { start + 1 } else { start };
+    let len = chars[begin.min(chars.len())..]
+        .iter()
+        .take_while(|c| c.is_alphanumeric() || **c == '_')
+        .count();
+    if len == 0 || (!quoted && chars[begin].is_ascii_digit()) {
+        return None;
+    }
+    let name: String = chars[begin..begin + len].iter().collect();
+    let mut end = begin + len;
+    if quoted {
+        if chars.get(end) != Some(&'\'') {
+            return None;
+        }
+        end += 1;
+    }
+    Some((name, quoted, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> HashMap<String, String> {
+        [("id", "42"), ("name", "O'Brien")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_interpolate_colon_references() {
+        assert_eq!(
+            interpolate("SELECT * FROM t WHERE id = :id AND n = :'name'", &vars()),
+            "SELECT * FROM t WHERE id = 42 AND n = 'O''Brien'"
+        );
+        assert_eq!(interpolate("SELECT $(ID)", &vars()), "SELECT 42");
+    }
+
+    #[test]
+    fn test_interpolate_script() {
+        assert_eq!(
+            interpolate_script("SELECT $(ID), :id, '$(id)' -- $(missing)", &vars()).unwrap(),
+            "SELECT 42, 42, '$(id)' -- $(missing)"
+        );
+        let path = std::env::var("PATH").unwrap_or_default();
+        assert_eq!(
+            interpolate_script("SELECT N'$(PATH)', $(PATH)", &HashMap::new()).unwrap(),
+            format!("SELECT N'$(PATH)', {}", path)
+        );
+        assert_eq!(
+            interpolate_script("SELECT $(meow_surely_undefined)", &vars()),
+            Err("undefined variable $(meow_surely_undefined)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_truthy() {
+        assert_eq!(truthy(" ON "), Some(true));
+        assert_eq!(truthy("0"), Some(false));
+        assert_eq!(truthy("-3"), Some(true));
+        assert_eq!(truthy("2.5"), None);
+        assert_eq!(truthy("NaN"), None);
+        assert_eq!(truthy("inf"), None);
+        assert_eq!(truthy("SELECT 1"), None);
+    }
+
+    #[test]
+    fn test_interpolate_leaves_literals_and_unknown_names() {
+        let sql = "SELECT ':id', [a:id], geography::Point(1, 2, 4326), :other -- :id\n/* :id */";
+        assert_eq!(interpolate(sql, &vars()), sql);
+        assert_eq!(interpolate("SELECT '10:30'", &vars()), "SELECT '10:30'");
+    }
+
+    #[test]
+    fn test_interpolate_dollar_references_only_in_code() {
+        let sql = "SELECT 'cost: $(id)', [$(id)] -- $(id)\n/* $(id) */";
+        assert_eq!(interpolate(sql, &vars()), sql);
+        assert_eq!(
+            interpolate("SELECT $(id), $(missing), $(PATH)", &vars()),
+            "SELECT 42, $(missing), $(PATH)"
+        );
+    }
+}
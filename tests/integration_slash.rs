@@ -1,14 +1,22 @@
 //! Integration tests for slash commands against a real SQL Server.
 //! Requires SQL Server running on localhost:1433 with sa/TestPass123!
 
-use std::time::Instant;
-
 /// Helper: connect to SQL Server and run a query, returning (columns, rows).
 async fn run_query(
     client: &mut claw::TcpClient,
     sql: &str,
 ) -> Result<(Vec<String>, Vec<Vec<String>>), Box<dyn std::error::Error>> {
-    let stream = client.execute(sql, &[]).await?;
+    run_query_params(client, sql, &[]).await
+}
+
+/// Helper: like `run_query`, with `params` bound to `@P1`, `@P2`, ...
+async fn run_query_params(
+    client: &mut claw::TcpClient,
+    sql: &str,
+    params: &[String],
+) -> Result<(Vec<String>, Vec<Vec<String>>), Box<dyn std::error::Error>> {
+    let params: Vec<&dyn claw::ToSql> = params.iter().map(|p| p as &dyn claw::ToSql).collect();
+    let stream = client.execute(sql, &params).await?;
     let mut stream = stream;
     let cols = stream.columns().await?;
     let columns: Vec<String> = match cols {
@@ -73,7 +81,7 @@ async fn test_slash_dt_list_tables() {
     {
         let (cols, rows) = run_query(&mut client, &sql).await.unwrap();
         assert!(cols.contains(&"TABLE_NAME".to_string()));
-        assert!(rows.len() > 0);
+        assert!(!rows.is_empty());
     }
 
     let _ = run_query(&mut client, "DROP TABLE dbo.__meow_test").await;
@@ -96,10 +104,10 @@ async fn test_slash_d_describe_table() {
     .await;
 
     let cmd = meow::commands::parse("\\d __meow_test2").unwrap();
-    if let meow::commands::CommandAction::ExecuteSql(sql) =
+    if let meow::commands::CommandAction::ExecuteQuery { sql, params } =
         meow::commands::to_action(&cmd, "", "", "")
     {
-        let (cols, rows) = run_query(&mut client, &sql).await.unwrap();
+        let (cols, rows) = run_query_params(&mut client, &sql, &params).await.unwrap();
         assert!(cols.contains(&"COLUMN_NAME".to_string()));
         assert!(cols.contains(&"DATA_TYPE".to_string()));
         assert_eq!(rows.len(), 2);
@@ -119,7 +127,7 @@ async fn test_slash_dn_list_databases() {
     {
         let (cols, rows) = run_query(&mut client, &sql).await.unwrap();
         assert!(cols.contains(&"name".to_string()));
-        assert!(rows.len() > 0);
+        assert!(!rows.is_empty());
     }
 }
 
@@ -134,6 +142,6 @@ async fn test_slash_ds_list_schemas() {
     {
         let (cols, rows) = run_query(&mut client, &sql).await.unwrap();
         assert!(cols.contains(&"name".to_string()));
-        assert!(rows.len() > 0);
+        assert!(!rows.is_empty());
     }
 }